@@ -0,0 +1,88 @@
+//! Times parsing a large, synthetic mmCIF file. Compare the sequential and parallel atom-loop
+//! parsers by running with and without the `parallel` feature:
+//!
+//! `cargo bench --bench mmcif_parse`
+//! `cargo bench --bench mmcif_parse --features parallel`
+
+use std::{fmt::Write, hint::black_box};
+
+use bio_files::MmCif;
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+
+const NUM_ATOMS: usize = 1_000_000;
+const ATOMS_PER_RES: usize = 8;
+const RES_PER_CHAIN: usize = 500;
+
+fn make_cif(num_atoms: usize) -> String {
+    let mut result = String::from(
+        "data_BENCH
+loop_
+_atom_site.group_PDB
+_atom_site.id
+_atom_site.type_symbol
+_atom_site.label_atom_id
+_atom_site.label_alt_id
+_atom_site.label_comp_id
+_atom_site.label_asym_id
+_atom_site.label_seq_id
+_atom_site.Cartn_x
+_atom_site.Cartn_y
+_atom_site.Cartn_z
+_atom_site.occupancy
+_atom_site.B_iso_or_equiv
+",
+    );
+
+    let names = [
+        ("N", "N"),
+        ("C", "CA"),
+        ("C", "C"),
+        ("O", "O"),
+        ("C", "CB"),
+        ("C", "CG"),
+        ("C", "CD"),
+        ("N", "NE"),
+    ];
+
+    for i in 0..num_atoms {
+        let res = i / ATOMS_PER_RES;
+        let chain = res / RES_PER_CHAIN;
+        let (el, name) = names[i % ATOMS_PER_RES];
+
+        writeln!(
+            result,
+            "ATOM {} {el} {name} . ARG C{chain} {} {:.3} {:.3} {:.3} 1.00 {:.2}",
+            i + 1,
+            res % RES_PER_CHAIN + 1,
+            (i % 97) as f64 * 1.1,
+            (i % 89) as f64 * 0.7,
+            (i % 83) as f64 * 0.9,
+            10. + (i % 50) as f64 * 0.5,
+        )
+        .unwrap();
+    }
+
+    result.push_str("#\n");
+    result
+}
+
+fn mmcif_parse(c: &mut Criterion) {
+    let text = make_cif(NUM_ATOMS);
+    let parallel = cfg!(feature = "parallel");
+
+    let mut group = c.benchmark_group("mmcif_parse");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(NUM_ATOMS as u64));
+
+    group.bench_function(format!("{NUM_ATOMS} atoms, parallel: {parallel}"), |b| {
+        b.iter(|| {
+            let cif = MmCif::new(black_box(&text)).unwrap();
+            assert_eq!(cif.atoms.len(), NUM_ATOMS);
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, mmcif_parse);
+criterion_main!(benches);
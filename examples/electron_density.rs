@@ -139,6 +139,8 @@ pub fn density_map_from_sf(
         dmin: min_v,
         dmax: max_v,
         dmean: mean,
+        nlabl: 0,
+        labels: Vec::new(),
     };
 
     let elapsed = start.elapsed().as_millis();
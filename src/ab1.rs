@@ -11,16 +11,27 @@ use std::{
     collections::HashMap,
     fs::File,
     io::{self, ErrorKind, Read, Seek, SeekFrom},
+    ops::Range,
     path::Path,
 };
 
 #[cfg(feature = "encode")]
 use bincode::{Decode, Encode};
-use na_seq::{Seq, seq_from_str};
+use na_seq::{Nucleotide, Seq, seq_from_str, seq_to_str_upper};
 
 const HEADER_SIZE: usize = 26;
 const DIR_SIZE: usize = 28;
 
+/// Peaks in the trace below this fraction of its maximum are treated as noise when calling bases.
+const PEAK_THRESH: f32 = 0.1;
+/// Called bases with a perfectly clean peak (no signal from other channels) get this quality.
+const MAX_CALLED_QUALITY: u8 = 60;
+
+/// Default error probability cutoff for Mott trimming, as in BioPython.
+pub const TRIM_CUTOFF: f64 = 0.05;
+/// Default minimum sequence length for Mott trimming; shorter sequences aren't trimmed.
+pub const TRIM_SEGMENT: usize = 20;
+
 /// The data structure representing AB1 data.
 #[cfg_attr(feature = "encode", derive(Encode, Decode))]
 #[derive(Clone, Debug, Default)]
@@ -34,7 +45,8 @@ pub struct SeqRecordAb1 {
     pub quality: Option<Vec<u8>>,
     pub quality_user: Option<Vec<u8>>,
     pub peak_heights: Vec<u16>,
-    /// Analyzed data, for each channel.
+    /// Analyzed data, for each channel. When loading, channels are assigned to bases using the
+    /// file's base order (`FWO_`).
     /// G
     pub data_ch1: Vec<u16>,
     /// A
@@ -49,6 +61,170 @@ pub struct SeqRecordAb1 {
     pub peak_locations_user: Option<Vec<u16>>,
 }
 
+impl SeqRecordAb1 {
+    /// The sequence and quality to export, trimmed if requested. Quality is padded with 0, or
+    /// truncated, to match the sequence length.
+    fn export_seq_qual(&self, trimmed: bool) -> (&[Nucleotide], Vec<u8>) {
+        let mut quality = self.quality.clone().unwrap_or_default();
+        quality.resize(self.sequence.len(), 0);
+
+        let (start, end) = if trimmed {
+            self.trim(TRIM_CUTOFF, TRIM_SEGMENT)
+        } else {
+            (0, self.sequence.len())
+        };
+
+        (&self.sequence[start..end], quality[start..end].to_vec())
+    }
+
+    /// Find the high-quality region of the sequence using Richard Mott's modified trimming
+    /// algorithm, as in BioPython. Returns the start and end (exclusive) indices of the bases
+    /// to keep. `cutoff` is the error probability above which a base counts against the
+    /// region. Sequences no longer than `min_segment`, or without quality data, are kept whole.
+    /// [`TRIM_CUTOFF`] and [`TRIM_SEGMENT`] are the usual values.
+    pub fn trim(&self, cutoff: f64, min_segment: usize) -> (usize, usize) {
+        let len = self.sequence.len();
+        let Some(quality) = &self.quality else {
+            return (0, len);
+        };
+
+        let range = abi_trim(&quality[..quality.len().min(len)], cutoff, min_segment);
+        (range.start, range.end)
+    }
+
+    /// The sequence, with low-quality ends removed using Mott trimming with default parameters.
+    pub fn trimmed_sequence(&self) -> Seq {
+        let (start, end) = self.trim(TRIM_CUTOFF, TRIM_SEGMENT);
+        self.sequence[start..end].to_vec()
+    }
+
+    /// The FASTA header line's contents: ID, and description if present.
+    fn export_header(&self) -> String {
+        if self.description.is_empty() {
+            self.id.clone()
+        } else {
+            format!("{} {}", self.id, self.description)
+        }
+    }
+
+    /// Export the sequence as FASTA, with lines wrapped at 60 bases. If `trimmed`, only the region
+    /// kept by Mott's quality trimming is included, as with BioPython's `abi-trim` format.
+    pub fn to_fasta(&self, trimmed: bool) -> String {
+        let (seq, _) = self.export_seq_qual(trimmed);
+
+        let mut result = format!(">{}\n", self.export_header());
+        for line in seq.chunks(60) {
+            result.push_str(&seq_to_str_upper(line));
+            result.push('\n');
+        }
+        result
+    }
+
+    /// Export the sequence and quality as FASTQ, with quality encoded as Phred+33. Bases without
+    /// quality get 0. If `trimmed`, only the region kept by Mott's quality trimming is included.
+    pub fn to_fastq(&self, trimmed: bool) -> String {
+        let (seq, quality) = self.export_seq_qual(trimmed);
+
+        let quality: String = quality.iter().map(|q| (q.min(&93) + 33) as char).collect();
+
+        format!(
+            "@{}\n{}\n+\n{}\n",
+            self.export_header(),
+            seq_to_str_upper(seq),
+            quality
+        )
+    }
+
+    /// The analyzed trace channels, in the order G, A, T, C.
+    fn channels(&self) -> [(&[u16], Nucleotide); 4] {
+        [
+            (&self.data_ch1, Nucleotide::G),
+            (&self.data_ch2, Nucleotide::A),
+            (&self.data_ch3, Nucleotide::T),
+            (&self.data_ch4, Nucleotide::C),
+        ]
+    }
+
+    /// Call bases from the raw trace channels, e.g. after custom processing of them. This is a
+    /// simple alternative to the instrument's calls in `sequence`. Peaks are local maxima of the
+    /// combined signal, at least half the expected peak spacing apart. The expected spacing is from
+    /// `peak_locations` if present, and from the trace otherwise. Each peak's base is its tallest
+    /// channel. Quality ranges from 0 to 60, by how much the tallest channel stands out from the
+    /// second tallest.
+    pub fn call_bases(&self) -> (Seq, Vec<u8>) {
+        let channels = self.channels();
+        let len = channels.iter().map(|(c, _)| c.len()).min().unwrap_or(0);
+
+        let combined: Vec<u16> = (0..len)
+            .map(|i| channels.iter().map(|(c, _)| c[i]).max().unwrap_or(0))
+            .collect();
+
+        let max = combined.iter().copied().max().unwrap_or(0);
+        if max == 0 {
+            return (Vec::new(), Vec::new());
+        }
+        let thresh = (max as f32 * PEAK_THRESH) as u16;
+
+        let mut candidates: Vec<usize> = (1..len.saturating_sub(1))
+            .filter(|&i| {
+                combined[i] >= thresh
+                    && combined[i] > combined[i - 1]
+                    && combined[i] >= combined[i + 1]
+            })
+            .collect();
+
+        let spacing = median_spacing(&self.peak_locations)
+            .or_else(|| {
+                let posits: Vec<u16> = candidates.iter().map(|&i| i as u16).collect();
+                median_spacing(&posits)
+            })
+            .unwrap_or(1);
+        let min_sep = (spacing / 2).max(1);
+
+        // Keep the tallest peaks first, skipping any too close to one already kept.
+        candidates.sort_by_key(|&i| std::cmp::Reverse(combined[i]));
+        let mut peaks: Vec<usize> = Vec::new();
+        for i in candidates {
+            if peaks.iter().all(|&p| p.abs_diff(i) >= min_sep) {
+                peaks.push(i);
+            }
+        }
+        peaks.sort_unstable();
+
+        let mut seq = Vec::with_capacity(peaks.len());
+        let mut quality = Vec::with_capacity(peaks.len());
+
+        for i in peaks {
+            let mut heights: Vec<(u16, Nucleotide)> =
+                channels.iter().map(|(c, nt)| (c[i], *nt)).collect();
+            heights.sort_by_key(|(h, _)| std::cmp::Reverse(*h));
+
+            let (top, nt) = heights[0];
+            let second = heights[1].0;
+            let prominence = (top - second) as f32 / top as f32;
+
+            seq.push(nt);
+            quality.push((prominence * MAX_CALLED_QUALITY as f32).round() as u8);
+        }
+
+        (seq, quality)
+    }
+}
+
+/// The median distance between consecutive positions. None if there are fewer than 2.
+fn median_spacing(posits: &[u16]) -> Option<usize> {
+    let mut diffs: Vec<usize> = posits
+        .windows(2)
+        .map(|w| w[1].abs_diff(w[0]) as usize)
+        .collect();
+    if diffs.is_empty() {
+        return None;
+    }
+
+    diffs.sort_unstable();
+    Some(diffs[diffs.len() / 2])
+}
+
 #[derive(Debug)]
 struct Header {
     pub file_version: u16,
@@ -111,6 +287,7 @@ impl Dir {
 #[derive(Debug)]
 struct AbiIterator<R: Read + Seek> {
     stream: R,
+    done: bool,
 }
 
 impl<R: Read + Seek> AbiIterator<R> {
@@ -123,10 +300,18 @@ impl<R: Read + Seek> AbiIterator<R> {
                 "Invalid AB1 file start marker",
             ));
         }
-        Ok(Self { stream })
+        Ok(Self {
+            stream,
+            done: false,
+        })
     }
 
     pub fn next(&mut self) -> io::Result<Option<SeqRecordAb1>> {
+        // An AB1 file contains a single record.
+        if self.done {
+            return Ok(None);
+        }
+
         let mut result = SeqRecordAb1::default();
         let mut header_data = [0; HEADER_SIZE];
 
@@ -136,6 +321,11 @@ impl<R: Read + Seek> AbiIterator<R> {
 
         let header = Header::from_bytes(header_data)?;
 
+        // Analyzed data, in the order of `base_order`.
+        let mut data: [Vec<u16>; 4] = Default::default();
+        let mut base_order = String::from("GATC");
+        let mut run_times = HashMap::new();
+
         for i in 0..header.num_elements {
             // todo: QC data_offset; coming out much too high.
             // Note: Element size should always be DIR_SIZE.
@@ -248,7 +438,7 @@ impl<R: Read + Seek> AbiIterator<R> {
                 },
                 "DATA9" => match tag_data {
                     TagData::U16(d) => {
-                        result.data_ch1 = d;
+                        data[0] = d;
                     }
                     _ => {
                         return Err(io::Error::new(
@@ -259,7 +449,7 @@ impl<R: Read + Seek> AbiIterator<R> {
                 },
                 "DATA10" => match tag_data {
                     TagData::U16(d) => {
-                        result.data_ch2 = d;
+                        data[1] = d;
                     }
                     _ => {
                         return Err(io::Error::new(
@@ -270,7 +460,7 @@ impl<R: Read + Seek> AbiIterator<R> {
                 },
                 "DATA11" => match tag_data {
                     TagData::U16(d) => {
-                        result.data_ch3 = d;
+                        data[2] = d;
                     }
                     _ => {
                         return Err(io::Error::new(
@@ -281,7 +471,7 @@ impl<R: Read + Seek> AbiIterator<R> {
                 },
                 "DATA12" => match tag_data {
                     TagData::U16(d) => {
-                        result.data_ch4 = d;
+                        data[3] = d;
                     }
                     _ => {
                         return Err(io::Error::new(
@@ -290,6 +480,45 @@ impl<R: Read + Seek> AbiIterator<R> {
                         ));
                     }
                 },
+                // Filter wheel order; the base each analyzed data channel corresponds to.
+                "FWO_1" => match tag_data {
+                    TagData::Str(s) => {
+                        base_order = s.trim().to_uppercase();
+                        result
+                            .annotations
+                            .insert("base_order".to_owned(), base_order.clone());
+                    }
+                    _ => return Err(io::Error::new(ErrorKind::InvalidData, "Invalid base order")),
+                },
+                "RUND1" | "RUND2" | "RUNT1" | "RUNT2" => match tag_data {
+                    TagData::Str(s) => {
+                        run_times.insert(key, s);
+                    }
+                    _ => {
+                        return Err(io::Error::new(
+                            ErrorKind::InvalidData,
+                            "Invalid run date or time",
+                        ));
+                    }
+                },
+                "MODL1" | "MCHN1" | "DySN1" => match tag_data {
+                    TagData::Str(s) => {
+                        let name = match key.as_str() {
+                            "MODL1" => "machine_model",
+                            "MCHN1" => "machine_name",
+                            _ => "dye",
+                        };
+                        result
+                            .annotations
+                            .insert(name.to_owned(), s.trim().to_owned());
+                    }
+                    _ => {
+                        return Err(io::Error::new(
+                            ErrorKind::InvalidData,
+                            "Invalid instrument metadata",
+                        ));
+                    }
+                },
                 _ => {
                     // todo: Implement others A/R.
                     eprintln!("Invalid key in AB1 file: {key:?}");
@@ -298,6 +527,37 @@ impl<R: Read + Seek> AbiIterator<R> {
             }
         }
 
+        // Run start and finish, as date and time.
+        for (name, date, time) in [
+            ("run_start", "RUND1", "RUNT1"),
+            ("run_finish", "RUND2", "RUNT2"),
+        ] {
+            if let Some(date) = run_times.get(date) {
+                let v = match run_times.get(time) {
+                    Some(time) => format!("{date} {time}"),
+                    None => date.clone(),
+                };
+                result.annotations.insert(name.to_owned(), v);
+            }
+        }
+
+        // Assign the analyzed data channels to bases, using the filter wheel order.
+        for (d, base) in data.into_iter().zip(base_order.chars()) {
+            match base {
+                'G' => result.data_ch1 = d,
+                'A' => result.data_ch2 = d,
+                'T' => result.data_ch3 = d,
+                'C' => result.data_ch4 = d,
+                _ => {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Invalid base in AB1 base order: {base_order}"),
+                    ));
+                }
+            }
+        }
+
+        self.done = true;
         Ok(Some(result))
     }
 }
@@ -313,59 +573,48 @@ fn parse_abi_tag(data: &[u8]) -> io::Result<(String, String)> {
     );
     Ok((tag_name, tag_number.to_string()))
 }
-//
-// fn abi_trim(seq_record: &fastq::Record) -> fastq::Record {
-//     // Richard Mott's modified trimming algorithm.
-//
-//     let segment = 20; // Minimum sequence length
-//     let cutoff = 0.05; // Default cutoff value for calculating base score
-//
-//     // If the length of the sequence is less than or equal to the segment size, return as is.
-//     if seq_record.seq().len() <= segment {
-//         return seq_record.clone();
-//     }
-//
-//     // Calculate base scores from quality values.
-//     let score_list: Vec<f64> = seq_record
-//         .qual()
-//         .iter()
-//         .map(|&qual| cutoff - 10f64.powf((qual as f64) / -10.0))
-//         .collect();
-//
-//     // Calculate cumulative score, initialize with zero.
-//     let mut cumulative_scores: Vec<f64> = vec![0.0];
-//     let mut trim_start = 0;
-//     let mut start_flag = false;
-//
-//     for i in 1..score_list.len() {
-//         let score = cumulative_scores[i - 1] + score_list[i];
-//         if score < 0.0 {
-//             cumulative_scores.push(0.0);
-//         } else {
-//             cumulative_scores.push(score);
-//             if !start_flag {
-//                 // Set trim_start when cumulative score is first greater than zero
-//                 trim_start = i;
-//                 start_flag = true;
-//             }
-//         }
-//     }
-//
-//     // Find the index of the highest cumulative score to mark the end of the trimming segment
-//     let trim_finish = cumulative_scores
-//         .iter()
-//         .enumerate()
-//         .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
-//         .map(|(idx, _)| idx)
-//         .unwrap_or(0);
-//
-//     // Extract the trimmed sequence
-//     let trimmed_seq = &seq_record.seq()[trim_start..trim_finish];
-//     let trimmed_qual = &seq_record.qual()[trim_start..trim_finish];
-//
-//     // Create a new trimmed record and return it
-//     fastq::Record::with_attrs(seq_record.id(), None, trimmed_seq, trimmed_qual)
-// }
+
+/// The range of bases to keep, using Richard Mott's modified trimming algorithm, as in BioPython's
+/// `_abi_trim`. Sequences no longer than the minimum segment length are kept whole.
+fn abi_trim(quality: &[u8], cutoff: f64, segment: usize) -> Range<usize> {
+    if quality.len() <= segment {
+        return 0..quality.len();
+    }
+
+    // Base scores from quality values.
+    let scores: Vec<f64> = quality
+        .iter()
+        .map(|&q| cutoff - 10f64.powf(q as f64 / -10.))
+        .collect();
+
+    let mut cumulative = vec![0.];
+    let mut trim_start = 0;
+    let mut start_found = false;
+
+    for (i, score) in scores.iter().enumerate().skip(1) {
+        let v = cumulative[i - 1] + score;
+        if v < 0. {
+            cumulative.push(0.);
+        } else {
+            cumulative.push(v);
+            if !start_found {
+                // The first base where the cumulative score is greater than zero.
+                trim_start = i;
+                start_found = true;
+            }
+        }
+    }
+
+    // The end is the first index of the highest cumulative score.
+    let mut trim_finish = 0;
+    for (i, v) in cumulative.iter().enumerate() {
+        if *v > cumulative[trim_finish] {
+            trim_finish = i;
+        }
+    }
+
+    trim_start..trim_finish.max(trim_start)
+}
 
 #[derive(Debug)]
 enum TagData {
@@ -408,6 +657,32 @@ fn parse_tag_data(elem_code: u16, _elem_num: usize, data: &[u8]) -> io::Result<T
                 .collect();
             Ok(TagData::U16(as_u16))
         }
+        // Date: year, month, day.
+        10 if data.len() >= 4 => Ok(TagData::Str(format!(
+            "{:04}-{:02}-{:02}",
+            i16::from_be_bytes([data[0], data[1]]),
+            data[2],
+            data[3]
+        ))),
+        // Time: hour, minute, second, hundredths of a second.
+        11 if data.len() >= 3 => Ok(TagData::Str(format!(
+            "{:02}:{:02}:{:02}",
+            data[0], data[1], data[2]
+        ))),
+        // Pascal string; the first byte is its length.
+        18 => {
+            let len = data.first().copied().unwrap_or(0) as usize;
+            let end = (len + 1).min(data.len());
+            Ok(TagData::Str(
+                String::from_utf8_lossy(data.get(1..end).unwrap_or(&[])).to_string(),
+            ))
+        }
+        // Null-terminated string.
+        19 => Ok(TagData::Str(
+            String::from_utf8_lossy(data)
+                .trim_end_matches(char::from(0))
+                .to_string(),
+        )),
         5 => {
             let as_u32 = data
                 .chunks_exact(4)
@@ -6,6 +6,12 @@
 //! https://itp.uni-frankfurt.de/~engel/amino.html
 //!
 //! All lengths are in angstrom (Å)
+//!
+//! [`infer_bonds`] is a simpler alternative that works for any element, using covalent radii
+//! instead of known bond lengths.
+//!
+//! The spacial grid used here also backs [`atoms_within_of`], for finding contacts between groups
+//! of atoms, e.g. at interfaces and binding pockets.
 
 use std::collections::HashMap;
 
@@ -39,6 +45,12 @@ impl BondSpecs {
 const COV_BOND_LEN_THRESH: f64 = 0.04; // todo: Adjust A/R based on performannce.
 const COV_DIST_GRID: f64 = 1.6; // Slightly larger than the largest bond distance + thresh.
 
+// For `infer_bonds`: Atoms closer than the sum of their covalent radii times this are bonded.
+const COV_RADIUS_TOLERANCE: f64 = 1.15;
+// For `infer_bonds`: Atoms closer than this are treated as overlapping (e.g. alternate
+// conformations), vice bonded.
+const MIN_BOND_DIST: f64 = 0.4;
+
 #[rustfmt::skip]
 fn get_specs() -> Vec<BondSpecs> {
     // Code shorteners
@@ -157,27 +169,80 @@ fn get_specs() -> Vec<BondSpecs> {
     ]
 }
 
+/// Configures bond inference.
+#[derive(Clone, Debug)]
+pub struct BondInferenceParams {
+    /// If an interatomic distance is within this distance (Å) of a known bond length, consider it
+    /// to be a bond.
+    pub default_tolerance: f64,
+    /// Tolerances (Å) for specific element pairs, in either order, used instead of the default.
+    /// Set to 0 to never create bonds between a pair, e.g. for metal coordination.
+    pub pair_overrides: HashMap<(Element, Element), f64>,
+    /// If set, limit the number of bonds per atom. The bonds that best match their known
+    /// lengths are kept.
+    pub max_bonds_per_atom: Option<usize>,
+}
+
+impl Default for BondInferenceParams {
+    fn default() -> Self {
+        Self {
+            default_tolerance: COV_BOND_LEN_THRESH,
+            pair_overrides: HashMap::new(),
+            max_bonds_per_atom: None,
+        }
+    }
+}
+
+impl BondInferenceParams {
+    fn tolerance(&self, el_0: Element, el_1: Element) -> f64 {
+        self.pair_overrides
+            .get(&(el_0, el_1))
+            .or_else(|| self.pair_overrides.get(&(el_1, el_0)))
+            .copied()
+            .unwrap_or(self.default_tolerance)
+    }
+}
+
 /// Infer bonds from atom distances. Uses spacial partitioning for efficiency.
 /// We Check pairs only within nearby bins.
 pub fn create_bonds(atoms: &[AtomGeneric]) -> Vec<BondGeneric> {
+    create_bonds_with_params(atoms, &BondInferenceParams::default())
+}
+
+/// Infer bonds from atom distances, with custom tolerances. See [`create_bonds`].
+pub fn create_bonds_with_params(
+    atoms: &[AtomGeneric],
+    params: &BondInferenceParams,
+) -> Vec<BondGeneric> {
     let specs = get_specs();
 
+    // Relaxed tolerances may require a larger grid, so we don't miss pairs.
+    let max_tol = params
+        .pair_overrides
+        .values()
+        .copied()
+        .fold(params.default_tolerance, f64::max);
+    let max_len = specs.iter().map(|s| s.len).fold(0., f64::max);
+    let grid_size = COV_DIST_GRID.max(max_len + max_tol);
+
     // We use spacial partitioning, so as not to copmare every pair of atoms.
     let posits: Vec<_> = atoms.iter().map(|a| &a.posit).collect();
     // Indices are all values here.
     let indices: Vec<_> = (0..posits.len()).collect();
-    let neighbor_pairs = setup_neighbor_pairs(&posits, &indices, COV_DIST_GRID);
+    let neighbor_pairs = setup_neighbor_pairs(&posits, &indices, grid_size);
 
     // todo: Should we create an Vec of neighbors for each atom. (Maybe storeed in a hashmap etc)
     // todo, then iterate over that for neighbors in the j loop? WOuld be more generalizable/extract
     // todo it out from the bus logic.
 
-    neighbor_pairs
+    // Bonds, with their atom indices, and deviation from the known length.
+    let mut candidates: Vec<_> = neighbor_pairs
         .par_iter()
         .filter_map(|(i, j)| {
             let atom_0 = &atoms[*i];
             let atom_1 = &atoms[*j];
             let dist = (atom_0.posit - atom_1.posit).magnitude();
+            let tolerance = params.tolerance(atom_0.element, atom_1.element);
 
             specs.iter().find_map(|spec| {
                 let matches_elements = (atom_0.element == spec.elements.0
@@ -186,23 +251,146 @@ pub fn create_bonds(atoms: &[AtomGeneric]) -> Vec<BondGeneric> {
 
                 // If both the element match and distance-threshold check pass,
                 // we create a Bond and stop searching any further specs.
-                if matches_elements && (dist - spec.len).abs() < COV_BOND_LEN_THRESH {
-                    Some(BondGeneric {
+                let dev = (dist - spec.len).abs();
+                if matches_elements && dev < tolerance {
+                    let bond = BondGeneric {
                         bond_type: spec.bond_type,
                         atom_0_sn: atom_0.serial_number,
                         atom_1_sn: atom_1.serial_number,
                         // atom_0: *i,
                         // atom_1: *j,
                         // is_backbone: atom_0.is_backbone() && atom_1.is_backbone(),
-                    })
+                    };
+                    Some((bond, (*i, *j), dev))
                 } else {
                     None
                 }
             })
         })
+        .collect();
+
+    let Some(max_bonds) = params.max_bonds_per_atom else {
+        return candidates.into_iter().map(|(bond, _, _)| bond).collect();
+    };
+
+    candidates.sort_by(|a, b| a.2.total_cmp(&b.2));
+
+    let mut bond_counts = vec![0; atoms.len()];
+    let mut result = Vec::new();
+    for (bond, (i, j), _) in candidates {
+        if bond_counts[i] < max_bonds && bond_counts[j] < max_bonds {
+            bond_counts[i] += 1;
+            bond_counts[j] += 1;
+            result.push(bond);
+        }
+    }
+
+    result
+}
+
+/// Infer bonds from atom distances, for any elements: Atoms are bonded if they're closer than the
+/// sum of their covalent radii, times a tolerance. Use this for structures without connectivity,
+/// e.g. from XYZ or mmCIF files, that contain elements [`create_bonds`] doesn't know bond lengths
+/// for. All bonds are single. Uses spacial partitioning for efficiency. Bonds are ordered by atom
+/// index.
+pub fn infer_bonds(atoms: &[AtomGeneric]) -> Vec<BondGeneric> {
+    let max_radius = atoms
+        .iter()
+        .map(|a| a.element.covalent_radius())
+        .fold(0., f64::max);
+    // At least the largest possible bond length.
+    let grid_size = (2. * max_radius * COV_RADIUS_TOLERANCE).max(MIN_BOND_DIST);
+
+    let posits: Vec<_> = atoms.iter().map(|a| &a.posit).collect();
+    let indices: Vec<_> = (0..posits.len()).collect();
+    let mut pairs = setup_neighbor_pairs(&posits, &indices, grid_size);
+    pairs.sort_unstable();
+
+    pairs
+        .into_iter()
+        .filter_map(|(i, j)| {
+            let atom_0 = &atoms[i];
+            let atom_1 = &atoms[j];
+
+            let dist = (atom_0.posit - atom_1.posit).magnitude();
+            let max_dist = (atom_0.element.covalent_radius() + atom_1.element.covalent_radius())
+                * COV_RADIUS_TOLERANCE;
+
+            (dist > MIN_BOND_DIST && dist < max_dist).then_some(BondGeneric {
+                bond_type: BondType::Single,
+                atom_0_sn: atom_0.serial_number,
+                atom_1_sn: atom_1.serial_number,
+            })
+        })
         .collect()
 }
 
+/// Find atoms in `target_sns` within `radius` (Å) of any atom in `query_sns`. For example, to find
+/// the atoms of chain A at an interface with chain B, pass chain B's atoms as the query, and chain
+/// A's as the target. Uses spacial partitioning, so this is fast for large structures.
+///
+/// Returns target serial numbers, in the order they appear in `target_sns`. An atom in both sets
+/// is always included. Serial numbers not present in `atoms` are ignored.
+pub fn atoms_within_of(
+    atoms: &[AtomGeneric],
+    query_sns: &[u32],
+    target_sns: &[u32],
+    radius: f64,
+) -> Vec<u32> {
+    if radius <= 0. {
+        return Vec::new();
+    }
+
+    let posit_by_sn: HashMap<u32, &Vec3> =
+        atoms.iter().map(|a| (a.serial_number, &a.posit)).collect();
+
+    // Bin the query atoms. With cells as large as the radius, any atom within range of a target is
+    // in the target's cell, or an adjacent one.
+    let mut grid: HashMap<(i32, i32, i32), Vec<&Vec3>> = HashMap::new();
+    for sn in query_sns {
+        if let Some(posit) = posit_by_sn.get(sn) {
+            grid.entry(grid_cell(posit, radius))
+                .or_default()
+                .push(posit);
+        }
+    }
+
+    let radius_sq = radius * radius;
+
+    target_sns
+        .iter()
+        .filter(|sn| {
+            let Some(posit) = posit_by_sn.get(sn) else {
+                return false;
+            };
+            let cell = grid_cell(posit, radius);
+
+            (-1..=1).any(|dx| {
+                (-1..=1).any(|dy| {
+                    (-1..=1).any(|dz| {
+                        grid.get(&(cell.0 + dx, cell.1 + dy, cell.2 + dz))
+                            .is_some_and(|cell_posits| {
+                                cell_posits
+                                    .iter()
+                                    .any(|p| (**p - **posit).magnitude_squared() <= radius_sq)
+                            })
+                    })
+                })
+            })
+        })
+        .copied()
+        .collect()
+}
+
+/// The spacial grid cell containing a position.
+fn grid_cell(posit: &Vec3, grid_size: f64) -> (i32, i32, i32) {
+    (
+        (posit.x / grid_size).floor() as i32,
+        (posit.y / grid_size).floor() as i32,
+        (posit.z / grid_size).floor() as i32,
+    )
+}
+
 /// A helper fn. Maps from a global index, to a local atom from a subset.
 fn _find_atom<'a>(
     atoms: &'a [AtomGeneric],
@@ -231,13 +419,9 @@ fn setup_neighbor_pairs(
     let mut grid: HashMap<(i32, i32, i32), Vec<usize>> = HashMap::new();
 
     for (i, posit) in posits.iter().enumerate() {
-        let grid_pos = (
-            (posit.x / grid_size).floor() as i32,
-            (posit.y / grid_size).floor() as i32,
-            (posit.z / grid_size).floor() as i32,
-        );
-
-        grid.entry(grid_pos).or_default().push(indexes[i]);
+        grid.entry(grid_cell(posit, grid_size))
+            .or_default()
+            .push(indexes[i]);
     }
 
     // Collect candidate atom pairs based on neighboring grid cells.
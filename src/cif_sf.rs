@@ -283,7 +283,7 @@ fn some_i(s: &str) -> Option<i32> {
     t.parse::<i32>().ok()
 }
 
-fn next_good_fft_len(n: usize) -> usize {
+pub(crate) fn next_good_fft_len(n: usize) -> usize {
     // power of two for simplicity
     n.next_power_of_two()
 }
@@ -10,13 +10,16 @@ use std::{
     collections::HashMap,
     fs::File,
     io,
-    io::{ErrorKind, Read},
+    io::{ErrorKind, Read, Write},
     path::Path,
 };
 
-use crate::md_params::{
-    AngleBendingParams, BondStretchingParams, DihedralParams, ForceFieldParamsVec, LjParams,
-    MassParams, get_atom_types,
+use crate::{
+    frcmod::push_line,
+    md_params::{
+        AngleBendingParams, BondStretchingParams, CmapData, DihedralParams, ForceFieldParamsVec,
+        LjParams, MassParams, get_atom_types,
+    },
 };
 
 impl ForceFieldParamsVec {
@@ -29,6 +32,8 @@ impl ForceFieldParamsVec {
         let mut vdw_alias_map: HashMap<String, String> = HashMap::new();
 
         let mut in_mod4 = false;
+        // CMAP blocks continue to the end of the data.
+        let mut cmap_lines = Vec::new();
 
         // These dat text-based files are tabular data, and don't have clear delineations bewteen sections.
         // we parse each line based on its content. Notably, the first column alone is a good indicator
@@ -37,8 +42,15 @@ impl ForceFieldParamsVec {
         // a dihedral (aka torsion) angle.
         for (i, line) in text.lines().enumerate() {
             let line = line.trim();
-            // Header or blank – also resets the MOD4 block if we just left it.
-            if i == 0 || line.is_empty() {
+            // The title; we keep it as a remark.
+            if i == 0 {
+                if !line.is_empty() {
+                    result.remarks.push(line.to_owned());
+                }
+                continue;
+            }
+            // Blank – also resets the MOD4 block if we just left it.
+            if line.is_empty() {
                 in_mod4 = false;
                 continue;
             }
@@ -54,6 +66,11 @@ impl ForceFieldParamsVec {
                 break;
             }
 
+            if line.starts_with("%FLAG CMAP") || !cmap_lines.is_empty() {
+                cmap_lines.push(line);
+                continue;
+            }
+
             let cols: Vec<&str> = line.split_whitespace().collect();
 
             // header that *starts* the block
@@ -122,6 +139,8 @@ impl ForceFieldParamsVec {
             }
         }
 
+        result.cmap = CmapData::from_lines(&cmap_lines)?;
+
         // for r in &result.van_der_waals {
         //     println!("Vdw: {:?}", r);
         // }
@@ -148,6 +167,58 @@ impl ForceFieldParamsVec {
         Ok(result)
     }
 
+    /// Create a string in the dat format, with sections in Amber's order: Title, masses,
+    /// hydrophilic atom types, bonds, angles, dihedrals, impropers, H-bond parameters,
+    /// nonbonded equivalences, and Lennard-Jones parameters. We don't store hydrophilic types,
+    /// H-bond parameters, equivalences (aliases are written as their own LJ lines), or
+    /// polarizability, which is written as 0.
+    pub fn to_dat(&self) -> String {
+        let title = self
+            .remarks
+            .first()
+            .map(String::as_str)
+            .unwrap_or("Amber force field parameters");
+        let mut result = format!("{title}\n");
+
+        for m in &self.mass {
+            let line = format!("{:<2} {:<7.3}{:>8.3}", m.atom_type, m.mass, 0.);
+            push_line(&mut result, line, &m.comment, "               ");
+        }
+        result.push('\n');
+
+        // Hydrophilic atom types.
+        result.push('\n');
+
+        self.push_bond_lines(&mut result);
+        result.push('\n');
+
+        self.push_angle_lines(&mut result);
+        result.push('\n');
+
+        self.push_dihedral_lines(&mut result);
+        result.push('\n');
+
+        self.push_improper_lines(&mut result);
+        result.push('\n');
+
+        // H-bond parameters, and nonbonded equivalences.
+        result.push_str("\n\n");
+
+        result.push_str("MOD4      RE\n");
+        self.push_lj_lines(&mut result);
+        result.push('\n');
+
+        result.push_str("END\n");
+
+        result
+    }
+
+    /// Write to file.
+    pub fn save_dat(&self, path: &Path) -> io::Result<()> {
+        let mut f = File::create(path)?;
+        write!(f, "{}", self.to_dat())
+    }
+
     /// todo: Sort out the syntax for loading from different sources.
     pub fn load_dat(path: &Path) -> io::Result<Self> {
         let mut file = File::open(path)?;
@@ -10,7 +10,7 @@ use std::{
 
 use lin_alg::f32::Vec3;
 
-use crate::FrameSlice;
+use crate::{AtomGeneric, FrameSlice, padded_bounds};
 
 /// Stored directly in the DCD file header.d
 pub struct DcdMetadata {
@@ -20,6 +20,8 @@ pub struct DcdMetadata {
     pub save_interval_steps: usize,
     pub dt: f32,
     pub end_time: f32,
+    /// Whether frames include a unit cell record.
+    pub has_unit_cell: bool,
 }
 
 impl DcdMetadata {
@@ -27,87 +29,164 @@ impl DcdMetadata {
         let f = File::open(path)?;
         let mut r = BufReader::new(f);
 
-        let hdr = read_record(&mut r)?;
-        if hdr.len() < 84 || &hdr[0..4] != b"CORD" {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Not a CORD/DCD file",
-            ));
-        }
-        let mut icntrl = [0i32; 20];
-        for (i, item) in icntrl.iter_mut().enumerate() {
-            let off = 4 + i * 4;
-            *item = i32::from_le_bytes(hdr[off..off + 4].try_into().unwrap());
-        }
-        let num_frames = icntrl[0] as usize;
-        let istart = icntrl[1] as f64;
-        let nsavc = icntrl[2] as f64;
-        let delta = f32::from_le_bytes(hdr[4 + 36..4 + 40].try_into().unwrap());
-
-        skip_title_record(&mut r)?;
-
-        let natom_block = read_record(&mut r)?;
-        if natom_block.len() != 4 {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Unexpected NATOM block size",
-            ));
-        }
-        let num_atoms = i32::from_le_bytes(natom_block[0..4].try_into().unwrap()) as usize;
+        let hdr = DcdHeader::read(&mut r)?;
 
-        let end_time = if num_frames == 0 {
+        let end_time = if hdr.num_frames == 0 {
             0.0
         } else {
-            ((istart + (num_frames as f64 - 1.0) * nsavc) * delta as f64) as f32
+            hdr.frame_time(hdr.num_frames - 1) as f32
         };
 
         Ok(Self {
-            num_atoms,
-            num_frames,
-            start_step: istart as f32,
-            save_interval_steps: nsavc as usize,
-            dt: delta,
+            num_atoms: hdr.num_atoms,
+            num_frames: hdr.num_frames,
+            start_step: hdr.istart as f32,
+            save_interval_steps: hdr.nsavc as usize,
+            dt: hdr.delta as f32,
             end_time,
+            has_unit_cell: hdr.has_unit_cell,
         })
     }
 }
 
+/// The periodic box. `bounds_low` and `bounds_high` span the box edge lengths (a, b, c) along x, y,
+/// and z. For non-orthorhombic (triclinic) boxes, the angles between box vectors are non-90°; use
+/// [`Self::box_vectors`] to get the actual edges.
 #[derive(Clone, Debug)]
 pub struct DcdUnitCell {
     pub bounds_low: Vec3,
     pub bounds_high: Vec3,
+    /// The angle between the b and c box vectors, in degrees.
+    pub alpha: f32,
+    /// The angle between the a and c box vectors, in degrees.
+    pub beta: f32,
+    /// The angle between the a and b box vectors, in degrees.
+    pub gamma: f32,
+}
+
+impl Default for DcdUnitCell {
+    fn default() -> Self {
+        Self {
+            bounds_low: Vec3::new(0., 0., 0.),
+            bounds_high: Vec3::new(0., 0., 0.),
+            alpha: 90.,
+            beta: 90.,
+            gamma: 90.,
+        }
+    }
 }
 
 impl DcdUnitCell {
+    /// The three box edge vectors, in Å. `a` lies along x, and `b` in the xy plane. For an
+    /// orthorhombic box, these are along the x, y, and z axes.
+    pub fn box_vectors(&self) -> [Vec3; 3] {
+        let len = self.bounds_high - self.bounds_low;
+        let (a, b, c) = (len.x as f64, len.y as f64, len.z as f64);
+
+        let (cos_a, cos_b) = (
+            (self.alpha as f64).to_radians().cos(),
+            (self.beta as f64).to_radians().cos(),
+        );
+        let (sin_g, cos_g) = (self.gamma as f64).to_radians().sin_cos();
+
+        let cx = c * cos_b;
+        let cy = c * (cos_a - cos_b * cos_g) / sin_g;
+        let cz = (c * c - cx * cx - cy * cy).max(0.).sqrt();
+
+        [
+            Vec3::new(a as f32, 0., 0.),
+            Vec3::new((b * cos_g) as f32, (b * sin_g) as f32, 0.),
+            Vec3::new(cx as f32, cy as f32, cz as f32),
+        ]
+    }
+
+    /// True if any box edge has zero (or negative) length, e.g. for a vacuum simulation. We don't
+    /// write unit cell records for these.
+    pub fn is_degenerate(&self) -> bool {
+        let len = self.bounds_high - self.bounds_low;
+        len.x <= 0. || len.y <= 0. || len.z <= 0.
+    }
+
+    /// Fractional coordinates along the box vectors, relative to `bounds_low`.
+    fn cartesian_to_fractional(&self, posit: Vec3) -> [f64; 3] {
+        let [a, b, c] = self.box_vectors();
+        let r = posit - self.bounds_low;
+
+        // The box vector matrix is triangular: `a` is along x, and `b` is in the xy plane.
+        let fc = r.z as f64 / c.z as f64;
+        let fb = (r.y as f64 - fc * c.y as f64) / b.y as f64;
+        let fa = (r.x as f64 - fb * b.x as f64 - fc * c.x as f64) / a.x as f64;
+
+        [fa, fb, fc]
+    }
+
+    fn fractional_to_cartesian(&self, frac: [f64; 3]) -> Vec3 {
+        let [a, b, c] = self.box_vectors();
+        let [fa, fb, fc] = frac;
+
+        let x = fa * a.x as f64 + fb * b.x as f64 + fc * c.x as f64;
+        let y = fb * b.y as f64 + fc * c.y as f64;
+        let z = fc * c.z as f64;
+
+        self.bounds_low + Vec3::new(x as f32, y as f32, z as f32)
+    }
+
     fn to_dcd_six(&self) -> [f64; 6] {
         let a = (self.bounds_high.x - self.bounds_low.x) as f64;
         let b = (self.bounds_high.y - self.bounds_low.y) as f64;
         let c = (self.bounds_high.z - self.bounds_low.z) as f64;
 
-        // Orthorhombic: angles are 90 degrees.
-        // Use the common X-PLOR ordering on disk: [A, gamma, B, beta, alpha, C].
-        // For 90/90/90 the permutations don’t change meaning.
-        [a, 90.0, b, 90.0, 90.0, c]
+        // The X-PLOR ordering on disk: [A, gamma, B, beta, alpha, C]. We write angles in degrees,
+        // as OpenMM and NAMD do.
+        [
+            a,
+            self.gamma as f64,
+            b,
+            self.beta as f64,
+            self.alpha as f64,
+            c,
+        ]
     }
 
     fn from_dcd_six(six: [f64; 6]) -> Self {
-        // We only store bounds_low/high, but DCD stores lengths/angles, not an origin.
-        // So we reconstruct a box from (0,0,0) to (A,B,C).
+        // DCD stores lengths/angles, not an origin. So we reconstruct a box from (0,0,0) to (A,B,C).
         let a = six[0] as f32;
         let b = six[2] as f32;
         let c = six[5] as f32;
 
+        let (mut gamma, mut beta, mut alpha) = (six[1], six[3], six[4]);
+        // CHARMM stores the cosines of the angles instead of degrees. Angles within [-1, 1] degrees
+        // aren't meaningful for a box, so treat these as cosines.
+        if [alpha, beta, gamma].iter().all(|v| v.abs() <= 1.) {
+            alpha = alpha.acos().to_degrees();
+            beta = beta.acos().to_degrees();
+            gamma = gamma.acos().to_degrees();
+        }
+
         Self {
-            bounds_low: Vec3 {
-                x: 0.0,
-                y: 0.0,
-                z: 0.0,
-            },
-            bounds_high: Vec3 { x: a, y: b, z: c },
+            bounds_low: Vec3::new(0., 0., 0.),
+            bounds_high: Vec3::new(a, b, c),
+            alpha: alpha as f32,
+            beta: beta as f32,
+            gamma: gamma as f32,
         }
     }
 }
 
+/// An orthorhombic box containing all atoms, with `padding` (Å) between the atoms' extent and each
+/// face. Useful for setting up vacuum simulations, or writing DCD files from non-periodic data.
+/// Unlike cells read from DCD files, this isn't anchored at the origin; `bounds_low` is the
+/// minimum atom coordinate, less the padding.
+pub fn bounding_cell(atoms: &[AtomGeneric], padding: f64) -> DcdUnitCell {
+    let (low, high) = padded_bounds(atoms, padding);
+
+    DcdUnitCell {
+        bounds_low: Vec3::new(low.x as f32, low.y as f32, low.z as f32),
+        bounds_high: Vec3::new(high.x as f32, high.y as f32, high.z as f32),
+        ..Default::default()
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct DcdFrame {
     /// fs
@@ -119,6 +198,61 @@ pub struct DcdFrame {
     pub unit_cell: DcdUnitCell,
 }
 
+impl DcdFrame {
+    /// Move each atom into the primary periodic box by whole box vectors, e.g. before computing
+    /// radial distribution functions or contacts. Works with triclinic boxes. Does nothing if the
+    /// unit cell is degenerate, e.g. from a vacuum simulation.
+    pub fn wrap_into_box(&mut self) {
+        if self.unit_cell.is_degenerate() {
+            return;
+        }
+
+        for posit in &mut self.atom_posits {
+            let frac = self
+                .unit_cell
+                .cartesian_to_fractional(*posit)
+                .map(|f| f - f.floor());
+            *posit = self.unit_cell.fractional_to_cartesian(frac);
+        }
+    }
+
+    /// Undo periodic wrapping relative to the previous frame, so atoms move continuously instead of
+    /// jumping across the box, e.g. for computing diffusion or drawing paths. Each atom is placed at
+    /// the periodic image nearest its position in `prev`. Apply to frames in order, passing the
+    /// already-unwrapped previous frame. Does nothing if the unit cell is degenerate.
+    pub fn unwrap(&mut self, prev: &DcdFrame) -> io::Result<()> {
+        if self.atom_posits.len() != prev.atom_posits.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Atom count mismatch between frames: {} and {}",
+                    self.atom_posits.len(),
+                    prev.atom_posits.len()
+                ),
+            ));
+        }
+
+        if self.unit_cell.is_degenerate() {
+            return Ok(());
+        }
+
+        for (posit, prev) in self.atom_posits.iter_mut().zip(&prev.atom_posits) {
+            let cur = self.unit_cell.cartesian_to_fractional(*posit);
+            let prev_frac = self.unit_cell.cartesian_to_fractional(*prev);
+
+            // The minimum-image displacement, then added to the previous position.
+            let mut frac = [0.; 3];
+            for i in 0..3 {
+                let d = cur[i] - prev_frac[i];
+                frac[i] = prev_frac[i] + d - d.round();
+            }
+            *posit = self.unit_cell.fractional_to_cartesian(frac);
+        }
+
+        Ok(())
+    }
+}
+
 /// Represents a molecular dynamics trajectory, and contains fields specific to DCD files.
 /// This is a minimal structure that mainly keeps track of atom positions. It doesn't include velocities,
 /// or data like energy, pressure, and temperature.
@@ -146,6 +280,63 @@ impl DcdTrajectory {
     pub fn save(&self, path: &Path) -> io::Result<()> {
         write_dcd(path, &self.frames)
     }
+
+    /// Like [`Self::save`], but only write the atoms at `indices`, in that order, e.g. to export
+    /// just the solute from a solvated system. Returns an error if any index is out of range for
+    /// any frame.
+    pub fn save_subset(&self, path: &Path, indices: &[usize]) -> io::Result<()> {
+        let frames = self
+            .frames
+            .iter()
+            .map(|frame| {
+                let atom_posits = indices
+                    .iter()
+                    .map(|&i| {
+                        frame.atom_posits.get(i).copied().ok_or_else(|| {
+                            io::Error::new(
+                                io::ErrorKind::InvalidInput,
+                                format!(
+                                    "Atom index {i} is out of range for a frame with {} atoms",
+                                    frame.atom_posits.len()
+                                ),
+                            )
+                        })
+                    })
+                    .collect::<io::Result<Vec<_>>>()?;
+
+                Ok(DcdFrame {
+                    time: frame.time,
+                    atom_posits,
+                    unit_cell: frame.unit_cell.clone(),
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        write_dcd(path, &frames)
+    }
+
+    /// Downsample to `target_frames` evenly spaced frames, always including the first and last.
+    /// Unlike a fixed stride, this keeps the endpoints, e.g. for previews. If the trajectory has
+    /// `target_frames` or fewer frames, all are kept.
+    pub fn decimate_to(&self, target_frames: usize) -> Self {
+        let n = self.frames.len();
+        if target_frames >= n {
+            return self.clone();
+        }
+        if target_frames <= 1 {
+            return Self {
+                frames: self.frames.iter().take(target_frames).cloned().collect(),
+            };
+        }
+
+        // Spacing is at least 1, as `target_frames < n`, so rounded indices don't repeat.
+        let spacing = (n - 1) as f64 / (target_frames - 1) as f64;
+        let frames = (0..target_frames)
+            .map(|i| self.frames[(i as f64 * spacing).round() as usize].clone())
+            .collect();
+
+        Self { frames }
+    }
 }
 
 /// Read frames from a DCD file, optionally filtered by a [`FrameSlice`].
@@ -161,51 +352,13 @@ pub fn read_dcd(path: &Path, slice: FrameSlice) -> io::Result<Vec<DcdFrame>> {
     let f = File::open(path)?;
     let mut r = BufReader::new(f);
 
-    let hdr = read_record(&mut r)?;
-    if hdr.len() < 84 || &hdr[0..4] != b"CORD" {
-        return Err(io::Error::new(
-            io::ErrorKind::InvalidData,
-            "Not a CORD/DCD file",
-        ));
-    }
-    let mut icntrl = [0i32; 20];
-    for (i, item) in icntrl.iter_mut().enumerate() {
-        let off = 4 + i * 4;
-        *item = i32::from_le_bytes(hdr[off..off + 4].try_into().unwrap());
-    }
-    let nset_total = icntrl[0] as usize;
-    let istart = icntrl[1] as f64;
-    let nsavc = icntrl[2] as f64;
-    let has_unitcell = icntrl[19] != 0 && icntrl[10] != 0;
-    let delta = f32::from_le_bytes(hdr[4 + 36..4 + 40].try_into().unwrap()) as f64;
+    let hdr = DcdHeader::read(&mut r)?;
 
-    skip_title_record(&mut r)?;
+    let mut frames = Vec::with_capacity(hdr.num_frames);
+    let mut unit_cell = DcdUnitCell::default();
 
-    let natom_block = read_record(&mut r)?;
-    if natom_block.len() != 4 {
-        return Err(io::Error::new(
-            io::ErrorKind::InvalidData,
-            "Unexpected NATOM block size",
-        ));
-    }
-    let n_atoms = i32::from_le_bytes(natom_block[0..4].try_into().unwrap()) as usize;
-
-    let mut frames = Vec::with_capacity(nset_total);
-    let mut unit_cell = DcdUnitCell {
-        bounds_low: Vec3 {
-            x: 0.,
-            y: 0.,
-            z: 0.,
-        },
-        bounds_high: Vec3 {
-            x: 0.,
-            y: 0.,
-            z: 0.,
-        },
-    };
-
-    for i in 0..nset_total {
-        let time = (istart + (i as f64) * nsavc) * delta;
+    for i in 0..hdr.num_frames {
+        let time = hdr.frame_time(i);
         let in_range = match slice {
             FrameSlice::Time { start, end } => {
                 start.map_or(true, |t| time >= t) && end.map_or(true, |t| time <= t)
@@ -216,55 +369,212 @@ pub fn read_dcd(path: &Path, slice: FrameSlice) -> io::Result<Vec<DcdFrame>> {
         };
 
         if !in_range {
-            // Seek past unit cell record if present.
-            if has_unitcell {
-                let len = read_u32_le(&mut r)?;
-                r.seek(SeekFrom::Current(len as i64 + 4))?;
-            }
-            // Seek past X, Y, Z records.
-            for _ in 0..3 {
-                let len = read_u32_le(&mut r)?;
-                r.seek(SeekFrom::Current(len as i64 + 4))?;
-            }
+            skip_frame(&mut r, &hdr)?;
             continue;
         }
 
-        if has_unitcell {
-            unit_cell = read_unit_cell_record(&mut r)?;
+        frames.push(read_frame(&mut r, &hdr, i, &mut unit_cell)?);
+    }
+
+    Ok(frames)
+}
+
+/// Reads frames from a DCD file one at a time, instead of loading the whole trajectory into
+/// memory. The header is parsed on open; each call to `next` then reads one frame.
+///
+/// ```ignore
+/// for frame in DcdReader::open(path)? {
+///     let frame = frame?;
+/// }
+/// ```
+pub struct DcdReader<R: Read + Seek = BufReader<File>> {
+    r: R,
+    hdr: DcdHeader,
+    next_frame: usize,
+    /// Carried between frames, for files that only store the unit cell when it changes.
+    unit_cell: DcdUnitCell,
+}
+
+impl DcdReader {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read + Seek> DcdReader<R> {
+    /// Create a reader from any seekable source, e.g. an in-memory buffer. Reads the header.
+    pub fn new(mut r: R) -> io::Result<Self> {
+        let hdr = DcdHeader::read(&mut r)?;
+
+        Ok(Self {
+            r,
+            hdr,
+            next_frame: 0,
+            unit_cell: DcdUnitCell::default(),
+        })
+    }
+
+    pub fn num_atoms(&self) -> usize {
+        self.hdr.num_atoms
+    }
+
+    /// The number of frames, as recorded in the header.
+    pub fn num_frames(&self) -> usize {
+        self.hdr.num_frames
+    }
+}
+
+impl<R: Read + Seek> Iterator for DcdReader<R> {
+    type Item = io::Result<DcdFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next_frame >= self.hdr.num_frames {
+            return None;
         }
 
-        let xb = read_record(&mut r)?;
-        let yb = read_record(&mut r)?;
-        let zb = read_record(&mut r)?;
+        let i = self.next_frame;
+        let result = read_frame(&mut self.r, &self.hdr, i, &mut self.unit_cell);
+
+        // Stop after an error; the stream position is no longer at a frame boundary.
+        self.next_frame = if result.is_ok() {
+            i + 1
+        } else {
+            self.hdr.num_frames
+        };
+
+        Some(result)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.hdr.num_frames - self.next_frame;
+        (0, Some(remaining))
+    }
+}
+
+/// The parts of the header we use for reading frames.
+struct DcdHeader {
+    num_frames: usize,
+    /// Starting timestep.
+    istart: f64,
+    /// Timesteps between frames.
+    nsavc: f64,
+    /// Timestep.
+    delta: f64,
+    has_unit_cell: bool,
+    num_atoms: usize,
+    endian: Endian,
+}
+
+impl DcdHeader {
+    /// Read the CORD, title, and NATOM records. Leaves the reader at the first frame. Detects
+    /// byte order from the first record's length marker, which is 84 for the CORD record.
+    fn read<R: Read>(r: &mut R) -> io::Result<Self> {
+        let mut len_b = [0; 4];
+        r.read_exact(&mut len_b)?;
+
+        let endian = if u32::from_le_bytes(len_b) == 84 {
+            Endian::Little
+        } else if u32::from_be_bytes(len_b) == 84 {
+            Endian::Big
+        } else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Not a CORD/DCD file",
+            ));
+        };
 
-        if xb.len() != 4 * n_atoms || yb.len() != 4 * n_atoms || zb.len() != 4 * n_atoms {
+        let hdr = read_record_payload(r, 84, endian)?;
+        if &hdr[0..4] != b"CORD" {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
-                "Coordinate block size mismatch",
+                "Not a CORD/DCD file",
             ));
         }
+        let mut icntrl = [0i32; 20];
+        for (i, item) in icntrl.iter_mut().enumerate() {
+            let off = 4 + i * 4;
+            *item = endian.i32(&hdr[off..off + 4]);
+        }
 
-        let xs = f32s_from_le_bytes(&xb)?;
-        let ys = f32s_from_le_bytes(&yb)?;
-        let zs = f32s_from_le_bytes(&zb)?;
-
-        let mut atom_posits = Vec::with_capacity(n_atoms);
-        for k in 0..n_atoms {
-            atom_posits.push(Vec3 {
-                x: xs[k],
-                y: ys[k],
-                z: zs[k],
-            });
+        skip_title_record(r, endian)?;
+
+        let natom_block = read_record(r, endian)?;
+        if natom_block.len() != 4 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Unexpected NATOM block size",
+            ));
         }
 
-        frames.push(DcdFrame {
-            time,
-            atom_posits,
-            unit_cell: unit_cell.clone(),
+        Ok(Self {
+            num_frames: icntrl[0] as usize,
+            istart: icntrl[1] as f64,
+            nsavc: icntrl[2] as f64,
+            delta: endian.f32(&hdr[4 + 36..4 + 40]) as f64,
+            has_unit_cell: icntrl[19] != 0 && icntrl[10] != 0,
+            num_atoms: endian.i32(&natom_block[0..4]) as usize,
+            endian,
+        })
+    }
+
+    fn frame_time(&self, i: usize) -> f64 {
+        (self.istart + (i as f64) * self.nsavc) * self.delta
+    }
+}
+
+/// Read the frame at the reader's position. `unit_cell` is updated if the frame has one.
+fn read_frame<R: Read>(
+    r: &mut R,
+    hdr: &DcdHeader,
+    i: usize,
+    unit_cell: &mut DcdUnitCell,
+) -> io::Result<DcdFrame> {
+    let n_atoms = hdr.num_atoms;
+
+    if hdr.has_unit_cell {
+        *unit_cell = read_unit_cell_record(r, hdr.endian)?;
+    }
+
+    let xb = read_record(r, hdr.endian)?;
+    let yb = read_record(r, hdr.endian)?;
+    let zb = read_record(r, hdr.endian)?;
+
+    if xb.len() != 4 * n_atoms || yb.len() != 4 * n_atoms || zb.len() != 4 * n_atoms {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Coordinate block size mismatch",
+        ));
+    }
+
+    let xs = f32s_from_bytes(&xb, hdr.endian)?;
+    let ys = f32s_from_bytes(&yb, hdr.endian)?;
+    let zs = f32s_from_bytes(&zb, hdr.endian)?;
+
+    let mut atom_posits = Vec::with_capacity(n_atoms);
+    for k in 0..n_atoms {
+        atom_posits.push(Vec3 {
+            x: xs[k],
+            y: ys[k],
+            z: zs[k],
         });
     }
 
-    Ok(frames)
+    Ok(DcdFrame {
+        time: hdr.frame_time(i),
+        atom_posits,
+        unit_cell: unit_cell.clone(),
+    })
+}
+
+/// Seek past the frame at the reader's position without decoding it.
+fn skip_frame<R: Read + Seek>(r: &mut R, hdr: &DcdHeader) -> io::Result<()> {
+    // Unit cell record if present, then X, Y, Z records.
+    let n_records = if hdr.has_unit_cell { 4 } else { 3 };
+    for _ in 0..n_records {
+        let len = read_u32(r, hdr.endian)?;
+        r.seek(SeekFrom::Current(len as i64 + 4))?;
+    }
+    Ok(())
 }
 
 /// Write frames to a DCD file, creating it if it does not exist or appending
@@ -278,6 +588,10 @@ pub fn read_dcd(path: &Path, slice: FrameSlice) -> io::Result<Vec<DcdFrame>> {
 /// (unlike TRR which embeds a timestamp per frame).  For newly created files
 /// `istart = 0`, `nsavc = 1`, and `delta` is derived from the first two frames'
 /// time difference so that frame times round-trip correctly.
+///
+/// Unit cell records are only written if at least one frame has a non-degenerate box (See
+/// [`DcdUnitCell::is_degenerate`]); trajectories from vacuum simulations are written with
+/// positions only. When appending, we follow the existing file.
 pub fn write_dcd(path: &Path, frames: &[DcdFrame]) -> io::Result<()> {
     if frames.is_empty() {
         return Ok(());
@@ -301,6 +615,7 @@ pub fn write_dcd(path: &Path, frames: &[DcdFrame]) -> io::Result<()> {
         .open(path)?;
 
     let file_len = f.metadata()?.len();
+    let has_unit_cell = frames.iter().any(|fr| !fr.unit_cell.is_degenerate());
 
     if file_len == 0 {
         // New file: write CORD header, title, and NATOM.
@@ -318,8 +633,9 @@ pub fn write_dcd(path: &Path, frames: &[DcdFrame]) -> io::Result<()> {
         icntrl[0] = nsets;
         icntrl[1] = 0; // istart
         icntrl[2] = 1; // nsavc
-        // icntrl[10] = 1: extra block present (unit cell); icntrl[19] = 1: has unit cell.
-        icntrl[10] = 1;
+        // icntrl[10] = 1: extra block present (unit cell); icntrl[19] = 1: CHARMM-style file. We
+        // only write the unit cell if at least one frame has a non-degenerate box.
+        icntrl[10] = has_unit_cell as i32;
         icntrl[19] = 1;
         for v in icntrl {
             header.extend_from_slice(&v.to_le_bytes());
@@ -345,7 +661,13 @@ pub fn write_dcd(path: &Path, frames: &[DcdFrame]) -> io::Result<()> {
     } else {
         // Existing file: verify header and NATOM, seek to end, then update NSET.
         f.seek(SeekFrom::Start(0))?;
-        let l1 = read_u32_le(&mut f)?;
+        let l1 = read_u32(&mut f, Endian::Little)?;
+        if l1 == 84_u32.swap_bytes() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "appending to big-endian DCD files is unsupported",
+            ));
+        }
         if !(84..=1024 * 1024).contains(&l1) {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
@@ -354,7 +676,7 @@ pub fn write_dcd(path: &Path, frames: &[DcdFrame]) -> io::Result<()> {
         }
         let mut hdr = vec![0u8; l1 as usize];
         f.read_exact(&mut hdr)?;
-        let l1e = read_u32_le(&mut f)?;
+        let l1e = read_u32(&mut f, Endian::Little)?;
         if l1e != l1 || &hdr[0..4] != b"CORD" {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
@@ -369,16 +691,17 @@ pub fn write_dcd(path: &Path, frames: &[DcdFrame]) -> io::Result<()> {
         }
         let cur_nset = icntrl[0];
 
-        if icntrl[19] == 0 || icntrl[10] == 0 {
+        let existing_has_cell = icntrl[19] != 0 && icntrl[10] != 0;
+        if has_unit_cell && !existing_has_cell {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
                 "existing DCD does not have unit cell blocks enabled",
             ));
         }
 
-        skip_title_record(&mut f)?;
+        skip_title_record(&mut f, Endian::Little)?;
 
-        let l3 = read_u32_le(&mut f)?;
+        let l3 = read_u32(&mut f, Endian::Little)?;
         if l3 != 4 {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
@@ -388,7 +711,7 @@ pub fn write_dcd(path: &Path, frames: &[DcdFrame]) -> io::Result<()> {
         let mut nb = [0u8; 4];
         f.read_exact(&mut nb)?;
         let natom_existing = i32::from_le_bytes(nb) as usize;
-        let l3e = read_u32_le(&mut f)?;
+        let l3e = read_u32(&mut f, Endian::Little)?;
         if l3e != l3 {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
@@ -403,25 +726,7 @@ pub fn write_dcd(path: &Path, frames: &[DcdFrame]) -> io::Result<()> {
         }
 
         f.seek(SeekFrom::End(0))?;
-
-        let mut xs = vec![0.; n_atoms];
-        let mut ys = vec![0.; n_atoms];
-        let mut zs = vec![0.; n_atoms];
-        for frame in frames {
-            for (i, p) in frame.atom_posits.iter().enumerate() {
-                xs[i] = p.x;
-                ys[i] = p.y;
-                zs[i] = p.z;
-            }
-            let xb = unsafe { core::slice::from_raw_parts(xs.as_ptr() as *const u8, xs.len() * 4) };
-            let yb = unsafe { core::slice::from_raw_parts(ys.as_ptr() as *const u8, ys.len() * 4) };
-            let zb = unsafe { core::slice::from_raw_parts(zs.as_ptr() as *const u8, zs.len() * 4) };
-
-            write_unit_cell_record(&mut f, &frame.unit_cell)?;
-            write_record(&mut f, xb)?;
-            write_record(&mut f, yb)?;
-            write_record(&mut f, zb)?;
-        }
+        write_frames(&mut f, frames, existing_has_cell)?;
 
         let new_nset = cur_nset
             .checked_add(frames.len() as i32)
@@ -436,6 +741,15 @@ pub fn write_dcd(path: &Path, frames: &[DcdFrame]) -> io::Result<()> {
     }
 
     // New file: write all frames.
+    write_frames(&mut f, frames, has_unit_cell)?;
+
+    f.flush()
+}
+
+/// Write frame records: The unit cell, if `with_unit_cell`, then X, Y, and Z coordinates.
+fn write_frames<W: Write>(w: &mut W, frames: &[DcdFrame], with_unit_cell: bool) -> io::Result<()> {
+    let n_atoms = frames[0].atom_posits.len();
+
     let mut xs = vec![0.; n_atoms];
     let mut ys = vec![0.; n_atoms];
     let mut zs = vec![0.; n_atoms];
@@ -448,13 +762,16 @@ pub fn write_dcd(path: &Path, frames: &[DcdFrame]) -> io::Result<()> {
         let xb = unsafe { core::slice::from_raw_parts(xs.as_ptr() as *const u8, xs.len() * 4) };
         let yb = unsafe { core::slice::from_raw_parts(ys.as_ptr() as *const u8, ys.len() * 4) };
         let zb = unsafe { core::slice::from_raw_parts(zs.as_ptr() as *const u8, zs.len() * 4) };
-        write_unit_cell_record(&mut f, &frame.unit_cell)?;
-        write_record(&mut f, xb)?;
-        write_record(&mut f, yb)?;
-        write_record(&mut f, zb)?;
+
+        if with_unit_cell {
+            write_unit_cell_record(w, &frame.unit_cell)?;
+        }
+        write_record(w, xb)?;
+        write_record(w, yb)?;
+        write_record(w, zb)?;
     }
 
-    f.flush()
+    Ok(())
 }
 
 /// A wrapper for writing a DCD record: Payload sandwiched by length.
@@ -466,17 +783,59 @@ fn write_record<W: Write>(w: &mut W, payload: &[u8]) -> io::Result<()> {
     w.write_all(&len.to_le_bytes())
 }
 
-fn read_u32_le<R: Read>(r: &mut R) -> io::Result<u32> {
+/// Byte order of a DCD file. We write little-endian files, but read either.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Endian {
+    Little,
+    Big,
+}
+
+impl Endian {
+    /// `b` must be 4 bytes long.
+    fn u32(self, b: &[u8]) -> u32 {
+        let b = b.try_into().unwrap();
+        match self {
+            Self::Little => u32::from_le_bytes(b),
+            Self::Big => u32::from_be_bytes(b),
+        }
+    }
+
+    /// `b` must be 4 bytes long.
+    fn i32(self, b: &[u8]) -> i32 {
+        self.u32(b) as i32
+    }
+
+    /// `b` must be 4 bytes long.
+    fn f32(self, b: &[u8]) -> f32 {
+        f32::from_bits(self.u32(b))
+    }
+
+    /// `b` must be 8 bytes long.
+    fn f64(self, b: &[u8]) -> f64 {
+        let b = b.try_into().unwrap();
+        match self {
+            Self::Little => f64::from_le_bytes(b),
+            Self::Big => f64::from_be_bytes(b),
+        }
+    }
+}
+
+fn read_u32<R: Read>(r: &mut R, endian: Endian) -> io::Result<u32> {
     let mut b = [0; 4];
     r.read_exact(&mut b)?;
-    Ok(u32::from_le_bytes(b))
+    Ok(endian.u32(&b))
+}
+
+fn read_record<R: Read>(r: &mut R, endian: Endian) -> io::Result<Vec<u8>> {
+    let len = read_u32(r, endian)? as usize;
+    read_record_payload(r, len, endian)
 }
 
-fn read_record<R: Read>(r: &mut R) -> io::Result<Vec<u8>> {
-    let len = read_u32_le(r)? as usize;
+/// Read the remainder of a record whose leading length marker has already been read.
+fn read_record_payload<R: Read>(r: &mut R, len: usize, endian: Endian) -> io::Result<Vec<u8>> {
     let mut payload = vec![0u8; len];
     r.read_exact(&mut payload)?;
-    let len_end = read_u32_le(r)? as usize;
+    let len_end = read_u32(r, endian)? as usize;
     if len_end != len {
         return Err(io::Error::new(
             io::ErrorKind::InvalidData,
@@ -486,7 +845,7 @@ fn read_record<R: Read>(r: &mut R) -> io::Result<Vec<u8>> {
     Ok(payload)
 }
 
-fn f32s_from_le_bytes(b: &[u8]) -> io::Result<Vec<f32>> {
+fn f32s_from_bytes(b: &[u8], endian: Endian) -> io::Result<Vec<f32>> {
     if !b.len().is_multiple_of(4) {
         return Err(io::Error::new(
             io::ErrorKind::InvalidData,
@@ -498,7 +857,7 @@ fn f32s_from_le_bytes(b: &[u8]) -> io::Result<Vec<f32>> {
     let mut out = Vec::with_capacity(n);
     for i in 0..n {
         let j = 4 * i;
-        out.push(f32::from_le_bytes(b[j..j + 4].try_into().unwrap()));
+        out.push(endian.f32(&b[j..j + 4]));
     }
     Ok(out)
 }
@@ -515,8 +874,8 @@ fn write_unit_cell_record<W: Write>(w: &mut W, unit_cell: &DcdUnitCell) -> io::R
     write_record(w, &payload)
 }
 
-fn read_unit_cell_record<R: Read>(r: &mut R) -> io::Result<DcdUnitCell> {
-    let b = read_record(r)?;
+fn read_unit_cell_record<R: Read>(r: &mut R, endian: Endian) -> io::Result<DcdUnitCell> {
+    let b = read_record(r, endian)?;
     if b.len() != 48 {
         return Err(io::Error::new(
             io::ErrorKind::InvalidData,
@@ -527,21 +886,21 @@ fn read_unit_cell_record<R: Read>(r: &mut R) -> io::Result<DcdUnitCell> {
     let mut six = [0f64; 6];
     for (i, v) in six.iter_mut().enumerate() {
         let j = i * 8;
-        *v = f64::from_le_bytes(b[j..j + 8].try_into().unwrap());
+        *v = endian.f64(&b[j..j + 8]);
     }
 
     Ok(DcdUnitCell::from_dcd_six(six))
 }
 
-fn skip_title_record<R: Read>(r: &mut R) -> io::Result<()> {
-    let b = read_record(r)?;
+fn skip_title_record<R: Read>(r: &mut R, endian: Endian) -> io::Result<()> {
+    let b = read_record(r, endian)?;
     if b.len() < 4 {
         return Err(io::Error::new(
             io::ErrorKind::InvalidData,
             "title record too short",
         ));
     }
-    let ntitle = i32::from_le_bytes(b[0..4].try_into().unwrap());
+    let ntitle = endian.i32(&b[0..4]);
     if ntitle < 0 {
         return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid NTITLE"));
     }
@@ -0,0 +1,162 @@
+//! Circular molecular fingerprints, for similarity search, clustering, and deduplicating molecule
+//! libraries. This is similar to ECFP / RDKit's Morgan fingerprints, but the bit assignments are
+//! specific to this library; don't compare fingerprints generated by other software with these.
+
+use std::collections::VecDeque;
+
+use na_seq::Element;
+
+use crate::{AtomGeneric, BondGeneric, BondType, graph::bond_adjacency};
+
+/// FNV-1a; used instead of the std hasher so fingerprints are stable across Rust versions.
+fn hash(vals: &[u64]) -> u64 {
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    for v in vals {
+        for b in v.to_le_bytes() {
+            h ^= b as u64;
+            h = h.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    h
+}
+
+fn bond_code(bond_type: BondType) -> u64 {
+    match bond_type {
+        BondType::Double => 2,
+        BondType::Triple => 3,
+        BondType::Quadruple => 4,
+        BondType::Aromatic | BondType::Delocalized => 5,
+        _ => 1,
+    }
+}
+
+/// Whether `to` is reachable from `from` without traversing the direct edge between them; i.e.
+/// whether that bond is part of a ring.
+fn in_ring(adj: &[Vec<(usize, u64)>], from: usize, to: usize) -> bool {
+    let mut visited = vec![false; adj.len()];
+    visited[from] = true;
+    let mut queue = VecDeque::from([from]);
+
+    while let Some(i) = queue.pop_front() {
+        for &(j, _) in &adj[i] {
+            if i == from && j == to {
+                continue;
+            }
+            if j == to {
+                return true;
+            }
+            if !visited[j] {
+                visited[j] = true;
+                queue.push_back(j);
+            }
+        }
+    }
+    false
+}
+
+/// Compute an ECFP-like circular fingerprint. Each heavy atom starts with an identifier hashed from
+/// its element, heavy-atom degree, hydrogen count, and ring membership. Each iteration, up to
+/// `radius`, combines an atom's identifier with its neighbors' and the connecting bond types. All
+/// identifiers are folded into a bit vector of `n_bits` bits, stored as 64-bit words.
+///
+/// `radius` of 2 corresponds to ECFP4.
+pub fn morgan_fingerprint(
+    atoms: &[AtomGeneric],
+    bonds: &[BondGeneric],
+    radius: u32,
+    n_bits: usize,
+) -> Vec<u64> {
+    let mut result = vec![0; n_bits.div_ceil(64)];
+    if n_bits == 0 {
+        return result;
+    }
+
+    // Heavy atoms only; hydrogens are included as a count on their parent atom.
+    let heavy: Vec<usize> = (0..atoms.len())
+        .filter(|&i| atoms[i].element != Element::Hydrogen)
+        .collect();
+
+    // Atom index to heavy atom index; `None` for hydrogens.
+    let mut heavy_i = vec![None; atoms.len()];
+    for (i, &atom_i) in heavy.iter().enumerate() {
+        heavy_i[atom_i] = Some(i);
+    }
+
+    let full_adj = bond_adjacency(atoms, bonds);
+    let mut adj = vec![Vec::new(); heavy.len()];
+    let mut h_count = vec![0; heavy.len()];
+
+    for (i, &atom_i) in heavy.iter().enumerate() {
+        for &(j, bond_type) in &full_adj[atom_i] {
+            match heavy_i[j] {
+                Some(j) => adj[i].push((j, bond_code(bond_type))),
+                None => h_count[i] += 1,
+            }
+        }
+    }
+
+    let mut ids: Vec<u64> = (0..heavy.len())
+        .map(|i| {
+            let ring = adj[i].iter().any(|&(j, _)| in_ring(&adj, i, j));
+            let el = atoms[heavy[i]].element.to_letter();
+            hash(&[
+                hash(&el.bytes().map(u64::from).collect::<Vec<_>>()),
+                adj[i].len() as u64,
+                h_count[i],
+                ring as u64,
+            ])
+        })
+        .collect();
+
+    let mut set_bit = |id: u64| {
+        let bit = (id % n_bits as u64) as usize;
+        result[bit / 64] |= 1 << (bit % 64);
+    };
+
+    for &id in &ids {
+        set_bit(id);
+    }
+
+    for iter in 1..=radius {
+        let next: Vec<u64> = (0..heavy.len())
+            .map(|i| {
+                let mut env: Vec<(u64, u64)> =
+                    adj[i].iter().map(|&(j, code)| (code, ids[j])).collect();
+                env.sort_unstable();
+
+                let mut vals = vec![iter as u64, ids[i]];
+                for (code, id) in env {
+                    vals.push(code);
+                    vals.push(id);
+                }
+                hash(&vals)
+            })
+            .collect();
+
+        for &id in &next {
+            set_bit(id);
+        }
+        ids = next;
+    }
+
+    result
+}
+
+/// Tanimoto (Jaccard) similarity between two fingerprints: The number of bits set in both, divided
+/// by the number set in either. 1.0 for identical fingerprints, including two empty ones.
+pub fn tanimoto(a: &[u64], b: &[u64]) -> f64 {
+    let mut both = 0;
+    let mut either = 0;
+
+    for i in 0..a.len().max(b.len()) {
+        let va = a.get(i).copied().unwrap_or_default();
+        let vb = b.get(i).copied().unwrap_or_default();
+        both += (va & vb).count_ones();
+        either += (va | vb).count_ones();
+    }
+
+    if either == 0 {
+        return 1.;
+    }
+    both as f64 / either as f64
+}
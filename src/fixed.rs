@@ -0,0 +1,120 @@
+//! Helpers for reading and writing fixed-width, column-based records, as used by PDB, GRO, and
+//! Amber inpcrd files. Columns are 0-based, and ranges are half-open: `start..end`. Note that
+//! format specs, e.g. for PDB, generally use 1-based, inclusive column numbers.
+//!
+//! Reading by column, vice splitting on whitespace, is required to handle values that fill their
+//! field, e.g. large negative coordinates, which leave no space between adjacent fields.
+
+use std::{
+    io::{self, ErrorKind},
+    str::FromStr,
+};
+
+/// Read a field, trimmed. Empty if the line ends before `start`; truncated if it ends before `end`.
+pub fn read_field(line: &str, start: usize, end: usize) -> &str {
+    let end = end.min(line.len());
+    if start >= end {
+        return "";
+    }
+    line.get(start..end).unwrap_or_default().trim()
+}
+
+/// Read and parse a field. `name` is used in the error message.
+pub fn parse_field<T: FromStr>(line: &str, start: usize, end: usize, name: &str) -> io::Result<T> {
+    let field = read_field(line, start, end);
+    field.parse().map_err(|_| {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            format!("Could not parse {name} from \"{field}\" (columns {start}..{end})"),
+        )
+    })
+}
+
+/// Like [`parse_field`], but returns None if the field is empty.
+pub fn parse_field_opt<T: FromStr>(
+    line: &str,
+    start: usize,
+    end: usize,
+    name: &str,
+) -> io::Result<Option<T>> {
+    if read_field(line, start, end).is_empty() {
+        return Ok(None);
+    }
+    parse_field(line, start, end, name).map(Some)
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Justify {
+    Left,
+    Right,
+}
+
+/// Builds a fixed-width line, field by field. Fields may be placed in any order; gaps are filled
+/// with spaces.
+#[derive(Clone, Debug, Default)]
+pub struct FixedLine {
+    line: Vec<u8>,
+}
+
+impl FixedLine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn put(&mut self, start: usize, end: usize, val: &str, justify: Justify) {
+        if self.line.len() < end {
+            self.line.resize(end, b' ');
+        }
+
+        let width = end - start;
+        let padded = match justify {
+            Justify::Left => format!("{val:<width$}"),
+            Justify::Right => format!("{val:>width$}"),
+        };
+        self.line[start..end].copy_from_slice(&padded.as_bytes()[..width]);
+    }
+
+    /// Place a string. It's truncated if it doesn't fit. Non-ASCII characters are replaced.
+    pub fn str(&mut self, start: usize, end: usize, val: &str, justify: Justify) -> &mut Self {
+        let val: String = val
+            .chars()
+            .map(|c| if c.is_ascii() { c } else { '?' })
+            .take(end - start)
+            .collect();
+        self.put(start, end, &val, justify);
+        self
+    }
+
+    /// Place a right-justified integer. Errors if it doesn't fit.
+    pub fn int(&mut self, start: usize, end: usize, val: i64) -> io::Result<&mut Self> {
+        self.number(start, end, &val.to_string())
+    }
+
+    /// Place a right-justified float, with a fixed number of decimal places. Errors if it doesn't
+    /// fit.
+    pub fn float(
+        &mut self,
+        start: usize,
+        end: usize,
+        val: f64,
+        precision: usize,
+    ) -> io::Result<&mut Self> {
+        self.number(start, end, &format!("{val:.precision$}"))
+    }
+
+    fn number(&mut self, start: usize, end: usize, val: &str) -> io::Result<&mut Self> {
+        if val.len() > end - start {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("{val} doesn't fit in columns {start}..{end}"),
+            ));
+        }
+        self.put(start, end, val, Justify::Right);
+        Ok(self)
+    }
+
+    pub fn as_str(&self) -> &str {
+        // We only write ASCII.
+        str::from_utf8(&self.line).unwrap()
+    }
+}
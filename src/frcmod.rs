@@ -6,7 +6,8 @@ use std::{
 };
 
 use crate::md_params::{
-    AngleBendingParams, BondStretchingParams, DihedralParams, ForceFieldParamsVec, MassParams,
+    AngleBendingParams, BondStretchingParams, CmapData, DihedralParams, ForceFieldParamsVec,
+    LjParams, MassParams,
 };
 
 #[derive(Debug, PartialEq)]
@@ -18,6 +19,7 @@ enum Section {
     Dihedral,
     Improper,
     Nonbond,
+    Cmap,
 }
 
 impl ForceFieldParamsVec {
@@ -28,6 +30,7 @@ impl ForceFieldParamsVec {
         let lines: Vec<&str> = text.lines().collect();
 
         let mut section = Section::Remark;
+        let mut cmap_lines = Vec::new();
 
         for line in lines {
             let line = line.trim();
@@ -60,9 +63,17 @@ impl ForceFieldParamsVec {
                     section = Section::Nonbond;
                     continue;
                 }
+                "CMAP" => {
+                    section = Section::Cmap;
+                    continue;
+                }
                 _ => {}
             }
 
+            if line.starts_with("%FLAG CMAP") {
+                section = Section::Cmap;
+            }
+
             match section {
                 Section::Remark => {
                     result.remarks.push(line.to_owned());
@@ -82,10 +93,15 @@ impl ForceFieldParamsVec {
                 Section::Improper => {
                     result.improper.push(DihedralParams::from_line(line)?.0);
                 }
-                Section::Nonbond => { /* skip or extend */ }
+                Section::Nonbond => {
+                    result.lennard_jones.push(LjParams::from_line(line)?);
+                }
+                Section::Cmap => cmap_lines.push(line),
             }
         }
 
+        result.cmap = CmapData::from_lines(&cmap_lines)?;
+
         // for r in &result.dihedral {
         //     println!("Dihe: {:?}", r);
         // }
@@ -97,136 +113,209 @@ impl ForceFieldParamsVec {
         Ok(result)
     }
 
-    /// Write to file
-    pub fn save_frcmod(&self, path: &Path) -> io::Result<()> {
-        let mut f = File::create(path)?;
+    /// Create a string in the FRCMOD format. Column widths and float precisions match the output
+    /// of Antechamber's `parmchk2`, e.g. `ca-c3-c1   64.680     112.590`. Atom types are padded
+    /// to 2 characters, e.g. `c -ca`.
+    pub fn to_frcmod(&self) -> String {
+        let mut result = String::new();
 
         for r in &self.remarks {
-            writeln!(f, "{r}")?;
+            result.push_str(&format!("{r}\n"));
         }
+        result.push('\n');
 
-        writeln!(f)?;
-
-        writeln!(f, "MASS")?;
+        result.push_str("MASS\n");
         for m in &self.mass {
-            if let Some(c) = &m.comment {
-                writeln!(f, "{} {:>10.4} ! {}", m.atom_type, m.mass, c)?;
-            } else {
-                writeln!(f, "{} {:>10.4}", m.atom_type, m.mass)?;
-            }
+            let line = format!("{:<2} {:.3}", m.atom_type, m.mass);
+            push_line(&mut result, line, &m.comment, "    ! ");
+        }
+        result.push('\n');
+
+        result.push_str("BOND\n");
+        self.push_bond_lines(&mut result);
+        result.push('\n');
+
+        result.push_str("ANGLE\n");
+        self.push_angle_lines(&mut result);
+        result.push('\n');
+
+        result.push_str("DIHE\n");
+        self.push_dihedral_lines(&mut result);
+        result.push('\n');
+
+        result.push_str("IMPROPER\n");
+        self.push_improper_lines(&mut result);
+        result.push('\n');
+
+        result.push_str("NONBON\n");
+        self.push_lj_lines(&mut result);
+        result.push('\n');
+
+        if !self.cmap.is_empty() {
+            result.push_str("CMAP\n");
+            self.push_cmap_lines(&mut result);
+            result.push('\n');
         }
-        writeln!(f)?;
 
-        writeln!(f, "BOND")?;
+        result
+    }
+
+    /// Write to file
+    pub fn save_frcmod(&self, path: &Path) -> io::Result<()> {
+        let mut f = File::create(path)?;
+        write!(f, "{}", self.to_frcmod())
+    }
+
+    /// todo: Sort out the syntax for loading from different sources.
+    pub fn load_frcmod(path: &Path) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+
+        let data_str: String = String::from_utf8(buffer)
+            .map_err(|_| io::Error::new(ErrorKind::InvalidData, "Invalid UTF8"))?;
+
+        Self::from_frcmod(&data_str)
+    }
+
+    // The line writers below are shared by the frcmod and dat formats.
+
+    pub(crate) fn push_bond_lines(&self, buf: &mut String) {
         for b in &self.bond {
-            if let Some(c) = &b.comment {
-                writeln!(
-                    f,
-                    "{}-{} {:>8.3} {:>8.3} {}",
-                    b.atom_types.0, b.atom_types.1, b.k_b, b.r_0, c
-                )?;
-            } else {
-                writeln!(
-                    f,
-                    "{}-{} {:>8.3} {:>8.3}",
-                    b.atom_types.0, b.atom_types.1, b.k_b, b.r_0
-                )?;
-            }
+            let names = type_names(&[&b.atom_types.0, &b.atom_types.1]);
+            let line = format!("{names:<5}{:>8.2}{:>9.4}", b.k_b, b.r_0);
+            push_line(buf, line, &b.comment, "   ");
         }
-        writeln!(f)?;
+    }
 
-        writeln!(f, "ANGLE")?;
+    pub(crate) fn push_angle_lines(&self, buf: &mut String) {
         for a in &self.angle {
-            if let Some(c) = &a.comment {
-                writeln!(
-                    f,
-                    "{}-{}-{} {:>8.3} {:>8.3} {}",
-                    a.atom_types.0,
-                    a.atom_types.1,
-                    a.atom_types.2,
-                    a.k,
-                    a.theta_0.to_degrees(),
-                    c
-                )?;
-            } else {
-                writeln!(
-                    f,
-                    "{}-{}-{} {:>8.3} {:>8.3}",
-                    a.atom_types.0,
-                    a.atom_types.1,
-                    a.atom_types.2,
-                    a.k,
-                    a.theta_0.to_degrees()
-                )?;
-            }
+            let names = type_names(&[&a.atom_types.0, &a.atom_types.1, &a.atom_types.2]);
+            let line = format!("{names:<8}{:>9.3}{:>12.3}", a.k, a.theta_0.to_degrees());
+            push_line(buf, line, &a.comment, "   ");
         }
-        writeln!(f)?;
+    }
 
-        writeln!(f, "DIHE")?;
-        for d in &self.dihedral {
-            let names = format!(
-                "{}-{}-{}-{}",
-                d.atom_types.0, d.atom_types.1, d.atom_types.2, d.atom_types.3
-            );
-            let mut line = format!(
-                "{} {:>3} {:>8.3} {:>8.3} {:>8.3}",
-                names,
+    /// Consecutive terms for the same atom types are written with negative periodicity on all
+    /// but the last, which tells Amber programs that more terms follow.
+    pub(crate) fn push_dihedral_lines(&self, buf: &mut String) {
+        for (i, d) in self.dihedral.iter().enumerate() {
+            let names = type_names(&[
+                &d.atom_types.0,
+                &d.atom_types.1,
+                &d.atom_types.2,
+                &d.atom_types.3,
+            ]);
+
+            let more_terms = self
+                .dihedral
+                .get(i + 1)
+                .is_some_and(|next| next.atom_types == d.atom_types);
+            let periodicity = if more_terms {
+                -(d.periodicity as f32)
+            } else {
+                d.periodicity as f32
+            };
+
+            let line = format!(
+                "{names:<11}{:>4}{:>9.3}{:>14.3}{:>16.3}",
                 d.divider,
                 d.barrier_height,
                 d.phase.to_degrees(),
-                d.periodicity
+                periodicity
             );
-            if let Some(n) = &d.comment {
-                line.push_str(&format!("  {n}"));
-            }
-            writeln!(f, "{line}")?;
+            push_line(buf, line, &with_penalty(d), "      ");
         }
-        writeln!(f)?;
+    }
 
-        writeln!(f, "IMPROPER")?;
+    pub(crate) fn push_improper_lines(&self, buf: &mut String) {
         for imp in &self.improper {
-            let names = format!(
-                "{}-{}-{}-{}",
-                imp.atom_types.0, imp.atom_types.1, imp.atom_types.2, imp.atom_types.3
+            let names = type_names(&[
+                &imp.atom_types.0,
+                &imp.atom_types.1,
+                &imp.atom_types.2,
+                &imp.atom_types.3,
+            ]);
+            let line = format!(
+                "{names:<11}{:>12.1}{:>15.1}{:>12.1}",
+                imp.barrier_height,
+                imp.phase.to_degrees(),
+                imp.periodicity as f32
             );
-            if let Some(c) = &imp.comment {
-                writeln!(
-                    f,
-                    "{} {:>8.3} {:>8.3} {:>8.3} {}",
-                    names,
-                    imp.barrier_height,
-                    imp.phase.to_degrees(),
-                    imp.periodicity,
-                    c
-                )?;
-            } else {
-                writeln!(
-                    f,
-                    "{} {:>8.3} {:>8.3} {:>8.3}",
-                    names,
-                    imp.barrier_height,
-                    imp.phase.to_degrees(),
-                    imp.periodicity
-                )?;
-            }
+            push_line(buf, line, &with_penalty(imp), "          ");
         }
-        writeln!(f)?;
+    }
+
+    pub(crate) fn push_cmap_lines(&self, buf: &mut String) {
+        for (i, cmap) in self.cmap.iter().enumerate() {
+            buf.push_str(&format!("%FLAG CMAP_COUNT {:>6}\n", i + 1));
+            buf.push_str(&format!("%FLAG CMAP_TITLE  {}\n", cmap.title));
+
+            buf.push_str(&format!("%FLAG CMAP_RESLIST {:>4}\n", cmap.residues.len()));
+            buf.push_str(&format!("{}\n", cmap.residues.join(" ")));
+
+            if !cmap.atom_types.is_empty() {
+                buf.push_str(&format!(
+                    "%FLAG CMAP_ATOMLIST {:>3}\n",
+                    cmap.atom_types.len()
+                ));
+                buf.push_str(&format!("{}\n", cmap.atom_types.join(" ")));
+            }
 
-        // todo: Placeholder. A/R.
-        writeln!(f, "NONBON")?;
+            buf.push_str(&format!("%FLAG CMAP_RESOLUTION {:>4}\n", cmap.resolution));
+            buf.push_str("%FLAG CMAP_PARAMETER\n");
+            for row in cmap.grid.chunks(8) {
+                for v in row {
+                    buf.push_str(&format!("{v:>11.5}"));
+                }
+                buf.push('\n');
+            }
+        }
+    }
 
-        Ok(())
+    /// R star and ε, as in the source files; we store σ.
+    pub(crate) fn push_lj_lines(&self, buf: &mut String) {
+        for lj in &self.lennard_jones {
+            buf.push_str(&format!(
+                "  {:<2}{:>16.4}{:>8.4}\n",
+                lj.atom_type,
+                lj.r_star(),
+                lj.eps
+            ));
+        }
     }
+}
 
-    /// todo: Sort out the syntax for loading from different sources.
-    pub fn load_frcmod(path: &Path) -> io::Result<Self> {
-        let mut file = File::open(path)?;
-        let mut buffer = Vec::new();
-        file.read_to_end(&mut buffer)?;
+/// Join atom types with `-`, padding each to 2 characters, e.g. `c -ca`.
+fn type_names(types: &[&str]) -> String {
+    types
+        .iter()
+        .map(|t| format!("{t:<2}"))
+        .collect::<Vec<_>>()
+        .join("-")
+}
 
-        let data_str: String = String::from_utf8(buffer)
-            .map_err(|_| io::Error::new(ErrorKind::InvalidData, "Invalid UTF8"))?;
+/// A dihedral's comment, with its penalty score re-appended in the form `parmchk2` writes.
+fn with_penalty(d: &DihedralParams) -> Option<String> {
+    let Some(score) = d.penalty_score else {
+        return d.comment.clone();
+    };
 
-        Self::from_frcmod(&data_str)
+    let penalty = format!("penalty score={score:>5.1}");
+    match &d.comment {
+        Some(c) if !c.is_empty() => Some(format!("{c}, {penalty}")),
+        _ => Some(penalty),
+    }
+}
+
+/// Add a parameter line, followed by its comment (if present and non-empty) after `sep`.
+pub(crate) fn push_line(buf: &mut String, line: String, comment: &Option<String>, sep: &str) {
+    buf.push_str(&line);
+    if let Some(c) = comment
+        && !c.is_empty()
+    {
+        buf.push_str(sep);
+        buf.push_str(c);
     }
+    buf.push('\n');
 }
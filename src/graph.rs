@@ -0,0 +1,195 @@
+//! Helpers that operate on the bond graph of a molecule, e.g. for separating fragments, or grouping
+//! residues into chains.
+
+use std::collections::{HashMap, HashSet};
+
+use na_seq::AtomTypeInRes;
+
+use crate::{AtomGeneric, BondGeneric, BondType, ChainGeneric, ResidueGeneric};
+
+/// Maximum distance between the C of one amino acid and the N of the next for us to consider them
+/// linked by a peptide bond, in Å. The bond length is about 1.33 Å.
+const PEPTIDE_BOND_MAX_LEN: f64 = 2.0;
+
+/// The bond graph as an adjacency list: For each atom index, the indices of atoms bonded to it,
+/// and the bond type. Bonds are resolved from serial numbers; ones that reference missing atoms
+/// are ignored.
+pub(crate) fn bond_adjacency(
+    atoms: &[AtomGeneric],
+    bonds: &[BondGeneric],
+) -> Vec<Vec<(usize, BondType)>> {
+    let sn_to_i: HashMap<u32, usize> = atoms
+        .iter()
+        .enumerate()
+        .map(|(i, a)| (a.serial_number, i))
+        .collect();
+
+    let mut result = vec![Vec::new(); atoms.len()];
+    for bond in bonds {
+        if let (Some(&i0), Some(&i1)) = (sn_to_i.get(&bond.atom_0_sn), sn_to_i.get(&bond.atom_1_sn))
+        {
+            result[i0].push((i1, bond.bond_type));
+            result[i1].push((i0, bond.bond_type));
+        }
+    }
+
+    result
+}
+
+/// Group atoms into connected fragments, using bonds. Returns atom indices for each fragment, in
+/// order of each fragment's first atom. Bonds that reference missing atoms are ignored.
+pub fn connected_components(atoms: &[AtomGeneric], bonds: &[BondGeneric]) -> Vec<Vec<usize>> {
+    let adj = bond_adjacency(atoms, bonds);
+
+    let mut result = Vec::new();
+    let mut visited = vec![false; atoms.len()];
+
+    for start in 0..atoms.len() {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+
+        let mut component = Vec::new();
+        let mut stack = vec![start];
+        while let Some(i) = stack.pop() {
+            component.push(i);
+            for &(j, _) in &adj[i] {
+                if !visited[j] {
+                    visited[j] = true;
+                    stack.push(j);
+                }
+            }
+        }
+
+        component.sort_unstable();
+        result.push(component);
+    }
+
+    result
+}
+
+/// Extract the largest connected fragment, e.g. to remove counter-ions, waters, and other
+/// fragments from a ligand file. Atoms retain their order, and are renumbered with serial numbers
+/// starting at 1; bonds are updated to match. If there's a tie, the first fragment is used.
+pub fn largest_component(
+    atoms: &[AtomGeneric],
+    bonds: &[BondGeneric],
+) -> (Vec<AtomGeneric>, Vec<BondGeneric>) {
+    let components = connected_components(atoms, bonds);
+
+    let Some(largest) = components.iter().rev().max_by_key(|c| c.len()) else {
+        return (Vec::new(), Vec::new());
+    };
+
+    let mut sn_map = HashMap::new();
+    let atoms_out: Vec<AtomGeneric> = largest
+        .iter()
+        .enumerate()
+        .map(|(i, &atom_i)| {
+            let sn = i as u32 + 1;
+            sn_map.insert(atoms[atom_i].serial_number, sn);
+
+            AtomGeneric {
+                serial_number: sn,
+                ..atoms[atom_i].clone()
+            }
+        })
+        .collect();
+
+    let bonds_out = bonds
+        .iter()
+        .filter_map(|b| {
+            Some(BondGeneric {
+                atom_0_sn: *sn_map.get(&b.atom_0_sn)?,
+                atom_1_sn: *sn_map.get(&b.atom_1_sn)?,
+                ..b.clone()
+            })
+        })
+        .collect();
+
+    (atoms_out, bonds_out)
+}
+
+/// Group residues into chains, for formats that don't store them. Consecutive residues (in the
+/// order given) are placed in the same chain if:
+/// - A bond links an atom of one to an atom of the other, or
+/// - The C atom of the first is within peptide-bond distance of the N atom of the second, or
+/// - Neither has backbone C and N atoms (e.g. ligands and waters, when no bonds are available),
+///   and their serial numbers are consecutive.
+///
+/// Chains are named A, B, C ... Z, then AA, AB etc. Atoms that aren't in any residue aren't assigned
+/// to a chain.
+pub fn assign_chains(
+    atoms: &[AtomGeneric],
+    residues: &[ResidueGeneric],
+    bonds: &[BondGeneric],
+) -> Vec<ChainGeneric> {
+    let atom_by_sn: HashMap<u32, &AtomGeneric> =
+        atoms.iter().map(|a| (a.serial_number, a)).collect();
+
+    let bonded: HashSet<(u32, u32)> = bonds
+        .iter()
+        .flat_map(|b| [(b.atom_0_sn, b.atom_1_sn), (b.atom_1_sn, b.atom_0_sn)])
+        .collect();
+
+    let backbone_atom = |res: &ResidueGeneric, type_in_res: AtomTypeInRes| {
+        res.atom_sns.iter().find_map(|sn| {
+            let atom = atom_by_sn.get(sn)?;
+            (atom.type_in_res.as_ref() == Some(&type_in_res)).then_some(*atom)
+        })
+    };
+
+    let linked = |prev: &ResidueGeneric, next: &ResidueGeneric| {
+        let has_bond = prev.atom_sns.iter().any(|sn_0| {
+            next.atom_sns
+                .iter()
+                .any(|sn_1| bonded.contains(&(*sn_0, *sn_1)))
+        });
+        if has_bond {
+            return true;
+        }
+
+        match (
+            backbone_atom(prev, AtomTypeInRes::C),
+            backbone_atom(next, AtomTypeInRes::N),
+        ) {
+            (Some(c), Some(n)) => (c.posit - n.posit).magnitude() < PEPTIDE_BOND_MAX_LEN,
+            (None, None) => next.serial_number == prev.serial_number + 1,
+            _ => false,
+        }
+    };
+
+    let mut result: Vec<ChainGeneric> = Vec::new();
+
+    for (i, res) in residues.iter().enumerate() {
+        if i == 0 || !linked(&residues[i - 1], res) {
+            result.push(ChainGeneric {
+                id: chain_id(result.len()),
+                residue_sns: Vec::new(),
+                atom_sns: Vec::new(),
+            });
+        }
+
+        let chain = result.last_mut().unwrap();
+        chain.residue_sns.push(res.serial_number);
+        chain.atom_sns.extend(&res.atom_sns);
+    }
+
+    result
+}
+
+/// A, B, ... Z, AA, AB, ... ZZ, AAA etc.
+fn chain_id(i: usize) -> String {
+    let mut result = Vec::new();
+    let mut n = i + 1;
+
+    while n > 0 {
+        n -= 1;
+        result.push(b'A' + (n % 26) as u8);
+        n /= 26;
+    }
+
+    result.reverse();
+    String::from_utf8(result).unwrap()
+}
@@ -32,7 +32,11 @@ use std::{
 use lin_alg::f64::Vec3;
 use na_seq::Element;
 
-use crate::{el_from_atom_name, gromacs::MoleculeInput};
+use crate::{
+    el_from_atom_name, element_symbol,
+    fixed::{FixedLine, Justify, parse_field, read_field},
+    gromacs::MoleculeInput,
+};
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct AtomGro {
@@ -95,11 +99,6 @@ impl Gro {
             //   x        [20..28]   (8.3f nm)
             //   y        [28..36]
             //   z        [36..44]
-            let gro_slice = |a: usize, b: usize| -> &str {
-                let len = line.len();
-                line[a.min(len)..b.min(len)].trim()
-            };
-
             if line.len() < 44 {
                 return Err(io::Error::new(
                     ErrorKind::InvalidData,
@@ -107,30 +106,20 @@ impl Gro {
                 ));
             }
 
-            let mol_id = gro_slice(0, 5).parse::<u32>().map_err(|_| {
-                io::Error::new(ErrorKind::InvalidData, "Could not parse residue ID")
-            })?;
-            let mol_name = gro_slice(5, 10).to_owned(); // resname
-            let atom_type = gro_slice(10, 15).to_owned(); // atomname
-
-            let serial_number = gro_slice(15, 20).parse::<u32>().map_err(|_| {
-                io::Error::new(ErrorKind::InvalidData, "Could not parse atom serial number")
-            })?;
-            let x = gro_slice(20, 28).parse::<f64>().map_err(|_| {
-                io::Error::new(ErrorKind::InvalidData, "Could not parse X coordinate")
-            })?;
-            let y = gro_slice(28, 36).parse::<f64>().map_err(|_| {
-                io::Error::new(ErrorKind::InvalidData, "Could not parse Y coordinate")
-            })?;
-            let z = gro_slice(36, 44).parse::<f64>().map_err(|_| {
-                io::Error::new(ErrorKind::InvalidData, "Could not parse Z coordinate")
-            })?;
+            let mol_id = parse_field(line, 0, 5, "residue ID")?;
+            let mol_name = read_field(line, 5, 10).to_owned(); // resname
+            let atom_type = read_field(line, 10, 15).to_owned(); // atomname
+
+            let serial_number = parse_field(line, 15, 20, "atom serial number")?;
+            let x = parse_field(line, 20, 28, "X coordinate")?;
+            let y = parse_field(line, 28, 36, "Y coordinate")?;
+            let z = parse_field(line, 36, 44, "Z coordinate")?;
 
             // Optional velocity columns: vx[44..52] vy[52..60] vz[60..68] (8.4f, nm/ps)
             let velocity = if line.len() >= 68 {
-                let vx = gro_slice(44, 52).parse::<f64>().ok();
-                let vy = gro_slice(52, 60).parse::<f64>().ok();
-                let vz = gro_slice(60, 68).parse::<f64>().ok();
+                let vx = read_field(line, 44, 52).parse::<f64>().ok();
+                let vy = read_field(line, 52, 60).parse::<f64>().ok();
+                let vz = read_field(line, 60, 68).parse::<f64>().ok();
 
                 match (vx, vy, vz) {
                     (Some(vx), Some(vy), Some(vz)) => Some(Vec3 {
@@ -186,33 +175,22 @@ impl Gro {
         for atom in &self.atoms {
             // Standard GRO fixed-width: resid(5) resname(5) atomname(5) atomserial(5) x(8.3) y(8.3) z(8.3)
             // Optional velocities: vx(8.4) vy(8.4) vz(8.4)
-            match atom.velocity {
-                Some(v) => writeln!(
-                    w,
-                    "{:>5}{:<5}{:>5}{:>5}{:>8.3}{:>8.3}{:>8.3}{:>8.4}{:>8.4}{:>8.4}",
-                    atom.mol_id % 100_000,
-                    &atom.mol_name[..atom.mol_name.len().min(5)],
-                    &atom.atom_type[..atom.atom_type.len().min(5)],
-                    atom.serial_number % 100_000,
-                    atom.posit.x,
-                    atom.posit.y,
-                    atom.posit.z,
-                    v.x,
-                    v.y,
-                    v.z,
-                )?,
-                None => writeln!(
-                    w,
-                    "{:>5}{:<5}{:>5}{:>5}{:>8.3}{:>8.3}{:>8.3}",
-                    atom.mol_id % 100_000,
-                    &atom.mol_name[..atom.mol_name.len().min(5)],
-                    &atom.atom_type[..atom.atom_type.len().min(5)],
-                    atom.serial_number % 100_000,
-                    atom.posit.x,
-                    atom.posit.y,
-                    atom.posit.z,
-                )?,
+            let mut line = FixedLine::new();
+            line.int(0, 5, (atom.mol_id % 100_000) as i64)?
+                .str(5, 10, &atom.mol_name, Justify::Left)
+                .str(10, 15, &atom.atom_type, Justify::Right)
+                .int(15, 20, (atom.serial_number % 100_000) as i64)?
+                .float(20, 28, atom.posit.x, 3)?
+                .float(28, 36, atom.posit.y, 3)?
+                .float(36, 44, atom.posit.z, 3)?;
+
+            if let Some(v) = atom.velocity {
+                line.float(44, 52, v.x, 4)?
+                    .float(52, 60, v.y, 4)?
+                    .float(60, 68, v.z, 4)?;
             }
+
+            writeln!(w, "{}", line.as_str())?;
         }
 
         writeln!(
@@ -347,14 +325,18 @@ pub fn make_gro_with_origin(
                         .clone()
                         .or_else(|| atom.type_in_res.as_ref().map(|t| t.to_string()))
                         .or_else(|| atom.type_in_res_general.clone())
-                        .unwrap_or_else(|| format!("{}{}", atom.element.to_letter(), atom_serial))
+                        .unwrap_or_else(|| {
+                            format!("{}{}", element_symbol(atom.element), atom_serial)
+                        })
                 } else {
                     atom.type_in_res
                         .as_ref()
                         .map(|t| t.to_string())
                         .or_else(|| atom.force_field_type.clone())
                         .or_else(|| atom.type_in_res_general.clone())
-                        .unwrap_or_else(|| format!("{}{}", atom.element.to_letter(), atom_serial))
+                        .unwrap_or_else(|| {
+                            format!("{}{}", element_symbol(atom.element), atom_serial)
+                        })
                 };
 
                 let posit = mol
@@ -368,17 +350,15 @@ pub fn make_gro_with_origin(
 
                 // GRO fixed-width format:
                 // resid(5) resname(5) atom(5) serial(5) x(8.3) y(8.3) z(8.3)
-                let _ = writeln!(
-                    s,
-                    "{:>5}{:<5}{:>5}{:>5}{:>8.3}{:>8.3}{:>8.3}",
-                    res_serial,
-                    &mol.name[..mol.name.len().min(5)],
-                    &atom_name[..atom_name.len().min(5)],
-                    atom_serial % 100_000,
-                    x_nm,
-                    y_nm,
-                    z_nm,
-                );
+                let mut line = FixedLine::new();
+                line.int(0, 5, (res_serial % 100_000) as i64)?
+                    .str(5, 10, &mol.name, Justify::Left)
+                    .str(10, 15, &atom_name, Justify::Right)
+                    .int(15, 20, (atom_serial % 100_000) as i64)?
+                    .float(20, 28, x_nm, 3)?
+                    .float(28, 36, y_nm, 3)?
+                    .float(36, 44, z_nm, 3)?;
+                let _ = writeln!(s, "{}", line.as_str());
                 atom_serial += 1;
             }
             res_serial += 1;
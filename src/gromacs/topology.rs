@@ -22,7 +22,7 @@
 use std::{collections::HashMap, io};
 
 use crate::{
-    AtomGeneric, BondGeneric,
+    AtomGeneric, BondGeneric, element_symbol,
     gromacs::solvate::Solvent,
     md_params::{ForceFieldParams, ForceFieldParamsIndexed},
 };
@@ -297,14 +297,14 @@ fn write_molecule_block(
             // Small molecule / hetero: FF type (e.g. "oh", "c3") takes priority
             atom.force_field_type
                 .clone()
-                .unwrap_or_else(|| format!("{}{}", atom.element.to_letter(), nr))
+                .unwrap_or_else(|| format!("{}{}", element_symbol(atom.element), nr))
         } else {
             // todo: QC this logic of ff_type vs TIR general... Sus. They are NOT the same!
             // Protein / standard residue: residue atom name ("CA", "CB") takes priority
             atom.type_in_res
                 .as_ref()
                 .map(|t| t.to_string())
-                .unwrap_or_else(|| format!("{}{}", atom.element.to_letter(), nr))
+                .unwrap_or_else(|| format!("{}{}", element_symbol(atom.element), nr))
         };
 
         s.push_str(&format!(
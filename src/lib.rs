@@ -6,6 +6,7 @@
 
 pub mod mmcif;
 pub mod mol2;
+pub mod pdb;
 pub mod pdbqt;
 pub mod sdf;
 
@@ -14,6 +15,7 @@ pub mod map;
 
 pub mod dat;
 pub mod frcmod;
+pub mod graph;
 pub mod gromacs;
 pub mod md_params;
 pub mod orca;
@@ -22,29 +24,47 @@ pub mod amber_typedef;
 pub mod bond_inference;
 pub mod cif_sf;
 pub mod dcd;
+pub mod fingerprint;
+pub mod fixed;
 mod mmcif_aux;
 pub mod mol_templates;
+pub mod molecule;
+pub mod mtz;
 pub mod prmtop;
+#[cfg(feature = "serde")]
+mod serde_adapters;
+pub mod symmetry;
+pub mod trajectory;
 pub mod xtc;
 pub mod xyz;
+pub mod zmatrix;
 
 use std::{
+    collections::HashMap,
     fmt,
     fmt::{Display, Formatter},
-    io,
-    io::ErrorKind,
+    fs, io,
+    io::{ErrorKind, Read},
+    path::Path,
     str::FromStr,
 };
 
 pub use ab1::*;
-pub use bond_inference::create_bonds;
+pub use bond_inference::{
+    BondInferenceParams, atoms_within_of, create_bonds, create_bonds_with_params, infer_bonds,
+};
 use lin_alg::f64::Vec3;
 pub use map::*;
 pub use mmcif::*;
 pub use mol2::*;
-use na_seq::{AminoAcid, AtomTypeInRes, Element};
+pub use molecule::Molecule;
+use na_seq::{AaIdent, AminoAcid, AtomTypeInRes, Element};
 pub use pdbqt::Pdbqt;
 pub use sdf::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+pub use symmetry::{SymOp, expand_symmetry, parse_symop_xyz};
+pub use trajectory::Trajectory;
 pub use xyz::*;
 
 // todo: SHould this be in na_seq?
@@ -200,16 +220,20 @@ impl FromStr for LipidStandard {
 /// molecules in this library. You may wish to augment the data here with a custom application-specific
 /// format.
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct AtomGeneric {
     /// A unique identifier for this atom, within its molecule. This may originate from data in
     /// mmCIF files, Mol2, SDF files, etc.
     pub serial_number: u32,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_adapters::vec3"))]
     pub posit: Vec3,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_adapters::element"))]
     pub element: Element,
     /// This identifier will be unique within a given residue. For example, within an
     /// amino acid on a protein. Different residues will have different sets of these.
     /// e.g. "CG1", "CA", "O", "C", "HA", "CD", "C9" etc.
     /// todo: This setup might be protein/aa specific.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_adapters::type_in_res"))]
     pub type_in_res: Option<AtomTypeInRes>,
     /// There are too many variants of this (with different numbers) for lipids, nucleic
     /// acids etc to use an enum effectively.
@@ -229,13 +253,25 @@ pub struct AtomGeneric {
     /// We get partial charge for ligands from (e.g. Amber-provided) Mol files, so we load it from the atom, vice
     /// the loaded FF params. Convert to appropriate units prior to running dynamics.
     pub partial_charge: Option<f32>,
+    /// Formal charge, in elementary charge units. E.g. from SDF charge fields. None if not
+    /// specified, which is generally equivalent to 0.
+    pub formal_charge: Option<i8>,
     /// Indicates, in proteins, that the atom isn't part of an amino acid. E.g., water or
     /// ligands.
     pub hetero: bool,
     pub occupancy: Option<f32>,
+    /// The isotropic B-factor (temperature factor), in Å². This indicates how much the atom's
+    /// position is smeared by thermal motion and disorder. From mmCIF and PDB files.
+    pub b_factor: Option<f32>,
     /// Used by mmCIF files to store alternate conformations. If this isn't None, there may
     /// be, for example, an "A" and "B" variant of this atom at slightly different positions.
     pub alt_conformation_id: Option<String>,
+    /// From formats that carry it, e.g. extended XYZ. In the file's units; generally Å/fs or Å/ps.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_adapters::vec3_opt"))]
+    pub velocity: Option<Vec3>,
+    /// From formats that carry it, e.g. extended XYZ. In the file's units; generally eV/Å.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_adapters::vec3_opt"))]
+    pub force: Option<Vec3>,
 }
 
 impl Display for AtomGeneric {
@@ -267,8 +303,141 @@ impl Display for AtomGeneric {
     }
 }
 
+/// Sum the atoms' formal charges, treating `None` as 0. If every atom has a partial charge, as
+/// loaded from Mol2 files and some SDF files, their total is the net charge the file declares; we
+/// check that it agrees with the formal charges. A mismatch often indicates a partially-applied
+/// charge block, e.g. `M  CHG` lines that omit some charged atoms.
+pub fn validate_formal_charges(atoms: &[AtomGeneric]) -> Result<i32, String> {
+    let total: i32 = atoms
+        .iter()
+        .map(|a| a.formal_charge.unwrap_or_default() as i32)
+        .sum();
+
+    if !atoms.is_empty() && atoms.iter().all(|a| a.partial_charge.is_some()) {
+        let partial_total: f32 = atoms.iter().map(|a| a.partial_charge.unwrap()).sum();
+
+        // Allows for rounding of partial charges in files.
+        if (partial_total - total as f32).abs() > 0.1 {
+            return Err(format!(
+                "Formal charges sum to {total}, but partial charges sum to {partial_total:.3}"
+            ));
+        }
+    }
+
+    Ok(total)
+}
+
+/// The molecular formula, in Hill notation, e.g. "C6H12O6". If there's carbon, it's listed first,
+/// then hydrogen, then other elements alphabetically. Otherwise, all elements are alphabetical.
+/// Counts of 1 are omitted.
+pub fn molecular_formula(atoms: &[AtomGeneric]) -> String {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for atom in atoms {
+        *counts.entry(element_symbol(atom.element)).or_default() += 1;
+    }
+
+    let mut symbols: Vec<&String> = counts.keys().collect();
+    let has_carbon = counts.contains_key("C");
+    symbols.sort_by_key(|s| match s.as_str() {
+        "C" if has_carbon => (0, String::new()),
+        "H" if has_carbon => (1, String::new()),
+        _ => (2, s.to_string()),
+    });
+
+    let mut result = String::new();
+    for sym in symbols {
+        result.push_str(sym);
+        if counts[sym] > 1 {
+            result.push_str(&counts[sym].to_string());
+        }
+    }
+    result
+}
+
+/// The molecular mass, in Daltons, from standard atomic weights.
+pub fn molecular_mass(atoms: &[AtomGeneric]) -> f64 {
+    atoms.iter().map(|a| a.element.atomic_weight() as f64).sum()
+}
+
+/// The mass-weighted center of the atoms, from standard atomic weights. The origin if there are
+/// no atoms.
+pub fn center_of_mass(atoms: &[AtomGeneric]) -> Vec3 {
+    let mass = molecular_mass(atoms);
+    if mass == 0. {
+        return Vec3::new_zero();
+    }
+
+    let weighted = atoms.iter().fold(Vec3::new_zero(), |acc, a| {
+        acc + a.posit * a.element.atomic_weight() as f64
+    });
+    weighted / mass
+}
+
+/// The unweighted mean of the atoms' positions. The origin if there are no atoms.
+pub fn geometric_center(atoms: &[AtomGeneric]) -> Vec3 {
+    if atoms.is_empty() {
+        return Vec3::new_zero();
+    }
+
+    let sum = atoms.iter().fold(Vec3::new_zero(), |acc, a| acc + a.posit);
+    sum / atoms.len() as f64
+}
+
+/// Move every atom by `offset`. For example, to center a molecule at the origin, use
+/// `translate(atoms, -center_of_mass(atoms))`.
+pub fn translate(atoms: &mut [AtomGeneric], offset: Vec3) {
+    for atom in atoms {
+        atom.posit += offset;
+    }
+}
+
+/// The first two bytes of any gzip stream.
+pub(crate) const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Read a text file, decompressing it first if it's gzipped, e.g. a `.cif.gz` from RCSB. This
+/// is detected by the gzip magic bytes, not the file extension.
+pub(crate) fn read_text_file(path: &Path) -> io::Result<String> {
+    let bytes = fs::read(path)?;
+
+    let bytes = if bytes.starts_with(&GZIP_MAGIC) {
+        let mut decompressed = Vec::new();
+        flate2::read::GzDecoder::new(bytes.as_slice()).read_to_end(&mut decompressed)?;
+        decompressed
+    } else {
+        bytes
+    };
+
+    String::from_utf8(bytes).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+}
+
+/// The low and high corners of the axis-aligned box containing all atoms, expanded by `padding`
+/// on every side. For no atoms, this is a box of that padding around the origin.
+pub(crate) fn padded_bounds(atoms: &[AtomGeneric], padding: f64) -> (Vec3, Vec3) {
+    let (mut low, mut high) = match atoms.first() {
+        Some(a) => (a.posit, a.posit),
+        None => (Vec3::new_zero(), Vec3::new_zero()),
+    };
+
+    for a in atoms {
+        low = Vec3::new(
+            low.x.min(a.posit.x),
+            low.y.min(a.posit.y),
+            low.z.min(a.posit.z),
+        );
+        high = Vec3::new(
+            high.x.max(a.posit.x),
+            high.y.max(a.posit.y),
+            high.z.max(a.posit.z),
+        );
+    }
+
+    let pad = Vec3::new(padding, padding, padding);
+    (low - pad, high + pad)
+}
+
 /// These are the Mol2 standard types, unless otherwise noted.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BondType {
     Single,
     Double,
@@ -391,6 +560,7 @@ impl FromStr for BondType {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BondGeneric {
     pub bond_type: BondType,
     /// You may wish to augment these serial numbers with atom indices in downstream
@@ -400,8 +570,11 @@ pub struct BondGeneric {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ResidueType {
-    AminoAcid(AminoAcid),
+    AminoAcid(
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_adapters::amino_acid"))] AminoAcid,
+    ),
     Water,
     Other(String),
 }
@@ -436,9 +609,20 @@ impl ResidueType {
             }
         }
     }
+
+    /// The inverse of `from_str`: The residue name as written in text-based formats, e.g. "SER",
+    /// or "HOH".
+    pub fn to_name(&self) -> String {
+        match self {
+            ResidueType::AminoAcid(aa) => aa.to_str(AaIdent::ThreeLetters).to_uppercase(),
+            ResidueType::Water => "HOH".to_string(),
+            ResidueType::Other(n) => n.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ResidueGeneric {
     /// We use serial number of display, search etc, and array index to select. Residue serial number is not
     /// unique in the molecule; only in the chain.
@@ -447,10 +631,20 @@ pub struct ResidueGeneric {
     /// Serial number
     pub atom_sns: Vec<u32>,
     pub end: ResidueEnd,
+    /// The mmCIF entity this residue belongs to (`_atom_site.label_entity_id`), if known.
+    pub entity_id: Option<String>,
+    /// The author-assigned residue number, from mmCIF's `_atom_site.auth_seq_id`, or a PDB file's
+    /// residue sequence number. Unlike mmCIF's `serial_number` (`label_seq_id`), which numbers
+    /// residues sequentially, this may skip numbers, be negative, or repeat with an insertion code.
+    pub auth_serial_number: Option<i32>,
+    /// The insertion code, e.g. `A` for residue 100A, which goes with `auth_serial_number`. Common
+    /// in antibody numbering schemes, where inserted residues share the previous residue's number.
+    pub insertion_code: Option<char>,
 }
 
 /// Can be used for amino acid, and nucleotide sequences.
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ResidueEnd {
     Internal,
     NTerminus,
@@ -459,7 +653,38 @@ pub enum ResidueEnd {
     Hetero,
 }
 
+/// Populate residue ends: The first residue is the N terminus, the last of the leading run of amino
+/// acids is the C terminus, and non-amino-acids are hetero.
+pub(crate) fn set_residue_ends(residues: &mut [ResidueGeneric]) {
+    let mut last_non_het = 0;
+    for (i, res) in residues.iter().enumerate() {
+        match res.res_type {
+            ResidueType::AminoAcid(_) => last_non_het = i,
+            _ => break,
+        }
+    }
+
+    for (i, res) in residues.iter_mut().enumerate() {
+        let mut end = ResidueEnd::Internal;
+
+        // Match arm won't work due to non-constant arms, e.g. non_hetero?
+        if i == 0 {
+            end = ResidueEnd::NTerminus;
+        } else if i == last_non_het {
+            end = ResidueEnd::CTerminus;
+        }
+
+        match res.res_type {
+            ResidueType::AminoAcid(_) => (),
+            _ => end = ResidueEnd::Hetero,
+        }
+
+        res.end = end;
+    }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ChainGeneric {
     pub id: String,
     // todo: Do we want both residues and atoms stored here? It's an overconstraint.
@@ -470,13 +695,56 @@ pub struct ChainGeneric {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum SecondaryStructure {
     Helix,
     Sheet,
     Coil,
 }
 
+/// Helix subtype, from mmCIF `_struct_conf.conf_type_id` or `pdbx_PDB_helix_class`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum HelixClass {
+    /// Right-handed α helix. PDB helix class 1.
+    Alpha,
+    /// Right-handed 3-10 helix. PDB helix class 5.
+    ThreeTen,
+    /// Right-handed π helix. PDB helix class 3.
+    Pi,
+    /// Any other class, e.g. left-handed or polyproline helices.
+    Other,
+}
+
+impl HelixClass {
+    /// Parse from a `_struct_conf.conf_type_id` value, e.g. `HELX_RH_3T_P`. Returns `None` for
+    /// the generic `HELX_P`, and for non-helix types.
+    pub fn from_conf_type(conf_type: &str) -> Option<Self> {
+        match conf_type {
+            "HELX_RH_AL_P" => Some(Self::Alpha),
+            "HELX_RH_3T_P" => Some(Self::ThreeTen),
+            "HELX_RH_PI_P" => Some(Self::Pi),
+            "HELX_P" => None,
+            t if t.starts_with("HELX") => Some(Self::Other),
+            _ => None,
+        }
+    }
+
+    /// Parse from a PDB helix class number, as in `_struct_conf.pdbx_PDB_helix_class`, or columns
+    /// 39-40 of a PDB `HELIX` record. Returns `None` if missing or invalid.
+    pub fn from_pdb_class(class: &str) -> Option<Self> {
+        match class.trim().parse::<u8>().ok()? {
+            1 => Some(Self::Alpha),
+            3 => Some(Self::Pi),
+            5 => Some(Self::ThreeTen),
+            2 | 4 | 6..=10 => Some(Self::Other),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// See note elsewhere regarding serial numbers vs indices: In your downstream applications, you may
 /// wish to convert sns to indices, for faster operations.
 pub struct BackboneSS {
@@ -484,6 +752,23 @@ pub struct BackboneSS {
     pub start_sn: u32,
     pub end_sn: u32,
     pub sec_struct: SecondaryStructure,
+    /// For helices, the helix subtype, if the file specifies one. Always `None` for sheets.
+    pub helix_class: Option<HelixClass>,
+}
+
+impl BackboneSS {
+    /// The DSSP-style code for this range. Helices without a specified class are α helices.
+    pub fn ss_code(&self) -> SsCode {
+        match self.sec_struct {
+            SecondaryStructure::Helix => match self.helix_class {
+                Some(HelixClass::ThreeTen) => SsCode::Helix310,
+                Some(HelixClass::Pi) => SsCode::PiHelix,
+                _ => SsCode::AlphaHelix,
+            },
+            SecondaryStructure::Sheet => SsCode::Strand,
+            SecondaryStructure::Coil => SsCode::Coil,
+        }
+    }
 }
 
 /// A way to allow users to slice a trajectory by either physical time or frame index.
@@ -536,6 +821,7 @@ impl Display for FrameSlice {
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// The method used to find a given molecular structure. This data is present in mmCIF files
 /// as the `_exptl.method` field.
 pub enum ExperimentalMethod {
@@ -600,6 +886,25 @@ impl FromStr for ExperimentalMethod {
 
 // todo: Util module?
 
+/// The element's symbol, e.g. "Fe". Use this instead of `Element::to_letter` when writing files;
+/// it corrects symbols that `to_letter` gets wrong.
+pub(crate) fn element_symbol(el: Element) -> String {
+    match el {
+        Element::Zinc => "Zn".to_owned(),
+        Element::Rubidium => "Rb".to_owned(),
+        _ => el.to_letter(),
+    }
+}
+
+/// The element for a symbol, e.g. "Fe", case-insensitive. Use this instead of
+/// `Element::from_letter` when reading files; it accepts the symbols [`element_symbol`] writes.
+pub(crate) fn element_from_symbol(symbol: &str) -> io::Result<Element> {
+    if symbol.eq_ignore_ascii_case("Rb") {
+        return Ok(Element::Rubidium);
+    }
+    Element::from_letter(symbol)
+}
+
 pub(crate) fn el_from_atom_name(name: &str) -> Element {
     let upper = name.to_uppercase();
 
@@ -640,6 +945,7 @@ pub(crate) fn el_from_atom_name(name: &str) -> Element {
 /// part of that pharmacophore type. This is atom-centered; it doesn't use absolute positions.
 /// (Ring centers etc)
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PharmacophoreFeatureGeneric {
     /// 1-based atom indices (serial numbers)
     pub atom_sns: Vec<u32>,
@@ -649,6 +955,7 @@ pub struct PharmacophoreFeatureGeneric {
 /// Atom-
 /// Based on ones observed from PubChem.
 #[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PharmacophoreTypeGeneric {
     Acceptor,
     Donor,
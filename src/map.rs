@@ -9,14 +9,20 @@
 use std::{
     fs,
     fs::File,
-    io::{self, ErrorKind, Read, Seek, SeekFrom, Write},
+    io::{self, Cursor, ErrorKind, Read, Seek, SeekFrom, Write},
     path::Path,
     process::Command,
 };
 
 use bio_apis::rcsb;
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use flate2::read::GzDecoder;
 use lin_alg::f64::{Mat3, Vec3};
+use na_seq::Element;
+
+#[cfg(feature = "remote")]
+use crate::MmCif;
+use crate::{AtomGeneric, GZIP_MAGIC, padded_bounds};
 
 const HEADER_SIZE: u64 = 1_024;
 
@@ -53,6 +59,10 @@ pub struct DensityHeaderInner {
     pub zorigin: Option<f32>, // todo: More header items A/R.
 }
 
+/// Number of 80-character text labels in the header, in words 57-256.
+const NUM_LABELS: usize = 10;
+const LABEL_LEN: usize = 80;
+
 /// Minimal subset of the 1024-byte CCP4/MRC header
 #[allow(unused)]
 #[derive(Clone, Debug)]
@@ -70,11 +80,25 @@ pub struct MapHeader {
     pub dmax: f32,
     /// Mean density
     pub dmean: f32,
+    /// Number of labels in use, from word 56. When saving, `labels.len()` is written instead.
+    pub nlabl: i32,
+    /// Up to 10 text labels of up to 80 characters each, e.g. describing the map's provenance.
+    /// Trailing whitespace is removed.
+    pub labels: Vec<String>,
 }
 
-fn read_map_header<R: Read + Seek>(mut r: R) -> io::Result<MapHeader> {
+/// Read the 1024-byte CCP4/MRC header, without touching the density data. This leaves the reader
+/// positioned at the end of the header; the data starts after the `nsymbt`-byte symmetry block.
+///
+/// The header is read in one call, then decoded from memory, so this is a single range request
+/// on remote sources.
+pub fn read_map_header<R: Read + Seek>(mut r: R) -> io::Result<MapHeader> {
     r.seek(SeekFrom::Start(0))?;
 
+    let mut buf = [0; HEADER_SIZE as usize];
+    r.read_exact(&mut buf)?;
+    let mut r = Cursor::new(&buf[..]);
+
     let nx = r.read_i32::<LittleEndian>()?;
     let ny = r.read_i32::<LittleEndian>()?;
     let nz = r.read_i32::<LittleEndian>()?;
@@ -137,6 +161,24 @@ fn read_map_header<R: Read + Seek>(mut r: R) -> io::Result<MapHeader> {
         ));
     }
 
+    // Word 56. Skip the machine stamp (54) and RMS (55).
+    r.seek(SeekFrom::Start(55 * 4))?;
+    let nlabl = r.read_i32::<LittleEndian>()?;
+
+    // Words 57-256
+    let mut label_buf = [0u8; NUM_LABELS * LABEL_LEN];
+    r.read_exact(&mut label_buf)?;
+
+    let labels = label_buf
+        .chunks_exact(LABEL_LEN)
+        .take(nlabl.clamp(0, NUM_LABELS as i32) as usize)
+        .map(|l| {
+            String::from_utf8_lossy(l)
+                .trim_end_matches(['\0', ' '])
+                .to_owned()
+        })
+        .collect();
+
     const EPS: f32 = 0.0001;
 
     if xorigin_.abs() > EPS {
@@ -151,9 +193,6 @@ fn read_map_header<R: Read + Seek>(mut r: R) -> io::Result<MapHeader> {
         zorigin = Some(zorigin_);
     }
 
-    // skip ahead to end of 1024-byte header
-    r.seek(SeekFrom::Start(HEADER_SIZE))?;
-
     let cell = UnitCell::new(
         cell[0] as f64,
         cell[1] as f64,
@@ -191,11 +230,263 @@ fn read_map_header<R: Read + Seek>(mut r: R) -> io::Result<MapHeader> {
         dmin,
         dmax,
         dmean,
+        nlabl,
+        labels,
     })
 }
 
+/// Cell edges from [`bounding_unit_cell`] are at least this long, in Å.
+const MIN_CELL_EXTENT: f64 = 1.;
+
+/// An orthorhombic unit cell containing all atoms, with `padding` (Å) between the atoms' extent
+/// and each face. See [`crate::dcd::bounding_cell`]. A `UnitCell` doesn't store an origin, so this
+/// also returns the Cartesian position of the cell's origin corner; subtract it from atom
+/// positions to place them inside the cell.
+///
+/// Edges shorter than 1 Å, e.g. for a planar molecule without padding, are widened to that about
+/// their center, as a cell must have nonzero volume.
+pub fn bounding_unit_cell(atoms: &[AtomGeneric], padding: f64) -> (UnitCell, Vec3) {
+    let (mut low, mut high) = padded_bounds(atoms, padding);
+
+    for (l, h) in [
+        (&mut low.x, &mut high.x),
+        (&mut low.y, &mut high.y),
+        (&mut low.z, &mut high.z),
+    ] {
+        if *h - *l < MIN_CELL_EXTENT {
+            let center = (*l + *h) / 2.;
+            *l = center - MIN_CELL_EXTENT / 2.;
+            *h = center + MIN_CELL_EXTENT / 2.;
+        }
+    }
+
+    let size = high - low;
+    (UnitCell::new(size.x, size.y, size.z, 90., 90., 90.), low)
+}
+
+/// Cromer-Mann coefficients for the X-ray form factor, from International Tables for
+/// Crystallography Vol C, Table 6.1.1.4: f(s) = Σ aᵢ exp(-bᵢ s²) + c, with s = sin θ / λ, and bᵢ in Å².
+/// `([a1, a2, a3, a4], [b1, b2, b3, b4], c)`.
+type FormFactor = ([f64; 4], [f64; 4], f64);
+
+fn form_factor(el: Element) -> Option<FormFactor> {
+    use Element::*;
+
+    Some(match el {
+        Hydrogen => (
+            [0.489918, 0.262003, 0.196767, 0.049879],
+            [20.6593, 7.74039, 49.5519, 2.20159],
+            0.001305,
+        ),
+        Carbon => (
+            [2.31, 1.02, 1.5886, 0.865],
+            [20.8439, 10.2075, 0.5687, 51.6512],
+            0.2156,
+        ),
+        Nitrogen => (
+            [12.2126, 3.1322, 2.0125, 1.1663],
+            [0.0057, 9.8933, 28.9975, 0.5826],
+            -11.529,
+        ),
+        Oxygen => (
+            [3.0485, 2.2868, 1.5463, 0.867],
+            [13.2771, 5.7011, 0.3239, 32.9089],
+            0.2508,
+        ),
+        Sodium => (
+            [4.7626, 3.1736, 1.2674, 1.1128],
+            [3.285, 8.8422, 0.3136, 129.424],
+            0.676,
+        ),
+        Magnesium => (
+            [5.4204, 2.1735, 1.2269, 2.3073],
+            [2.8275, 79.2611, 0.3808, 7.1937],
+            0.8584,
+        ),
+        Phosphorus => (
+            [6.4345, 4.1791, 1.78, 1.4908],
+            [1.9067, 27.157, 0.526, 68.1645],
+            1.1149,
+        ),
+        Sulfur => (
+            [6.9053, 5.2034, 1.4379, 1.5863],
+            [1.4679, 22.2151, 0.2536, 56.172],
+            0.8669,
+        ),
+        Chlorine => (
+            [11.4604, 7.1962, 6.2556, 1.6455],
+            [0.0104, 1.1662, 18.5194, 47.7784],
+            -9.5574,
+        ),
+        Calcium => (
+            [8.6266, 7.3873, 1.5899, 1.0211],
+            [10.4421, 0.6599, 85.7484, 178.437],
+            1.3751,
+        ),
+        Iron => (
+            [11.7695, 7.3573, 3.5222, 2.3045],
+            [4.7611, 0.3072, 15.3535, 76.8805],
+            1.0369,
+        ),
+        Zinc => (
+            [14.0743, 7.0318, 5.1652, 2.41],
+            [3.2655, 0.2333, 10.3163, 58.7097],
+            1.3041,
+        ),
+        _ => return None,
+    })
+}
+
+/// Gaussian terms `(weight, B)` making up an atom's real-space density. The weights sum to its
+/// electron count. Elements without tabulated coefficients use a single Gaussian.
+fn density_terms(el: Element) -> Vec<(f64, f64)> {
+    match form_factor(el) {
+        // Each term aᵢ exp(-bᵢ s²) transforms to a Gaussian with B = 4bᵢ, in the usual
+        // crystallographic convention exp(-B s² / 4). c is a point charge: B = 0.
+        Some((a, b, c)) => (0..4).map(|i| (a[i], 4. * b[i])).chain([(c, 0.)]).collect(),
+        None => vec![(el.atomic_number() as f64, 10.)],
+    }
+}
+
+/// Model electron density for a set of atoms, on an `nx × ny × nz` grid spanning the unit cell, e.g.
+/// for comparing against an experimental map, or computing a difference map. Each atom contributes
+/// a sum of Gaussians from its element's X-ray scattering factor, so its density integrates to its
+/// electron count. These are broadened by the atom's B-factor, if present, and by `resolution` (Å):
+/// a Gaussian blur with σ = 0.225 × resolution, as in ChimeraX's `molmap`. Density wraps across
+/// periodic cell boundaries. Returns an error if a grid dimension is 0.
+pub fn model_density(
+    atoms: &[AtomGeneric],
+    cell: &UnitCell,
+    grid: (usize, usize, usize),
+    resolution: f64,
+) -> io::Result<DensityMap> {
+    use std::f64::consts::PI;
+
+    if grid.0 == 0 || grid.1 == 0 || grid.2 == 0 {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Grid dimensions must be nonzero; got {grid:?}"),
+        ));
+    }
+
+    // Terms below this are cut off.
+    const CUTOFF: f64 = 1e-5;
+    // Keeps the point-charge term finite at zero resolution and B-factor.
+    const MIN_B: f64 = 0.1;
+
+    let (nx, ny, nz) = grid;
+    let dims = [nx as isize, ny as isize, nz as isize];
+    let mut data = vec![0.; nx * ny * nz];
+
+    let sigma_res = 0.225 * resolution.max(0.);
+    let b_res = 8. * PI.powi(2) * sigma_res.powi(2);
+
+    for atom in atoms {
+        let b_atom = atom.b_factor.unwrap_or_default().max(0.) as f64 + b_res;
+
+        // (Peak height, exponent coefficient) of each Gaussian: ρ(r) = h exp(-k r²).
+        let terms: Vec<(f64, f64)> = density_terms(atom.element)
+            .into_iter()
+            .map(|(w, b)| {
+                let b = (b + b_atom).max(MIN_B);
+                (w * (4. * PI / b).powf(1.5), 4. * PI.powi(2) / b)
+            })
+            .collect();
+
+        // Out to where the broadest term falls below the cutoff.
+        let k_min = terms.iter().map(|t| t.1).fold(f64::INFINITY, f64::min);
+        let r_cut = ((1. / CUTOFF).ln() / k_min).sqrt();
+
+        // Grid bounds: The fractional image of the cube enclosing the cutoff sphere.
+        let mut lo = [isize::MAX; 3];
+        let mut hi = [isize::MIN; 3];
+        for corner in 0..8 {
+            let offset = Vec3::new(
+                if corner & 1 == 0 { -r_cut } else { r_cut },
+                if corner & 2 == 0 { -r_cut } else { r_cut },
+                if corner & 4 == 0 { -r_cut } else { r_cut },
+            );
+            let f = cell.cartesian_to_fractional(atom.posit + offset);
+            for (i, v) in [f.x, f.y, f.z].into_iter().enumerate() {
+                lo[i] = lo[i].min((v * dims[i] as f64).floor() as isize);
+                hi[i] = hi[i].max((v * dims[i] as f64).ceil() as isize);
+            }
+        }
+
+        for k in lo[2]..=hi[2] {
+            for j in lo[1]..=hi[1] {
+                for i in lo[0]..=hi[0] {
+                    // Unwrapped, so no minimum-image search is required.
+                    let frac = Vec3::new(
+                        i as f64 / nx as f64,
+                        j as f64 / ny as f64,
+                        k as f64 / nz as f64,
+                    );
+                    let r_sq =
+                        (cell.fractional_to_cartesian(frac) - atom.posit).magnitude_squared();
+                    if r_sq > r_cut.powi(2) {
+                        continue;
+                    }
+
+                    let val: f64 = terms.iter().map(|(h, k)| h * (-k * r_sq).exp()).sum();
+
+                    let (iw, jw, kw) = (
+                        i.rem_euclid(dims[0]) as usize,
+                        j.rem_euclid(dims[1]) as usize,
+                        k.rem_euclid(dims[2]) as usize,
+                    );
+                    data[(kw * ny + jw) * nx + iw] += val as f32;
+                }
+            }
+        }
+    }
+
+    let dmin = data.iter().copied().fold(f32::INFINITY, f32::min);
+    let dmax = data.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let dmean = data.iter().sum::<f32>() / data.len().max(1) as f32;
+
+    let hdr = MapHeader {
+        inner: DensityHeaderInner {
+            nxstart: 0,
+            nystart: 0,
+            nzstart: 0,
+            mx: nx as i32,
+            my: ny as i32,
+            mz: nz as i32,
+            cell: cell.clone(),
+            mapc: 1,
+            mapr: 2,
+            maps: 3,
+            ispg: 1,
+            nsymbt: 0,
+            version: 20140,
+            xorigin: None,
+            yorigin: None,
+            zorigin: None,
+        },
+        nx: nx as i32,
+        ny: ny as i32,
+        nz: nz as i32,
+        mode: 2,
+        dmin,
+        dmax,
+        dmean,
+        nlabl: 0,
+        labels: Vec::new(),
+    };
+
+    DensityMap::new(hdr, data)
+}
+
+/// Relative tolerance on cell lengths for [`UnitCell::is_compatible`]. Covers rounding, and small
+/// differences between the cell deposited with coordinates and with structure factors.
+pub const CELL_LENGTH_TOL: f64 = 0.01;
+/// Tolerance on cell angles, in degrees, for [`UnitCell::is_compatible`].
+pub const CELL_ANGLE_TOL: f64 = 0.5;
+
 /// Unit cell dimensions. [XYZ] length. Then α: Angle between Y and Z, β: Angle
-/// between X and Z, and γ: ANgle between X and Y. Distances are in Å, and angles are in degrees.
+/// between X and Z, and γ: ANgle between X and Y. Distances are in Å. Angles are passed to `new`
+/// in degrees, and stored in radians.
 #[derive(Clone, Debug)]
 pub struct UnitCell {
     pub a: f64,
@@ -243,6 +534,83 @@ impl UnitCell {
         }
     }
 
+    /// Like [`Self::new`], but returns an error instead of panicking if the parameters don't
+    /// describe a cell with volume, e.g. if a length is 0, or the angles can't form a cell.
+    pub fn try_new(
+        a: f64,
+        b: f64,
+        c: f64,
+        alpha_deg: f64,
+        beta_deg: f64,
+        gamma_deg: f64,
+    ) -> io::Result<Self> {
+        let angles = [alpha_deg, beta_deg, gamma_deg];
+        let lengths_ok = [a, b, c].iter().all(|v| v.is_finite() && *v > 0.);
+        let angles_ok = angles.iter().all(|v| v.is_finite() && *v > 0. && *v < 180.);
+
+        let [ca, cb, cg] = angles.map(|v| v.to_radians().cos());
+        let volume_ok = 1. - ca.powi(2) - cb.powi(2) - cg.powi(2) + 2. * ca * cb * cg > 0.;
+
+        if !(lengths_ok && angles_ok && volume_ok) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Invalid unit cell: {a}, {b}, {c} Å; {alpha_deg}°, {beta_deg}°, {gamma_deg}°"
+                ),
+            ));
+        }
+
+        Ok(Self::new(a, b, c, alpha_deg, beta_deg, gamma_deg))
+    }
+
+    /// From the three cell edge vectors, in Å, e.g. from an extended XYZ `Lattice`. Only their
+    /// lengths and angles are used: The result is in the standard orientation, with `a` along x,
+    /// and `b` in the xy plane.
+    pub fn from_vectors(a: Vec3, b: Vec3, c: Vec3) -> Self {
+        let angle = |u: Vec3, v: Vec3| {
+            (u.dot(v) / (u.magnitude() * v.magnitude()))
+                .clamp(-1., 1.)
+                .acos()
+                .to_degrees()
+        };
+
+        Self::new(
+            a.magnitude(),
+            b.magnitude(),
+            c.magnitude(),
+            angle(b, c),
+            angle(a, c),
+            angle(a, b),
+        )
+    }
+
+    /// Whether two cells describe the same lattice, e.g. a model's and its density map's: Lengths
+    /// agree within a relative `CELL_LENGTH_TOL`, and angles within `CELL_ANGLE_TOL`.
+    pub fn is_compatible(&self, other: &Self) -> bool {
+        let lengths_ok = [(self.a, other.a), (self.b, other.b), (self.c, other.c)]
+            .iter()
+            .all(|(l0, l1)| (l0 - l1).abs() <= CELL_LENGTH_TOL * l0.abs().max(l1.abs()));
+
+        let angles_ok = [
+            (self.alpha, other.alpha),
+            (self.beta, other.beta),
+            (self.gamma, other.gamma),
+        ]
+        .iter()
+        .all(|(a0, a1)| (a0 - a1).abs().to_degrees() <= CELL_ANGLE_TOL);
+
+        lengths_ok && angles_ok
+    }
+
+    /// The cell volume, in Å³.
+    pub fn volume(&self) -> f64 {
+        let (ca, cb, cg) = (self.alpha.cos(), self.beta.cos(), self.gamma.cos());
+        self.a
+            * self.b
+            * self.c
+            * (1. - ca.powi(2) - cb.powi(2) - cg.powi(2) + 2. * ca * cb * cg).sqrt()
+    }
+
     pub fn fractional_to_cartesian(&self, f: Vec3) -> Vec3 {
         // todo: Don't clone!
         self.ortho.clone() * f
@@ -252,36 +620,182 @@ impl UnitCell {
         // todo: Don't clone!
         self.ortho_inv.clone() * c
     }
+
+    /// The distance between two Cartesian points under the minimum image convention, i.e. to the
+    /// nearest periodic image of `b`. The displacement is wrapped into the cell in fractional
+    /// coordinates; for triclinic cells, the neighboring images are then checked too, as wrapping
+    /// alone doesn't always find the nearest one.
+    pub fn min_image_distance(&self, a: Vec3, b: Vec3) -> f64 {
+        let mut d = self.cartesian_to_fractional(b - a);
+        d.x -= d.x.round();
+        d.y -= d.y.round();
+        d.z -= d.z.round();
+
+        let mut result = f64::MAX;
+        for i in -1..=1 {
+            for j in -1..=1 {
+                for k in -1..=1 {
+                    let image = d + Vec3::new(i as f64, j as f64, k as f64);
+                    let dist_sq = self.fractional_to_cartesian(image).magnitude_squared();
+                    result = result.min(dist_sq);
+                }
+            }
+        }
+
+        result.sqrt()
+    }
 }
 
-/// Load the header, and density from Map data.
-fn read_header_dens<R: Read + Seek>(data: &mut R) -> io::Result<(MapHeader, Vec<f32>)> {
-    let hdr = read_map_header(&mut *data)?;
+/// Byte offset of the first density value, i.e. after the header and symmetry block.
+fn data_offset(hdr: &MapHeader) -> u64 {
+    HEADER_SIZE + hdr.inner.nsymbt.max(0) as u64
+}
 
-    if hdr.mode != 2 {
-        return Err(io::Error::new(
+/// The size in bytes of one voxel for a given MRC mode. Integer modes are read as their raw
+/// values; the header's min, max, and mean are in the same units.
+fn voxel_size(mode: i32) -> io::Result<usize> {
+    match mode {
+        0 => Ok(1),     // int8
+        1 | 6 => Ok(2), // int16, uint16
+        2 => Ok(4),     // float32
+        _ => Err(io::Error::new(
             ErrorKind::InvalidData,
-            format!("Unsupported mode: {}", hdr.mode),
-        ));
+            format!("Unsupported mode: {mode}"),
+        )),
     }
+}
 
-    data.seek(SeekFrom::Start(HEADER_SIZE + hdr.inner.nsymbt as u64))?;
+/// Read the full density block described by `hdr`, in file order.
+pub fn read_map_data<R: Read + Seek>(mut r: R, hdr: &MapHeader) -> io::Result<Vec<f32>> {
+    read_map_voxels(
+        &mut r,
+        hdr,
+        [0, 0, 0],
+        [hdr.nx as usize, hdr.ny as usize, hdr.nz as usize],
+    )
+}
 
-    let n = (hdr.nx * hdr.ny * hdr.nz) as usize;
-    let mut dens = Vec::with_capacity(n);
+/// Read a rectangular block of voxels, `start` (inclusive) to `end` (exclusive), indexed in
+/// file order (column, row, section). Each section of the block is a single contiguous read, from
+/// its first voxel to its last, and whole sections are read together. This maps well onto HTTP
+/// range requests when only a small region of a large map is required.
+///
+/// Returns values in file order, with the column index varying fastest.
+pub fn read_map_voxels<R: Read + Seek>(
+    mut r: R,
+    hdr: &MapHeader,
+    start: [usize; 3],
+    end: [usize; 3],
+) -> io::Result<Vec<f32>> {
+    let voxel_size = voxel_size(hdr.mode)?;
+
+    let dims = [hdr.nx as usize, hdr.ny as usize, hdr.nz as usize];
+    for i in 0..3 {
+        if start[i] > end[i] || end[i] > dims[i] {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Voxel range {}..{} out of bounds on axis {i} (size {})",
+                    start[i], end[i], dims[i]
+                ),
+            ));
+        }
+    }
 
-    for _ in 0..n {
-        dens.push(data.read_f32::<LittleEndian>()?);
+    let row_len = end[0] - start[0];
+    let n_rows = end[1] - start[1];
+    let n_sections = end[2] - start[2];
+
+    if row_len == 0 || n_rows == 0 || n_sections == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut result = Vec::with_capacity(row_len * n_rows * n_sections);
+
+    let decode = |buf: &[u8], result: &mut Vec<f32>| {
+        let chunks = buf.chunks_exact(voxel_size);
+        match hdr.mode {
+            0 => result.extend(chunks.map(|b| b[0] as i8 as f32)),
+            1 => result.extend(chunks.map(|b| i16::from_le_bytes([b[0], b[1]]) as f32)),
+            6 => result.extend(chunks.map(|b| u16::from_le_bytes([b[0], b[1]]) as f32)),
+            _ => result.extend(chunks.map(|b| f32::from_le_bytes(b.try_into().unwrap()))),
+        }
+    };
+
+    let seek_to = |r: &mut R, i_voxel: usize| {
+        r.seek(SeekFrom::Start(
+            data_offset(hdr) + (i_voxel * voxel_size) as u64,
+        ))
+    };
+
+    // Whole sections are contiguous in the file, so read them at once.
+    if row_len == dims[0] && n_rows == dims[1] {
+        let mut buf = vec![0; row_len * n_rows * n_sections * voxel_size];
+        seek_to(&mut r, start[2] * dims[1] * dims[0])?;
+        r.read_exact(&mut buf)?;
+
+        decode(&buf, &mut result);
+        return Ok(result);
+    }
+
+    // Otherwise, read the span from each section's first voxel to its last, and skip the parts
+    // of rows outside the block.
+    let stride = dims[0] * voxel_size;
+    let mut buf = vec![0; (n_rows - 1) * stride + row_len * voxel_size];
+
+    for k in start[2]..end[2] {
+        seek_to(&mut r, (k * dims[1] + start[1]) * dims[0] + start[0])?;
+        r.read_exact(&mut buf)?;
+
+        for j in 0..n_rows {
+            decode(
+                &buf[j * stride..j * stride + row_len * voxel_size],
+                &mut result,
+            );
+        }
     }
 
+    Ok(result)
+}
+
+/// Load the header, and density from Map data.
+fn read_header_dens<R: Read + Seek>(data: &mut R) -> io::Result<(MapHeader, Vec<f32>)> {
+    let hdr = read_map_header(&mut *data)?;
+    let dens = read_map_data(&mut *data, &hdr)?;
+
     Ok((hdr, dens))
 }
 
-pub(crate) fn get_origin_frac(hdr: &MapHeader, cell: &UnitCell) -> Vec3 {
-    if let (Some(ox), Some(oy), Some(oz)) =
-        (hdr.inner.xorigin, hdr.inner.yorigin, hdr.inner.zorigin)
-    {
-        cell.cartesian_to_fractional(Vec3::new(ox as f64, oy as f64, oz as f64))
+/// Which header fields set the map origin. A map can store its origin in two places: The MRC-2014
+/// origin words (words 50-52, in Å), and the CCP4 `n[xyz]start` grid offsets. Most maps set only
+/// one, or both consistently. Some set both inconsistently; use this to force one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OriginMode {
+    /// Use the MRC-2014 origin words. Missing (zero) words give an origin of 0.
+    Mrc2014,
+    /// Use `n[xyz]start / m[xyz]`, ignoring the origin words.
+    NStart,
+    /// Use the MRC-2014 origin words if all three are nonzero; otherwise `n[xyz]start`.
+    #[default]
+    Auto,
+}
+
+pub(crate) fn get_origin_frac(hdr: &MapHeader, cell: &UnitCell, mode: OriginMode) -> Vec3 {
+    let words = (hdr.inner.xorigin, hdr.inner.yorigin, hdr.inner.zorigin);
+
+    let use_words = match mode {
+        OriginMode::Mrc2014 => true,
+        OriginMode::NStart => false,
+        OriginMode::Auto => matches!(words, (Some(_), Some(_), Some(_))),
+    };
+
+    if use_words {
+        let (ox, oy, oz) = words;
+        cell.cartesian_to_fractional(Vec3::new(
+            ox.unwrap_or(0.) as f64,
+            oy.unwrap_or(0.) as f64,
+            oz.unwrap_or(0.) as f64,
+        ))
     } else {
         Vec3::new(
             hdr.inner.nxstart as f64 / hdr.inner.mx as f64,
@@ -299,6 +813,8 @@ pub struct DensityMap {
     pub hdr: MapHeader,
     /// Header origin, already converted to fractional
     pub origin_frac: Vec3,
+    /// Which header fields `origin_frac` was taken from. Set with [`Self::with_origin_mode`].
+    pub origin_mode: OriginMode,
     /// A map from file axis to crystal axis
     pub perm_f2c: [usize; 3],
     /// A map from crystal axis to file axis. (Reverse of `perm_f2c`)
@@ -308,6 +824,21 @@ pub struct DensityMap {
     pub(crate) mean: f32,
     /// Sigma; used for normalizing data, e.g. prior to display.
     pub(crate) inv_sigma: f32,
+    /// Calculated from the data, as with `mean`.
+    pub(crate) min: f32,
+    pub(crate) max: f32,
+}
+
+/// How to scale density values, e.g. for display. See [`DensityMap::normalize`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum NormMode {
+    /// Values as stored, e.g. in e · Å⁻³.
+    Raw,
+    /// Standard deviations from the mean. See [`DensityMap::density_to_sig`].
+    #[default]
+    Sigma,
+    /// The map's minimum maps to 0, and its maximum to 1.
+    MinMax,
 }
 
 impl DensityMap {
@@ -323,7 +854,8 @@ impl DensityMap {
             perm_c2f[*c] = f;
         }
 
-        let origin_frac = get_origin_frac(&hdr, &hdr.inner.cell);
+        let origin_mode = OriginMode::default();
+        let origin_frac = get_origin_frac(&hdr, &hdr.inner.cell, origin_mode);
 
         let n = data.len() as f32;
 
@@ -340,113 +872,146 @@ impl DensityMap {
         let sigma = variance.sqrt().max(1e-6); // guard against σ ≈ 0
         let inv_sigma = 1. / sigma;
 
+        let min = data.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = data.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+
         Ok(Self {
             hdr,
             origin_frac,
+            origin_mode,
             perm_f2c,
             perm_c2f,
             data,
             mean,
             inv_sigma,
+            min,
+            max,
         })
     }
+
+    /// Re-derive the origin from the header using `mode`. Use this if a map's density is misplaced
+    /// because its origin words and `n[xyz]start` disagree. See [`OriginMode`].
+    pub fn with_origin_mode(mut self, mode: OriginMode) -> Self {
+        self.origin_mode = mode;
+        self.origin_frac = get_origin_frac(&self.hdr, &self.hdr.inner.cell, mode);
+        self
+    }
+
     /// Create a new density map, e.g. from a File or byte array.
     pub fn open<R: Read + Seek>(data: &mut R) -> io::Result<Self> {
         let (hdr, data) = read_header_dens(data)?;
         Self::new(hdr, data)
     }
 
-    /// Uses nearest-neighbour lookup to calculate density at a point.
-    pub fn density_at_point(&self, cart: Vec3) -> f32 {
-        // Cartesian to fractional (wrap to [0,1) )
-        let mut frac = self.hdr.inner.cell.cartesian_to_fractional(cart);
-        frac.x -= frac.x.floor();
-        frac.y -= frac.y.floor();
-        frac.z -= frac.z.floor();
-
-        // Fractional to crystallographic voxel index.
-        let ic = [
-            (frac.x * self.hdr.inner.mx as f64 - 0.5).round() as isize,
-            (frac.y * self.hdr.inner.my as f64 - 0.5).round() as isize,
-            (frac.z * self.hdr.inner.mz as f64 - 0.5).round() as isize,
-        ];
-
-        // Crystallographic ➜ file order
-        let ifile = [
-            pmod(ic[self.perm_c2f[0]], self.hdr.nx as usize),
-            pmod(ic[self.perm_c2f[1]], self.hdr.ny as usize),
-            pmod(ic[self.perm_c2f[2]], self.hdr.nz as usize),
-        ];
+    /// Create a new density map from any seekable reader. The header is read first, then the
+    /// density block, each in a single read. This works with remote sources, e.g. [`remote::HttpRangeReader`] when the
+    /// `remote` feature is enabled. To load only part of the grid, use [`read_map_header`] and
+    /// [`read_map_voxels`] directly.
+    pub fn from_reader<R: Read + Seek>(mut r: R) -> io::Result<Self> {
+        Self::open(&mut r)
+    }
 
-        // Linear offset in file order (x is fastest dimesion, from the experimental data)
-        let offset = (ifile[2] * self.hdr.ny as usize + ifile[1]) * self.hdr.nx as usize + ifile[0];
+    /// Uses nearest-neighbour lookup to calculate density at a point. Voxel `(0, 0, 0)` is at the
+    /// map origin (`origin_frac`), so this is correct for maps with a nonzero `nxstart` etc.
+    pub fn density_at_point(&self, cart: Vec3) -> f32 {
+        self.nearest(self.grid_coords(cart))
+    }
 
-        // Divide by volume: These values are density per unit volume. This is required to get consistent values
-        // that are invariant of the cell parameters, and that can be used in physical calculations.
-        self.data[offset]
+    /// Like [`Self::density_at_point`], but ignores the map origin, and treats voxels as centered
+    /// half a voxel from their grid point, as earlier versions did. This is only correct for maps
+    /// whose origin is at the cell origin. Unlike earlier versions, axes are mapped to file order
+    /// the same way as in [`Self::density_at_point`]; for maps whose axis order (`mapc`, `mapr`,
+    /// `maps`) isn't x, y, z, values may differ from those versions.
+    pub fn density_at_point_raw(&self, cart: Vec3) -> f32 {
+        self.nearest(self.grid_coords_raw(cart))
     }
 
     /// Electron-density value at a Cartesian point, using periodic trilinear
     /// interpolation.  Returned value is still in whatever scale `self.data`
     /// is stored (e·Å⁻³ or σ-units after your normalization pass).
     ///
-    /// This produces smoother visuals than the nearest-neighbor approach.
+    /// This produces smoother visuals than the nearest-neighbor approach. As with
+    /// [`Self::density_at_point`], this accounts for the map origin.
     pub fn density_at_point_trilinear(&self, cart: Vec3) -> f32 {
-        // 1. Cartesian → fractional, wrap into [0,1]
-        let mut frac = self.hdr.inner.cell.cartesian_to_fractional(cart);
+        self.trilinear(self.grid_coords(cart))
+    }
 
-        // shift by the map origin if you want strict CCP4 compliance:
-        // frac -= self.origin_frac;
+    /// Like [`Self::density_at_point_trilinear`], but without the origin shift. See
+    /// [`Self::density_at_point_raw`], including how this differs from earlier versions.
+    pub fn density_at_point_trilinear_raw(&self, cart: Vec3) -> f32 {
+        self.trilinear(self.grid_coords_raw(cart))
+    }
 
+    /// Cartesian to grid coordinates along the crystallographic axes, in voxels from the map
+    /// origin. Per CCP4, grid point `i` is at fractional coordinate `origin + i / m`.
+    fn grid_coords(&self, cart: Vec3) -> [f64; 3] {
+        let frac = self.hdr.inner.cell.cartesian_to_fractional(cart) - self.origin_frac;
+
+        [
+            frac.x * self.hdr.inner.mx as f64,
+            frac.y * self.hdr.inner.my as f64,
+            frac.z * self.hdr.inner.mz as f64,
+        ]
+    }
+
+    /// Grid coordinates without the origin shift, and offset by half a voxel.
+    fn grid_coords_raw(&self, cart: Vec3) -> [f64; 3] {
+        // Cartesian to fractional (wrap to [0,1) )
+        let mut frac = self.hdr.inner.cell.cartesian_to_fractional(cart);
         frac.x -= frac.x.floor();
         frac.y -= frac.y.floor();
         frac.z -= frac.z.floor();
 
-        // Crystallographic fractional → cryst grid coordinates
-        // grid coordinate in *float* space; (0 … mx−1) etc.
-        let gx = frac.x * self.hdr.inner.mx as f64 - 0.5;
-        let gy = frac.y * self.hdr.inner.my as f64 - 0.5;
-        let gz = frac.z * self.hdr.inner.mz as f64 - 0.5;
-
-        let ix0 = gx.floor() as isize;
-        let iy0 = gy.floor() as isize;
-        let iz0 = gz.floor() as isize;
+        [
+            frac.x * self.hdr.inner.mx as f64 - 0.5,
+            frac.y * self.hdr.inner.my as f64 - 0.5,
+            frac.z * self.hdr.inner.mz as f64 - 0.5,
+        ]
+    }
 
-        let dx = (gx - ix0 as f64) as f32; // 0 … 1
-        let dy = (gy - iy0 as f64) as f32;
-        let dz = (gz - iz0 as f64) as f32;
+    /// The value at a crystallographic voxel index, wrapping periodically.
+    fn voxel(&self, ic: [isize; 3]) -> f32 {
+        let dims = [
+            self.hdr.nx as usize,
+            self.hdr.ny as usize,
+            self.hdr.nz as usize,
+        ];
 
-        // weights for the eight corners
-        let wx = [1.0 - dx, dx];
-        let wy = [1.0 - dy, dy];
-        let wz = [1.0 - dz, dz];
+        // Crystallographic ➜ file order
+        let ifile = [
+            pmod(ic[self.perm_f2c[0]], dims[0]),
+            pmod(ic[self.perm_f2c[1]], dims[1]),
+            pmod(ic[self.perm_f2c[2]], dims[2]),
+        ];
 
-        // Accumulate weighted density from the 8 surrounding voxels
-        let mut rho = 0.;
+        // Linear offset in file order (x is fastest dimesion, from the experimental data)
+        self.data[(ifile[2] * dims[1] + ifile[1]) * dims[0] + ifile[0]]
+    }
 
-        for (cz, w_z) in [iz0, iz0 + 1].iter().zip(wz) {
-            // Convert Cryst-Z to file-Z
-            let fz = pmod(*cz, self.hdr.nz as usize);
+    fn nearest(&self, g: [f64; 3]) -> f32 {
+        self.voxel(g.map(|v| v.round() as isize))
+    }
 
-            for (cy, w_y) in [iy0, iy0 + 1].iter().zip(wy) {
-                let fy = pmod(*cy, self.hdr.ny as usize);
-                for (cx, w_x) in [ix0, ix0 + 1].iter().zip(wx) {
-                    let fx = pmod(*cx, self.hdr.nx as usize);
+    fn trilinear(&self, g: [f64; 3]) -> f32 {
+        let i0 = g.map(|v| v.floor() as isize);
 
-                    // crystallographic → file order permutation
-                    let ifile = [fx, fy, fz];
+        // weights for the eight corners
+        let w: [[f32; 2]; 3] = std::array::from_fn(|a| {
+            let d = (g[a] - i0[a] as f64) as f32; // 0 … 1
+            [1. - d, d]
+        });
 
-                    let voxel = [
-                        ifile[self.perm_c2f[0]],
-                        ifile[self.perm_c2f[1]],
-                        ifile[self.perm_c2f[2]],
+        // Accumulate weighted density from the 8 surrounding voxels
+        let mut rho = 0.;
+        for (dz, w_z) in w[2].iter().enumerate() {
+            for (dy, w_y) in w[1].iter().enumerate() {
+                for (dx, w_x) in w[0].iter().enumerate() {
+                    let ic = [
+                        i0[0] + dx as isize,
+                        i0[1] + dy as isize,
+                        i0[2] + dz as isize,
                     ];
-
-                    let offset = (voxel[2] * self.hdr.ny as usize + voxel[1])
-                        * self.hdr.nx as usize
-                        + voxel[0];
-
-                    rho += w_x * w_y * w_z * self.data[offset];
+                    rho += w_x * w_y * w_z * self.voxel(ic);
                 }
             }
         }
@@ -464,10 +1029,81 @@ impl DensityMap {
         (val - self.mean) * self.inv_sigma
     }
 
-    /// Load a map from file.
+    /// Scale a density value using `mode`. A generalization of [`Self::density_to_sig`]. For
+    /// `MinMax`, a map with constant density gives 0.
+    pub fn normalize(&self, val: f32, mode: NormMode) -> f32 {
+        match mode {
+            NormMode::Raw => val,
+            NormMode::Sigma => self.density_to_sig(val),
+            NormMode::MinMax => {
+                let range = self.max - self.min;
+                if range > 0. {
+                    (val - self.min) / range
+                } else {
+                    0.
+                }
+            }
+        }
+    }
+
+    /// The mean density, calculated from the data. (Not the header's `dmean`.)
+    pub fn mean(&self) -> f32 {
+        self.mean
+    }
+
+    /// The standard deviation of the density, calculated from the data.
+    pub fn sigma(&self) -> f32 {
+        1. / self.inv_sigma
+    }
+
+    /// The minimum density, calculated from the data.
+    pub fn min(&self) -> f32 {
+        self.min
+    }
+
+    /// The maximum density, calculated from the data.
+    pub fn max(&self) -> f32 {
+        self.max
+    }
+
+    /// The volume, in Å³, of the region with density above `level_sigma`, in sigma units. (See
+    /// `density_to_sig`). This is the number of voxels above the threshold, times the volume of
+    /// one voxel. Useful for choosing a contour level that encloses e.g. the expected molecular
+    /// volume.
+    pub fn enclosed_volume(&self, level_sigma: f32) -> f64 {
+        let inner = &self.hdr.inner;
+        let voxel_vol = inner.cell.volume() / (inner.mx as f64 * inner.my as f64 * inner.mz as f64);
+
+        let count = self
+            .data
+            .iter()
+            .filter(|v| self.density_to_sig(**v) > level_sigma)
+            .count();
+
+        count as f64 * voxel_vol
+    }
+
+    /// The fraction of the unit cell volume with density above `level_sigma`, in sigma units.
+    pub fn enclosed_fraction(&self, level_sigma: f32) -> f64 {
+        self.enclosed_volume(level_sigma) / self.hdr.inner.cell.volume()
+    }
+
+    /// Create a density map from in-memory data, e.g. a downloaded file. Gzipped data is
+    /// detected by its magic bytes and decompressed.
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        if bytes.starts_with(&GZIP_MAGIC) {
+            let mut decompressed = Vec::new();
+            GzDecoder::new(bytes).read_to_end(&mut decompressed)?;
+            return Self::open(&mut Cursor::new(decompressed));
+        }
+
+        Self::open(&mut Cursor::new(bytes))
+    }
+
+    /// Load a map from file. CCP4 and MRC maps share a format, so this accepts `.map`, `.ccp4`,
+    /// and `.mrc` files, as well as gzipped versions of these, e.g. `.map.gz`.
     pub fn load(path: &Path) -> io::Result<Self> {
-        let mut file = File::open(path)?;
-        Self::open(&mut file)
+        Self::from_bytes(&fs::read(path)?)
     }
 
     /// Save the density map to a file.
@@ -523,13 +1159,14 @@ impl DensityMap {
             hdr_buf.write_i32::<LittleEndian>(self.hdr.inner.my)?; // 9
             hdr_buf.write_i32::<LittleEndian>(self.hdr.inner.mz)?; // 10
 
-            // 11..16 cell a,b,c,α,β,γ
-            hdr_buf.write_f32::<LittleEndian>(self.hdr.inner.cell.a as f32)?;
-            hdr_buf.write_f32::<LittleEndian>(self.hdr.inner.cell.b as f32)?;
-            hdr_buf.write_f32::<LittleEndian>(self.hdr.inner.cell.c as f32)?;
-            hdr_buf.write_f32::<LittleEndian>(self.hdr.inner.cell.alpha as f32)?;
-            hdr_buf.write_f32::<LittleEndian>(self.hdr.inner.cell.beta as f32)?;
-            hdr_buf.write_f32::<LittleEndian>(self.hdr.inner.cell.gamma as f32)?;
+            // 11..16 cell a,b,c,α,β,γ. The file stores angles in degrees; we store radians.
+            let cell = &self.hdr.inner.cell;
+            hdr_buf.write_f32::<LittleEndian>(cell.a as f32)?;
+            hdr_buf.write_f32::<LittleEndian>(cell.b as f32)?;
+            hdr_buf.write_f32::<LittleEndian>(cell.c as f32)?;
+            hdr_buf.write_f32::<LittleEndian>(cell.alpha.to_degrees() as f32)?;
+            hdr_buf.write_f32::<LittleEndian>(cell.beta.to_degrees() as f32)?;
+            hdr_buf.write_f32::<LittleEndian>(cell.gamma.to_degrees() as f32)?;
 
             hdr_buf.write_i32::<LittleEndian>(self.hdr.inner.mapc)?; // 17 MAPC
             hdr_buf.write_i32::<LittleEndian>(self.hdr.inner.mapr)?; // 18 MAPR
@@ -576,11 +1213,17 @@ impl DensityMap {
             hdr_buf.write_f32::<LittleEndian>(rms)?;
 
             // Word 56: NLABL
-            let nlabel: i32 = 0;
-            hdr_buf.write_i32::<LittleEndian>(nlabel)?;
-
-            // Words 57..256: 10 × 80-char labels (we'll leave empty)
-            // = 800 bytes
+            let labels = &self.hdr.labels[..self.hdr.labels.len().min(NUM_LABELS)];
+            hdr_buf.write_i32::<LittleEndian>(labels.len() as i32)?;
+
+            // Words 57..256: 10 × 80-char labels, space-padded. Unused ones are left as 0.
+            for label in labels {
+                let mut buf = [b' '; LABEL_LEN];
+                let bytes = label.as_bytes();
+                let len = bytes.len().min(LABEL_LEN);
+                buf[..len].copy_from_slice(&bytes[..len]);
+                hdr_buf.extend_from_slice(&buf);
+            }
             hdr_buf.resize(HEADER_SIZE as usize, 0);
         }
 
@@ -702,6 +1345,140 @@ pub fn density_from_2fo_fc_rcsb_gemmi(
     Ok(result)
 }
 
+/// Download a structure's coordinates and its 2Fo-Fc density from RCSB, ready for sampling the
+/// density around the model. The map is synthesized from structure factors by Gemmi, which must be
+/// available on the PATH env var. Returns an error if the model's unit cell doesn't match the
+/// map's, as the density would then be misplaced. Entries without a cell, e.g. cryo-EM structures,
+/// aren't checked.
+#[cfg(feature = "remote")]
+pub fn load_structure_with_density(pdb_id: &str) -> io::Result<(MmCif, DensityMap)> {
+    let cif = MmCif::load_rcsb(pdb_id)?;
+    let map = density_from_2fo_fc_rcsb_gemmi(pdb_id, None)?;
+
+    if let Some(cell) = &cif.unit_cell
+        && !cell.is_compatible(&map.hdr.inner.cell)
+    {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Unit cell of {pdb_id}'s coordinates ({:.2}, {:.2}, {:.2}) doesn't match its density map's \
+                 ({:.2}, {:.2}, {:.2})",
+                cell.a,
+                cell.b,
+                cell.c,
+                map.hdr.inner.cell.a,
+                map.hdr.inner.cell.b,
+                map.hdr.inner.cell.c
+            ),
+        ));
+    }
+
+    Ok((cif, map))
+}
+
+/// Reading maps over HTTP, using range requests. This allows sampling a region of a large
+/// remote map without downloading the whole file.
+#[cfg(feature = "remote")]
+pub mod remote {
+    use std::io::{self, ErrorKind, Read, Seek, SeekFrom};
+
+    /// A [`Read`] + [`Seek`] adapter over an HTTP resource. Each read issues a `Range` request
+    /// for the bytes requested, so wrap it in a `BufReader` if reading many small values.
+    /// The server must support range requests.
+    pub struct HttpRangeReader {
+        url: String,
+        len: u64,
+        pos: u64,
+    }
+
+    fn http_err(e: ureq::Error) -> io::Error {
+        io::Error::other(format!("HTTP error: {e}"))
+    }
+
+    impl HttpRangeReader {
+        /// Issues a `HEAD` request to determine the resource's length.
+        pub fn new(url: &str) -> io::Result<Self> {
+            let resp = ureq::head(url)
+                .header("Accept-Encoding", "identity")
+                .call()
+                .map_err(http_err)?;
+
+            let len = resp
+                .headers()
+                .get("content-length")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<u64>().ok())
+                .ok_or_else(|| {
+                    io::Error::new(ErrorKind::InvalidData, "Missing Content-Length on map URL")
+                })?;
+
+            Ok(Self {
+                url: url.to_owned(),
+                len,
+                pos: 0,
+            })
+        }
+
+        /// The length of the remote resource, in bytes.
+        pub fn len(&self) -> u64 {
+            self.len
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.len == 0
+        }
+    }
+
+    impl Read for HttpRangeReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if buf.is_empty() || self.pos >= self.len {
+                return Ok(0);
+            }
+
+            let end = (self.pos + buf.len() as u64).min(self.len) - 1;
+            let mut resp = ureq::get(&self.url)
+                .header("Accept-Encoding", "identity")
+                .header("Range", &format!("bytes={}-{end}", self.pos))
+                .call()
+                .map_err(http_err)?;
+
+            if resp.status().as_u16() != 206 {
+                return Err(io::Error::new(
+                    ErrorKind::Unsupported,
+                    "Server does not support HTTP range requests",
+                ));
+            }
+
+            let bytes = resp.body_mut().read_to_vec().map_err(http_err)?;
+            let n = bytes.len().min(buf.len());
+            buf[..n].copy_from_slice(&bytes[..n]);
+            self.pos += n as u64;
+
+            Ok(n)
+        }
+    }
+
+    impl Seek for HttpRangeReader {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            let new = match pos {
+                SeekFrom::Start(v) => v as i64,
+                SeekFrom::End(v) => self.len as i64 + v,
+                SeekFrom::Current(v) => self.pos as i64 + v,
+            };
+
+            if new < 0 {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    "Seek to a negative position",
+                ));
+            }
+
+            self.pos = new as u64;
+            Ok(self.pos)
+        }
+    }
+}
+
 /// Positive modulus that always lands in 0..n-1
 fn pmod(i: isize, n: usize) -> usize {
     ((i % n as isize) + n as isize) as usize % n
@@ -191,6 +191,9 @@ pub struct DihedralParams {
     /// particular connectivity.
     pub periodicity: u8,
     pub comment: Option<String>,
+    /// From the `penalty score=` note Antechamber's `parmchk2` appends to estimated parameters.
+    /// Higher values indicate a less reliable estimate. This is removed from `comment`.
+    pub penalty_score: Option<f32>,
 }
 
 impl DihedralParams {
@@ -239,6 +242,17 @@ impl DihedralParams {
             comment = Some(cols[col1_i + 3..].join(" "));
         }
 
+        // e.g. `same as X -c3-n -X , penalty score= 18.0`
+        let mut penalty_score = None;
+        if let Some(c) = &comment
+            && let Some((note, score)) = c.split_once("penalty score=")
+        {
+            penalty_score = Some(parse_float(score.trim())?);
+
+            let note = note.trim().trim_end_matches(',').trim();
+            comment = Some(note.to_owned());
+        }
+
         Ok((
             Self {
                 atom_types,
@@ -247,6 +261,7 @@ impl DihedralParams {
                 phase,
                 periodicity,
                 comment,
+                penalty_score,
             },
             improper,
         ))
@@ -269,16 +284,16 @@ pub struct LjParams {
     pub eps: f32,
 }
 
+// todo: QC this factor of 2!
+// 1.122 is 2^(1/6)
+// todo: We're getting conflicting information on if we should
+// todo use a factor of 2, or 4 as the prefix here.
+const SIGMA_FACTOR: f32 = 2. / 1.122_462_048_309_373;
+
 impl LjParams {
-    /// Parse a single van-der-Waals (Lennard-Jones) parameter line in a dat file, e.g. `parm19.dat`
-    /// from Amber.
+    /// Parse a single van-der-Waals (Lennard-Jones) parameter line in a dat or frcmod file, e.g.
+    /// `parm19.dat` from Amber.
     pub fn from_line(line: &str) -> io::Result<Self> {
-        // todo: QC this factor of 2!
-        // 1.122 is 2^(1/6)
-        // todo: We're getting conflicting information on if we should
-        // todo use a factor of 2, or 4 as the prefix here.
-        const SIGMA_FACTOR: f32 = 2. / 1.122_462_048_309_373;
-
         let cols: Vec<_> = line.split_whitespace().collect();
 
         if cols.len() < 3 {
@@ -300,6 +315,106 @@ impl LjParams {
             eps,
         })
     }
+
+    /// R star (R_min / 2), in Å, as stored in Amber parameter files. The inverse of the conversion
+    /// to σ in `from_line`.
+    pub fn r_star(&self) -> f32 {
+        self.sigma / SIGMA_FACTOR
+    }
+}
+
+/// A CMAP correction map: Grid-based energy corrections over the φ and ψ backbone dihedrals,
+/// e.g. per-residue maps in ff19SB. Parsed from `%FLAG CMAP_*` blocks.
+#[derive(Clone, Debug, Default)]
+pub struct CmapData {
+    /// Number of grid points along each of φ and ψ, spanning 360°.
+    pub resolution: usize,
+    /// Energy corrections, kcal/mol. `resolution²` values, in the order listed in the file.
+    pub grid: Vec<f32>,
+    pub title: String,
+    /// Residues this map applies to, e.g. "ALA".
+    pub residues: Vec<String>,
+    /// Atom types of the atoms defining φ and ψ, if listed.
+    pub atom_types: Vec<String>,
+}
+
+impl CmapData {
+    /// Parse the `%FLAG CMAP_*` lines of one or more maps. Each map starts with `CMAP_COUNT`.
+    /// A flag's values are on its line, and any following lines up to the next flag.
+    pub fn from_lines(lines: &[&str]) -> io::Result<Vec<Self>> {
+        // Flag name, and its values.
+        let mut flags: Vec<(&str, Vec<&str>)> = Vec::new();
+
+        for line in lines {
+            // Strip comments.
+            let line = line.split('!').next().unwrap_or_default().trim();
+            if line.is_empty() || line.starts_with("%COMMENT") {
+                continue;
+            }
+
+            let mut cols = line.split_whitespace();
+            if line.starts_with("%FLAG") {
+                cols.next();
+                let name = cols.next().unwrap_or_default();
+                flags.push((name, cols.collect()));
+            } else if let Some((_, vals)) = flags.last_mut() {
+                vals.extend(cols);
+            }
+        }
+
+        let mut result: Vec<Self> = Vec::new();
+
+        for (name, vals) in flags {
+            if name == "CMAP_COUNT" || result.is_empty() {
+                result.push(Default::default());
+            }
+            let cmap = result.last_mut().unwrap();
+
+            // Lists may start with their length.
+            let list = || {
+                let skip = usize::from(vals.len() > 1 && vals[0].parse::<usize>().is_ok());
+                vals[skip..].iter().map(|v| v.to_string()).collect()
+            };
+
+            match name {
+                "CMAP_TITLE" => cmap.title = vals.join(" "),
+                "CMAP_RESOLUTION" => {
+                    let v = vals.first().unwrap_or(&"");
+                    cmap.resolution = v.parse().map_err(|_| {
+                        io::Error::new(
+                            ErrorKind::InvalidData,
+                            format!("Invalid CMAP resolution: {v}"),
+                        )
+                    })?;
+                }
+                "CMAP_RESLIST" => cmap.residues = list(),
+                "CMAP_ATOMLIST" | "CMAP_ATMLIST" => cmap.atom_types = list(),
+                "CMAP_PARAMETER" => {
+                    for v in vals {
+                        cmap.grid.push(parse_float(v)?);
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        for cmap in &result {
+            if cmap.grid.len() != cmap.resolution.pow(2) {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "CMAP {} has {} values; expected {} for resolution {}",
+                        cmap.title,
+                        cmap.grid.len(),
+                        cmap.resolution.pow(2),
+                        cmap.resolution
+                    ),
+                ));
+            }
+        }
+
+        Ok(result)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -346,6 +461,8 @@ pub struct ForceFieldParamsVec {
     pub improper: Vec<DihedralParams>,
     pub mass: Vec<MassParams>,
     pub lennard_jones: Vec<LjParams>,
+    /// φ/ψ correction maps, e.g. for proteins in ff19SB.
+    pub cmap: Vec<CmapData>,
     pub remarks: Vec<String>,
 }
 
@@ -424,6 +541,8 @@ pub struct ForceFieldParams {
     pub improper: HashMap<(String, String, String, String), Vec<DihedralParams>>,
     pub mass: HashMap<String, MassParams>,
     pub lennard_jones: HashMap<String, LjParams>,
+    /// φ/ψ correction maps, e.g. for proteins in ff19SB.
+    pub cmap: Vec<CmapData>,
 }
 
 impl ForceFieldParams {
@@ -467,24 +586,53 @@ impl ForceFieldParams {
                 .insert(val.atom_type.clone(), val.clone());
         }
 
+        result.cmap = params.cmap.clone();
+
         result
     }
 
-    /// Merge `other` into a copy of `self`. Entries already present in `self` win;
-    /// entries only in `other` are added. This lets you build a comprehensive global
-    /// fallback from multiple force-field tables (e.g. GAFF2 + ff19SB).
+    /// Merge `other` into a copy of `self`. Entries already present in `self` win, including
+    /// over ones in `other` listed in the reverse atom order; entries only in `other` are added.
+    /// This lets you build a comprehensive global fallback from multiple force-field tables
+    /// (e.g. GAFF2 + ff19SB). This is [`Self::merge`] with the opposite precedence.
     pub fn merge_with(&self, other: &Self) -> Self {
         let mut result = other.clone();
-        result.mass.extend(self.mass.clone());
-        result.lennard_jones.extend(self.lennard_jones.clone());
-        result.bond.extend(self.bond.clone());
-        result.angle.extend(self.angle.clone());
-        // For dihedrals/impropers, self's entry replaces other's entirely (same key).
-        result.dihedral.extend(self.dihedral.clone());
-        result.improper.extend(self.improper.clone());
+        result.merge(self);
         result
     }
 
+    /// Merge `other` into `self`, in place. Entries in `other` win on key collisions, including
+    /// ones listed in the reverse atom order. Use this to apply a ligand-specific frcmod over
+    /// a base dat file, e.g. GAFF2. Dihedrals and impropers for a key are replaced as a set.
+    /// See [`Self::merge_with`] for a copy in which `self`'s entries win instead.
+    pub fn merge(&mut self, other: &Self) {
+        for (k, v) in &other.bond {
+            self.bond.remove(&(k.1.clone(), k.0.clone()));
+            self.bond.insert(k.clone(), v.clone());
+        }
+
+        for (k, v) in &other.angle {
+            self.angle.remove(&(k.2.clone(), k.1.clone(), k.0.clone()));
+            self.angle.insert(k.clone(), v.clone());
+        }
+
+        for (k, v) in &other.dihedral {
+            let rev = (k.3.clone(), k.2.clone(), k.1.clone(), k.0.clone());
+            self.dihedral.remove(&rev);
+            self.dihedral.insert(k.clone(), v.clone());
+        }
+
+        for (k, v) in &other.improper {
+            let rev = (k.3.clone(), k.2.clone(), k.1.clone(), k.0.clone());
+            self.improper.remove(&rev);
+            self.improper.insert(k.clone(), v.clone());
+        }
+
+        self.mass.extend(other.mass.clone());
+        self.lennard_jones.extend(other.lennard_jones.clone());
+        merge_cmap(&mut self.cmap, &other.cmap);
+    }
+
     /// A convenience wrapper.
     pub fn from_frcmod(text: &str) -> io::Result<Self> {
         Ok(Self::new(&ForceFieldParamsVec::from_frcmod(text)?))
@@ -524,9 +672,8 @@ impl ForceFieldParams {
         out
     }
 
-    /// A utility function that handles proper and improper dihedral data,
-    /// tries both atom orders, and falls back to wildcard (“X”) matches on
-    /// the outer atoms when an exact hit is not found.
+    /// Look up bond stretching parameters. Tries both atom orders, and, if `wildcard_allowed`,
+    /// falls back to wildcard (“X”) matches when an exact hit is not found.
     pub fn get_bond(
         &self,
         atom_types: &(String, String),
@@ -561,9 +708,8 @@ impl ForceFieldParams {
 
     // todo: YOu may need to augment all these helps with support for "C*", "N*" etc.
 
-    /// A utility function that handles proper and improper dihedral data,
-    /// tries both atom orders, and falls back to wildcard (“X”) matches on
-    /// the outer atoms when an exact hit is not found.
+    /// Look up angle bending parameters. Tries both atom orders (a-b-c and c-b-a), and, if
+    /// `wildcard_allowed`, falls back to wildcard (“X”) matches when an exact hit is not found.
     pub fn get_valence_angle(
         &self,
         atom_types: &(String, String, String),
@@ -648,6 +794,26 @@ impl ForceFieldParams {
         }
         None
     }
+
+    /// Lennard-Jones parameters for a pair of atom types, combined using the Lorentz-Berthelot
+    /// rules: σ(i, j) = 0.5 * (σ_i + σ_j), and ε(i, j) = sqrt(ε_i * ε_j). Returns (σ, ε), or `None`
+    /// if either type is missing.
+    pub fn lj_pair(&self, type_a: &str, type_b: &str) -> Option<(f32, f32)> {
+        let a = self.lennard_jones.get(type_a)?;
+        let b = self.lennard_jones.get(type_b)?;
+
+        Some((0.5 * (a.sigma + b.sigma), (a.eps * b.eps).sqrt()))
+    }
+}
+
+/// Add CMAPs from `other` to `cmap`, replacing any with the same title.
+fn merge_cmap(cmap: &mut Vec<CmapData>, other: &[CmapData]) {
+    for c in other {
+        match cmap.iter_mut().find(|v| v.title == c.title) {
+            Some(existing) => *existing = c.clone(),
+            None => cmap.push(c.clone()),
+        }
+    }
 }
 
 /// Helper to deal with spaces in the FF-type col, while still allowing col separation
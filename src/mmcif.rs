@@ -1,13 +1,12 @@
 //! Load atoms and related data (e.g. secondary structure) from mmCIF files.
 //! These are the coordinate files that come from the RCSB PDB.
 //!
-//! Note: This does not parse bond data. This is usually not available. Bonds can
-//! be inferred from computations. (Send in a Github issue or PR if you want bond support, and
-//! include an example mmCIF that has them.).
+//! Note: Bond data is limited to connections in the `_struct_conn` category, e.g. disulfide bonds,
+//! metal coordination, and bonds to ligands. Bonds within residues are usually not available, and
+//! can be inferred from computations.
 
 use std::{
-    collections::HashMap,
-    fs,
+    collections::{HashMap, HashSet},
     fs::File,
     io,
     io::{ErrorKind, Write},
@@ -17,14 +16,82 @@ use std::{
 
 use bio_apis::rcsb;
 use lin_alg::f64::Vec3;
-use na_seq::{AtomTypeInRes, Element};
+use na_seq::AtomTypeInRes;
 use regex::Regex;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    AtomGeneric, BackboneSS, ChainGeneric, ExperimentalMethod, ResidueEnd, ResidueGeneric,
-    ResidueType, mmcif_aux::load_ss,
+    AtomGeneric, BackboneSS, BondGeneric, BondType, ChainGeneric, ExperimentalMethod, ResidueEnd,
+    ResidueGeneric, ResidueType, SecondaryStructure, SymOp, UnitCell, element_from_symbol,
+    element_symbol, mmcif_aux::load_ss, parse_symop_xyz, read_text_file, set_residue_ends,
 };
 
+/// A per-residue secondary structure assignment, using the 8-state DSSP codes.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum SsCode {
+    /// H
+    AlphaHelix,
+    /// G
+    Helix310,
+    /// I
+    PiHelix,
+    /// E
+    Strand,
+    /// B
+    Bridge,
+    /// T
+    Turn,
+    /// S
+    Bend,
+    /// -
+    Coil,
+}
+
+impl SsCode {
+    pub fn to_char(self) -> char {
+        match self {
+            Self::AlphaHelix => 'H',
+            Self::Helix310 => 'G',
+            Self::PiHelix => 'I',
+            Self::Strand => 'E',
+            Self::Bridge => 'B',
+            Self::Turn => 'T',
+            Self::Bend => 'S',
+            Self::Coil => '-',
+        }
+    }
+
+    /// Parses a DSSP code. Blanks, and `C`, as used by some tools, are treated as coil.
+    pub fn from_char(c: char) -> io::Result<Self> {
+        Ok(match c {
+            'H' => Self::AlphaHelix,
+            'G' => Self::Helix310,
+            'I' => Self::PiHelix,
+            'E' => Self::Strand,
+            'B' => Self::Bridge,
+            'T' => Self::Turn,
+            'S' => Self::Bend,
+            '-' | ' ' | 'C' => Self::Coil,
+            _ => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Invalid DSSP code: {c}"),
+                ));
+            }
+        })
+    }
+
+    /// Reduce to the 3-state (helix, sheet, coil) representation.
+    pub fn to_3_state(self) -> SecondaryStructure {
+        match self {
+            Self::AlphaHelix | Self::Helix310 | Self::PiHelix => SecondaryStructure::Helix,
+            Self::Strand | Self::Bridge => SecondaryStructure::Sheet,
+            _ => SecondaryStructure::Coil,
+        }
+    }
+}
+
 /// Represents the most commonly-used data from the mmCIF format, used by the RCSB PDB to represent
 /// protein structures. May also be used in other cases, such as molecular dynamics snapshots.
 /// Note that it often does not include bond data or hydrogen atoms; these can be added automatically
@@ -33,17 +100,553 @@ use crate::{
 /// This struct will likely
 /// be used as an intermediate format, and converted to something application-specific.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct MmCif {
     pub ident: String,
     pub metadata: HashMap<String, String>,
     pub atoms: Vec<AtomGeneric>,
-    // This is sometimes included in mmCIF files, although seems to be absent
-    // from most (all?) on RCSB PDB.
-    // pub bonds: Vec<BondGeneric>,
+    /// From the `_struct_conn` category: Inter-residue connections such as disulfide bonds, metal
+    /// coordination, and covalent ligand attachments. Empty if the file has none.
+    pub bonds: Vec<BondGeneric>,
+    /// Chemical component descriptions from `_chem_comp`, keyed by component ID, e.g. "ATP".
+    pub chem_comps: HashMap<String, ChemComp>,
     pub chains: Vec<ChainGeneric>,
     pub residues: Vec<ResidueGeneric>,
     pub secondary_structure: Vec<BackboneSS>,
     pub experimental_method: Option<ExperimentalMethod>,
+    /// Atom positions for each model of an ensemble, e.g. from NMR, in the same order as `atoms`.
+    /// `atoms` holds the first model; when saving, its positions are used for the first model
+    /// instead of `models[0]`. Empty if there's only one model. Models whose atoms don't match the
+    /// first model's are skipped when loading.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_adapters::vec3_nested"))]
+    pub models: Vec<Vec<Vec3>>,
+    /// The crystallographic unit cell, from the `_cell` category. None if any of its lengths or
+    /// angles are missing, as is common for NMR structures, or don't form a valid cell. Note that
+    /// RCSB cryo-EM entries carry a placeholder `_cell` with 1 Å edges and 90° angles; check
+    /// `experimental_method` before relying on it.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_adapters::unit_cell_opt")
+    )]
+    pub unit_cell: Option<UnitCell>,
+    /// The Hermann-Mauguin space group symbol, e.g. "P 21 21 21", from
+    /// `_symmetry.space_group_name_H-M` or `_space_group.name_H-M_alt`. If neither is present, this
+    /// is the International Tables number, e.g. "19", from `_space_group.IT_number` or
+    /// `_symmetry.Int_Tables_number`.
+    pub space_group: Option<String>,
+    /// Symmetry operators of the space group, from `_space_group_symop.operation_xyz` or
+    /// `_symmetry_equiv.pos_as_xyz`. Empty if the file has none. See [`crate::expand_symmetry`].
+    pub symmetry_ops: Vec<SymOp>,
+}
+
+/// A chemical component, e.g. an amino acid or ligand, from the `_chem_comp` category.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChemComp {
+    /// E.g. "ADENOSINE-5'-TRIPHOSPHATE"
+    pub name: String,
+    /// E.g. "C10 H16 N5 O13 P3"
+    pub formula: Option<String>,
+    /// E.g. "L-peptide linking", "non-polymer"
+    pub type_: String,
+}
+
+fn parse_chem_comps(table: &Table) -> HashMap<String, ChemComp> {
+    let mut result = HashMap::new();
+    let Some(c_id) = table.col("id") else {
+        return result;
+    };
+
+    // "?" and "." are mmCIF's unknown and inapplicable values.
+    let get = |row: &[String], tag: &str| {
+        table
+            .col(tag)
+            .and_then(|c| row.get(c))
+            .filter(|v| *v != "?" && *v != ".")
+            .cloned()
+    };
+
+    for row in &table.rows {
+        let Some(id) = row.get(c_id) else {
+            continue;
+        };
+        result.insert(
+            id.clone(),
+            ChemComp {
+                name: get(row, "name").unwrap_or_default(),
+                formula: get(row, "formula"),
+                type_: get(row, "type").unwrap_or_default(),
+            },
+        );
+    }
+
+    result
+}
+
+/// Maps a `_struct_conn.conn_type_id`, and optional `pdbx_value_order`, to a bond type. Returns
+/// None for entries that aren't bonds, e.g. base-pair mismatches.
+fn conn_bond_type(conn_type: &str, value_order: Option<&str>) -> Option<BondType> {
+    let conn_type = conn_type.to_lowercase();
+
+    if conn_type.starts_with("covale") || conn_type == "disulf" {
+        return Some(
+            value_order
+                .and_then(|v| BondType::from_str(v).ok())
+                .unwrap_or(BondType::Single),
+        );
+    }
+
+    match conn_type.as_str() {
+        // Coordination bonds are dative; not a normal covalent bond.
+        "metalc" => Some(BondType::Dummy),
+        "hydrog" => Some(BondType::NotConnected),
+        "mismat" => None,
+        _ => Some(BondType::Unknown),
+    }
+}
+
+/// A single-item value from the metadata, unquoted. None if absent, or one of the CIF placeholders
+/// for unknown (`?`) or inapplicable (`.`) values.
+fn metadata_val<'a>(metadata: &'a HashMap<String, String>, tag: &str) -> Option<&'a str> {
+    let v = metadata.get(tag)?.as_str();
+    if v.is_empty() || v == "?" || v == "." {
+        None
+    } else {
+        Some(v)
+    }
+}
+
+/// Parse the operators in a symmetry table's `tag` column, e.g. "operation_xyz".
+fn parse_symmetry_ops(table: &Table, tag: &str) -> io::Result<Vec<SymOp>> {
+    let Some(c) = table.col(tag) else {
+        return Ok(Vec::new());
+    };
+
+    table
+        .rows
+        .iter()
+        .filter_map(|row| row.get(c))
+        .map(|v| parse_symop_xyz(v))
+        .collect()
+}
+
+fn unit_cell_from_metadata(metadata: &HashMap<String, String>) -> Option<UnitCell> {
+    let get =
+        |k: &str| -> Option<f64> { metadata_val(metadata, &format!("_cell.{k}"))?.parse().ok() };
+
+    UnitCell::try_new(
+        get("length_a")?,
+        get("length_b")?,
+        get("length_c")?,
+        get("angle_alpha")?,
+        get("angle_beta")?,
+        get("angle_gamma")?,
+    )
+    .ok()
+}
+
+fn space_group_from_metadata(metadata: &HashMap<String, String>) -> Option<String> {
+    [
+        "_symmetry.space_group_name_H-M",
+        "_space_group.name_H-M_alt",
+        "_space_group.IT_number",
+        "_symmetry.Int_Tables_number",
+    ]
+    .iter()
+    .find_map(|tag| metadata_val(metadata, tag))
+    .map(str::to_owned)
+}
+
+/// Choose one alternate conformation ID from these atoms, according to `policy`. For
+/// [`AltLocPolicy::HighestOccupancy`], compares the mean occupancy of each ID's atoms.
+fn choose_altloc<'a>(atoms: &[&'a AtomGeneric], policy: AltLocPolicy) -> Option<&'a str> {
+    // Each ID, in order of first appearance, with its occupancy sum and atom count.
+    let mut ids: Vec<(&str, f32, usize)> = Vec::new();
+    for atom in atoms {
+        let Some(id) = atom.alt_conformation_id.as_deref() else {
+            continue;
+        };
+        let occ = atom.occupancy.unwrap_or(1.);
+
+        match ids.iter_mut().find(|(v, _, _)| *v == id) {
+            Some((_, sum, n)) => {
+                *sum += occ;
+                *n += 1;
+            }
+            None => ids.push((id, occ, 1)),
+        }
+    }
+
+    let first = ids.first()?.0;
+    let mean = |(_, sum, n): &(&str, f32, usize)| sum / *n as f32;
+
+    Some(match policy {
+        // Ties go to the first listed.
+        AltLocPolicy::HighestOccupancy => {
+            ids.iter()
+                .fold(
+                    &ids[0],
+                    |best, v| if mean(v) > mean(best) { v } else { best },
+                )
+                .0
+        }
+        AltLocPolicy::First => first,
+        AltLocPolicy::SpecificId(id) => ids
+            .iter()
+            .map(|(v, _, _)| *v)
+            .find(|v| v.len() == id.len_utf8() && v.starts_with(id))
+            .unwrap_or(first),
+    })
+}
+
+/// An atom's name, as written in the file if available, e.g. "CA" or "HG21".
+fn atom_name(atom: &AtomGeneric) -> Option<String> {
+    match (&atom.type_in_res_general, &atom.type_in_res) {
+        (Some(name), _) => Some(name.clone()),
+        (None, Some(AtomTypeInRes::Hetero(name))) => Some(name.clone()),
+        (None, Some(t)) => Some(t.to_string()),
+        (None, None) => None,
+    }
+}
+
+/// Removes quotes around mmCIF values, e.g. atom names like `"O5'"`.
+fn unquote(s: &str) -> &str {
+    let b = s.as_bytes();
+    if b.len() >= 2 && (b[0] == b'"' || b[0] == b'\'') && b[b.len() - 1] == b[0] {
+        &s[1..s.len() - 1]
+    } else {
+        s
+    }
+}
+
+/// Split a line of loop values on whitespace, keeping quoted values, which may contain spaces,
+/// together. Quotes are removed.
+fn split_values(line: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let chars: Vec<char> = line.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i].is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        if chars[i] == '\'' || chars[i] == '"' {
+            // A closing quote must be followed by whitespace, or the end of the line.
+            let quote = chars[i];
+            i += 1;
+            while i < chars.len()
+                && !(chars[i] == quote && chars.get(i + 1).is_none_or(|c| c.is_whitespace()))
+            {
+                i += 1;
+            }
+            result.push(chars[start + 1..i.min(chars.len())].iter().collect());
+            i += 1;
+        } else {
+            while i < chars.len() && !chars[i].is_whitespace() {
+                i += 1;
+            }
+            result.push(chars[start..i].iter().collect());
+        }
+    }
+
+    result
+}
+
+/// Read a multi-line text field, which starts with a line beginning with ';', and ends with a line
+/// containing only ';'. `start` is the index of the opening line. Returns the value, and the index
+/// of the line after the closing one.
+fn read_text_field(lines: &[&str], start: usize) -> (String, usize) {
+    let mut val = lines[start][1..].to_owned();
+
+    let mut i = start + 1;
+    while i < lines.len() && !lines[i].starts_with(';') {
+        val.push('\n');
+        val.push_str(lines[i]);
+        i += 1;
+    }
+
+    (val.trim().to_owned(), (i + 1).min(lines.len()))
+}
+
+/// Categories, other than `_atom_site`, we read into tables.
+const TABLE_CATEGORIES: &[&str] = &[
+    "_struct_conn",
+    "_chem_comp",
+    "_space_group_symop",
+    "_symmetry_equiv",
+];
+
+/// The values of a category, from a loop, or a set of key-value pairs.
+#[derive(Default)]
+struct Table {
+    /// Tags without the category prefix, e.g. "conn_type_id".
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    fn col(&self, tag: &str) -> Option<usize> {
+        self.headers.iter().position(|h| h == tag)
+    }
+
+    /// A category written as key-value pairs, as is done when there's only one row.
+    fn from_metadata(metadata: &HashMap<String, String>, category: &str) -> Option<Self> {
+        let prefix = format!("{category}.");
+        let (headers, row): (Vec<_>, Vec<_>) = metadata
+            .iter()
+            .filter_map(|(k, v)| Some((k.strip_prefix(&prefix)?.to_owned(), unquote(v).to_owned())))
+            .unzip();
+
+        if headers.is_empty() {
+            return None;
+        }
+        Some(Self {
+            headers,
+            rows: vec![row],
+        })
+    }
+}
+
+/// Resolve `_struct_conn` rows to bonds, using serial numbers keyed by (asym ID, seq ID, atom
+/// name), with the seq ID being `label_seq_id`, or `auth_seq_id` for non-polymer residues.
+fn struct_conn_bonds(
+    table: &Table,
+    sn_by_label: &HashMap<(&str, &str, &str), u32>,
+    sn_by_auth: &HashMap<(&str, &str, &str), u32>,
+) -> Vec<BondGeneric> {
+    let col = |tag: &str| table.col(tag);
+
+    let Some(c_type) = col("conn_type_id") else {
+        return Vec::new();
+    };
+    let c_order = col("pdbx_value_order");
+
+    let partner = |row: &[String], p: &str| -> Option<u32> {
+        let asym = row.get(col(&format!("{p}_label_asym_id"))?)?;
+        let atom = row.get(col(&format!("{p}_label_atom_id"))?)?;
+        let seq = row.get(col(&format!("{p}_label_seq_id"))?)?;
+
+        if seq != "." && seq != "?" {
+            return sn_by_label
+                .get(&(asym.as_str(), seq.as_str(), atom.as_str()))
+                .copied();
+        }
+
+        let auth_seq = row.get(col(&format!("{p}_auth_seq_id"))?)?;
+        sn_by_auth
+            .get(&(asym.as_str(), auth_seq.as_str(), atom.as_str()))
+            .copied()
+    };
+
+    let mut result = Vec::new();
+    for row in &table.rows {
+        let Some(conn_type) = row.get(c_type) else {
+            continue;
+        };
+        let order = c_order.and_then(|c| row.get(c)).map(String::as_str);
+        let Some(bond_type) = conn_bond_type(conn_type, order) else {
+            continue;
+        };
+
+        if let (Some(atom_0_sn), Some(atom_1_sn)) = (partner(row, "ptnr1"), partner(row, "ptnr2")) {
+            result.push(BondGeneric {
+                bond_type,
+                atom_0_sn,
+                atom_1_sn,
+            });
+        }
+    }
+
+    result
+}
+
+/// Alternate conformers whose occupancies sum to within this of 1 are considered consistent.
+pub const ALTLOC_OCCUPANCY_TOL: f32 = 0.02;
+
+/// How to choose a single conformer from alternate locations (altLocs), in
+/// [`MmCif::flatten_altlocs`].
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum AltLocPolicy {
+    /// Keep the conformer with the highest mean occupancy in each residue. Ties go to the first
+    /// listed.
+    #[default]
+    HighestOccupancy,
+    /// Keep the first conformer listed, usually "A".
+    First,
+    /// Keep the conformer with this ID, e.g. 'B'. Atoms that don't have a conformer with this
+    /// ID use their first one.
+    SpecificId(char),
+}
+
+/// With the `parallel` feature, atom loops with at least this many lines are parsed in parallel.
+#[cfg(feature = "parallel")]
+const PARALLEL_MIN_ATOMS: usize = 10_000;
+
+/// Column indices of the `_atom_site` fields we read.
+struct AtomSiteCols {
+    het: usize,
+    id: usize,
+    x: usize,
+    y: usize,
+    z: usize,
+    el: usize,
+    name: usize,
+    alt_id: usize,
+    res: usize,
+    chain: usize,
+    res_sn: usize,
+    occ: usize,
+    b: Option<usize>,
+    auth_res_sn: Option<usize>,
+    ins_code: Option<usize>,
+    entity: Option<usize>,
+    model: Option<usize>,
+    num_headers: usize,
+}
+
+impl AtomSiteCols {
+    fn new(headers: &[&str]) -> io::Result<Self> {
+        let col = |tag: &str| -> io::Result<usize> {
+            headers.iter().position(|h| *h == tag).ok_or_else(|| {
+                io::Error::new(ErrorKind::InvalidData, format!("mmCIF missing {tag}"))
+            })
+        };
+
+        Ok(Self {
+            het: col("_atom_site.group_PDB")?,
+            id: col("_atom_site.id")?,
+            x: col("_atom_site.Cartn_x")?,
+            y: col("_atom_site.Cartn_y")?,
+            z: col("_atom_site.Cartn_z")?,
+            el: col("_atom_site.type_symbol")?,
+            name: col("_atom_site.label_atom_id")?,
+            alt_id: col("_atom_site.label_alt_id")?,
+            res: col("_atom_site.label_comp_id")?,
+            chain: col("_atom_site.label_asym_id")?,
+            res_sn: col("_atom_site.label_seq_id")?,
+            occ: col("_atom_site.occupancy")?,
+            b: col("_atom_site.B_iso_or_equiv").ok(),
+            auth_res_sn: col("_atom_site.auth_seq_id").ok(),
+            ins_code: col("_atom_site.pdbx_PDB_ins_code").ok(),
+            entity: col("_atom_site.label_entity_id").ok(),
+            model: col("_atom_site.pdbx_PDB_model_num").ok(),
+            num_headers: headers.len(),
+        })
+    }
+}
+
+/// One parsed `_atom_site` row, along with the fields used for residue, chain, and connection
+/// bookkeeping.
+struct AtomRow<'a> {
+    atom: AtomGeneric,
+    atom_name: &'a str,
+    res_name: &'a str,
+    chain_id: &'a str,
+    res_sn: &'a str,
+    auth_res_sn: Option<&'a str>,
+    ins_code: Option<char>,
+    entity_id: Option<&'a str>,
+    model: Option<&'a str>,
+}
+
+/// Parse one atom line. Returns `None` for lines with too few fields.
+fn parse_atom_row<'a>(line: &'a str, cols: &AtomSiteCols) -> io::Result<Option<AtomRow<'a>>> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < cols.num_headers {
+        return Ok(None);
+    }
+
+    let hetero = fields[cols.het].trim() == "HETATM";
+
+    let serial_number = fields[cols.id].parse::<u32>().unwrap_or(0);
+    let x = fields[cols.x].parse::<f64>().unwrap_or(0.0);
+    let y = fields[cols.y].parse::<f64>().unwrap_or(0.0);
+    let z = fields[cols.z].parse::<f64>().unwrap_or(0.0);
+
+    let element = element_from_symbol(fields[cols.el])?;
+    let atom_name = fields[cols.name];
+
+    let alt_conformation_id = match fields[cols.alt_id] {
+        "?" | "." => None,
+        v => Some(v.to_string()),
+    };
+
+    let type_in_res = if hetero {
+        if !atom_name.is_empty() {
+            Some(AtomTypeInRes::Hetero(atom_name.to_string()))
+        } else {
+            None
+        }
+    } else {
+        AtomTypeInRes::from_str(atom_name).ok()
+    };
+
+    let occ = match fields[cols.occ] {
+        "?" | "." => None,
+        v => v.parse().ok(),
+    };
+    let b_factor = cols.b.and_then(|c| match fields[c] {
+        "?" | "." => None,
+        v => v.parse().ok(),
+    });
+
+    Ok(Some(AtomRow {
+        atom: AtomGeneric {
+            serial_number,
+            posit: Vec3::new(x, y, z),
+            element,
+            type_in_res,
+            // The name as written, e.g. for names `AtomTypeInRes` can't represent.
+            type_in_res_general: (!atom_name.is_empty()).then(|| atom_name.to_string()),
+            occupancy: occ,
+            b_factor,
+            hetero,
+            alt_conformation_id,
+            ..Default::default()
+        },
+        atom_name,
+        res_name: fields[cols.res],
+        chain_id: fields[cols.chain],
+        res_sn: fields[cols.res_sn],
+        auth_res_sn: cols.auth_res_sn.map(|c| fields[c]),
+        ins_code: cols
+            .ins_code
+            .map(|c| fields[c])
+            .filter(|v| *v != "?" && *v != ".")
+            .and_then(|v| v.chars().next()),
+        entity_id: cols
+            .entity
+            .map(|c| fields[c])
+            .filter(|v| *v != "?" && *v != "."),
+        model: cols.model.map(|c| fields[c]),
+    }))
+}
+
+/// Parse the data lines of an `_atom_site` loop, in order. With the `parallel` feature, large loops
+/// are parsed across threads; residue and chain bookkeeping happen afterwards, in order, so the
+/// result is the same either way.
+fn parse_atom_rows<'a>(lines: &[&'a str], cols: &AtomSiteCols) -> io::Result<Vec<AtomRow<'a>>> {
+    #[cfg(feature = "parallel")]
+    if lines.len() >= PARALLEL_MIN_ATOMS {
+        use rayon::prelude::*;
+
+        let rows: Vec<_> = lines
+            .par_iter()
+            .map(|l| parse_atom_row(l.trim(), cols))
+            .collect::<io::Result<_>>()?;
+        return Ok(rows.into_iter().flatten().collect());
+    }
+
+    let mut result = Vec::with_capacity(lines.len());
+    for line in lines {
+        if let Some(row) = parse_atom_row(line.trim(), cols)? {
+            result.push(row);
+        }
+    }
+    Ok(result)
 }
 
 impl MmCif {
@@ -56,10 +659,22 @@ impl MmCif {
 
         let mut metadata = HashMap::<String, String>::new();
         let mut atoms = Vec::<AtomGeneric>::new();
+        // Model numbers, and atom positions of each.
+        let mut model_ids = Vec::<&str>::new();
+        let mut models = Vec::<Vec<Vec3>>::new();
         let mut residues = Vec::<ResidueGeneric>::new();
         let mut chains = Vec::<ChainGeneric>::new();
-        let mut res_idx = HashMap::<(String, u32), usize>::new();
-        let mut chain_idx = HashMap::<String, usize>::new();
+        // Keyed by chain index, entity ID, residue serial number, and author residue number and
+        // insertion code. Non-polymer residues have no label_seq_id, so the entity and author
+        // number distinguish e.g. different ligands sharing a chain.
+        type ResKey<'a> = (usize, &'a str, u32, Option<&'a str>, Option<char>);
+        let mut res_idx = HashMap::<ResKey, usize>::new();
+        let mut chain_idx = HashMap::<&str, usize>::new();
+
+        // For resolving `_struct_conn` partners to atom serial numbers.
+        let mut sn_by_label = HashMap::<(&str, &str, &str), u32>::new();
+        let mut sn_by_auth = HashMap::<(&str, &str, &str), u32>::new();
+        let mut tables = HashMap::<&str, Table>::new();
 
         let lines: Vec<&str> = text.lines().collect();
         let mut i = 0;
@@ -95,6 +710,41 @@ impl MmCif {
                     }
                 }
 
+                if let Some(category) = headers.first().and_then(|h| {
+                    TABLE_CATEGORIES
+                        .iter()
+                        .find(|c| h.strip_prefix(**c).is_some_and(|t| t.starts_with('.')))
+                }) {
+                    let table = tables.entry(category).or_default();
+                    table.headers = headers
+                        .iter()
+                        .map(|h| h[category.len() + 1..].to_owned())
+                        .collect();
+
+                    // Rows may wrap across lines; accumulate values until we have a full row.
+                    let mut row = Vec::new();
+                    while i < n {
+                        line = lines[i].trim();
+                        if line == "#" || line == "loop_" || line.starts_with('_') {
+                            break;
+                        }
+
+                        if lines[i].starts_with(';') {
+                            let (val, next) = read_text_field(&lines, i);
+                            row.push(val);
+                            i = next;
+                        } else {
+                            row.extend(split_values(line));
+                            i += 1;
+                        }
+
+                        if row.len() >= table.headers.len() {
+                            table.rows.push(std::mem::take(&mut row));
+                        }
+                    }
+                    continue;
+                }
+
                 // If not an atom loops, skip first rows.
                 if !headers
                     .first()
@@ -105,103 +755,75 @@ impl MmCif {
                         if line == "#" || line == "loop_" || line.starts_with('_') {
                             break;
                         }
-                        i += 1;
+                        // Skip text fields whole; their lines may look like tags or loops.
+                        if lines[i].starts_with(';') {
+                            i = read_text_field(&lines, i).1;
+                        } else {
+                            i += 1;
+                        }
                     }
                     continue;
                 }
 
-                let col = |tag: &str| -> io::Result<usize> {
-                    headers.iter().position(|h| *h == tag).ok_or_else(|| {
-                        io::Error::new(ErrorKind::InvalidData, format!("mmCIF missing {tag}"))
-                    })
-                };
-                let het = col("_atom_site.group_PDB")?;
-                let c_id = col("_atom_site.id")?;
-                let c_x = col("_atom_site.Cartn_x")?;
-                let c_y = col("_atom_site.Cartn_y")?;
-                let c_z = col("_atom_site.Cartn_z")?;
-                let c_el = col("_atom_site.type_symbol")?;
-                let c_name = col("_atom_site.label_atom_id")?;
-                let c_alt_id = col("_atom_site.label_alt_id")?;
-                let c_res = col("_atom_site.label_comp_id")?;
-                let c_chain = col("_atom_site.label_asym_id")?;
-                let c_res_sn = col("_atom_site.label_seq_id")?;
-                let c_occ = col("_atom_site.occupancy")?;
+                let cols = AtomSiteCols::new(&headers)?;
 
+                let start = i;
                 while i < n {
                     line = lines[i].trim();
                     if line.is_empty() || line == "#" || line == "loop_" || line.starts_with('_') {
                         break;
                     }
-                    let fields: Vec<&str> = line.split_whitespace().collect();
-                    if fields.len() < headers.len() {
-                        i += 1;
-                        continue;
-                    }
+                    i += 1;
+                }
 
-                    // Atom lines.
-                    let hetero = fields[het].trim() == "HETATM";
+                for row in parse_atom_rows(&lines[start..i], &cols)? {
+                    let AtomRow {
+                        atom,
+                        atom_name,
+                        res_name,
+                        chain_id,
+                        res_sn,
+                        auth_res_sn,
+                        ins_code,
+                        entity_id,
+                        model,
+                    } = row;
 
-                    let serial_number = fields[c_id].parse::<u32>().unwrap_or(0);
-                    let x = fields[c_x].parse::<f64>().unwrap_or(0.0);
-                    let y = fields[c_y].parse::<f64>().unwrap_or(0.0);
-                    let z = fields[c_z].parse::<f64>().unwrap_or(0.0);
+                    if let Some(model) = model {
+                        let model_i = match model_ids.iter().position(|m| *m == model) {
+                            Some(m_i) => m_i,
+                            None => {
+                                model_ids.push(model);
+                                models.push(Vec::new());
+                                models.len() - 1
+                            }
+                        };
+                        models[model_i].push(atom.posit);
 
-                    let element = Element::from_letter(fields[c_el])?;
-                    let atom_name = fields[c_name];
+                        // Later models share the first's atoms, residues, and chains.
+                        if model_i > 0 {
+                            continue;
+                        }
+                    }
 
-                    let alt_conformation_id = if fields[c_alt_id] == "." {
-                        None
-                    } else {
-                        Some(fields[c_alt_id].to_string())
-                    };
+                    let serial_number = atom.serial_number;
+                    atoms.push(atom);
 
-                    let type_in_res = if hetero {
-                        if !atom_name.is_empty() {
-                            Some(AtomTypeInRes::Hetero(atom_name.to_string()))
-                        } else {
-                            None
-                        }
-                    } else {
-                        AtomTypeInRes::from_str(atom_name).ok()
-                    };
-
-                    let occ = match fields[c_occ] {
-                        "?" | "." => None,
-                        v => v.parse().ok(),
-                    };
-
-                    atoms.push(AtomGeneric {
-                        serial_number,
-                        posit: Vec3::new(x, y, z),
-                        element,
-                        type_in_res,
-                        occupancy: occ,
-                        hetero,
-                        alt_conformation_id,
-                        ..Default::default()
-                    });
+                    let atom_name = unquote(atom_name);
+                    sn_by_label
+                        .entry((chain_id, res_sn, atom_name))
+                        .or_insert(serial_number);
+                    if let Some(auth) = auth_res_sn {
+                        sn_by_auth
+                            .entry((chain_id, auth, atom_name))
+                            .or_insert(serial_number);
+                    }
 
                     // --------- Residue / Chain bookkeeping -----------
-                    let res_sn = fields[c_res_sn].parse::<u32>().unwrap_or(0);
-                    let chain_id = fields[c_chain];
-                    let res_key = (chain_id.to_string(), res_sn);
-
-                    // Residues
-                    let r_i = *res_idx.entry(res_key.clone()).or_insert_with(|| {
-                        let idx = residues.len();
-                        residues.push(ResidueGeneric {
-                            serial_number: res_sn,
-                            res_type: ResidueType::from_str(fields[c_res]),
-                            atom_sns: Vec::new(),
-                            end: ResidueEnd::Internal, // We update this after.
-                        });
-                        idx
-                    });
-                    residues[r_i].atom_sns.push(serial_number);
+                    let res_sn = res_sn.parse::<u32>().unwrap_or(0);
 
                     // Chains
-                    let c_i = *chain_idx.entry(chain_id.to_string()).or_insert_with(|| {
+                    let c_i = *chain_idx.entry(chain_id).or_insert_with(|| {
                         let idx = chains.len();
                         chains.push(ChainGeneric {
                             id: chain_id.to_string(),
@@ -211,23 +833,60 @@ impl MmCif {
                         idx
                     });
                     chains[c_i].atom_sns.push(serial_number);
-                    if !chains[c_i].residue_sns.contains(&res_sn) {
-                        chains[c_i].residue_sns.push(res_sn);
-                    }
 
-                    i += 1;
+                    // Residues
+                    let auth_res_sn = auth_res_sn.filter(|v| *v != "?" && *v != ".");
+                    let res_key = (
+                        c_i,
+                        entity_id.unwrap_or_default(),
+                        res_sn,
+                        auth_res_sn,
+                        ins_code,
+                    );
+                    let r_i = *res_idx.entry(res_key).or_insert_with(|| {
+                        let idx = residues.len();
+                        residues.push(ResidueGeneric {
+                            serial_number: res_sn,
+                            res_type: ResidueType::from_str(res_name),
+                            atom_sns: Vec::new(),
+                            end: ResidueEnd::Internal, // We update this after.
+                            entity_id: entity_id.map(str::to_owned),
+                            auth_serial_number: auth_res_sn.and_then(|v| v.parse().ok()),
+                            insertion_code: ins_code,
+                        });
+                        chains[c_i].residue_sns.push(res_sn);
+                        idx
+                    });
+                    residues[r_i].atom_sns.push(serial_number);
                 }
                 continue; // outer while will handle terminator line
             }
 
             if line.starts_with('_') {
                 if let Some((tag, val)) = line.split_once(char::is_whitespace) {
-                    metadata.insert(
-                        tag.to_string(),
-                        val.trim_matches('\'').to_string().trim().to_string(),
-                    );
+                    metadata.insert(tag.to_string(), unquote(val.trim()).trim().to_string());
                 } else {
-                    metadata.insert(line.to_string().trim().to_string(), String::new());
+                    // The value is on the following line(s): Either a text field, or a single
+                    // (usually quoted) value.
+                    let tag = line.to_string();
+                    let next = lines.get(i + 1).copied().unwrap_or_default();
+
+                    if next.starts_with(';') {
+                        let (val, next_i) = read_text_field(&lines, i + 1);
+                        metadata.insert(tag, val);
+                        i = next_i;
+                        continue;
+                    }
+
+                    let next = next.trim();
+                    if !next.is_empty() && !next.starts_with('_') && next != "#" && next != "loop_"
+                    {
+                        metadata.insert(tag, unquote(next).to_owned());
+                        i += 2;
+                        continue;
+                    }
+
+                    metadata.insert(tag, String::new());
                 }
             }
 
@@ -235,32 +894,27 @@ impl MmCif {
         }
 
         // Populate the residue end, now that we know when the last non-het one is.
-        {
-            let mut last_non_het = 0;
-            for (i, res) in residues.iter().enumerate() {
-                match res.res_type {
-                    ResidueType::AminoAcid(_) => last_non_het = i,
-                    _ => break,
-                }
-            }
-
-            for (i, res) in residues.iter_mut().enumerate() {
-                let mut end = ResidueEnd::Internal;
+        set_residue_ends(&mut residues);
 
-                // Match arm won't work due to non-constant arms, e.g. non_hetero?
-                if i == 0 {
-                    end = ResidueEnd::NTerminus;
-                } else if i == last_non_het {
-                    end = ResidueEnd::CTerminus;
-                }
-
-                match res.res_type {
-                    ResidueType::AminoAcid(_) => (),
-                    _ => end = ResidueEnd::Hetero,
-                }
-
-                res.end = end;
+        // Models must share the first model's atoms; skip any that don't.
+        let mut model_i = 0;
+        models.retain(|m| {
+            let keep = m.len() == atoms.len();
+            if !keep {
+                eprintln!(
+                    "Skipping model {}, which has {} atoms; model {} has {}",
+                    model_ids[model_i],
+                    m.len(),
+                    model_ids[0],
+                    atoms.len()
+                );
             }
+            model_i += 1;
+            keep
+        });
+
+        if models.len() <= 1 {
+            models.clear();
         }
 
         let ident = metadata
@@ -271,25 +925,79 @@ impl MmCif {
             .trim()
             .to_owned();
 
+        for category in TABLE_CATEGORIES {
+            if !tables.contains_key(category)
+                && let Some(table) = Table::from_metadata(&metadata, category)
+            {
+                tables.insert(category, table);
+            }
+        }
+
+        let bonds = tables
+            .get("_struct_conn")
+            .map(|t| struct_conn_bonds(t, &sn_by_label, &sn_by_auth))
+            .unwrap_or_default();
+
+        let chem_comps = tables
+            .get("_chem_comp")
+            .map(parse_chem_comps)
+            .unwrap_or_default();
+
         // let ss_load = Instant::now();
         let secondary_structure = load_ss(text)?;
 
         // let ss_load_time = ss_load.elapsed().as_millis();
         // println!("Loaded SS from mmCIF in {ss_load_time} ms (TEMP)");
 
+        let unit_cell = unit_cell_from_metadata(&metadata);
+        let space_group = space_group_from_metadata(&metadata);
+
+        let symmetry_ops = match tables
+            .get("_space_group_symop")
+            .map(|t| (t, "operation_xyz"))
+            .or_else(|| tables.get("_symmetry_equiv").map(|t| (t, "pos_as_xyz")))
+        {
+            Some((table, tag)) => parse_symmetry_ops(table, tag)?,
+            None => Vec::new(),
+        };
+
         Ok(Self {
             ident,
             metadata,
             atoms,
+            bonds,
+            chem_comps,
             chains,
             residues,
             secondary_structure,
             experimental_method,
+            models,
+            unit_cell,
+            space_group,
+            symmetry_ops,
         })
     }
 
     // todo: QC this.
+    /// Returns an error if any model in `models` has a different number of atoms than `atoms`.
     pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some((i, m)) = self
+            .models
+            .iter()
+            .enumerate()
+            .find(|(_, m)| m.len() != self.atoms.len())
+        {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Model {} has {} atom positions, but there are {} atoms",
+                    i + 1,
+                    m.len(),
+                    self.atoms.len()
+                ),
+            ));
+        }
+
         let mut file = File::create(path)?;
 
         fn quote_if_needed(s: &str) -> String {
@@ -316,23 +1024,25 @@ impl MmCif {
             if k == "_struct.entry_id" || k == "_entry.id" || k == "_exptl.method" {
                 continue;
             }
-            if k.starts_with('_') {
+            if !k.starts_with('_') {
+                continue;
+            }
+            if v.contains('\n') {
+                // Quoted values can't span lines; use a text field.
+                writeln!(file, "{k}\n;{v}\n;")?;
+            } else {
                 writeln!(file, "{} {}", k, quote_if_needed(v))?;
             }
         }
         writeln!(file, "#")?;
 
         // Build lookups for atom → residue and atom → chain
-        let mut atom_to_res = HashMap::<u32, u32>::new();
+        let mut atom_to_res = HashMap::<u32, &ResidueGeneric>::new();
         for r in &self.residues {
             for &sn in &r.atom_sns {
-                atom_to_res.insert(sn, r.serial_number);
+                atom_to_res.insert(sn, r);
             }
         }
-        let mut res_map = HashMap::<u32, &ResidueGeneric>::new();
-        for r in &self.residues {
-            res_map.insert(r.serial_number, r);
-        }
         let mut atom_to_chain = HashMap::<u32, &str>::new();
         for c in &self.chains {
             for &sn in &c.atom_sns {
@@ -349,62 +1059,357 @@ impl MmCif {
         writeln!(file, "_atom_site.Cartn_z")?;
         writeln!(file, "_atom_site.type_symbol")?;
         writeln!(file, "_atom_site.label_atom_id")?;
+        writeln!(file, "_atom_site.label_alt_id")?;
         writeln!(file, "_atom_site.label_comp_id")?;
         writeln!(file, "_atom_site.label_asym_id")?;
+        writeln!(file, "_atom_site.label_entity_id")?;
         writeln!(file, "_atom_site.label_seq_id")?;
+        writeln!(file, "_atom_site.auth_seq_id")?;
+        writeln!(file, "_atom_site.pdbx_PDB_ins_code")?;
         writeln!(file, "_atom_site.occupancy")?;
+        writeln!(file, "_atom_site.B_iso_or_equiv")?;
+        writeln!(file, "_atom_site.pdbx_PDB_model_num")?;
 
-        for a in &self.atoms {
-            let group = if a.hetero { "HETATM" } else { "ATOM" };
-            let sym = a.element.to_string();
-            let atom_name = match &a.type_in_res {
-                Some(na_seq::AtomTypeInRes::Hetero(n)) => n.clone(),
-                Some(t) => t.to_string(),
-                None => sym.clone(),
-            };
-            let res_sn = *atom_to_res.get(&a.serial_number).unwrap_or(&0u32);
-            let (res_name, chain_id) = if let Some(r) = res_map.get(&res_sn) {
-                (
-                    r.res_type.to_string(),
-                    atom_to_chain.get(&a.serial_number).copied().unwrap_or("A"),
-                )
-            } else {
-                (
-                    "UNK".to_string(),
-                    atom_to_chain.get(&a.serial_number).copied().unwrap_or("A"),
-                )
-            };
-            let occ_s = match a.occupancy {
-                Some(o) => format!("{:.2}", o),
-                None => "?".to_string(),
-            };
+        // IDs must be unique across models; later models continue after the first's.
+        let mut next_id = self
+            .atoms
+            .iter()
+            .map(|a| a.serial_number)
+            .max()
+            .unwrap_or(0)
+            + 1;
+
+        for model_i in 0..self.models.len().max(1) {
+            for (atom_i, a) in self.atoms.iter().enumerate() {
+                let posit = if model_i == 0 {
+                    a.posit
+                } else {
+                    self.models[model_i][atom_i]
+                };
+                let id = if model_i == 0 {
+                    a.serial_number
+                } else {
+                    next_id += 1;
+                    next_id - 1
+                };
+
+                let group = if a.hetero { "HETATM" } else { "ATOM" };
+                let sym = element_symbol(a.element);
+                let atom_name = match &a.type_in_res {
+                    Some(na_seq::AtomTypeInRes::Hetero(n)) => n.clone(),
+                    Some(t) => t.to_string(),
+                    None => sym.clone(),
+                };
+                let res = atom_to_res.get(&a.serial_number);
+                let res_sn = res.map(|r| r.serial_number).unwrap_or(0);
+                let entity_id = res.and_then(|r| r.entity_id.as_deref()).unwrap_or("?");
+                let auth_res_sn = res
+                    .and_then(|r| r.auth_serial_number)
+                    .map(|v| v.to_string())
+                    .unwrap_or_else(|| "?".to_owned());
+                let ins_code = res
+                    .and_then(|r| r.insertion_code)
+                    .map(|c| c.to_string())
+                    .unwrap_or_else(|| "?".to_owned());
+                let (res_name, chain_id) = if let Some(r) = res {
+                    (
+                        r.res_type.to_name(),
+                        atom_to_chain.get(&a.serial_number).copied().unwrap_or("A"),
+                    )
+                } else {
+                    (
+                        "UNK".to_string(),
+                        atom_to_chain.get(&a.serial_number).copied().unwrap_or("A"),
+                    )
+                };
+                let occ_s = match a.occupancy {
+                    Some(o) => format!("{:.2}", o),
+                    None => "?".to_string(),
+                };
+                let b_s = match a.b_factor {
+                    Some(b) => format!("{b:.2}"),
+                    None => "?".to_string(),
+                };
+
+                let alt_id = a.alt_conformation_id.as_deref().unwrap_or(".");
 
-            writeln!(
-                file,
-                "{} {} {:.3} {:.3} {:.3} {} {} {} {} {}",
-                group,
-                a.serial_number,
-                a.posit.x,
-                a.posit.y,
-                a.posit.z,
-                quote_if_needed(&sym),
-                quote_if_needed(&atom_name),
-                quote_if_needed(&res_name),
-                quote_if_needed(chain_id),
-                res_sn,
-            )?;
-            writeln!(file, "{}", occ_s)?;
+                writeln!(
+                    file,
+                    "{} {} {:.3} {:.3} {:.3} {} {} {} {} {} {} {} {} {} {} {} {}",
+                    group,
+                    id,
+                    posit.x,
+                    posit.y,
+                    posit.z,
+                    quote_if_needed(&sym),
+                    quote_if_needed(&atom_name),
+                    quote_if_needed(alt_id),
+                    quote_if_needed(&res_name),
+                    quote_if_needed(chain_id),
+                    quote_if_needed(entity_id),
+                    res_sn,
+                    auth_res_sn,
+                    ins_code,
+                    occ_s,
+                    b_s,
+                    model_i + 1,
+                )?;
+            }
         }
 
         writeln!(file, "#")?;
         Ok(())
     }
 
+    /// Load from a file. Gzipped files, e.g. `.cif.gz` from RCSB, are decompressed.
     pub fn load(path: &Path) -> io::Result<Self> {
-        let data_str = fs::read_to_string(path)?;
+        let data_str = read_text_file(path)?;
         Self::new(&data_str)
     }
 
+    /// The crystallographic unit cell. An alias for the `unit_cell` field, kept for compatibility.
+    pub fn cell(&self) -> Option<UnitCell> {
+        self.unit_cell.clone()
+    }
+
+    /// Per-residue secondary structure, keyed by chain ID and residue serial number, as residue
+    /// serial numbers are only unique within a chain. This is derived from the helix and sheet
+    /// ranges in `secondary_structure`: Residues with an atom in a helix range get that helix's
+    /// code (`AlphaHelix`, `Helix310`, or `PiHelix`), ones in a sheet range are `Strand`, and all
+    /// others are `Coil`. Residues not in a chain use an empty chain ID.
+    pub fn residue_ss(&self) -> HashMap<(String, u32), SsCode> {
+        let mut chain_by_sn = HashMap::new();
+        for chain in &self.chains {
+            for sn in &chain.atom_sns {
+                chain_by_sn.insert(*sn, chain.id.as_str());
+            }
+        }
+
+        let mut result = HashMap::with_capacity(self.residues.len());
+
+        for res in &self.residues {
+            if !matches!(res.res_type, ResidueType::AminoAcid(_)) {
+                continue;
+            }
+
+            let chain_id = res
+                .atom_sns
+                .first()
+                .and_then(|sn| chain_by_sn.get(sn))
+                .copied()
+                .unwrap_or_default();
+
+            let mut code = SsCode::Coil;
+            for ss in &self.secondary_structure {
+                if res
+                    .atom_sns
+                    .iter()
+                    .any(|sn| *sn >= ss.start_sn && *sn <= ss.end_sn)
+                {
+                    code = ss.ss_code();
+                    break;
+                }
+            }
+
+            result.insert((chain_id.to_owned(), res.serial_number), code);
+        }
+
+        result
+    }
+
+    /// Collapse alternate conformations (altLocs) to a single conformer, chosen as in
+    /// [`Self::flatten_altlocs`] with [`AltLocPolicy::HighestOccupancy`]. The other conformers
+    /// are removed from atoms, models, residues, chains, and bonds.
+    pub fn collapse_alt_conformations(&mut self) {
+        let removed: HashSet<u32> = self
+            .atoms
+            .iter()
+            .zip(self.altloc_keep(AltLocPolicy::HighestOccupancy))
+            .filter(|(_, keep)| !keep)
+            .map(|(a, _)| a.serial_number)
+            .collect();
+
+        if removed.is_empty() {
+            return;
+        }
+
+        self.remove_atoms(&removed);
+    }
+
+    /// Remove atoms by serial number from atoms, models, residues, chains, and bonds.
+    fn remove_atoms(&mut self, removed: &HashSet<u32>) {
+        let keep: Vec<bool> = self
+            .atoms
+            .iter()
+            .map(|a| !removed.contains(&a.serial_number))
+            .collect();
+
+        for model in &mut self.models {
+            if model.len() == keep.len() {
+                let mut k = keep.iter();
+                model.retain(|_| *k.next().unwrap());
+            }
+        }
+
+        self.atoms.retain(|a| !removed.contains(&a.serial_number));
+        for res in &mut self.residues {
+            res.atom_sns.retain(|sn| !removed.contains(sn));
+        }
+        for chain in &mut self.chains {
+            chain.atom_sns.retain(|sn| !removed.contains(sn));
+        }
+        self.bonds
+            .retain(|b| !removed.contains(&b.atom_0_sn) && !removed.contains(&b.atom_1_sn));
+    }
+
+    /// A single-conformer copy of the atoms, choosing one of each set of alternate conformers
+    /// according to `policy`. The conformer is chosen once per residue, so that a residue's atoms
+    /// come from the same one; atoms without it fall back to choosing among their own. Atoms
+    /// without an alternate conformation ID are always kept. Order is preserved. Unlike
+    /// [`Self::collapse_alt_conformations`], this leaves `self` unchanged.
+    pub fn flatten_altlocs(&self, policy: AltLocPolicy) -> Vec<AtomGeneric> {
+        self.atoms
+            .iter()
+            .zip(self.altloc_keep(policy))
+            .filter(|(_, k)| *k)
+            .map(|(a, _)| a.clone())
+            .collect()
+    }
+
+    /// Whether to keep each atom when choosing a single conformer according to `policy`.
+    fn altloc_keep(&self, policy: AltLocPolicy) -> Vec<bool> {
+        let groups = self.altloc_groups();
+
+        // The conformer to keep in each residue. Atoms not in a residue are chosen individually.
+        let mut by_res: HashMap<usize, Vec<&AtomGeneric>> = HashMap::new();
+        for (res_i, atom_indices) in &groups {
+            if let Some(r) = res_i {
+                let atoms = by_res.entry(*r).or_default();
+                atoms.extend(atom_indices.iter().map(|i| &self.atoms[*i]));
+            }
+        }
+        let res_choice: HashMap<usize, Option<&str>> = by_res
+            .into_iter()
+            .map(|(r, atoms)| (r, choose_altloc(&atoms, policy)))
+            .collect();
+
+        let mut keep = vec![true; self.atoms.len()];
+
+        for (res_i, atom_indices) in &groups {
+            let atoms: Vec<_> = atom_indices.iter().map(|i| &self.atoms[*i]).collect();
+            let alt_id = |i: usize| self.atoms[i].alt_conformation_id.as_deref();
+
+            let res_id = res_i.and_then(|r| res_choice[&r]);
+            let chosen = atom_indices
+                .iter()
+                .copied()
+                .find(|i| res_id.is_some() && alt_id(*i) == res_id)
+                .or_else(|| {
+                    let id = choose_altloc(&atoms, policy);
+                    atom_indices.iter().copied().find(|i| alt_id(*i) == id)
+                })
+                .unwrap_or(atom_indices[0]);
+
+            for i in atom_indices {
+                keep[*i] = *i == chosen;
+            }
+        }
+
+        keep
+    }
+
+    /// Atom indices of each set of alternate conformers: Atoms with an alternate conformation ID,
+    /// grouped by residue index and atom name, as written in the file. In order of first
+    /// appearance. Atoms without a name can't be matched to their alternates, so each is in its
+    /// own group.
+    fn altloc_groups(&self) -> Vec<(Option<usize>, Vec<usize>)> {
+        let mut res_by_sn = HashMap::new();
+        for (i, res) in self.residues.iter().enumerate() {
+            for sn in &res.atom_sns {
+                res_by_sn.insert(*sn, i);
+            }
+        }
+
+        let mut groups: Vec<(Option<usize>, Vec<usize>)> = Vec::new();
+        let mut group_by_key = HashMap::new();
+
+        for (i, atom) in self.atoms.iter().enumerate() {
+            if atom.alt_conformation_id.is_none() {
+                continue;
+            }
+
+            let res_i = res_by_sn.get(&atom.serial_number).copied();
+
+            let Some(name) = atom_name(atom) else {
+                groups.push((res_i, vec![i]));
+                continue;
+            };
+
+            let g = *group_by_key.entry((res_i, name)).or_insert_with(|| {
+                groups.push((res_i, Vec::new()));
+                groups.len() - 1
+            });
+            groups[g].1.push(i);
+        }
+
+        groups
+    }
+
+    /// Total occupancy of a set of alternate conformers. Atoms without an occupancy count as 1.
+    fn altloc_occupancy_sum(&self, atom_indices: &[usize]) -> f32 {
+        atom_indices
+            .iter()
+            .map(|i| self.atoms[*i].occupancy.unwrap_or(1.))
+            .sum()
+    }
+
+    /// Find residues whose alternate conformers' occupancies don't sum to 1, within
+    /// `ALTLOC_OCCUPANCY_TOL`. This is a common deposition error. Returns the residue serial
+    /// number (0 for atoms not in a residue), and the sum for the atom that deviates most from 1.
+    pub fn check_altloc_occupancy(&self) -> Vec<(u32, f32)> {
+        let mut result: Vec<(Option<usize>, f32)> = Vec::new();
+
+        for (res_i, atom_indices) in self.altloc_groups() {
+            let sum = self.altloc_occupancy_sum(&atom_indices);
+            if (sum - 1.).abs() <= ALTLOC_OCCUPANCY_TOL {
+                continue;
+            }
+
+            match result.iter_mut().find(|(r, _)| *r == res_i) {
+                Some((_, worst)) => {
+                    if (sum - 1.).abs() > (*worst - 1.).abs() {
+                        *worst = sum;
+                    }
+                }
+                None => result.push((res_i, sum)),
+            }
+        }
+
+        result
+            .into_iter()
+            .map(|(res_i, sum)| {
+                let sn = res_i.map(|i| self.residues[i].serial_number).unwrap_or(0);
+                (sn, sum)
+            })
+            .collect()
+    }
+
+    /// Rescale the occupancies of alternate conformers that don't sum to 1, as reported by
+    /// [`Self::check_altloc_occupancy`], so that they do. Relative occupancies are preserved.
+    pub fn normalize_altloc_occupancy(&mut self) {
+        for (_, atom_indices) in self.altloc_groups() {
+            let sum = self.altloc_occupancy_sum(&atom_indices);
+            if (sum - 1.).abs() <= ALTLOC_OCCUPANCY_TOL || sum <= 0. {
+                continue;
+            }
+
+            for i in atom_indices {
+                let occ = self.atoms[i].occupancy.unwrap_or(1.);
+                self.atoms[i].occupancy = Some(occ / sum);
+            }
+        }
+    }
+
     /// Download Load from DrugBank from the RCSB Protein Data Bank. (PDB)
     pub fn load_rcsb(ident: &str) -> io::Result<Self> {
         let data_str =
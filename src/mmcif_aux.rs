@@ -2,7 +2,7 @@
 
 use std::{collections::HashMap, io};
 
-use crate::{BackboneSS, SecondaryStructure};
+use crate::{BackboneSS, HelixClass, SecondaryStructure};
 
 // todo: Save SS to CIF.
 
@@ -140,6 +140,7 @@ pub fn load_ss(text: &str) -> io::Result<Vec<BackboneSS>> {
         let i_bs = find(&h, "beg_label_seq_id");
         let i_ea = find(&h, "end_label_asym_id");
         let i_es = find(&h, "end_label_seq_id");
+        let i_class = find(&h, "pdbx_PDB_helix_class");
         let (i_type, i_ba, i_bs, i_ea, i_es) = match (i_type, i_ba, i_bs, i_ea, i_es) {
             (Some(a), Some(b), Some(c), Some(d), Some(e)) => (a, b, c, d, e),
             _ => continue,
@@ -164,10 +165,19 @@ pub fn load_ss(text: &str) -> io::Result<Vec<BackboneSS>> {
             None => continue,
         };
 
+        // The specific conf type takes precedence; most entries use the generic `HELX_P`, and
+        // specify the subtype in the helix class column.
+        let helix_class = HelixClass::from_conf_type(&c[i_type]).or_else(|| {
+            i_class
+                .and_then(|i| c.get(i))
+                .and_then(|v| HelixClass::from_pdb_class(v))
+        });
+
         ss.push(BackboneSS {
             start_sn,
             end_sn,
             sec_struct: SecondaryStructure::Helix,
+            helix_class,
         });
     }
 
@@ -204,6 +214,7 @@ pub fn load_ss(text: &str) -> io::Result<Vec<BackboneSS>> {
             start_sn,
             end_sn,
             sec_struct: SecondaryStructure::Sheet,
+            helix_class: None,
         });
     }
 
@@ -15,16 +15,21 @@ use std::{
 use bio_apis::amber_geostd;
 use lin_alg::f64::Vec3;
 use na_seq::AtomTypeInRes;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    AtomGeneric, BondGeneric, BondType, PharmacophoreFeatureGeneric, Sdf, el_from_atom_name,
+    AtomGeneric, BondGeneric, BondType, ChainGeneric, MmCif, PharmacophoreFeatureGeneric,
+    ResidueEnd, ResidueGeneric, ResidueType, Sdf, el_from_atom_name, element_symbol,
     sdf::{format_pharmacophore_features, parse_pharmacophore_features},
+    set_residue_ends,
 };
 
 // For our custom format addition.
 const PHARMACOPHORE_TAG: &str = "@<BIO_FILES>PHARMACOPHORE";
 
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum MolType {
     Small,
     Bipolymer,
@@ -65,6 +70,7 @@ impl FromStr for MolType {
 }
 
 #[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ChargeType {
     None,
     DelRe,
@@ -133,6 +139,7 @@ impl FromStr for ChargeType {
 /// be used as an intermediate format, and converted to something application-specific.
 // todo: Combine this and SDF into one struct?
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Mol2 {
     pub ident: String,
     pub metadata: HashMap<String, String>,
@@ -145,6 +152,89 @@ pub struct Mol2 {
     /// how they're stored in SDF.
     pub pharmacophore_features: Vec<PharmacophoreFeatureGeneric>,
     pub comment: Option<String>,
+    /// From the atoms' substructure columns. Small molecules usually have a single residue.
+    pub residues: Vec<ResidueGeneric>,
+    /// From the `@<TRIPOS>SUBSTRUCTURE` chain column. Substructures without one are in chain "A".
+    pub chains: Vec<ChainGeneric>,
+}
+
+/// A row of the `@<TRIPOS>SUBSTRUCTURE` section: `subst_id subst_name root_atom [subst_type
+/// [dict_type [chain [sub_type ...]]]]`. Absent values are "****".
+struct Substructure {
+    chain: Option<String>,
+    /// The residue type, e.g. "SER", for substructures whose names include a number, e.g. "SER2".
+    sub_type: Option<String>,
+}
+
+/// Split a substructure name into its residue name and number, e.g. "SER2" into ("SER", Some(2)).
+fn split_subst_name(name: &str) -> (&str, Option<u32>) {
+    let i = name.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    if i == 0 {
+        return (name, None);
+    }
+    (&name[..i], name[i..].parse().ok())
+}
+
+/// Group atoms into residues by substructure ID, in order of first appearance, and residues into
+/// chains. `atom_substs` holds each atom's substructure ID and name, if present.
+fn build_residues(
+    atoms: &[AtomGeneric],
+    atom_substs: &[Option<(u32, String)>],
+    substructures: &HashMap<u32, Substructure>,
+) -> (Vec<ResidueGeneric>, Vec<ChainGeneric>) {
+    let mut residues: Vec<ResidueGeneric> = Vec::new();
+    let mut chains: Vec<ChainGeneric> = Vec::new();
+    let mut res_by_subst = HashMap::new();
+
+    for (atom, subst) in atoms.iter().zip(atom_substs) {
+        // Atoms without substructure columns share a single residue.
+        let (subst_id, subst_name) = match subst {
+            Some((id, name)) => (*id, name.as_str()),
+            None => (0, "UNL"),
+        };
+
+        let r_i = *res_by_subst.entry(subst_id).or_insert_with(|| {
+            let subst = substructures.get(&subst_id);
+            let (name, number) = split_subst_name(subst_name);
+            let res_type = match subst.and_then(|s| s.sub_type.as_deref()) {
+                Some(t) => ResidueType::from_str(t),
+                None => ResidueType::from_str(name),
+            };
+            let serial_number = number.unwrap_or(subst_id);
+
+            let chain_id = subst.and_then(|s| s.chain.as_deref()).unwrap_or("A");
+            let c_i = match chains.iter().position(|c| c.id == chain_id) {
+                Some(i) => i,
+                None => {
+                    chains.push(ChainGeneric {
+                        id: chain_id.to_owned(),
+                        residue_sns: Vec::new(),
+                        atom_sns: Vec::new(),
+                    });
+                    chains.len() - 1
+                }
+            };
+            chains[c_i].residue_sns.push(serial_number);
+
+            residues.push(ResidueGeneric {
+                serial_number,
+                res_type,
+                atom_sns: Vec::new(),
+                end: ResidueEnd::Internal, // We update this after.
+                entity_id: None,
+                auth_serial_number: None,
+                insertion_code: None,
+            });
+            (residues.len() - 1, c_i)
+        });
+
+        residues[r_i.0].atom_sns.push(atom.serial_number);
+        chains[r_i.1].atom_sns.push(atom.serial_number);
+    }
+
+    set_residue_ends(&mut residues);
+
+    (residues, chains)
 }
 
 impl Mol2 {
@@ -181,6 +271,9 @@ impl Mol2 {
         let mut in_atom_section = false;
         let mut in_bond_section = false;
         let mut in_bond_pharmacophore_section = false;
+        let mut in_subst_section = false;
+        let mut atom_substs = Vec::new();
+        let mut substructures = HashMap::new();
         let mut metadata = HashMap::new();
         let mut pending_metadata_key: Option<String> = None;
         let mut pending_metadata_lines: Vec<String> = Vec::new();
@@ -195,6 +288,7 @@ impl Mol2 {
                 in_atom_section = true;
                 in_bond_section = false;
                 in_bond_pharmacophore_section = false;
+                in_subst_section = false;
                 if let Some(key) = pending_metadata_key.take() {
                     metadata.insert(key, pending_metadata_lines.join("\n"));
                     pending_metadata_lines.clear();
@@ -206,6 +300,7 @@ impl Mol2 {
                 in_atom_section = false;
                 in_bond_section = true;
                 in_bond_pharmacophore_section = false;
+                in_subst_section = false;
                 if let Some(key) = pending_metadata_key.take() {
                     metadata.insert(key, pending_metadata_lines.join("\n"));
                     pending_metadata_lines.clear();
@@ -214,13 +309,14 @@ impl Mol2 {
             }
 
             if upper.contains("@<TRIPOS>SUBSTRUCTURE") {
-                // todo: As required. Example:
+                // Example:
                 //    1 SER     2 RESIDUE           4 A     SER     1 ROOT
                 //      2 VAL    13 RESIDUE           4 A     VAL     2
                 //      3 PRO    29 RESIDUE           4 A     PRO     2
                 in_atom_section = false;
                 in_bond_section = false;
                 in_bond_pharmacophore_section = false;
+                in_subst_section = true;
                 if let Some(key) = pending_metadata_key.take() {
                     metadata.insert(key, pending_metadata_lines.join("\n"));
                     pending_metadata_lines.clear();
@@ -237,6 +333,7 @@ impl Mol2 {
                 in_atom_section = false;
                 in_bond_section = false;
                 in_bond_pharmacophore_section = false;
+                in_subst_section = false;
                 continue;
             }
 
@@ -244,6 +341,7 @@ impl Mol2 {
                 in_atom_section = false;
                 in_bond_section = false;
                 in_bond_pharmacophore_section = true;
+                in_subst_section = false;
                 if let Some(key) = pending_metadata_key.take() {
                     metadata.insert(key, pending_metadata_lines.join("\n"));
                     pending_metadata_lines.clear();
@@ -251,11 +349,21 @@ impl Mol2 {
                 continue;
             }
 
+            // Other Tripos sections, e.g. CRYSIN, which we don't parse.
+            if upper.starts_with("@<TRIPOS>") {
+                in_atom_section = false;
+                in_bond_section = false;
+                in_bond_pharmacophore_section = false;
+                in_subst_section = false;
+                continue;
+            }
+
             // Our custom metadata parsing. Match all @ lines that don't match one above.
             if line.starts_with('@') && !upper.contains("<TRIPOS>") {
                 in_atom_section = false;
                 in_bond_section = false;
                 in_bond_pharmacophore_section = false;
+                in_subst_section = false;
                 if let Some(key) = pending_metadata_key.take() {
                     metadata.insert(key, pending_metadata_lines.join("\n"));
                     pending_metadata_lines.clear();
@@ -334,6 +442,14 @@ impl Mol2 {
                     None
                 };
 
+                let subst = match (cols.get(6), cols.get(7)) {
+                    (Some(id), Some(name)) => {
+                        id.parse::<u32>().ok().map(|id| (id, name.to_string()))
+                    }
+                    _ => None,
+                };
+                atom_substs.push(subst);
+
                 atoms.push(AtomGeneric {
                     serial_number,
                     type_in_res,
@@ -370,7 +486,8 @@ impl Mol2 {
                     )
                 })?;
 
-                let bond_type = BondType::from_str(cols[3])?;
+                // Some writers emit non-standard tokens; keep the bond rather than fail the file.
+                let bond_type = BondType::from_str(cols[3]).unwrap_or(BondType::Unknown);
 
                 // 1 = single
                 // 2 = double
@@ -390,6 +507,25 @@ impl Mol2 {
             if in_bond_pharmacophore_section {
                 pharmacophore_rows.push(line.to_owned());
             }
+
+            if in_subst_section {
+                let cols: Vec<&str> = line.split_whitespace().collect();
+                let value = |i: usize| {
+                    cols.get(i)
+                        .filter(|v| !v.starts_with("****"))
+                        .map(|v| v.to_string())
+                };
+
+                if let Some(id) = cols.first().and_then(|v| v.parse::<u32>().ok()) {
+                    substructures.insert(
+                        id,
+                        Substructure {
+                            chain: value(5),
+                            sub_type: value(6),
+                        },
+                    );
+                }
+            }
         }
 
         // Flush any trailing custom metadata section.
@@ -402,12 +538,19 @@ impl Mol2 {
         let mol_type = MolType::from_str(lines[3])?;
         let charge_type = ChargeType::from_str(lines[4])?;
 
+        // Line 5 holds status bits, and line 6 the comment. "****" marks empty status bits when a
+        // comment follows. Some writers omit the status bits line, and put the comment on line 5.
         // todo: Multi-line comments are supported by Mol2.
-        let comment = if lines[5].contains("****") {
-            None
-        } else {
-            Some(lines[5].to_owned())
+        let comment_line = match lines.get(5).map(|l| l.trim()) {
+            Some("****") | Some("") => lines.get(6),
+            _ => lines.get(5),
         };
+        let comment = comment_line
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty() && !l.starts_with('@'))
+            .map(str::to_owned);
+
+        let (residues, chains) = build_residues(&atoms, &atom_substs, &substructures);
 
         let pharmacophore_features = if pharmacophore_rows.is_empty() {
             Vec::new()
@@ -424,59 +567,95 @@ impl Mol2 {
             bonds,
             comment,
             pharmacophore_features,
+            residues,
+            chains,
         })
     }
 
+    /// The charge type written to the MOLECULE header. If atoms have partial charges, but the
+    /// charge type is `None`, e.g. after assigning charges to a structure loaded without them,
+    /// this is `User`, so readers don't discard the charges.
+    fn charge_type_to_write(&self) -> ChargeType {
+        let has_charges = self.atoms.iter().any(|a| a.partial_charge.is_some());
+
+        match &self.charge_type {
+            ChargeType::None if has_charges => ChargeType::User,
+            ct => ct.clone(),
+        }
+    }
+
+    /// Write in the Tripos Mol2 format. Atom IDs are renumbered from 1, in order, and bonds refer
+    /// to these IDs; they are the same as the serial numbers if those are already sequential.
     pub fn write_to(&self, w: &mut impl Write) -> io::Result<()> {
-        // There is a subtlety here. Add that to your parser as well. There are two values
-        // todo in the ws we have; this top ident is not the DB id.
+        // There is a subtlety here: This top ident is not necessarily the DB id.
         writeln!(w, "@<TRIPOS>MOLECULE")?;
         writeln!(w, "{}", self.ident)?;
-        writeln!(w, "{} {}", self.atoms.len(), self.bonds.len())?;
+        writeln!(w, "{:>5} {:>5}", self.atoms.len(), self.bonds.len())?;
         writeln!(w, "{}", self.mol_type.to_str())?;
-        writeln!(w, "{}", self.charge_type)?;
+        writeln!(w, "{}", self.charge_type_to_write())?;
 
-        // //  todo: Multi-line comments are supported by Mol2
-        // let comment = match &self.comment {
-        //     Some(c) => &c,
-        //     None => "****",
-        // };
+        // Status bits; "****" means empty. Then the optional comment.
+        // todo: Multi-line comments are supported by Mol2
+        writeln!(w, "****")?;
+        writeln!(w, "{}", self.comment.as_deref().unwrap_or_default())?;
+        writeln!(w)?;
 
-        // **** Means a non-optional field is empty.
-        // writeln!(w, "{comment}")?;
-        // Optional line (comments, molecule weight, etc.)
+        let atom_ids: HashMap<u32, usize> = self
+            .atoms
+            .iter()
+            .enumerate()
+            .map(|(i, a)| (a.serial_number, i + 1))
+            .collect();
+
+        // The substructure name must be a single token.
+        let mol_subst_name = match self.ident.trim() {
+            "" => "UNL",
+            id if id.contains(char::is_whitespace) => "UNL",
+            id => id,
+        };
 
-        writeln!(w)?;
-        writeln!(w)?;
+        // With more than one residue, we write each as a substructure, named e.g. "SER2".
+        let multi_residue = self.residues.len() > 1;
+        let mut subst_by_atom = HashMap::new();
+        if multi_residue {
+            for (i, res) in self.residues.iter().enumerate() {
+                let name = format!("{}{}", res.res_type.to_name(), res.serial_number);
+                for sn in &res.atom_sns {
+                    subst_by_atom.insert(*sn, (i + 1, name.clone()));
+                }
+            }
+        }
 
         writeln!(w, "@<TRIPOS>ATOM")?;
-        for atom in &self.atoms {
+        for (i, atom) in self.atoms.iter().enumerate() {
             let type_in_res = match &atom.type_in_res {
                 Some(n) => n.to_string(),
-                None => atom.element.to_letter(),
+                None => element_symbol(atom.element),
             };
 
+            // The SYBYL atom type, e.g. "C.3". Bare element symbols are valid types for many
+            // elements, so serve as a placeholder.
             let ff_type = match &atom.force_field_type {
                 Some(f) => f.to_owned(),
-                // Not ideal, but will do as a placeholder.
-                None => atom.element.to_letter().to_lowercase(),
+                None => element_symbol(atom.element),
             };
 
-            // todo: A/R
-            // let res_name = String::new();
-            // for res in &self.
+            let (subst_id, subst_name) = match subst_by_atom.get(&atom.serial_number) {
+                Some((id, name)) => (*id, name.as_str()),
+                None => (1, mol_subst_name),
+            };
 
             writeln!(
                 w,
                 "{:>7} {:<8} {:>10.4} {:>10.4} {:>10.4} {:<6} {:>5} {:<8} {:>9.6}",
-                atom.serial_number,
+                i + 1,
                 type_in_res,
                 atom.posit.x,
                 atom.posit.y,
                 atom.posit.z,
                 ff_type,
-                "1",        // Assumes 1 residue.
-                self.ident, // todo: This should really be the residue information.
+                subst_id,
+                subst_name,
                 atom.partial_charge.unwrap_or_default()
             )?;
         }
@@ -484,16 +663,52 @@ impl Mol2 {
         writeln!(w, "@<TRIPOS>BOND")?;
 
         for (i, bond) in self.bonds.iter().enumerate() {
+            let atom_id = |sn: u32| {
+                atom_ids.get(&sn).copied().ok_or_else(|| {
+                    io::Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Bond {} refers to missing atom {sn}", i + 1),
+                    )
+                })
+            };
+
             writeln!(
                 w,
                 "{:>6}{:>6}{:>6} {:<3}",
                 i + 1,
-                bond.atom_0_sn,
-                bond.atom_1_sn,
+                atom_id(bond.atom_0_sn)?,
+                atom_id(bond.atom_1_sn)?,
                 bond.bond_type.to_mol2_str(),
             )?;
         }
 
+        if multi_residue {
+            writeln!(w, "@<TRIPOS>SUBSTRUCTURE")?;
+
+            for (i, res) in self.residues.iter().enumerate() {
+                let first_sn = res.atom_sns.first().copied().unwrap_or_default();
+                let root_atom = atom_ids.get(&first_sn).copied().unwrap_or_default();
+                let chain = self
+                    .chains
+                    .iter()
+                    .find(|c| c.atom_sns.contains(&first_sn))
+                    .map(|c| c.id.as_str())
+                    .unwrap_or("A");
+
+                let res_name = res.res_type.to_name();
+                writeln!(
+                    w,
+                    "{:>6} {:<8}{:>6} RESIDUE {:>4} {:<5} {:<8}",
+                    i + 1,
+                    format!("{res_name}{}", res.serial_number),
+                    root_atom,
+                    1,
+                    chain,
+                    res_name,
+                )?;
+            }
+        }
+
         if !self.pharmacophore_features.is_empty() {
             writeln!(w, "{PHARMACOPHORE_TAG}")?;
             let v = format_pharmacophore_features(&self.pharmacophore_features);
@@ -528,6 +743,29 @@ impl Mol2 {
     }
 }
 
+/// For analyzing a Mol2 structure, e.g. a protein, with the same residue and chain model as one
+/// loaded from mmCIF. Unlike mmCIF, bonds include all of the Mol2's bonds, not just inter-residue
+/// connections.
+impl From<Mol2> for MmCif {
+    fn from(m: Mol2) -> Self {
+        Self {
+            ident: m.ident,
+            metadata: m.metadata,
+            atoms: m.atoms,
+            bonds: m.bonds,
+            chem_comps: HashMap::new(),
+            chains: m.chains,
+            residues: m.residues,
+            secondary_structure: Vec::new(),
+            experimental_method: None,
+            models: Vec::new(),
+            unit_cell: None,
+            space_group: None,
+            symmetry_ops: Vec::new(),
+        }
+    }
+}
+
 impl From<Sdf> for Mol2 {
     fn from(m: Sdf) -> Self {
         Self {
@@ -536,9 +774,15 @@ impl From<Sdf> for Mol2 {
             atoms: m.atoms.clone(),
             bonds: m.bonds.clone(),
             mol_type: MolType::Small,
-            charge_type: ChargeType::None,
+            charge_type: if m.atoms.iter().any(|a| a.partial_charge.is_some()) {
+                ChargeType::User
+            } else {
+                ChargeType::None
+            },
             pharmacophore_features: m.pharmacophore_features,
             comment: None,
+            residues: m.residues,
+            chains: m.chains,
         }
     }
 }
@@ -149,9 +149,13 @@ pub fn load_templates(template_text: &str) -> io::Result<HashMap<String, Templat
                         type_in_res_general: Some(ar.name.clone()),
                         force_field_type: Some(ar.ff_type.clone()),
                         partial_charge: Some(ar.q as f32),
+                        formal_charge: None,
                         hetero: false,
                         occupancy: None,
+                        b_factor: None,
                         alt_conformation_id: None,
+                        velocity: None,
+                        force: None,
                     })
                     .collect();
 
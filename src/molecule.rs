@@ -0,0 +1,91 @@
+//! A single entry point for loading molecules from any of the structure formats this library
+//! supports, for applications that take mixed inputs.
+
+use std::{io, io::ErrorKind, path::Path};
+
+use crate::{
+    AtomGeneric, BondGeneric, MmCif, Mol2, ResidueGeneric, Sdf, Xyz, pdb::Pdb, read_text_file,
+};
+
+/// A molecule loaded from one of several formats. Use the accessors for data common to all of
+/// them, or match on the variant for format-specific data.
+// Variant sizes don't matter here: There's one of these per loaded file.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug)]
+pub enum Molecule {
+    Mol2(Mol2),
+    Sdf(Sdf),
+    Pdb(Pdb),
+    MmCif(MmCif),
+    Xyz(Xyz),
+}
+
+impl Molecule {
+    /// Load a file, choosing the parser from its extension: `.mol2`, `.sdf`, `.pdb`, `.cif` or
+    /// `.mmcif`, or `.xyz`. The extension is case-insensitive. Gzipped files, e.g. `.cif.gz`, are
+    /// decompressed.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        let name = name.strip_suffix(".gz").unwrap_or(&name);
+
+        let ext = Path::new(name)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default();
+
+        let text = || read_text_file(path);
+
+        Ok(match ext {
+            "mol2" => Self::Mol2(Mol2::new(&text()?)?),
+            "sdf" => Self::Sdf(Sdf::new(&text()?)?),
+            "pdb" => Self::Pdb(Pdb::new(&text()?)?),
+            "cif" | "mmcif" => Self::MmCif(MmCif::new(&text()?)?),
+            "xyz" => Self::Xyz(Xyz::new(&text()?)?),
+            _ => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Unsupported molecule file extension: {}. Expected mol2, sdf, pdb, cif, mmcif, or xyz.",
+                        path.display()
+                    ),
+                ));
+            }
+        })
+    }
+
+    pub fn atoms(&self) -> &[AtomGeneric] {
+        match self {
+            Self::Mol2(m) => &m.atoms,
+            Self::Sdf(m) => &m.atoms,
+            Self::Pdb(m) => &m.atoms,
+            Self::MmCif(m) => &m.atoms,
+            Self::Xyz(m) => &m.atoms,
+        }
+    }
+
+    /// Empty for PDB and XYZ, which don't include bonds. For mmCIF, this is only inter-residue
+    /// connections from `_struct_conn`.
+    pub fn bonds(&self) -> &[BondGeneric] {
+        match self {
+            Self::Mol2(m) => &m.bonds,
+            Self::Sdf(m) => &m.bonds,
+            Self::MmCif(m) => &m.bonds,
+            Self::Pdb(_) | Self::Xyz(_) => &[],
+        }
+    }
+
+    /// Empty for XYZ.
+    pub fn residues(&self) -> &[ResidueGeneric] {
+        match self {
+            Self::Mol2(m) => &m.residues,
+            Self::Sdf(m) => &m.residues,
+            Self::Pdb(m) => &m.residues,
+            Self::MmCif(m) => &m.residues,
+            Self::Xyz(_) => &[],
+        }
+    }
+}
@@ -1,380 +1,432 @@
-
-//! For interoperability between MTZ files, and our reflections structs
+//! Reads MTZ reflection files, the CCP4 format used by most crystallographic data processing and
+//! refinement programs. Map coefficients in these, e.g. the FWT and PHWT columns written by
+//! Refmac and Phenix, can be converted to electron density with a Fourier synthesis.
 //!
 //! (None of these sources are a great description of the format)
 //! [Gemmi source](https://github.com/project-gemmi/gemmi/blob/master/src/mtz.cpp)
 //! [Gemmi docs] https://gemmi.readthedocs.io/en/latest/hkl.html)
 //! [Unnoficial guide (Vague, but has general info)](https://staraniso.globalphasing.org/html/mtzformat.html)
 //!
-//! Note: For now, we use Gemmi instead of this, to convert MTZ to map, then read the map.
+//! The file starts with an 80-byte preamble: `MTZ `, the position of the text headers, and a
+//! machine stamp encoding the number formats. Reflection records follow, as one 4-byte float per
+//! column. The headers are 80-character text records, ending with `END`.
 
 use std::{
+    f64::consts::TAU,
     fs::File,
     io,
-    io::{ErrorKind, Read, Seek, SeekFrom, Write},
+    io::{ErrorKind, Read},
     path::Path,
 };
 
-use crate::reflection::{MapStatus, Reflection, ReflectionsData};
+use rustfft::{FftPlanner, num_complex::Complex};
 
-const HEADER_BLOCK: usize = 80;
+use crate::{
+    DensityHeaderInner, DensityMap, MapHeader, SymOp, UnitCell, cif_sf::next_good_fft_len,
+    parse_symop_xyz,
+};
 
 const INITIAL_BYTES: [u8; 4] = *b"MTZ "; // 4D 54 5A 20
+/// Reflection data starts after the preamble, at word 21.
+const DATA_START: usize = 80;
+const RECORD_LEN: usize = 80;
+
+/// Column labels for 2mFo-DFc map coefficients, as (amplitude, phase), in order of preference.
+const MAP_COEF_LABELS: [(&str, &str); 2] = [("FWT", "PHWT"), ("2FOFCWT", "PH2FOFCWT")];
 
-#[macro_export]
-macro_rules! parse_le {
-    ($bytes:expr, $t:ty, $range:expr) => {{ <$t>::from_le_bytes($bytes[$range].try_into().unwrap()) }};
+fn io_err(text: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, text)
 }
 
-#[macro_export]
-macro_rules! copy_le {
-    ($dest:expr, $src:expr, $range:expr) => {{ $dest[$range].copy_from_slice(&$src.to_le_bytes()) }};
+#[derive(Clone, Debug)]
+pub struct MtzColumn {
+    pub label: String,
+    /// E.g. 'H' for Miller indices, 'F' for amplitudes, 'Q' for standard deviations, 'P' for
+    /// phases in degrees, and 'I' for integers, such as the free-R flag.
+    pub type_: char,
+    pub min: f32,
+    pub max: f32,
+    pub dataset_id: i32,
 }
 
-fn io_err(text: &str) -> io::Error {
-    io::Error::new(ErrorKind::InvalidData, text)
+/// A merged MTZ reflection file. Batch headers, used by unmerged files, aren't loaded.
+#[derive(Clone, Debug)]
+pub struct Mtz {
+    pub title: String,
+    pub cell: UnitCell,
+    /// E.g. "P 1 21 1".
+    pub space_group: String,
+    pub space_group_number: i32,
+    pub symmetry_ops: Vec<SymOp>,
+    /// (low, high), in Å.
+    pub resolution: Option<(f32, f32)>,
+    pub columns: Vec<MtzColumn>,
+    /// Reflection values, with one row per reflection, and one value per column in each row.
+    /// Missing values are NaN.
+    pub data: Vec<f32>,
 }
 
-// /// Fast, locale-independent atof: parse a `f32` from the start of `s`, advancing the slice.
-// fn fast_atof(s: &mut &str) -> io::Result<f32> {
-//     // Skip leading whitespace
-//     let trimmed = s.trim_start();
-//     let advance = s.len() - trimmed.len();
-//     *s = &s[advance..];
-//     // Skip leading '+'
-//     if s.starts_with('+') {
-//         *s = &s[1..];
-//     }
-//     // Parse with lexical_core, getting the number of bytes consumed
-//     let bytes = s.as_bytes();
-//     let (value, n) = lexical_core::parse_partial::<f64>(bytes);
-//     // Advance past the parsed characters
-//     *s = &s[n..];
-//     Ok(value as f32)
-// }
-
-/// Read the main MTZ headers from a byte stream.
-pub fn read_main_headers(buf: &[u8], save_headers: Option<&mut Vec<String>>) -> io::Result<()> {
-    // todo: Struct?
-    // let mut nsymop  = 0;
-    let mut header_offset = 0;
-    // let mut symops = Vec::new();
-    // let mut version_stamp = String::new();
-    // let mut title = String::new();
-    // let mut batches = Vec::new();
-    // let mut cell = Vec::new();
-    // let mut min_1_d2 = 0;
-    // let mut max_1_d2 = 0;
-    // let mut columns = Vec::new();
-    // let mut SortOrder = String::new();
-    // let mut data = Vec::new();
-    // let mut spacegroup_name = String::new();
-    // let mut spacegroup_number = 0;
-
-    const HEADER_SIZE: usize = 80;
-
-    // Each header line is 80 bytes
-    let mut buf2 = [0u8; HEADER_SIZE];
-
-    // Compute expected position (4 bytes per record, header_offset is 1-based)
-    // let header_pos = 4 * (header_offset as u64 - 1);
-    // let cur_pos = buf.seek(SeekFrom::Current(0))?;
-    // if cur_pos != header_pos {
-    //     return Err(io_err("Invalid header pos"));
-    // }
-
-    // Counters and flags
-    let mut ncol = 0;
-    // let mut has_batch = false;
-
-    let mut i = 0;
-    loop {
-        // Read exactly 80 bytes or break at EOF
-        // if let Err(_) = buf.read_exact(&mut buf) {
-        //     break;
-        // }
-        // Convert to String (including spaces)
-        let line = String::from_utf8_lossy(&buf[i..i + 80]).to_string();
-
-        // Optionally save raw header text
-        // if let Some(ref mut hdrs) = save_headers {
-        //     hdrs.push(line.clone());
-        // }
-
-        // Stop at END record (first three chars)
-        if &line[..3] == "END" {
-            break;
+impl Mtz {
+    pub fn new(buf: &[u8]) -> io::Result<Self> {
+        if buf.len() < DATA_START || buf[0..4] != INITIAL_BYTES {
+            return Err(io_err("Invalid MTZ start bytes; should be b'MTZ '."));
         }
 
-        // Skip record name and any spaces
-        // let mut args = skip_word_and_space(&line);
-
-        // todo temp
-        println!("HEADER Type: {:?}", &line[..4]);
-
-        i += HEADER_SIZE;
-        //     // Dispatch on the 4-character record code
-        //     match &line[..4] {
-        //         "VERS" => {
-        //             version_stamp = rtrim_str(args);
-        //         }
-        //         "TITL" => {
-        //             title = rtrim_str(args);
-        //         }
-        //         "NCOL" => {
-        //             ncol = simple_atoi(&mut args)?;
-        //             nreflections = simple_atoi(&mut args)?;
-        //             let nbatches = simple_atoi(&mut args)?;
-        //             if nbatches < 0 || nbatches > 10_000_000 {
-        //                 return Err(MtzError::WrongNcol);
-        //             }
-        //             batches.resize(nbatches as usize, Default::default());
-        //         }
-        //         "CELL" => {
-        //            cell = read_cell_parameters(args)?;
-        //         }
-        //         "SORT" => {
-        //             for slot in &mut sort_order {
-        //                 *slot = simple_atoi(&mut args)?;
-        //             }
-        //         }
-        //         "SYMI" => {
-        //             nsymop = simple_atoi(&mut args)?;
-        //             symops.reserve(self.nsymop as usize);
-        //             // skip primitive count
-        //             let _ = simple_atoi(&mut args)?;
-        //             // skip lattice type
-        //             args = skip_word_and_space(skip_blank(args));
-        //             spacegroup_number = simple_atoi(&mut args)?;
-        //             args = skip_blank(args);
-        //             // spacegroup name in quotes or bare
-        //             if !args.starts_with('"') {
-        //                 spacegroup_name = read_word(args).to_string();
-        //             } else if let Some(end) = args[1..].find('"') {
-        //                 spacegroup_name = args[1..1+end].to_string();
-        //             }
-        //         }
-        //         "SYMM" => {
-        //             symops.push(parse_triplet(args));
-        //         }
-        //         "RESO" => {
-        //             min_1_d2 = fast_atof(&mut args)?;
-        //             max_1_d2 = fast_atof(&mut args)?;
-        //         }
-        //         "VALM" => {
-        //             if !args.starts_with('N') {
-        //                 let v = fast_atof(&mut args)?;
-        //                 valm = v; // or assign to self.valm
-        //             }
-        //         }
-        //         "COLU" => {
-        //             let mut col = Column::default();
-        //             col.label = read_word(&mut args).to_string();
-        //             col.col_type = read_word(&mut args).chars().next().unwrap();
-        //             col.min_value = fast_atof(&mut args)?;
-        //             col.max_value = fast_atof(&mut args)?;
-        //             col.dataset_id = simple_atoi(&mut args)?;
-        //             col.parent = self as *mut _;
-        //             col.idx = columns.len();
-        //             columns.push(col);
-        //         }
-        //         "COLS" => {
-        //             let lbl = read_word(&mut args);
-        //             if let Some(last) = columns.last_mut() {
-        //                 if last.label == lbl {
-        //                     last.source = read_word(args).to_string();
-        //                 }
-        //             }
-        //         }
-        //         "COLG" => {}
-        //         "NDIF" => {
-        //             let nd = simple_atoi(&mut args)?;
-        //             self.datasets.reserve(nd as usize);
-        //         }
-        //         "PROJ" => {
-        //             let mut ds = Dataset::default();
-        //             ds.id = simple_atoi(&mut args)?;
-        //             ds.project_name = read_word(skip_word_and_space(&line[5..])).to_string();
-        //             self.datasets.push(ds);
-        //         }
-        //         "CRYS" => {
-        //             let id = simple_atoi(&mut args)?;
-        //             if id == self.last_dataset().id {
-        //                 self.last_dataset_mut().crystal_name = read_word(args).to_string();
-        //             }
-        //         }
-        //         "DATA" => {
-        //             let id = simple_atoi(&mut args)?;
-        //             if id == self.last_dataset().id {
-        //                 self.last_dataset_mut().dataset_name = read_word(args).to_string();
-        //             }
-        //         }
-        //         "DCEL" => {
-        //             let id = simple_atoi(&mut args)?;
-        //             if id == self.last_dataset().id {
-        //                 self.last_dataset_mut().cell = read_cell_parameters(args)?;
-        //             }
-        //         }
-        //         "DWAV" => {
-        //             let id = simple_atoi(&mut args)?;
-        //             if id == self.last_dataset().id {
-        //                 self.last_dataset_mut().wavelength = fast_atof(&mut args)?;
-        //             }
-        //         }
-        //         "BATCH" => {
-        //             has_batch = true;
-        //         }
-        //         _ => {
-        //             println!("Unknown header: {}", rtrim_str(&line));
-        //         }
-        //     }
-        // }
-        //
-        // // consistency checks
-        // if ncol != columns.len() as i32 {
-        //     return Err(MtzError::ColumnCountMismatch);
-        // }
-        // if has_batch != !self.batches.is_empty() {
-        //     return Err(MtzError::BatchMismatch);
-        // }
-        // if !data.is_empty() {
-        //     let expected = columns.len() * nreflections as usize;
-        //     if data.len() > expected {
-        //         data.truncate(expected);
-        //     } else if data.len() < expected {
-        //         return Err(MtzError::DataSizeMismatch);
-        //     }
-    }
-
-    Ok(())
-}
+        // The machine stamp's first 4 half-bytes give the real, complex, integer, and character
+        // formats. Big endian: 1. Little endian: 4.
+        let real_be = buf[8] >> 4 == 1;
+        let int_be = buf[9] >> 4 == 1;
+
+        let word = |bytes: &[u8]| -> [u8; 4] { bytes.try_into().unwrap() };
+        let header_offset = if int_be {
+            i32::from_be_bytes(word(&buf[4..8]))
+        } else {
+            i32::from_le_bytes(word(&buf[4..8]))
+        };
+        if header_offset == -1 {
+            return Err(io_err(
+                "MTZ files with 64-bit header offsets aren't supported.",
+            ));
+        }
+        // In 4-byte words, 1-based.
+        let header_pos = 4 * (header_offset as usize).saturating_sub(1);
+        if header_pos < DATA_START || header_pos > buf.len() {
+            return Err(io_err("Invalid MTZ header position"));
+        }
 
-impl ReflectionsData {
-    /// Small subset of MTZ – merged reflections, one dataset.
-    pub fn from_mtz(buf: &[u8]) -> io::Result<ReflectionsData> {
-        let mut pos = 0;
+        let mut title = String::new();
+        let mut cell = None;
+        let mut space_group = String::new();
+        let mut space_group_number = 0;
+        let mut symmetry_ops = Vec::new();
+        let mut resolution = None;
+        let mut missing_val = None;
+        let mut columns = Vec::new();
+        let mut ncol = 0;
+        let mut nrefl = 0;
+
+        let parse_err = |line: &str| io_err(&format!("Invalid MTZ header record: {}", line.trim()));
+
+        for record in buf[header_pos..].chunks(RECORD_LEN) {
+            let line = String::from_utf8_lossy(record);
+            let keyword = line.get(..4).unwrap_or_default();
+            // The rest of the record, after the keyword.
+            let args = line
+                .split_once(' ')
+                .map(|(_, a)| a.trim())
+                .unwrap_or_default();
+            let nums: Vec<&str> = args.split_whitespace().collect();
+
+            match keyword {
+                "END " | "END" => break,
+                "TITL" => title = args.to_owned(),
+                "NCOL" => {
+                    if nums.len() < 2 {
+                        return Err(parse_err(&line));
+                    }
+                    ncol = nums[0].parse().map_err(|_| parse_err(&line))?;
+                    nrefl = nums[1].parse().map_err(|_| parse_err(&line))?;
+                }
+                "CELL" => {
+                    let vals: Vec<f64> = nums.iter().filter_map(|v| v.parse().ok()).collect();
+                    if vals.len() < 6 {
+                        return Err(parse_err(&line));
+                    }
+                    cell = Some(UnitCell::new(
+                        vals[0], vals[1], vals[2], vals[3], vals[4], vals[5],
+                    ));
+                }
+                "SYMI" => {
+                    // E.g. `SYMINF   4  2 P     4                 'P 1 21 1' PG2`
+                    if let Some(v) = nums.get(3) {
+                        space_group_number = v.parse().unwrap_or_default();
+                    }
+                    if let Some(start) = args.find('\'') {
+                        if let Some(len) = args[start + 1..].find('\'') {
+                            space_group = args[start + 1..start + 1 + len].to_owned();
+                        }
+                    } else if let Some(v) = nums.get(4) {
+                        space_group = v.to_string();
+                    }
+                }
+                "SYMM" => symmetry_ops.push(parse_symop_xyz(args)?),
+                "RESO" => {
+                    // As min and max 1/d².
+                    let vals: Vec<f32> = nums.iter().filter_map(|v| v.parse().ok()).collect();
+                    if vals.len() >= 2 && vals[0] > 0. && vals[1] > 0. {
+                        resolution = Some((1. / vals[0].sqrt(), 1. / vals[1].sqrt()));
+                    }
+                }
+                "VALM" => {
+                    if let Some(v) = nums.first() {
+                        missing_val = v.parse::<f32>().ok();
+                    }
+                }
+                "COLU" => {
+                    // E.g. `COLUMN FWT    F    0.0000    245.09    1`
+                    if nums.len() < 5 {
+                        return Err(parse_err(&line));
+                    }
+                    columns.push(MtzColumn {
+                        label: nums[0].to_owned(),
+                        type_: nums[1].chars().next().unwrap_or(' '),
+                        min: nums[2].parse().unwrap_or_default(),
+                        max: nums[3].parse().unwrap_or_default(),
+                        dataset_id: nums[4].parse().unwrap_or_default(),
+                    });
+                }
+                _ => (),
+            }
+        }
 
-        if buf[0..4] != INITIAL_BYTES {
-            return Err(io_err("Invalid MTZ start bytes; should be b'MTZ '."));
+        let Some(cell) = cell else {
+            return Err(io_err("MTZ header is missing its CELL record"));
+        };
+        if columns.len() != ncol {
+            return Err(io_err(&format!(
+                "MTZ header lists {ncol} columns, but describes {}",
+                columns.len()
+            )));
         }
 
-        let header_addr = parse_le!(buf, u32, 4..8) as usize;
-        println!("Header addr: {:?}", header_addr);
+        let data_len = ncol * nrefl;
+        if DATA_START + data_len * 4 > header_pos {
+            return Err(io_err(
+                "MTZ reflection data is shorter than its header indicates",
+            ));
+        }
 
-        // This encodes the number formats of the architecture the file was written on. (In fact,
-        // the machine stamp is positioned 2 words from the start, where a word is sizeof(float), i.e.
-        // typically 8 bytes in. The first 4 half-bytes represent the real, complex, integer and
-        // character formats, and the last two bytes are currently unused.)
-        let machine_stamp = parse_le!(buf, u32, 8..12);
+        let data = buf[DATA_START..DATA_START + data_len * 4]
+            .chunks_exact(4)
+            .map(|b| {
+                let v = if real_be {
+                    f32::from_be_bytes(word(b))
+                } else {
+                    f32::from_le_bytes(word(b))
+                };
+                match missing_val {
+                    Some(m) if v == m => f32::NAN,
+                    _ => v,
+                }
+            })
+            .collect();
 
-        // Big endian: 1. Little endian: 4. Other values mean native byte order.
-        // e.g. 4 for numerical, and 1 for char.
-        let real_fmt = buf[8] >> 4;
-        let cplx_fmt = buf[8] & 0xf;
-        let int_fmt = buf[9] >> 4;
-        let char_fmt = buf[9] & 0xf;
+        Ok(Self {
+            title,
+            cell,
+            space_group,
+            space_group_number,
+            symmetry_ops,
+            resolution,
+            columns,
+            data,
+        })
+    }
 
-        // Hmm. It seems then we jump to byte 80?
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf)?;
 
-        let refl_data = &buf[21..header_addr];
-        let header_data = &buf[header_addr..];
+        Self::new(&buf)
+    }
 
-        // read_main_headers(&buf[80..], None);
-        read_main_headers(header_data, None)?;
+    pub fn num_reflections(&self) -> usize {
+        if self.columns.is_empty() {
+            0
+        } else {
+            self.data.len() / self.columns.len()
+        }
+    }
 
-        println!("HEader data: {:x?}", &header_data[..100]);
+    pub fn column_index(&self, label: &str) -> Option<usize> {
+        self.columns.iter().position(|c| c.label == label)
+    }
 
-        let points = Vec::new();
-        Ok(Self {
-            space_group: "P 1".to_string(), // not stored in minimal header - fake it
-            cell_len_a: 0.,
-            cell_len_b: 0.,
-            cell_len_c: 0.,
-            cell_angle_alpha: 0.,
-            cell_angle_beta: 0.,
-            cell_angle_gamma: 0.,
-            points,
-        })
+    /// All values of a column, one per reflection. None if there's no column with this label.
+    pub fn column(&self, label: &str) -> Option<Vec<f32>> {
+        let i = self.column_index(label)?;
+        Some(
+            self.data
+                .iter()
+                .skip(i)
+                .step_by(self.columns.len())
+                .copied()
+                .collect(),
+        )
+    }
 
-        // Ok(Self {
-        //     space_group: "P 1".to_string(), // not stored in minimal header - fake it
-        //     cell_len_a: cell[0],
-        //     cell_len_b: cell[1],
-        //     cell_len_c: cell[2],
-        //     cell_angle_alpha: cell[3],
-        //     cell_angle_beta: cell[4],
-        //     cell_angle_gamma: cell[5],
-        //     points,
-        // })
+    /// Compute the 2mFo-DFc electron density map from the FWT and PHWT columns, or 2FOFCWT and
+    /// PH2FOFCWT if those aren't present. See [`Self::to_density_map_from`].
+    pub fn to_density_map(&self) -> io::Result<DensityMap> {
+        let Some((amp, phase)) = MAP_COEF_LABELS
+            .iter()
+            .find(|(a, p)| self.column_index(a).is_some() && self.column_index(p).is_some())
+        else {
+            return Err(io_err(
+                "MTZ file has no map coefficient columns; expected FWT and PHWT, or 2FOFCWT and PH2FOFCWT",
+            ));
+        };
+
+        self.to_density_map_from(amp, phase)
     }
 
-    pub fn to_mtz(&self) -> Vec<u8> {
-        let mut result = Vec::new();
-
-        result.extend_from_slice(format!("{:<80}\n", "MTZ:V1.1").as_bytes());
-        result.extend(format!("TITLE {:<70}\n", "written by Rust").as_bytes());
-        result.extend(
-            format!(
-                "CELL {:8.3} {:8.3} {:8.3} {:7.3} {:7.3} {:7.3}{:10}\n",
-                self.cell_len_a,
-                self.cell_len_b,
-                self.cell_len_c,
-                self.cell_angle_alpha,
-                self.cell_angle_beta,
-                self.cell_angle_gamma,
-                ""
-            )
-                .as_bytes(),
-        );
-
-        // column directory
-        let cols = ["H", "K", "L", "F", "SIGF", "FREE"];
-        for (i, &c) in cols.iter().enumerate() {
-            result.extend(format!("COLUMN{:5}{:<8}{:>5}{:>5}\n", "", c, 1, i + 1).as_bytes());
+    /// Compute an electron density map from amplitude and phase columns, e.g. "DELFWT" and
+    /// "PHDELWT" for a difference map. Reflections are expanded to the full sphere using the
+    /// symmetry operators and Friedel's law, then Fourier transformed over the unit cell, on a
+    /// grid with a spacing of about a third of the resolution. Values are in e · Å⁻³, with the
+    /// F000 term omitted, so the map's mean is 0.
+    pub fn to_density_map_from(
+        &self,
+        amp_label: &str,
+        phase_label: &str,
+    ) -> io::Result<DensityMap> {
+        let col = |label: &str| {
+            self.column_index(label).ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("MTZ file has no {label} column"),
+                )
+            })
+        };
+        let (c_h, c_k, c_l) = (col("H")?, col("K")?, col("L")?);
+        let (c_amp, c_phase) = (col(amp_label)?, col(phase_label)?);
+
+        let identity = [SymOp::identity()];
+        let ops = if self.symmetry_ops.is_empty() {
+            &identity[..]
+        } else {
+            &self.symmetry_ops[..]
+        };
+
+        // (hkl, amplitude, phase in radians)
+        let refls: Vec<([f64; 3], f64, f64)> = self
+            .data
+            .chunks_exact(self.columns.len())
+            .filter(|row| row[c_amp].is_finite() && row[c_phase].is_finite())
+            .map(|row| {
+                (
+                    [row[c_h] as f64, row[c_k] as f64, row[c_l] as f64],
+                    row[c_amp] as f64,
+                    (row[c_phase] as f64).to_radians(),
+                )
+            })
+            .collect();
+
+        // The full sphere of reflections: (hkl, F)
+        let mut expanded = Vec::with_capacity(refls.len() * ops.len() * 2);
+        for (hkl, amp, phase) in &refls {
+            for op in ops {
+                // h' = h R, and φ' = φ - 2π h · t
+                let r = &op.rot;
+                let hkl_op = [0, 1, 2].map(|j| {
+                    (hkl[0] * r[0][j] + hkl[1] * r[1][j] + hkl[2] * r[2][j]).round() as i32
+                });
+                let shift = TAU * (hkl[0] * op.trans.x + hkl[1] * op.trans.y + hkl[2] * op.trans.z);
+
+                let f = Complex::from_polar(*amp, phase - shift);
+                expanded.push((hkl_op, f));
+                expanded.push((hkl_op.map(|v| -v), f.conj()));
+            }
         }
-        result.extend("END\n".as_bytes());
 
-        // pad to 80-byte blocks
-        while result.len() % HEADER_BLOCK != 0 {
-            result.push(b' ');
+        let mut dims = [1; 3];
+        for (hkl, _) in &expanded {
+            for (d, v) in dims.iter_mut().zip(hkl) {
+                *d = (*d).max(next_good_fft_len(3 * v.unsigned_abs() as usize));
+            }
+        }
+        let [nx, ny, nz] = dims;
+
+        // Symmetry-equivalent reflections may coincide, e.g. for centric ones; these are set
+        // rather than summed.
+        let mut grid = vec![Complex::new(0., 0.); nx * ny * nz];
+        for (hkl, f) in expanded {
+            let [h, k, l] = [0, 1, 2].map(|i| hkl[i].rem_euclid(dims[i] as i32) as usize);
+            grid[(l * ny + k) * nx + h] = f;
         }
 
-        let mut bin = Vec::new();
-        for p in &self.points {
-            copy_le!(bin, p.h as f32, 0..4);
-            copy_le!(bin, p.k as f32, 4..8);
-            copy_le!(bin, p.l as f32, 8..12);
-            copy_le!(bin, p.amp as f32, 12..16);
-            copy_le!(bin, p.amp_uncertainty as f32, 20..24);
-            copy_le!(
-                bin,
-                if matches!(p.status, MapStatus::FreeSet) {
-                    1.0
-                } else {
-                    0.0
-                } as f32,
-                24..28
-            );
+        // ρ(x) = 1/V Σ F(h) exp(-2πi h · x): A forward transform along each axis.
+        let mut planner = FftPlanner::new();
+        for axis in 0..3 {
+            fft_axis(&mut grid, dims, axis, &mut planner);
         }
 
-        result.extend(bin);
-        result
+        let vol = self.cell.volume();
+        let data: Vec<f32> = grid.iter().map(|v| (v.re / vol) as f32).collect();
+
+        let dmin = data.iter().copied().fold(f32::INFINITY, f32::min);
+        let dmax = data.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let dmean = data.iter().sum::<f32>() / data.len() as f32;
+
+        let hdr = MapHeader {
+            inner: DensityHeaderInner {
+                cell: self.cell.clone(),
+                mapc: 1,
+                mapr: 2,
+                maps: 3,
+                mx: nx as i32,
+                my: ny as i32,
+                mz: nz as i32,
+                nxstart: 0,
+                nystart: 0,
+                nzstart: 0,
+                ispg: self.space_group_number.max(1),
+                nsymbt: 0,
+                version: 20140,
+                xorigin: None,
+                yorigin: None,
+                zorigin: None,
+            },
+            nx: nx as i32,
+            ny: ny as i32,
+            nz: nz as i32,
+            mode: 2,
+            dmin,
+            dmax,
+            dmean,
+            nlabl: 0,
+            labels: Vec::new(),
+        };
+
+        DensityMap::new(hdr, data)
     }
 }
 
-pub fn load_mtz(path: &Path) -> io::Result<ReflectionsData> {
-    let mut file = File::open(path)?;
-    let mut buf = Vec::new();
-    file.read_to_end(&mut buf)?;
-
-    ReflectionsData::from_mtz(&buf)
-}
-
-pub fn save_mtz(data: &ReflectionsData, path: &Path) -> io::Result<()> {
-    let buf = data.to_mtz();
-
-    let mut file = File::open(path)?;
-    file.write_all(&buf)?;
+/// An in-place forward FFT along one axis of a 3D grid, with x varying fastest.
+fn fft_axis(
+    grid: &mut [Complex<f64>],
+    dims: [usize; 3],
+    axis: usize,
+    planner: &mut FftPlanner<f64>,
+) {
+    let [nx, ny, nz] = dims;
+    let fft = planner.plan_fft_forward(dims[axis]);
+
+    if axis == 0 {
+        // Contiguous; this transforms each row in turn.
+        fft.process(grid);
+        return;
+    }
 
-    Ok(())
+    let stride = if axis == 1 { nx } else { nx * ny };
+    // The start of each line along the axis.
+    let starts: Vec<usize> = if axis == 1 {
+        (0..nz)
+            .flat_map(|k| (0..nx).map(move |i| k * nx * ny + i))
+            .collect()
+    } else {
+        (0..nx * ny).collect()
+    };
+
+    let mut line = vec![Complex::new(0., 0.); dims[axis]];
+    for start in starts {
+        for (j, v) in line.iter_mut().enumerate() {
+            *v = grid[start + j * stride];
+        }
+        fft.process(&mut line);
+        for (j, v) in line.iter().enumerate() {
+            grid[start + j * stride] = *v;
+        }
+    }
 }
@@ -515,6 +515,22 @@ impl BasisSet {
     }
 }
 
+impl BasisSet {
+    /// Look up a basis set from its keyword, ignoring case.
+    pub fn from_keyword(kw: &str) -> Option<Self> {
+        [
+            BasisSetCategory::Pople,
+            BasisSetCategory::Ahlrich,
+            BasisSetCategory::KarlseruheDhf,
+            BasisSetCategory::CorrelationConsistent,
+        ]
+        .into_iter()
+        .flat_map(|c| c.get_sets())
+        .filter(|b| *b != None)
+        .find(|b| b.keyword().eq_ignore_ascii_case(kw))
+    }
+}
+
 /// May be useful to organize basis sets.
 #[derive(Clone, Copy, PartialEq, Debug, Default)]
 pub enum BasisSetCategory {
@@ -601,6 +617,15 @@ impl BasisSetCategory {
                 B6_311PlusPlusG_3df_3pd,
             ],
             Self::Ahlrich => vec![
+                Sv,
+                SvP,
+                Svp,
+                Tzv,
+                TzvP,
+                Tzvp,
+                Tzvpp,
+                Qzvp,
+                Qzvpp,
                 DefSvP,
                 DefSvp,
                 DefTzvp,
@@ -1,13 +1,15 @@
-//! For computing atom-centered charges, e.g. MBIS, CHELPG, and RESP.
+//! For computing atom-centered charges, e.g. MBIS and CHELPG. Also parses the Mulliken and
+//! Loewdin population analyses ORCA prints by default, and NBO natural charges.
 //! [Docs](https://www.faccts.de/docs/orca/6.1/manual/contents/spectroscopyproperties/population.html?q=mbis&n=0#mbis-charges)
 
-// todo: Support CHELPG and RESP.
-
 use std::io;
 
 use lin_alg::f64::Vec3;
 
-use crate::orca::make_inp_block;
+use crate::orca::{hess::FrequenciesOutput, make_inp_block};
+
+/// Converts dipole moments from atomic units (e · a₀) to Debye.
+const AU_TO_DEBYE: f64 = 2.541_746;
 
 /// Note: these fields currently do not do anything; all are returned.
 #[derive(Debug, Clone, Default)]
@@ -24,6 +26,99 @@ impl MbisChargesCfg {
     }
 }
 
+/// Settings for the `%chelpg` block. CHELPG charges are fit to reproduce the electrostatic
+/// potential on a grid of points around the molecule.
+/// [CHELPG Charges](https://www.faccts.de/docs/orca/6.1/manual/contents/spectroscopyproperties/population.html#chelpg-charges)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChelpgCfg {
+    /// Spacing of the grid, in Å.
+    pub grid: f64,
+    /// The maximum distance from any atom to a grid point, in Å.
+    pub rmax: f64,
+    /// Constrain the charges to reproduce the molecule's dipole moment.
+    pub dipole: bool,
+}
+
+impl Default for ChelpgCfg {
+    /// ORCA's defaults.
+    fn default() -> Self {
+        Self {
+            grid: 0.3,
+            rmax: 2.8,
+            dipole: false,
+        }
+    }
+}
+
+impl ChelpgCfg {
+    pub fn make_inp(&self) -> String {
+        let contents = vec![
+            ("GRID", self.grid.to_string()),
+            ("RMAX", self.rmax.to_string()),
+            (
+                "DIPOLE",
+                if self.dipole { "TRUE" } else { "FALSE" }.to_string(),
+            ),
+        ];
+        make_inp_block("chelpg", &contents, &[])
+    }
+}
+
+/// Charges fit to the electrostatic potential, from the `CHELPG Charges` table. Can be used for
+/// force field parameterization, e.g. of ligands.
+///
+/// There's no RESP equivalent: ORCA doesn't perform RESP fits itself. These are done by external
+/// programs (e.g. Multiwfn, or AmberTools' `resp`) from the wavefunction or an ESP grid, so they're
+/// out of scope for this module. CHELPG charges are the closest ORCA-native alternative.
+#[derive(Debug, Clone)]
+pub struct ChelpgChargesOutput {
+    pub text: String,
+    /// Per-atom charges, in input atom order.
+    pub charges: Vec<f64>,
+    /// If the job included a frequency calculation, e.g. with the `Freq` keyword.
+    pub frequencies: Option<FrequenciesOutput>,
+}
+
+impl ChelpgChargesOutput {
+    /// Create by parsing ORCA's stdout (or `.out`) text. If there are multiple CHELPG tables, the
+    /// last is used. Rows look like "   0   O   :      -0.774912".
+    pub fn new(text: String) -> io::Result<Self> {
+        let start = text.rfind("CHELPG Charges").ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "CHELPG Charges section not found",
+            )
+        })?;
+
+        let mut charges = Vec::new();
+        // Skip the header, and the separator line after it.
+        for line in text[start..].lines().skip(2) {
+            let Some((atom, charge)) = line.split_once(':') else {
+                break;
+            };
+            let atom: Vec<_> = atom.split_whitespace().collect();
+            if atom.len() != 2 || atom[0].parse::<usize>().is_err() {
+                break;
+            }
+
+            charges.push(parse_f64(charge.trim())?);
+        }
+
+        if charges.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "CHELPG Charges table is empty",
+            ));
+        }
+
+        Ok(Self {
+            text,
+            charges,
+            frequencies: None,
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AtomChargeData {
     pub charge: f64,
@@ -68,6 +163,8 @@ pub struct ChargesOutput {
     pub dipole: Vec<Vec3>,
     pub quadrupole: Vec<Quadrupole>,
     pub octopole: Vec<Octopole>,
+    /// If the job included a frequency calculation, e.g. with the `Freq` keyword.
+    pub frequencies: Option<FrequenciesOutput>,
 }
 
 impl ChargesOutput {
@@ -338,10 +435,313 @@ impl ChargesOutput {
             dipole,
             quadrupole,
             octopole,
+            frequencies: None,
         })
     }
 }
 
+/// Population analyses ORCA prints per-atom charges for, in addition to MBIS.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PopulationMethod {
+    Mulliken,
+    Loewdin,
+}
+
+impl PopulationMethod {
+    fn header(self) -> &'static str {
+        match self {
+            Self::Mulliken => "MULLIKEN ATOMIC CHARGES",
+            Self::Loewdin => "LOEWDIN ATOMIC CHARGES",
+        }
+    }
+}
+
+/// A per-atom result of a population analysis.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AtomPopulation {
+    pub charge: f64,
+    /// Present for open-shell calculations, where ORCA prints charges and spin densities together.
+    pub spin_density: Option<f64>,
+}
+
+/// Parse per-atom Mulliken or Loewdin charges, and spin densities if present, from ORCA output
+/// text. Values are in input atom order. If the section appears multiple times, e.g. in a geometry
+/// optimization, the last one is used.
+pub fn parse_population(text: &str, method: PopulationMethod) -> io::Result<Vec<AtomPopulation>> {
+    let header = method.header();
+    let start = text.rfind(header).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{header} section not found"),
+        )
+    })?;
+
+    let mut result = Vec::new();
+    // Skip the header line and its dashed underline.
+    for line in text[start..].lines().skip(2) {
+        let t = line.trim();
+        if t.is_empty() || t.starts_with("Sum of") {
+            break;
+        }
+
+        // "0 C :   -0.123456    1.012345"
+        let Some((_, values)) = t.split_once(':') else {
+            break;
+        };
+        let values: Vec<_> = values.split_whitespace().collect();
+        if values.is_empty() {
+            break;
+        }
+
+        let charge = parse_f64(values[0])?;
+        let spin_density = match values.get(1) {
+            Some(v) => Some(parse_f64(v)?),
+            None => None,
+        };
+
+        result.push(AtomPopulation {
+            charge,
+            spin_density,
+        });
+    }
+
+    if result.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("No atoms found in {header}"),
+        ));
+    }
+
+    Ok(result)
+}
+
+/// From a single point calculation. Contains the energy, orbital energies, and the Mulliken and
+/// Loewdin charges ORCA prints by default.
+#[derive(Debug, Clone)]
+pub struct SinglePointOutput {
+    pub text: String,
+    /// From `FINAL SINGLE POINT ENERGY`. `None` if ORCA didn't print it.
+    pub energy_hartree: Option<f64>,
+    /// Orbital energies in Hartree, from the `ORBITAL ENERGIES` table, in ascending order. For
+    /// open-shell calculations, these are the spin-up orbitals. Empty if ORCA didn't print this
+    /// section.
+    pub orbital_energies: Vec<f64>,
+    /// Index into `orbital_energies` of the highest occupied orbital. `None` if there are no
+    /// orbital energies.
+    pub homo_index: Option<usize>,
+    /// Per-atom Mulliken charges, in input atom order. Empty if ORCA didn't print this section,
+    /// e.g. with reduced output settings.
+    pub mulliken: Vec<f64>,
+    /// Per-atom Loewdin charges, in input atom order. Empty if ORCA didn't print this section.
+    pub loewdin: Vec<f64>,
+    /// The molecular dipole moment, in Debye, from `Total Dipole Moment` in the `DIPOLE MOMENT`
+    /// block. (ORCA prints these components in atomic units; they're converted here.)
+    pub dipole_debye: Option<Vec3>,
+    /// The dipole moment's magnitude, in Debye, from `Magnitude (Debye)`.
+    pub dipole_total: Option<f64>,
+    /// Rotational constants A, B, and C, in cm⁻¹, from the `Rotational spectrum` block.
+    pub rotational_constants: Option<[f64; 3]>,
+    /// The energy in Hartree at each SCF iteration, from the last `SCF ITERATIONS` table. Useful
+    /// for troubleshooting convergence.
+    pub scf_energies: Vec<f64>,
+    /// If the last SCF procedure reported `SCF CONVERGED AFTER n CYCLES`.
+    pub scf_converged: bool,
+}
+
+impl SinglePointOutput {
+    /// Create by parsing ORCA's stdout (or `.out`) text. If there are multiple energies or orbital
+    /// tables, e.g. from a geometry optimization, the last ones are used. Sections ORCA didn't
+    /// print are left empty, or `None`.
+    pub fn new(text: String) -> io::Result<Self> {
+        let energy_hartree = text
+            .lines()
+            .rev()
+            .find_map(|l| l.trim().strip_prefix("FINAL SINGLE POINT ENERGY"))
+            .and_then(|v| v.split_whitespace().next())
+            .map(parse_f64)
+            .transpose()?;
+
+        let (orbital_energies, homo_index) = parse_orbital_energies(&text)?;
+
+        let charges = |method: PopulationMethod| -> io::Result<Vec<f64>> {
+            if !text.contains(method.header()) {
+                return Ok(Vec::new());
+            }
+
+            Ok(parse_population(&text, method)?
+                .into_iter()
+                .map(|p| p.charge)
+                .collect())
+        };
+
+        let mulliken = charges(PopulationMethod::Mulliken)?;
+        let loewdin = charges(PopulationMethod::Loewdin)?;
+
+        // E.g. "Total Dipole Moment    :     -0.000001      0.000000     -0.744070"
+        let last_values = |prefix: &str| -> io::Result<Option<Vec<f64>>> {
+            text.lines()
+                .rev()
+                .find_map(|l| l.trim().strip_prefix(prefix))
+                .map(|v| {
+                    v.trim_start_matches(|c: char| c.is_whitespace() || c == ':')
+                        .split_whitespace()
+                        .map(parse_f64)
+                        .collect()
+                })
+                .transpose()
+        };
+
+        let dipole_debye = match last_values("Total Dipole Moment")? {
+            Some(v) if v.len() >= 3 => Some(Vec3::new(v[0], v[1], v[2]) * AU_TO_DEBYE),
+            _ => None,
+        };
+        let dipole_total = last_values("Magnitude (Debye)")?.and_then(|v| v.first().copied());
+        let rotational_constants = match last_values("Rotational constants in cm-1")? {
+            Some(v) if v.len() >= 3 => Some([v[0], v[1], v[2]]),
+            _ => None,
+        };
+
+        let (scf_energies, scf_converged) = parse_scf_history(&text);
+
+        Ok(Self {
+            text,
+            energy_hartree,
+            orbital_energies,
+            homo_index,
+            mulliken,
+            loewdin,
+            dipole_debye,
+            dipole_total,
+            rotational_constants,
+            scf_energies,
+            scf_converged,
+        })
+    }
+
+    /// The HOMO-LUMO gap, in Hartree. `None` if ORCA didn't print orbital energies, or any virtual
+    /// orbitals.
+    pub fn homo_lumo_gap(&self) -> Option<f64> {
+        let homo_index = self.homo_index?;
+        let homo = self.orbital_energies.get(homo_index)?;
+        let lumo = self.orbital_energies.get(homo_index + 1)?;
+        Some(lumo - homo)
+    }
+}
+
+/// Parse the last `ORBITAL ENERGIES` table. Returns energies in Hartree, and the index of the
+/// highest occupied orbital. Rows look like "   4   2.0000      -0.937362       -25.5069". Empty if
+/// the table isn't present.
+fn parse_orbital_energies(text: &str) -> io::Result<(Vec<f64>, Option<usize>)> {
+    let lines: Vec<&str> = text.lines().collect();
+    let Some(start) = lines.iter().rposition(|l| l.trim() == "ORBITAL ENERGIES") else {
+        return Ok((Vec::new(), None));
+    };
+
+    let Some(header) = lines[start..].iter().position(|l| {
+        let t = l.trim();
+        t.starts_with("NO") && t.contains("OCC") && t.contains("E(Eh)")
+    }) else {
+        return Ok((Vec::new(), None));
+    };
+
+    let mut energies = Vec::new();
+    let mut homo_index = None;
+
+    for line in &lines[start + header + 1..] {
+        // Ends at a blank line, or e.g. "SPIN DOWN ORBITALS" for open-shell systems.
+        let parts: Vec<_> = line.split_whitespace().collect();
+        if parts.len() < 4 || parts[0].parse::<usize>().is_err() {
+            break;
+        }
+
+        let occ = parse_f64(parts[1])?;
+        if occ > 0. {
+            homo_index = Some(energies.len());
+        }
+        energies.push(parse_f64(parts[2])?);
+    }
+
+    Ok((energies, homo_index))
+}
+
+/// Parse the energy in Hartree at each iteration from the last `SCF ITERATIONS` table, and whether
+/// that SCF procedure converged. This works on the output of runs that failed or are still running,
+/// e.g. to inspect why the SCF didn't converge; in that case, the energies up to that point are
+/// returned. Empty if there's no SCF iteration table.
+pub fn parse_scf_history(text: &str) -> (Vec<f64>, bool) {
+    let Some(start) = text.rfind("SCF ITERATIONS") else {
+        return (Vec::new(), false);
+    };
+
+    let mut energies = Vec::new();
+    for line in text[start..].lines() {
+        if line.contains("SCF CONVERGED AFTER") {
+            return (energies, true);
+        }
+        if line.contains("SCF NOT CONVERGED") || line.contains("TOTAL SCF ENERGY") {
+            break;
+        }
+
+        // "    3    -76.3223578621   -3.1e-05  ..."; other lines are headers and status messages,
+        // e.g. when switching from DIIS to SOSCF.
+        let mut cols = line.split_whitespace();
+        if let (Some(iter), Some(energy)) = (cols.next(), cols.next())
+            && iter.parse::<u32>().is_ok()
+            && let Ok(energy) = energy.parse::<f64>()
+        {
+            energies.push(energy);
+        }
+    }
+
+    (energies, false)
+}
+
+/// Parse NBO natural charges from the "Summary of Natural Population Analysis" table, in input atom
+/// order. This is present when NBO analysis is enabled, e.g. with the `NBO` keyword. For open-shell
+/// systems, NBO prints this table for the total density first, then per spin; we use the first.
+pub fn parse_nbo_charges(text: &str) -> io::Result<Vec<f64>> {
+    let not_found = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Natural population analysis not found",
+        )
+    };
+
+    let section =
+        section_after(text, "Summary of Natural Population Analysis").ok_or_else(not_found)?;
+    let mut rows = parse_table_rows(section, |t| t.starts_with("Atom") && t.contains("Charge"))
+        .ok_or_else(not_found)?;
+
+    let mut result = Vec::new();
+    for line in rows.by_ref() {
+        let t = line.trim();
+        if t.starts_with("---") {
+            continue;
+        }
+        if t.is_empty() || t.starts_with("===") {
+            break;
+        }
+
+        // "O  1   -0.92410      1.99975     6.91433    0.01002     8.92410"
+        let parts: Vec<_> = t.split_whitespace().collect();
+        if parts.len() < 3 {
+            break;
+        }
+        result.push(parse_f64(parts[2])?);
+    }
+
+    if result.is_empty() {
+        return Err(not_found());
+    }
+
+    Ok(result)
+}
+
+fn parse_f64(s: &str) -> io::Result<f64> {
+    s.parse::<f64>()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
 fn section_after<'a>(haystack: &'a str, needle: &str) -> Option<&'a str> {
     let i = haystack.find(needle)?;
     Some(&haystack[i + needle.len()..])
@@ -0,0 +1,50 @@
+//! [GOAT conformer search](https://www.faccts.de/docs/orca/6.0/tutorials/prop/goat.html) output.
+//! GOAT writes the final conformer ensemble to `<basename>.finalensemble.xyz`, a multi-frame XYZ
+//! file with each conformer's energy (Hartree) in its comment line.
+
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+};
+
+use crate::{Xyz, new_xyz_trajectory};
+
+#[derive(Clone, Debug)]
+pub struct ConformerSearchOutput {
+    pub text: String,
+    /// Conformers, and their energies in Hartree, sorted by energy; lowest first.
+    pub conformers: Vec<(Xyz, f64)>,
+}
+
+impl ConformerSearchOutput {
+    /// Create from the text of a `.finalensemble.xyz` file, and ORCA's stdout text.
+    pub fn new(ensemble: &str, text: String) -> io::Result<Self> {
+        let mut conformers = Vec::new();
+
+        for (i, xyz) in new_xyz_trajectory(ensemble)?.into_iter().enumerate() {
+            // The energy is the first numeric token of the comment, e.g. "-347.12345678".
+            let energy = xyz
+                .comment
+                .split_whitespace()
+                .find_map(|t| t.parse::<f64>().ok())
+                .ok_or_else(|| {
+                    io::Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Missing energy in the comment line of conformer {i}"),
+                    )
+                })?;
+
+            conformers.push((xyz, energy));
+        }
+
+        conformers.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        Ok(Self { text, conformers })
+    }
+
+    pub fn load(ensemble_path: &Path, text: String) -> io::Result<Self> {
+        let ensemble = fs::read_to_string(ensemble_path)?;
+        Self::new(&ensemble, text)
+    }
+}
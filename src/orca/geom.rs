@@ -1,6 +1,6 @@
 //! [Geometry Optimizations](https://www.faccts.de/docs/orca/6.1/manual/contents/structurereactivity/optimizations.html)
 
-use crate::orca::make_inp_block;
+use crate::orca::make_inp_block_nested;
 
 /// [Geometry Optimization Thresholds](https://www.faccts.de/docs/orca/6.1/manual/contents/structurereactivity/optimizations.html#geometry-optimization-thresholds)
 #[derive(Clone, Copy, PartialEq, Debug, Default)]
@@ -22,6 +22,36 @@ impl Convergence {
     }
 }
 
+/// A bond length, angle, or dihedral, from 0-based atom indices.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum InternalCoord {
+    Bond(usize, usize),
+    Angle(usize, usize, usize),
+    Dihedral(usize, usize, usize, usize),
+}
+
+impl InternalCoord {
+    /// E.g. "D 0 1 2 3".
+    fn make_inp(self) -> String {
+        match self {
+            Self::Bond(a, b) => format!("B {a} {b}"),
+            Self::Angle(a, b, c) => format!("A {a} {b} {c}"),
+            Self::Dihedral(a, b, c, d) => format!("D {a} {b} {c} {d}"),
+        }
+    }
+}
+
+/// A relaxed surface scan: The coordinate is stepped from `start` to `end`, inclusive, and the
+/// rest of the geometry is optimized at each step. Values are in Å, or degrees.
+/// [Relaxed Surface Scans](https://www.faccts.de/docs/orca/6.1/manual/contents/structurereactivity/optimizations.html#relaxed-surface-scans)
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Scan {
+    pub coord: InternalCoord,
+    pub start: f64,
+    pub end: f64,
+    pub steps: u32,
+}
+
 /// [Geometry Optimizations, Table 4.4](https://www.faccts.de/docs/orca/6.1/manual/contents/structurereactivity/optimizations.html#id8)
 #[derive(Clone, Debug)]
 pub struct Geom {
@@ -30,6 +60,10 @@ pub struct Geom {
     pub convergence: Convergence,
     pub in_hess: Option<String>,
     pub print_internal_hess: bool,
+    /// These are frozen at their initial values during optimization.
+    /// [Constrained Optimizations](https://www.faccts.de/docs/orca/6.1/manual/contents/structurereactivity/optimizations.html#constrained-optimizations)
+    pub constraints: Vec<InternalCoord>,
+    pub scans: Vec<Scan>,
 }
 
 impl Geom {
@@ -44,6 +78,34 @@ impl Geom {
             keywords.push("read");
         }
 
-        make_inp_block("geom", &contents, &keywords)
+        let mut nested = Vec::new();
+
+        if !self.constraints.is_empty() {
+            let lines = self
+                .constraints
+                .iter()
+                .map(|c| format!("{{ {} C }}", c.make_inp()))
+                .collect();
+            nested.push(("Constraints", lines));
+        }
+
+        if !self.scans.is_empty() {
+            let lines = self
+                .scans
+                .iter()
+                .map(|s| {
+                    format!(
+                        "{} = {}, {}, {}",
+                        s.coord.make_inp(),
+                        s.start,
+                        s.end,
+                        s.steps
+                    )
+                })
+                .collect();
+            nested.push(("Scan", lines));
+        }
+
+        make_inp_block_nested("geom", &contents, &keywords, &nested)
     }
 }
@@ -0,0 +1,268 @@
+//! For parsing the `.hess` files ORCA writes from frequency calculations. These contain the
+//! Hessian, normal modes, and IR spectrum in a structured format that's more reliable to parse
+//! than the main output.
+//! [Frequencies](https://www.faccts.de/docs/orca/6.1/manual/contents/structurereactivity/frequencies.html)
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+};
+
+#[derive(Debug, Clone)]
+pub struct Hessian {
+    /// The second derivative matrix of energy w.r.t. Cartesian coordinates, in Hartree/Bohr².
+    /// 3N x 3N, in row-major order: x, y, z for each atom.
+    pub matrix: Vec<Vec<f64>>,
+    /// Vibrational frequencies, in cm⁻¹. The first 6 (or 5 for linear molecules) are
+    /// translations and rotations, and are 0. Imaginary frequencies are negative.
+    pub frequencies: Vec<f64>,
+    /// Normal modes; `modes[i]` is the 3N-length displacement vector for `frequencies[i]`.
+    pub modes: Vec<Vec<f64>>,
+    /// IR intensities, in km/mol, one per frequency. Empty if the file has no `$ir_spectrum` block.
+    pub ir_intensities: Vec<f64>,
+}
+
+fn parse_f64(s: &str) -> io::Result<f64> {
+    s.parse()
+        .map_err(|_| io::Error::new(ErrorKind::InvalidData, format!("Invalid number: {s}")))
+}
+
+/// Split a `.hess` file into `$`-prefixed blocks, keyed by name (without the `$`). Skips `#`
+/// comment lines.
+fn blocks(text: &str) -> HashMap<&str, Vec<&str>> {
+    let mut result = HashMap::new();
+    let mut current: Option<(&str, Vec<&str>)> = None;
+
+    for line in text.lines() {
+        let t = line.trim();
+        if let Some(name) = t.strip_prefix('$') {
+            if let Some((name, lines)) = current.take() {
+                result.insert(name, lines);
+            }
+            current = Some((name, Vec::new()));
+        } else if !t.is_empty()
+            && !t.starts_with('#')
+            && let Some((_, lines)) = current.as_mut()
+        {
+            lines.push(t);
+        }
+    }
+
+    if let Some((name, lines)) = current {
+        result.insert(name, lines);
+    }
+    result
+}
+
+/// Parse a matrix block, as used by `$hessian` and `$normal_modes`. The first line contains the
+/// dimensions; the matrix is then written in groups of columns, each with a header line of column
+/// indices, followed by one line per row, starting with the row index.
+fn parse_matrix(lines: &[&str]) -> io::Result<Vec<Vec<f64>>> {
+    let dims: Vec<usize> = lines
+        .first()
+        .map(|l| {
+            l.split_whitespace()
+                .filter_map(|v| v.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let (n_rows, n_cols) = match dims[..] {
+        [n] => (n, n),
+        [r, c, ..] => (r, c),
+        _ => {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "Missing matrix dimensions",
+            ));
+        }
+    };
+
+    let mut result = vec![vec![0.; n_cols]; n_rows];
+    let mut cols: Vec<usize> = Vec::new();
+
+    for line in &lines[1..] {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+
+        // Column index header; these are integers, while values always have a decimal point.
+        if parts.iter().all(|p| p.parse::<usize>().is_ok()) {
+            cols = parts.iter().map(|p| p.parse().unwrap()).collect();
+            continue;
+        }
+
+        let row: usize = parts[0].parse().map_err(|_| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid matrix row: {line}"),
+            )
+        })?;
+
+        for (col, val) in cols.iter().zip(&parts[1..]) {
+            if row >= n_rows || *col >= n_cols {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Matrix index ({row}, {col}) out of bounds"),
+                ));
+            }
+            result[row][*col] = parse_f64(val)?;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Parse the value in column `col` of each row of a list block, e.g. `$vibrational_frequencies`.
+/// The first line is the number of rows.
+fn parse_list(lines: &[&str], col: usize) -> io::Result<Vec<f64>> {
+    lines
+        .iter()
+        .skip(1)
+        .map(|l| {
+            let val = l.split_whitespace().nth(col).ok_or_else(|| {
+                io::Error::new(ErrorKind::InvalidData, format!("Missing column: {l}"))
+            })?;
+            parse_f64(val)
+        })
+        .collect()
+}
+
+impl Hessian {
+    /// Create from the text of a `.hess` file.
+    pub fn new(text: &str) -> io::Result<Self> {
+        let blocks = blocks(text);
+
+        let get = |name: &str| {
+            blocks.get(name).ok_or_else(|| {
+                io::Error::new(ErrorKind::InvalidData, format!("Missing ${name} block"))
+            })
+        };
+
+        let matrix = parse_matrix(get("hessian")?)?;
+        let frequencies = parse_list(get("vibrational_frequencies")?, 1)?;
+
+        // Stored with one mode per column; transpose so each mode is contiguous.
+        let mode_cols = parse_matrix(get("normal_modes")?)?;
+        let n_modes = mode_cols.first().map_or(0, |r| r.len());
+        let modes = (0..n_modes)
+            .map(|j| mode_cols.iter().map(|row| row[j]).collect())
+            .collect();
+
+        // Columns: Frequency, ε, intensity (km/mol), then transition dipole components.
+        let ir_intensities = match blocks.get("ir_spectrum") {
+            Some(lines) => parse_list(lines, 2)?,
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            matrix,
+            frequencies,
+            modes,
+            ir_intensities,
+        })
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let data_str = fs::read_to_string(path)?;
+        Self::new(&data_str)
+    }
+}
+
+/// From a frequency calculation.
+#[derive(Debug, Clone)]
+pub struct FrequenciesOutput {
+    pub text: String,
+    /// From the `.hess` file ORCA wrote, if available. This includes the normal modes.
+    pub hessian: Option<Hessian>,
+    /// Vibrational frequencies in cm⁻¹, from the `VIBRATIONAL FREQUENCIES` block of the output,
+    /// without the zero-frequency translations and rotations: 3N - 6 (or 3N - 5 for linear
+    /// molecules). Imaginary frequencies, e.g. from a transition state, are negative.
+    ///
+    /// Use this for vibrational analysis, e.g. checking for imaginary modes or plotting a spectrum.
+    /// `hessian.frequencies`, if present, has all 3N values instead, including the zero modes, so that its
+    /// indices line up with `hessian.modes`; use it when you need the mode vectors.
+    pub frequencies_cm1: Vec<f64>,
+    /// IR intensities in km/mol, from the `IR SPECTRUM` block; one per entry in `frequencies_cm1`.
+    /// 0 for modes not listed there.
+    pub ir_intensities: Vec<f64>,
+}
+
+impl FrequenciesOutput {
+    /// Create from ORCA's stdout (or `.out`) text. If the output contains multiple frequency
+    /// calculations, the last is used. `hessian` is `None`; set it from the `.hess` file if needed.
+    pub fn new(text: String) -> io::Result<Self> {
+        let vib = section_lines(&text, "VIBRATIONAL FREQUENCIES").ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                "VIBRATIONAL FREQUENCIES section not found",
+            )
+        })?;
+
+        // "   6:      1595.12 cm**-1", optionally followed by "***imaginary mode***".
+        let mut modes: Vec<(usize, f64)> = Vec::new();
+        for line in vib {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() >= 3 && parts[2] == "cm**-1" {
+                let Some(Ok(mode)) = parts[0].strip_suffix(':').map(str::parse) else {
+                    continue;
+                };
+                modes.push((mode, parse_f64(parts[1])?));
+            } else if !modes.is_empty() {
+                break;
+            }
+        }
+
+        // Translations and rotations are listed first, with frequency 0.
+        let n_trans_rot = modes.iter().take_while(|(_, f)| *f == 0.).count();
+        let modes = &modes[n_trans_rot..];
+
+        let mut ir_intensities = vec![0.; modes.len()];
+        if let Some(lines) = section_lines(&text, "IR SPECTRUM") {
+            let mut int_col = None;
+
+            // " Mode   freq       eps      Int      T**2         TX        TY        TZ", then rows
+            // like "  6:   1595.12   0.012345   62.39  0.002415  ( 0.000000  0.049145 -0.000000)".
+            for line in lines {
+                let parts: Vec<&str> = line.split_whitespace().collect();
+                if parts.first() == Some(&"Mode") {
+                    int_col = parts.iter().position(|p| *p == "Int");
+                    continue;
+                }
+                let Some(col) = int_col else {
+                    continue;
+                };
+                // The table ends at a blank line.
+                if parts.is_empty() {
+                    break;
+                }
+
+                // Skips the units and separator lines.
+                let Some(Ok(mode)) = parts[0].strip_suffix(':').map(str::parse::<usize>) else {
+                    continue;
+                };
+                let Some(val) = parts.get(col) else {
+                    continue;
+                };
+
+                if let Some(i) = modes.iter().position(|(m, _)| *m == mode) {
+                    ir_intensities[i] = parse_f64(val)?;
+                }
+            }
+        }
+
+        Ok(Self {
+            text,
+            hessian: None,
+            frequencies_cm1: modes.iter().map(|(_, f)| *f).collect(),
+            ir_intensities,
+        })
+    }
+}
+
+/// The lines after the last occurrence of a section title line, e.g. "IR SPECTRUM".
+fn section_lines<'a>(text: &'a str, title: &str) -> Option<impl Iterator<Item = &'a str>> {
+    let lines: Vec<&str> = text.lines().collect();
+    let start = lines.iter().rposition(|l| l.trim() == title)?;
+    Some(lines.into_iter().skip(start + 1))
+}
@@ -168,6 +168,76 @@ impl Method {
         .to_string()
     }
 
+    /// Look up a method from its `!` keyword, ignoring case. Aliases that share a keyword with
+    /// another variant (e.g. [`Self::Dft`]) resolve to the more specific one.
+    pub fn from_keyword(kw: &str) -> Option<Self> {
+        const METHODS: [Method; 59] = [
+            Method::HartreeFock,
+            Method::Hf_3c,
+            Method::Mp2Perturbation,
+            Method::SpinComponentScaledMp2,
+            Method::OrbitalOptimzedMp2,
+            Method::DoubleHybridDft,
+            Method::TripleHybridDft,
+            Method::CoupledCluster,
+            Method::Xtb,
+            Method::SemiEmpericalMethods,
+            Method::BP86,
+            Method::BLYP,
+            Method::OLYP,
+            Method::GLYP,
+            Method::XLYP,
+            Method::PW91,
+            Method::MPWPW,
+            Method::MPWLYP,
+            Method::PBE,
+            Method::RPBE,
+            Method::REVPBE,
+            Method::RPW86PBE,
+            Method::PWP,
+            Method::B97_3c,
+            Method::wB97x_3c,
+            Method::B97M_V,
+            Method::V97M_D3BJ,
+            Method::B97M,
+            Method::SCANFUNC,
+            Method::RSCAN,
+            Method::R2SCAN,
+            Method::TPSS,
+            Method::REVTPSS,
+            Method::r2SCAN_3c,
+            Method::B1LYP,
+            Method::B3LYP,
+            Method::B3LYP_G,
+            Method::O3LYP,
+            Method::X3LYP,
+            Method::B1P86,
+            Method::B3PW91,
+            Method::PW1PW,
+            Method::MPW1PW,
+            Method::MPW1LYP,
+            Method::PBE0,
+            Method::REVPBE0,
+            Method::REVPBE38,
+            Method::BHANDHLYP,
+            Method::M06,
+            Method::M062X,
+            Method::PW6B95,
+            Method::TPSSH,
+            Method::TPSS0,
+            Method::r2SCANH,
+            Method::r2SCAN0,
+            Method::r2SCAN50,
+            Method::PBEh_3c,
+            Method::B3LYP_3c,
+            Method::FractionalOccupationDensity,
+        ];
+
+        METHODS
+            .into_iter()
+            .find(|m| m.keyword().eq_ignore_ascii_case(kw))
+    }
+
     /// [Manual 3.6: Composite methods) These methods don't use a separate basis set; they have small tailored
     /// ones of their own.
     pub fn is_composite(self) -> bool {
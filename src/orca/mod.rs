@@ -26,8 +26,10 @@
 
 pub mod basis_sets;
 pub mod charges;
+pub mod conformers;
 pub mod dynamics;
 pub mod geom;
+pub mod hess;
 pub mod method;
 mod plots;
 pub mod scf;
@@ -38,22 +40,27 @@ use std::{
     fs,
     fs::File,
     io::{self, ErrorKind, Write},
-    path::Path,
+    path::{Path, PathBuf},
     process::Command,
 };
 
 use basis_sets::BasisSet;
 use lin_alg::f64::Vec3;
 use method::{Method, MethodSection};
+use na_seq::Element;
 use scf::Scf;
 use solvation::{Solvator, SolvatorImplicit};
 
 use crate::{
-    AtomGeneric,
+    AtomGeneric, Xyz, element_from_symbol, element_symbol,
     orca::{
-        charges::{ChargesOutput, MbisChargesCfg},
+        charges::{
+            ChargesOutput, ChelpgCfg, ChelpgChargesOutput, MbisChargesCfg, SinglePointOutput,
+        },
+        conformers::ConformerSearchOutput,
         dynamics::{Dynamics, DynamicsOutput},
         geom::Geom,
+        hess::{FrequenciesOutput, Hessian},
         plots::Plots,
     },
 };
@@ -61,9 +68,86 @@ use crate::{
 // Used for creating intermediate files
 const TEMP_DIR: &str = "orca_temp";
 
+/// Generated inputs set this with `%base`, so ORCA names its side-car output files, e.g. `.gbw`,
+/// `.hess`, and `.property.txt`, after it regardless of the input filename.
+pub const OUTPUT_BASE: &str = "orca_job";
+
+/// Locate a side-car file ORCA wrote to `dir`, e.g. `find_sidecar(dir, "hess")` for
+/// `orca_job.hess`. Returns a `NotFound` error if it's missing.
+pub fn find_sidecar(dir: &Path, suffix: &str) -> io::Result<PathBuf> {
+    let path = dir.join(format!("{OUTPUT_BASE}.{suffix}"));
+    if !path.is_file() {
+        return Err(io::Error::new(
+            ErrorKind::NotFound,
+            format!("ORCA output file not found: {}", path.display()),
+        ));
+    }
+    Ok(path)
+}
+
+/// Remove side-car files left in `dir` by an earlier run, e.g. with `keep_scratch` set, so that
+/// [`find_sidecar`] can't pick them up if this run doesn't write its own.
+fn remove_sidecars(dir: &Path) -> io::Result<()> {
+    let prefix = format!("{OUTPUT_BASE}.");
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let stale = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with(&prefix));
+
+        if stale && path.is_file() {
+            fs::remove_file(&path)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The lines of the input file ORCA echoes in its output, with the `|  1> ` prefixes removed.
+fn input_echo(text: &str) -> io::Result<Vec<&str>> {
+    let start = text.find("INPUT FILE").ok_or_else(|| {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            "No INPUT FILE section in ORCA output",
+        )
+    })?;
+
+    let mut result = Vec::new();
+    for line in text[start..].lines() {
+        if line.contains("****END OF INPUT****") {
+            return Ok(result);
+        }
+
+        if let Some((prefix, rest)) = line.split_once('>')
+            && let Some(n) = prefix.trim().strip_prefix('|')
+            && n.trim().parse::<usize>().is_ok()
+        {
+            result.push(rest.strip_prefix(' ').unwrap_or(rest));
+        }
+    }
+
+    Err(io::Error::new(
+        ErrorKind::InvalidData,
+        "Unterminated INPUT FILE section in ORCA output",
+    ))
+}
+
 /// A helper. The &str and String use reflects how we use this in practie,
 /// e.g. with &str literals vs format!().
 fn make_inp_block(block_name: &str, contents: &[(&str, String)], keywords: &[&str]) -> String {
+    make_inp_block_nested(block_name, contents, keywords, &[])
+}
+
+/// Like [`make_inp_block`], with nested blocks after the contents, e.g. `Constraints` in `%geom`.
+/// Each has a name, and its lines.
+fn make_inp_block_nested(
+    block_name: &str,
+    contents: &[(&str, String)],
+    keywords: &[&str],
+    nested: &[(&str, Vec<String>)],
+) -> String {
     let mut r = String::new();
 
     r.push('%');
@@ -78,6 +162,14 @@ fn make_inp_block(block_name: &str, contents: &[(&str, String)], keywords: &[&st
         r.push_str(&format!("    {} {}\n", k, v));
     }
 
+    for (name, lines) in nested {
+        r.push_str(&format!("    {name}\n"));
+        for line in lines {
+            r.push_str(&format!("        {line}\n"));
+        }
+        r.push_str("    end\n");
+    }
+
     r.push_str("end");
     r
 }
@@ -120,6 +212,26 @@ impl GcpOption {
         }
         .to_string()
     }
+
+    pub fn from_keyword(kw: &str) -> Option<Self> {
+        [
+            Self::HfMinis,
+            Self::HfSv,
+            Self::Hf631Gd,
+            Self::HfSvp,
+            Self::HfTz,
+            Self::DftMinis,
+            Self::DftSv,
+            Self::Dft631Gd,
+            Self::DftLanl,
+            Self::DftVsP_,
+            Self::DftSvp,
+            Self::DftTz,
+            Self::File,
+        ]
+        .into_iter()
+        .find(|o| o.keyword().eq_ignore_ascii_case(kw))
+    }
 }
 
 /// [Geometry optimization thresholds](https://www.faccts.de/docs/orca/6.1/manual/contents/structurereactivity/optimizations.html?q=tightopt&n=0#geometry-optimization-thresholds)
@@ -144,6 +256,12 @@ impl GeomOptThresh {
             Self::VeryTight => "VeryTightOpt".to_string(),
         }
     }
+
+    pub fn from_keyword(kw: &str) -> Option<Self> {
+        [Self::Loose, Self::Opt, Self::Tight, Self::VeryTight]
+            .into_iter()
+            .find(|t| t.keyword().eq_ignore_ascii_case(kw))
+    }
 }
 
 impl Display for GeomOptThresh {
@@ -167,11 +285,13 @@ pub enum Task {
     #[default]
     SinglePoint,
     GeometryOptimization((GeomOptThresh, Option<Geom>)),
-    // todo: Support RESP charges too. May just involve "RESP" instead of "MBIS".
+    // todo: Support RESP charges too.
     /// Minimal Basis Iterative Stockholder. Can be used for force field parameterization.
     /// [Charge tutorial](https://www.faccts.de/docs/orca/5.0/tutorials/prop/charges.html)
     /// [MBIS Charges](https://www.faccts.de/docs/orca/6.1/manual/contents/spectroscopyproperties/population.html?q=mbis&n=0#mbis-charges)
     MbisCharges(MbisChargesCfg),
+    /// Charges fit to the electrostatic potential. Can be used for force field parameterization.
+    ChelpgCharges(ChelpgCfg),
     MolDynamics(Dynamics),
     // todo: Others A/R
 }
@@ -182,6 +302,7 @@ impl Display for Task {
             Self::SinglePoint => "Single point energy",
             Self::GeometryOptimization(_) => "Optimize geometry",
             Self::MbisCharges(_) => "MBIS charges",
+            Self::ChelpgCharges(_) => "CHELPG charges",
             Self::MolDynamics(_) => "Mol dynamics (Ab-initio)",
         };
 
@@ -219,12 +340,35 @@ impl Keyword {
             Self::NumericalGradient => "NUMGRAD".to_string(),
             Self::D4Dispersion => "D4".to_string(),
             Self::ConformerSearch => "GOAT".to_string(),
-            Self::Gcp(option) => format!("GCP({}", option.keyword()),
+            Self::Gcp(option) => format!("GCP({})", option.keyword()),
             Self::UseSymmetry => "UseSymmetry".to_string(),
             Self::AnFreq => "AnFreq".to_string(),
             Self::NumFreq => "NumFreq".to_string(),
         }
     }
+
+    /// Look up a keyword from its text in the `!` line, ignoring case.
+    pub fn from_keyword(kw: &str) -> Option<Self> {
+        let kw_lower = kw.to_lowercase();
+        if let Some(option) = kw_lower
+            .strip_prefix("gcp(")
+            .and_then(|v| v.strip_suffix(')'))
+        {
+            return GcpOption::from_keyword(option).map(Self::Gcp);
+        }
+
+        [
+            Self::Freq,
+            Self::NumericalGradient,
+            Self::D4Dispersion,
+            Self::ConformerSearch,
+            Self::UseSymmetry,
+            Self::AnFreq,
+            Self::NumFreq,
+        ]
+        .into_iter()
+        .find(|k| k.keyword().to_lowercase() == kw_lower)
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Debug, Default)]
@@ -292,7 +436,18 @@ pub struct OrcaInput {
     // /// If None, calculate single point energies as the default mode.
     // pub opt_mode: Option<GeomOptThresh>,
     pub keywords: Vec<Keyword>,
+    /// Keywords written verbatim to the first line, after `keywords`. For ones we don't model,
+    /// e.g. `TightSCF`, `RIJCOSX`, or `def2/J`.
+    pub raw_keywords: Vec<String>,
     pub atoms: Vec<AtomGeneric>,
+    /// Indices into `atoms` of ghost atoms: These contribute basis functions, but no nuclei or
+    /// electrons. Written with a trailing `:` after the element, e.g. `H:`. Used for BSSE
+    /// counterpoise corrections, e.g. computing a monomer's energy in the dimer's basis.
+    pub ghost_atoms: Vec<usize>,
+    /// Net charge of the system. If None, 0.
+    pub charge: Option<i32>,
+    /// Spin multiplicity (2S + 1). If None, 1 (singlet).
+    pub multiplicity: Option<u32>,
     /// todo: Ref [this list of input blocks from the docs](https://www.faccts.de/docs/orca/6.1/manual/contents/essentialelements/input.html);
     pub solvator: Option<Solvator>,
     pub solvator_implicit: Option<SolvatorImplicit>,
@@ -301,6 +456,10 @@ pub struct OrcaInput {
     pub symmetry: Option<Symmetry>, // todo: Combine into task?
     // pub dynamics: Option<Dynamics>,
     pub plots: Option<Plots>,
+    /// If true, don't delete the temporary directory ORCA runs in after [`Self::run`]. This keeps
+    /// scratch and auxiliary output files, e.g. `.xyz` and `.trj` files, for inspection. They're
+    /// removed at the start of the next run.
+    pub keep_scratch: bool,
     // todo: A/R: https://www.faccts.de/docs/orca/6.1/manual/contents/essentialelements/stabilityanalysis.html
     // pub shark: Option<Shark>,
 }
@@ -316,8 +475,71 @@ impl OrcaInput {
         }
     }
 
+    /// Create an input from an XYZ file's atoms, using its charge and multiplicity if they were
+    /// specified in the comment line.
+    pub fn from_xyz(xyz: &Xyz, method: Method, basis_set: BasisSet) -> Self {
+        Self {
+            charge: xyz.charge,
+            multiplicity: xyz.multiplicity,
+            ..Self::new(method, basis_set, &xyz.atoms)
+        }
+    }
+
     /// Create an .inp string for input into ORCA.
     pub fn make_inp(&self) -> String {
+        let mut result = self.make_job_header();
+
+        result.push_str(&format!(
+            "\n\n* xyz {} {}\n",
+            self.charge.unwrap_or(0),
+            self.multiplicity.unwrap_or(1)
+        ));
+
+        // --- Atoms ---
+        for (i, atom) in self.atoms.iter().enumerate() {
+            let mut el = element_symbol(atom.element);
+            if self.ghost_atoms.contains(&i) {
+                el.push(':');
+            }
+
+            result.push_str(&format!(
+                "{:<2} {:>12.5} {:>12.5} {:>12.5}\n",
+                el, atom.posit.x, atom.posit.y, atom.posit.z
+            ));
+        }
+
+        result.push('*');
+
+        result
+    }
+
+    /// Create an .inp string for a compound input, which runs several jobs in sequence, e.g.
+    /// optimization, then frequencies, then a single point at a higher level of theory. Jobs are
+    /// separated by `$new_job`. The first job's atoms are used as its geometry; each later job reads
+    /// the geometry written by the job before it, via `* xyzfile`, and its own `atoms` are ignored.
+    pub fn make_multi_inp(jobs: &[OrcaInput]) -> String {
+        let mut result = String::new();
+
+        for (i, job) in jobs.iter().enumerate() {
+            if i == 0 {
+                result.push_str(&job.make_inp());
+                continue;
+            }
+
+            result.push_str("\n\n$new_job\n");
+            result.push_str(&job.make_job_header());
+            result.push_str(&format!(
+                "\n\n* xyzfile {} {} {OUTPUT_BASE}.xyz\n",
+                job.charge.unwrap_or(0),
+                job.multiplicity.unwrap_or(1)
+            ));
+        }
+
+        result
+    }
+
+    /// The keyword line, and blocks; everything except the geometry.
+    fn make_job_header(&self) -> String {
         let mut result = String::new();
 
         // --- Initial line ---
@@ -329,14 +551,8 @@ impl OrcaInput {
 
         match &self.task {
             Task::SinglePoint => {} // No action or keyword.
-            Task::GeometryOptimization((thresh, geom)) => {
-                // todo: Integrate our geom block here?
+            Task::GeometryOptimization((thresh, _)) => {
                 result.push_str(&format!(" {}", thresh.keyword()));
-
-                if let Some(v) = geom {
-                    result.push('\n');
-                    result.push_str(&v.make_inp());
-                }
             }
             Task::MbisCharges(cfg) => {
                 result.push_str(" MBIS");
@@ -346,6 +562,7 @@ impl OrcaInput {
                     result.push_str(&cfg.make_inp());
                 }
             }
+            Task::ChelpgCharges(_) => result.push_str(" CHELPG"),
             Task::MolDynamics(md) => {
                 result.push_str(&format!(" {}", md.make_inp()));
             }
@@ -359,6 +576,12 @@ impl OrcaInput {
             result.push_str(&format!(" {}", kw.keyword()));
         }
 
+        for kw in &self.raw_keywords {
+            result.push_str(&format!(" {kw}"));
+        }
+
+        result.push_str(&format!("\n%base \"{OUTPUT_BASE}\""));
+
         // --- Blocks ---
         if let Some(v) = &self.method_section {
             result.push('\n');
@@ -400,25 +623,150 @@ impl OrcaInput {
             result.push_str(&v.make_inp());
         }
 
-        result.push_str("\n\n* xyz 0 1\n");
+        if let Task::GeometryOptimization((_, Some(geom))) = &self.task {
+            result.push('\n');
+            result.push_str(&geom.make_inp());
+        }
 
-        // --- Atoms ---
-        for atom in &self.atoms {
-            result.push_str(&format!(
-                "{:<2} {:>12.5} {:>12.5} {:>12.5}\n",
-                atom.element.to_letter(),
-                atom.posit.x,
-                atom.posit.y,
-                atom.posit.z
+        if let Task::ChelpgCharges(cfg) = &self.task {
+            result.push('\n');
+            result.push_str(&cfg.make_inp());
+        }
+
+        result
+    }
+
+    /// Reconstruct the input from the copy ORCA echoes near the top of its output, under
+    /// "INPUT FILE". This recovers the method, basis set, task, keywords, charge, multiplicity,
+    /// and atoms, including ghost atoms. `%` blocks other than `%base` are not reconstructed;
+    /// their settings are ignored. Keywords without a representation here are kept in
+    /// `raw_keywords`.
+    pub fn from_output(text: &str) -> io::Result<Self> {
+        let inp = input_echo(text)?;
+        let mut result = Self {
+            method: Method::None,
+            basis_set: BasisSet::None,
+            ..Self::default()
+        };
+
+        let mut lines = inp.iter();
+        let mut found_method = false;
+        while let Some(line) = lines.next() {
+            let line = line.trim();
+
+            if let Some(kws) = line.strip_prefix('!') {
+                for kw in kws.split_whitespace() {
+                    if !found_method && let Some(v) = Method::from_keyword(kw) {
+                        result.method = v;
+                        found_method = true;
+                    } else if let Some(v) = BasisSet::from_keyword(kw) {
+                        result.basis_set = v;
+                    } else if let Some(v) = GeomOptThresh::from_keyword(kw) {
+                        result.task = Task::GeometryOptimization((v, None));
+                    } else if kw.eq_ignore_ascii_case("MBIS") {
+                        result.task = Task::MbisCharges(MbisChargesCfg::default());
+                    } else if kw.eq_ignore_ascii_case("CHELPG") {
+                        result.task = Task::ChelpgCharges(ChelpgCfg::default());
+                    } else if let Some(v) = Keyword::from_keyword(kw) {
+                        result.keywords.push(v);
+                    } else {
+                        result.raw_keywords.push(kw.to_owned());
+                    }
+                }
+                continue;
+            }
+
+            let cols: Vec<&str> = line.split_whitespace().collect();
+            if cols.len() == 4 && cols[0] == "*" && cols[1].eq_ignore_ascii_case("xyz") {
+                let parse_err = || {
+                    io::Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Invalid coordinate line: {line}"),
+                    )
+                };
+                result.charge = Some(cols[2].parse().map_err(|_| parse_err())?);
+                result.multiplicity = Some(cols[3].parse().map_err(|_| parse_err())?);
+
+                for line in lines.by_ref() {
+                    let mut cols: Vec<&str> = line.split_whitespace().collect();
+                    if cols.first() == Some(&"*") {
+                        break;
+                    }
+
+                    // Ghost atoms, as "H:" or "H :".
+                    let mut ghost = false;
+                    if cols.get(1) == Some(&":") {
+                        cols.remove(1);
+                        ghost = true;
+                    } else if let Some(el) = cols.first().and_then(|c| c.strip_suffix(':')) {
+                        cols[0] = el;
+                        ghost = true;
+                    }
+                    if ghost {
+                        result.ghost_atoms.push(result.atoms.len());
+                    }
+
+                    if cols.len() < 4 {
+                        return Err(io::Error::new(
+                            ErrorKind::InvalidData,
+                            format!("Invalid atom line: {line}"),
+                        ));
+                    }
+
+                    let mut posit = [0.; 3];
+                    for (i, v) in cols[1..4].iter().enumerate() {
+                        posit[i] = v.parse().map_err(|_| {
+                            io::Error::new(
+                                ErrorKind::InvalidData,
+                                format!("Invalid atom line: {line}"),
+                            )
+                        })?;
+                    }
+
+                    result.atoms.push(AtomGeneric {
+                        serial_number: result.atoms.len() as u32 + 1,
+                        posit: Vec3::new(posit[0], posit[1], posit[2]),
+                        element: element_from_symbol(cols[0])?,
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+
+        if !found_method {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "No method keyword in the echoed input",
             ));
         }
 
-        result.push('*');
+        Ok(result)
+    }
 
-        result
+    fn validate(&self) -> io::Result<()> {
+        if self.multiplicity == Some(0) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "Multiplicity must be at least 1",
+            ));
+        }
+
+        // This is what atoms get when their element can't be determined, e.g. from an atom name.
+        if let Some(i) = self.atoms.iter().position(|a| a.element == Element::Other) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("The atom at index {i} has an unknown element"),
+            ));
+        }
+
+        Ok(())
     }
 
+    /// Write the .inp file. Returns an error if the input is invalid, e.g. with a multiplicity of 0,
+    /// or an atom with an unknown element.
     pub fn save(&self, path: &Path) -> io::Result<()> {
+        self.validate()?;
+
         let mut file = File::create(path)?;
         let text = self.make_inp();
 
@@ -427,14 +775,46 @@ impl OrcaInput {
 
     /// Run this command in Orca, and collect the output. Requires `orca` to be available
     /// on the system PATH environment variable.
+    ///
+    /// If ORCA doesn't terminate normally, the returned error wraps an [`OrcaRunError`],
+    /// containing the [`TerminationStatus`] and ORCA's output text.
     /// todo: Outputs a string for now; adjust this as required into a custom output struct
     pub fn run(&self) -> io::Result<OrcaOutput> {
+        self.validate()?;
+        self.run_inp(&self.make_inp())
+    }
+
+    /// Run a compound input, created by [`Self::make_multi_inp`], in ORCA. The output is parsed
+    /// according to the final job's task, from that job's section of the output.
+    pub fn run_multi(jobs: &[OrcaInput]) -> io::Result<OrcaOutput> {
+        let Some(last) = jobs.last() else {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "No jobs to run"));
+        };
+
+        for job in jobs {
+            job.validate()?;
+        }
+
+        last.run_inp(&Self::make_multi_inp(jobs))
+    }
+
+    /// Run ORCA on an input's text, and parse the output according to this job's task.
+    fn run_inp(&self, inp_text: &str) -> io::Result<OrcaOutput> {
         let dir = Path::new(TEMP_DIR);
         fs::create_dir_all(dir)?;
+        remove_sidecars(dir)?;
+
+        let cleanup = || {
+            if self.keep_scratch {
+                Ok(())
+            } else {
+                fs::remove_dir_all(dir)
+            }
+        };
 
         let inp_fname = "temp_orca_input.inp";
         let inp_path = dir.join(Path::new(inp_fname));
-        self.save(&inp_path)?;
+        fs::write(&inp_path, inp_text)?;
 
         let cmd_out = match Command::new("orca")
             .current_dir(dir)
@@ -444,7 +824,7 @@ impl OrcaInput {
             Ok(out) => out,
             Err(e) if e.kind() == ErrorKind::NotFound => {
                 // Orca binary not found on PATH
-                fs::remove_dir_all(dir)?;
+                cleanup()?;
 
                 return Err(io::Error::new(
                     ErrorKind::NotFound,
@@ -454,30 +834,73 @@ impl OrcaInput {
             Err(e) => return Err(e),
         };
 
-        if !cmd_out.status.success() {
-            let stderr_str = String::from_utf8_lossy(&cmd_out.stderr);
-            fs::remove_dir_all(dir)?;
+        // Convert stdout bytes to String
+        let result_text = String::from_utf8_lossy(&cmd_out.stdout).to_string();
+        let status = TerminationStatus::from_output(&result_text);
+
+        if status != TerminationStatus::Normal {
+            cleanup()?;
+
+            if !cmd_out.status.success() && status == TerminationStatus::Incomplete {
+                let stderr_str = String::from_utf8_lossy(&cmd_out.stderr);
+                return Err(io::Error::other(format!(
+                    "Problem reading out temporary ORCA file: {}",
+                    stderr_str
+                )));
+            }
 
-            return Err(io::Error::other(format!(
-                "Problem reading out temporary ORCA file: {}",
-                stderr_str
-            )));
+            // Callers can recover the status and text with `e.get_ref()` and `downcast_ref`.
+            return Err(io::Error::other(OrcaRunError {
+                status,
+                text: result_text,
+            }));
         }
 
-        // Convert stdout bytes to String
-        let result_text = String::from_utf8(cmd_out.stdout)
-            .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+        let result_text = final_job_output(&result_text).to_owned();
 
-        if !result_text.contains("****ORCA TERMINATED NORMALLY****") {
-            return Err(io::Error::other(format!(
-                "ORCA terminated abnormally: {result_text}"
-            )));
-        }
+        // Parse before cleaning up, as some outputs are read from side-car files, but clean up
+        // regardless of whether parsing succeeds.
+        let result = self.parse_output(dir, result_text);
 
-        let result = match &self.task {
+        // Remove the entire temporary directory.
+        cleanup()?;
+
+        result
+    }
+
+    /// Parse ORCA's output according to this job's task. `dir` contains any side-car files. This
+    /// is called by [`Self::run`]; use it directly if running ORCA another way.
+    ///
+    /// If a frequency keyword is set, frequencies are parsed from the output text for any task. For
+    /// a single point, they're returned as [`OrcaOutput::Frequencies`]; otherwise, in the task
+    /// output's `frequencies` field. If ORCA wrote a `.hess` file, it's loaded into `hessian`.
+    pub fn parse_output(&self, dir: &Path, result_text: String) -> io::Result<OrcaOutput> {
+        let frequencies = if self
+            .keywords
+            .iter()
+            .any(|k| matches!(k, Keyword::Freq | Keyword::AnFreq | Keyword::NumFreq))
+        {
+            let mut out = FrequenciesOutput::new(result_text.clone())?;
+            if let Ok(path) = find_sidecar(dir, "hess") {
+                out.hessian = Some(Hessian::load(&path)?);
+            }
+            Some(out)
+        } else {
+            None
+        };
+
+        Ok(match &self.task {
             Task::SinglePoint => {
-                // todo
-                OrcaOutput::Text(result_text)
+                if self.keywords.contains(&Keyword::ConformerSearch) {
+                    let ensemble = find_sidecar(dir, "finalensemble.xyz")?;
+                    let out = ConformerSearchOutput::load(&ensemble, result_text)?;
+                    OrcaOutput::ConformerSearch(out)
+                } else if let Some(out) = frequencies {
+                    OrcaOutput::Frequencies(out)
+                } else {
+                    let out = SinglePointOutput::new(result_text)?;
+                    OrcaOutput::SinglePoint(out)
+                }
             }
             Task::MolDynamics(md) => {
                 let out = dir.join(&md.traj_out_dir);
@@ -486,34 +909,159 @@ impl OrcaInput {
             }
             Task::MbisCharges(_) => {
                 let out = ChargesOutput::new(result_text)?;
-                OrcaOutput::Charges(out)
+                OrcaOutput::Charges(ChargesOutput { frequencies, ..out })
+            }
+            Task::ChelpgCharges(_) => {
+                let out = ChelpgChargesOutput::new(result_text)?;
+                OrcaOutput::ChelpgCharges(ChelpgChargesOutput { frequencies, ..out })
             }
             Task::GeometryOptimization(_) => {
                 let out = GeometryOutput::new(result_text)?;
-                OrcaOutput::Geometry(out)
+                OrcaOutput::Geometry(GeometryOutput { frequencies, ..out })
             }
-        };
-
-        // Remove the entire temporary directory.
-        fs::remove_dir_all(dir)?;
+        })
+    }
+}
 
-        Ok(result)
+/// For output from a compound input, with multiple jobs, the section for the final one. This
+/// starts at the last "JOB NUMBER" banner. Output from a single job is returned unchanged.
+pub fn final_job_output(text: &str) -> &str {
+    match text.rfind("JOB NUMBER") {
+        Some(i) => {
+            let line_start = text[..i].rfind('\n').map(|j| j + 1).unwrap_or(0);
+            &text[line_start..]
+        }
+        None => text,
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+/// How an ORCA run ended, as determined from its output text. Returned (in an [`OrcaRunError`])
+/// from [`OrcaInput::run`] on failure, so callers can react to specific failure modes.
+#[derive(Clone, PartialEq, Debug)]
 pub enum TerminationStatus {
     Normal,
-    Error, // todo a/r
+    /// The SCF procedure did not converge.
+    ScfNotConverged,
+    /// Geometry optimization reached its maximum number of cycles without converging.
+    GeomOptNotConverged,
+    /// A license problem, e.g. missing or expired. Contains the relevant line.
+    License(String),
+    /// ORCA aborted the run for another reason. Contains the error line, if found.
+    Aborted(String),
+    /// No termination message was found, e.g. if the process was killed or is still running.
+    Incomplete,
+}
+
+impl TerminationStatus {
+    /// Classify an ORCA run from its output (`.out`, or stdout) text. A geometry optimization
+    /// failure takes priority over the normal termination message, as ORCA still prints it in
+    /// this case. An SCF that didn't converge in a run that terminated normally is only a
+    /// warning; check for it with [`Self::has_scf_warning`].
+    pub fn from_output(text: &str) -> Self {
+        if text.contains("did not converge but reached the maximum number of optimization cycles")
+            || text.contains("Geometry optimization did not converge")
+        {
+            return Self::GeomOptNotConverged;
+        }
+
+        if text.contains("****ORCA TERMINATED NORMALLY****") {
+            return Self::Normal;
+        }
+
+        if Self::has_scf_warning(text) {
+            return Self::ScfNotConverged;
+        }
+
+        if let Some(line) = text.lines().find(|l| {
+            let lower = l.to_lowercase();
+            lower.contains("license")
+                && (lower.contains("error")
+                    || lower.contains("expired")
+                    || lower.contains("invalid"))
+        }) {
+            return Self::License(line.trim().to_owned());
+        }
+
+        if let Some(i) = text.find("aborting the run") {
+            let before: Vec<&str> = text[..i]
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .collect();
+
+            // Prefer an explicit error line; otherwise, the line just before the abort message.
+            let msg = before
+                .iter()
+                .rev()
+                .take(10)
+                .find(|l| l.to_lowercase().contains("error"))
+                .or(before.last())
+                .map(|l| l.to_string())
+                .unwrap_or_default();
+
+            return Self::Aborted(msg);
+        }
+
+        Self::Incomplete
+    }
+
+    /// Whether ORCA reported an SCF that didn't converge. This may be set for runs that terminated
+    /// normally, e.g. if ORCA continued past an SCF that converged only loosely.
+    pub fn has_scf_warning(text: &str) -> bool {
+        text.contains("SCF NOT CONVERGED")
+    }
+}
+
+impl Display for TerminationStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Normal => write!(f, "ORCA terminated normally"),
+            Self::ScfNotConverged => write!(f, "ORCA SCF did not converge"),
+            Self::GeomOptNotConverged => write!(f, "ORCA geometry optimization did not converge"),
+            Self::License(msg) => write!(f, "ORCA license error: {msg}"),
+            Self::Aborted(msg) => write!(f, "ORCA aborted the run: {msg}"),
+            Self::Incomplete => write!(f, "ORCA did not terminate normally"),
+        }
+    }
+}
+
+impl std::error::Error for TerminationStatus {}
+
+/// The error returned from [`OrcaInput::run`] when ORCA doesn't terminate normally. Contains the
+/// output text, so it can still be inspected, e.g. with [`charges::parse_scf_history`].
+#[derive(Clone, Debug)]
+pub struct OrcaRunError {
+    pub status: TerminationStatus,
+    /// ORCA's full output text.
+    pub text: String,
+}
+
+impl Display for OrcaRunError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.status)
+    }
+}
+
+impl std::error::Error for OrcaRunError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.status)
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum OrcaOutput {
     Text(String),
+    /// From a single point calculation without a more specific keyword.
+    SinglePoint(SinglePointOutput),
     Dynamics(DynamicsOutput),
     Charges(ChargesOutput),
+    ChelpgCharges(ChelpgChargesOutput),
     /// E.g. from geometry optimization.
     Geometry(GeometryOutput),
+    /// From a GOAT conformer search.
+    ConformerSearch(ConformerSearchOutput),
+    /// From a frequency calculation, e.g. with the `Freq` keyword.
+    Frequencies(FrequenciesOutput),
     // termination_status: TerminationStatus,
 }
 
@@ -532,74 +1080,124 @@ pub enum OrcaOutput {
 #[derive(Debug, Clone)]
 pub struct GeometryOutput {
     pub text: String,
+    /// The optimized coordinates, from the final energy evaluation at the stationary point. This is
+    /// the last frame of `trajectory`.
     pub posits: Vec<Vec3>,
+    /// In Hartree, from each `FINAL SINGLE POINT ENERGY`; one per optimization cycle, then one
+    /// from the final evaluation. Useful for checking convergence.
+    pub energies: Vec<f64>,
+    /// Coordinates at each optimization cycle, from each `CARTESIAN COORDINATES (ANGSTROEM)` block.
+    pub trajectory: Vec<Vec<Vec3>>,
+    /// If the job included a frequency calculation at the optimized geometry, e.g. with the `Freq`
+    /// keyword.
+    pub frequencies: Option<FrequenciesOutput>,
 }
 
 impl GeometryOutput {
     pub fn new(text: String) -> io::Result<Self> {
-        let mut posits = Vec::new();
-
-        // 1. Find the start of the "FINAL ENERGY EVALUATION" section.
-        // We look for this first to ensure we aren't grabbing initial or intermediate steps.
+        // We look for this first, to ensure the optimization converged.
         let final_eval_marker = "*** FINAL ENERGY EVALUATION AT THE STATIONARY POINT ***";
-        let section_start = text.find(final_eval_marker).ok_or_else(|| {
-            io::Error::new(
+        if !text.contains(final_eval_marker) {
+            return Err(io::Error::new(
                 io::ErrorKind::NotFound,
                 "Final stationary point not reached yet",
-            )
-        })?;
+            ));
+        }
+
+        let energies = text
+            .lines()
+            .filter_map(|l| l.trim().strip_prefix("FINAL SINGLE POINT ENERGY"))
+            .map(|v| v.split_whitespace().next().unwrap_or_default())
+            .map(|v| {
+                v.parse::<f64>()
+                    .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
 
-        // 2. Only search within the text AFTER that marker
-        let remaining_text = &text[section_start..];
         let coord_header = "CARTESIAN COORDINATES (ANGSTROEM)";
+        let trajectory = text
+            .match_indices(coord_header)
+            .map(|(i, _)| parse_cartesian_block(&text[i..]))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let posits = trajectory.last().cloned().unwrap_or_default();
+
+        Ok(Self {
+            text,
+            posits,
+            energies,
+            trajectory,
+            frequencies: None,
+        })
+    }
 
-        let header_pos = remaining_text.find(coord_header).ok_or_else(|| {
-            io::Error::new(
-                io::ErrorKind::NotFound,
-                "Could not find coordinates in final section",
-            )
-        })?;
+    /// Create a copy of the input atoms, with positions replaced by the optimized ones. Atoms must
+    /// be in the same order as the input to the optimization. Returns an error if the number of
+    /// atoms doesn't match.
+    pub fn apply_to(&self, atoms: &[AtomGeneric]) -> io::Result<Vec<AtomGeneric>> {
+        if atoms.len() != self.posits.len() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Atom count mismatch: {} atoms, and {} optimized positions",
+                    atoms.len(),
+                    self.posits.len()
+                ),
+            ));
+        }
 
-        // 3. Iterate through lines starting from the coordinate header
-        let mut lines = remaining_text[header_pos..].lines();
+        Ok(atoms
+            .iter()
+            .zip(&self.posits)
+            .map(|(atom, posit)| AtomGeneric {
+                posit: *posit,
+                ..atom.clone()
+            })
+            .collect())
+    }
+}
 
-        // Skip the header line and the "-----------------" separator line
-        lines.next();
-        lines.next();
+/// Parse a `CARTESIAN COORDINATES (ANGSTROEM)` block, starting at its header.
+fn parse_cartesian_block(text: &str) -> io::Result<Vec<Vec3>> {
+    let mut posits = Vec::new();
+    let mut lines = text.lines();
 
-        for line in lines {
-            let trimmed = line.trim();
+    // Skip the header line and the "-----------------" separator line
+    lines.next();
+    lines.next();
 
-            // ORCA usually ends these blocks with a line of dashes or an empty line
-            if trimmed.is_empty() || trimmed.starts_with('-') {
-                break;
-            }
+    for line in lines {
+        let trimmed = line.trim();
 
-            let parts: Vec<&str> = trimmed.split_whitespace().collect();
-
-            // Format: Symbol  X  Y  Z
-            if parts.len() >= 4 {
-                let x = parts[1]
-                    .parse::<f64>()
-                    .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
-                let y = parts[2]
-                    .parse::<f64>()
-                    .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
-                let z = parts[3]
-                    .parse::<f64>()
-                    .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
-
-                posits.push(Vec3 { x, y, z });
-            }
+        // ORCA usually ends these blocks with a line of dashes or an empty line
+        if trimmed.is_empty() || trimmed.starts_with('-') {
+            break;
         }
 
-        if posits.is_empty() {
-            return Err(io::Error::new(
-                io::ErrorKind::NotFound,
-                "Coordinate block was empty or malformed",
-            ));
+        let parts: Vec<&str> = trimmed.split_whitespace().collect();
+
+        // Format: Symbol  X  Y  Z
+        if parts.len() >= 4 {
+            let x = parts[1]
+                .parse::<f64>()
+                .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+            let y = parts[2]
+                .parse::<f64>()
+                .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+            let z = parts[3]
+                .parse::<f64>()
+                .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+
+            posits.push(Vec3 { x, y, z });
         }
+    }
 
-        Ok(Self { text, posits })
+    if posits.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "Coordinate block was empty or malformed",
+        ));
     }
+
+    Ok(posits)
 }
@@ -0,0 +1,491 @@
+//! For reading and writing legacy PDB (.pdb) files. This parses coordinates (`ATOM` and `HETATM`)
+//! and secondary structure (`HELIX` and `SHEET`) into the same atom, residue, and chain types as
+//! mmCIF, so downstream code can be format-agnostic. It also parses header metadata: The `HEADER`,
+//! `TITLE`, resolution (`REMARK 2`), and biological assemblies (`REMARK 350`).
+//!
+//! Writing is limited to coordinates. Use [`write_pdb_atoms`] to write atoms loaded from another
+//! format, e.g. mmCIF, for tools that only read PDB.
+//!
+//! [PDB format spec, v3.3](https://www.wwpdb.org/documentation/file-format-content/format33/v3.3.html)
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufWriter, ErrorKind, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use lin_alg::f64::{Mat3, Vec3};
+use na_seq::{AtomTypeInRes, Element};
+
+use crate::{
+    AtomGeneric, BackboneSS, ChainGeneric, HelixClass, ResidueEnd, ResidueGeneric, ResidueType,
+    SecondaryStructure, el_from_atom_name, element_from_symbol, element_symbol,
+    fixed::{FixedLine, Justify, parse_field, parse_field_opt, read_field},
+    read_text_file, set_residue_ends,
+};
+
+/// One of the transformations that generates a biological assembly from the deposited coordinates.
+#[derive(Clone, Debug)]
+pub struct BiomtTransform {
+    pub rotation: Mat3,
+    /// Å
+    pub translation: Vec3,
+}
+
+impl BiomtTransform {
+    pub fn apply(&self, posit: Vec3) -> Vec3 {
+        self.rotation.clone() * posit + self.translation
+    }
+}
+
+/// A biological assembly, from a `REMARK 350` `BIOMOLECULE` section.
+#[derive(Clone, Debug)]
+pub struct BioAssembly {
+    pub id: u32,
+    /// The chains the transforms apply to.
+    pub chains: Vec<String>,
+    pub transforms: Vec<BiomtTransform>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PdbHeader {
+    /// The 4-character PDB ID.
+    pub ident: String,
+    pub classification: String,
+    /// As written in the file, e.g. "16-JUL-99".
+    pub deposition_date: Option<String>,
+    pub title: Option<String>,
+    /// Å. None if not applicable, e.g. for NMR structures.
+    pub resolution: Option<f32>,
+    pub assemblies: Vec<BioAssembly>,
+}
+
+impl PdbHeader {
+    /// Parse header records from PDB text. Coordinate and other records are ignored.
+    pub fn new(text: &str) -> io::Result<Self> {
+        let mut result = Self::default();
+        let mut title_parts = Vec::new();
+
+        // BIOMT rows for the transform in progress: (serial, row).
+        let mut biomt_rows: Vec<(u32, [f64; 4])> = Vec::new();
+
+        for line in text.lines() {
+            let record = read_field(line, 0, 6);
+
+            match record {
+                "HEADER" => {
+                    result.classification = read_field(line, 10, 50).to_owned();
+                    let date = read_field(line, 50, 59);
+                    if !date.is_empty() {
+                        result.deposition_date = Some(date.to_owned());
+                    }
+                    result.ident = read_field(line, 62, 66).to_owned();
+                }
+                "TITLE" => title_parts.push(read_field(line, 10, 80).to_owned()),
+                "REMARK" => {
+                    let remark_num = read_field(line, 7, 10);
+                    let content = read_field(line, 11, 80);
+
+                    match remark_num {
+                        "2" => {
+                            if let Some(v) = content.strip_prefix("RESOLUTION.") {
+                                result.resolution =
+                                    v.split_whitespace().next().and_then(|v| v.parse().ok());
+                            }
+                        }
+                        "350" => {
+                            parse_remark_350(content, &mut result.assemblies, &mut biomt_rows)?
+                        }
+                        _ => (),
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        if !title_parts.is_empty() {
+            result.title = Some(title_parts.join(" "));
+        }
+
+        Ok(result)
+    }
+
+    /// Load from a file. Gzipped files, e.g. `.pdb.gz`, are decompressed.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let data_str = read_text_file(path)?;
+        Self::new(&data_str)
+    }
+}
+
+fn parse_remark_350(
+    content: &str,
+    assemblies: &mut Vec<BioAssembly>,
+    biomt_rows: &mut Vec<(u32, [f64; 4])>,
+) -> io::Result<()> {
+    if let Some(v) = content.strip_prefix("BIOMOLECULE:") {
+        let id = v.trim().parse().map_err(|_| {
+            io::Error::new(ErrorKind::InvalidData, format!("Invalid biomolecule: {v}"))
+        })?;
+        assemblies.push(BioAssembly {
+            id,
+            chains: Vec::new(),
+            transforms: Vec::new(),
+        });
+        biomt_rows.clear();
+        return Ok(());
+    }
+
+    let Some(assembly) = assemblies.last_mut() else {
+        return Ok(());
+    };
+
+    // E.g. "APPLY THE FOLLOWING TO CHAINS: A, B", continued with "AND CHAINS: C, D".
+    if content.starts_with("APPLY THE FOLLOWING TO CHAINS:") || content.starts_with("AND CHAINS:") {
+        let (_, chains) = content.split_once(':').unwrap();
+        assembly.chains.extend(
+            chains
+                .split(',')
+                .map(str::trim)
+                .filter(|c| !c.is_empty())
+                .map(str::to_owned),
+        );
+        return Ok(());
+    }
+
+    if content.starts_with("BIOMT") {
+        let cols: Vec<&str> = content.split_whitespace().collect();
+        if cols.len() < 6 {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid BIOMT line: {content}"),
+            ));
+        }
+
+        let serial = cols[1].parse().unwrap_or_default();
+        let mut row = [0.; 4];
+        for (i, v) in cols[2..6].iter().enumerate() {
+            row[i] = v.parse().map_err(|_| {
+                io::Error::new(ErrorKind::InvalidData, format!("Invalid BIOMT value: {v}"))
+            })?;
+        }
+        biomt_rows.push((serial, row));
+
+        if biomt_rows.len() == 3 {
+            let r: Vec<[f64; 4]> = biomt_rows.drain(..).map(|(_, r)| r).collect();
+            let col = |j: usize| Vec3::new(r[0][j], r[1][j], r[2][j]);
+
+            assembly.transforms.push(BiomtTransform {
+                rotation: Mat3::from_cols(col(0), col(1), col(2)),
+                translation: col(3),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Atoms, residues, chains, and secondary structure from a PDB file. For files with multiple
+/// models, e.g. from NMR, only the first is read.
+#[derive(Clone, Debug)]
+pub struct Pdb {
+    pub header: PdbHeader,
+    pub atoms: Vec<AtomGeneric>,
+    pub chains: Vec<ChainGeneric>,
+    pub residues: Vec<ResidueGeneric>,
+    pub secondary_structure: Vec<BackboneSS>,
+}
+
+impl Pdb {
+    /// From the text of a PDB file.
+    pub fn new(text: &str) -> io::Result<Self> {
+        let header = PdbHeader::new(text)?;
+
+        let mut atoms = Vec::new();
+        let mut chains: Vec<ChainGeneric> = Vec::new();
+        let mut residues: Vec<ResidueGeneric> = Vec::new();
+
+        let mut chain_idx: HashMap<&str, usize> = HashMap::new();
+        // Keyed by chain index, residue sequence number, and insertion code.
+        let mut res_idx: HashMap<(usize, &str, &str), usize> = HashMap::new();
+        // For resolving secondary structure ranges: CA serial numbers, by chain and residue number.
+        let mut ca_sns: HashMap<(&str, &str), u32> = HashMap::new();
+        let mut ss_lines = Vec::new();
+
+        for line in text.lines() {
+            let record = read_field(line, 0, 6);
+
+            match record {
+                "ATOM" | "HETATM" => (),
+                "HELIX" | "SHEET" => {
+                    ss_lines.push(line);
+                    continue;
+                }
+                "ENDMDL" => break,
+                _ => continue,
+            }
+
+            let atom = parse_atom_line(line)?;
+            let serial_number = atom.serial_number;
+
+            let chain_id = read_field(line, 21, 22);
+            let res_sn = read_field(line, 22, 26);
+            let ins_code = read_field(line, 26, 27);
+            let res_name = read_field(line, 17, 20);
+
+            if read_field(line, 12, 16) == "CA" {
+                ca_sns.entry((chain_id, res_sn)).or_insert(serial_number);
+            }
+            atoms.push(atom);
+
+            let c_i = *chain_idx.entry(chain_id).or_insert_with(|| {
+                chains.push(ChainGeneric {
+                    id: chain_id.to_owned(),
+                    residue_sns: Vec::new(),
+                    atom_sns: Vec::new(),
+                });
+                chains.len() - 1
+            });
+            chains[c_i].atom_sns.push(serial_number);
+
+            // Residue numbers can be negative in PDB files; we store these as 0, but keep the
+            // original in `auth_serial_number`.
+            let res_sn_parsed = res_sn.parse::<u32>().unwrap_or(0);
+            let r_i = *res_idx.entry((c_i, res_sn, ins_code)).or_insert_with(|| {
+                residues.push(ResidueGeneric {
+                    serial_number: res_sn_parsed,
+                    res_type: ResidueType::from_str(res_name),
+                    atom_sns: Vec::new(),
+                    end: ResidueEnd::Internal, // We update this after.
+                    entity_id: None,
+                    auth_serial_number: res_sn.parse().ok(),
+                    insertion_code: ins_code.chars().next(),
+                });
+                chains[c_i].residue_sns.push(res_sn_parsed);
+                residues.len() - 1
+            });
+            residues[r_i].atom_sns.push(serial_number);
+        }
+
+        set_residue_ends(&mut residues);
+
+        let secondary_structure = ss_lines
+            .into_iter()
+            .filter_map(|l| parse_ss_line(l, &ca_sns))
+            .collect();
+
+        Ok(Self {
+            header,
+            atoms,
+            chains,
+            residues,
+            secondary_structure,
+        })
+    }
+
+    /// Load from a file. Gzipped files, e.g. `.pdb.gz`, are decompressed.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let data_str = read_text_file(path)?;
+        Self::new(&data_str)
+    }
+
+    /// Write the `HEADER` record, if we have an ident, and coordinates.
+    pub fn write_to(&self, w: &mut impl Write) -> io::Result<()> {
+        if !self.header.ident.is_empty() {
+            let mut line = FixedLine::new();
+            line.str(0, 6, "HEADER", Justify::Left)
+                .str(10, 50, &self.header.classification, Justify::Left)
+                .str(
+                    50,
+                    59,
+                    self.header.deposition_date.as_deref().unwrap_or_default(),
+                    Justify::Left,
+                )
+                .str(62, 66, &self.header.ident, Justify::Left);
+            writeln!(w, "{}", line.as_str())?;
+        }
+
+        write_pdb_atoms(w, &self.atoms, &self.residues, &self.chains)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let file = File::create(path)?;
+        let mut w = BufWriter::new(file);
+        self.write_to(&mut w)
+    }
+}
+
+/// The 4-column atom name field. Names start in the second column, unless they fill the field, or
+/// the element symbol has two letters, e.g. " CA " for an α carbon, but "CA  " for calcium.
+fn atom_name_field(name: &str, element: Element) -> String {
+    if name.len() >= 4 || element_symbol(element).len() == 2 {
+        name.to_owned()
+    } else {
+        format!(" {name}")
+    }
+}
+
+/// Write atoms as fixed-column `ATOM` and `HETATM` records, followed by `END`. Residue and chain
+/// information is looked up by atom serial number; atoms not in a residue are written as "UNL" 1,
+/// and atoms not in a chain as chain "A". Serial and residue numbers too large for their columns
+/// wrap around, as is conventional for large structures.
+pub fn write_pdb_atoms(
+    w: &mut impl Write,
+    atoms: &[AtomGeneric],
+    residues: &[ResidueGeneric],
+    chains: &[ChainGeneric],
+) -> io::Result<()> {
+    let mut atom_to_res = HashMap::<u32, &ResidueGeneric>::new();
+    for r in residues {
+        for &sn in &r.atom_sns {
+            atom_to_res.insert(sn, r);
+        }
+    }
+    let mut atom_to_chain = HashMap::<u32, &str>::new();
+    for c in chains {
+        for &sn in &c.atom_sns {
+            atom_to_chain.insert(sn, &c.id);
+        }
+    }
+
+    for atom in atoms {
+        let record = if atom.hetero { "HETATM" } else { "ATOM" };
+
+        let name = match (&atom.type_in_res, &atom.type_in_res_general) {
+            (Some(t), _) => t.to_string(),
+            (None, Some(t)) => t.clone(),
+            (None, None) => element_symbol(atom.element).to_uppercase(),
+        };
+
+        let (res_name, res_sn, ins_code) = match atom_to_res.get(&atom.serial_number) {
+            // PDB files use author numbering, which insertion codes go with.
+            Some(r) => (
+                r.res_type.to_name(),
+                r.auth_serial_number.unwrap_or(r.serial_number as i32),
+                r.insertion_code.map(String::from).unwrap_or_default(),
+            ),
+            None => ("UNL".to_owned(), 1, String::new()),
+        };
+        let chain_id = atom_to_chain
+            .get(&atom.serial_number)
+            .copied()
+            .unwrap_or("A");
+
+        let charge = match atom.formal_charge {
+            Some(q) if q > 0 => format!("{q}+"),
+            Some(q) if q < 0 => format!("{}-", -q),
+            _ => String::new(),
+        };
+
+        let mut line = FixedLine::new();
+        line.str(0, 6, record, Justify::Left)
+            .int(6, 11, (atom.serial_number % 100_000) as i64)?
+            .str(12, 16, &atom_name_field(&name, atom.element), Justify::Left)
+            .str(
+                16,
+                17,
+                atom.alt_conformation_id.as_deref().unwrap_or_default(),
+                Justify::Left,
+            )
+            .str(17, 20, &res_name, Justify::Right)
+            .str(21, 22, chain_id, Justify::Left)
+            .int(22, 26, (res_sn % 10_000) as i64)?
+            .str(26, 27, &ins_code, Justify::Left)
+            .float(30, 38, atom.posit.x, 3)?
+            .float(38, 46, atom.posit.y, 3)?
+            .float(46, 54, atom.posit.z, 3)?
+            .float(54, 60, atom.occupancy.unwrap_or(1.) as f64, 2)?
+            .float(60, 66, atom.b_factor.unwrap_or_default() as f64, 2)?
+            .str(
+                76,
+                78,
+                &element_symbol(atom.element).to_uppercase(),
+                Justify::Right,
+            )
+            .str(78, 80, &charge, Justify::Left);
+
+        writeln!(w, "{}", line.as_str())?;
+    }
+
+    writeln!(w, "END")
+}
+
+/// Parse an `ATOM` or `HETATM` record.
+fn parse_atom_line(line: &str) -> io::Result<AtomGeneric> {
+    let hetero = read_field(line, 0, 6) == "HETATM";
+    let atom_name = read_field(line, 12, 16);
+
+    // The element column is sometimes missing in older files; infer it from the atom name.
+    let element = match read_field(line, 76, 78) {
+        "" => el_from_atom_name(atom_name),
+        v => element_from_symbol(v)?,
+    };
+
+    let type_in_res = if hetero {
+        if !atom_name.is_empty() {
+            Some(AtomTypeInRes::Hetero(atom_name.to_string()))
+        } else {
+            None
+        }
+    } else {
+        AtomTypeInRes::from_str(atom_name).ok()
+    };
+
+    let alt_conformation_id = match read_field(line, 16, 17) {
+        "" => None,
+        v => Some(v.to_owned()),
+    };
+
+    // E.g. "2+", or "1-".
+    let charge = read_field(line, 78, 80);
+    let formal_charge = match (charge.get(..1), charge.get(1..)) {
+        (Some(n), Some("+")) => n.parse::<i8>().ok(),
+        (Some(n), Some("-")) => n.parse::<i8>().ok().map(|v| -v),
+        _ => None,
+    };
+
+    Ok(AtomGeneric {
+        serial_number: parse_field(line, 6, 11, "atom serial number")?,
+        posit: Vec3::new(
+            parse_field(line, 30, 38, "x")?,
+            parse_field(line, 38, 46, "y")?,
+            parse_field(line, 46, 54, "z")?,
+        ),
+        element,
+        type_in_res,
+        occupancy: parse_field_opt(line, 54, 60, "occupancy")?,
+        b_factor: parse_field_opt(line, 60, 66, "temperature factor")?,
+        formal_charge,
+        hetero,
+        alt_conformation_id,
+        ..Default::default()
+    })
+}
+
+/// Parse a `HELIX` or `SHEET` record. Returns None if either end's CA atom isn't present.
+fn parse_ss_line(line: &str, ca_sns: &HashMap<(&str, &str), u32>) -> Option<BackboneSS> {
+    // Chain and residue number columns for the start and end of the range.
+    let (sec_struct, start, end) = match read_field(line, 0, 6) {
+        "HELIX" => (SecondaryStructure::Helix, (19, 21..25), (31, 33..37)),
+        _ => (SecondaryStructure::Sheet, (21, 22..26), (32, 33..37)),
+    };
+
+    let ca = |(chain_col, res_cols): (usize, std::ops::Range<usize>)| {
+        let chain = read_field(line, chain_col, chain_col + 1);
+        let res_sn = read_field(line, res_cols.start, res_cols.end);
+        ca_sns.get(&(chain, res_sn)).copied()
+    };
+
+    let helix_class = match sec_struct {
+        SecondaryStructure::Helix => HelixClass::from_pdb_class(read_field(line, 38, 40)),
+        _ => None,
+    };
+
+    Some(BackboneSS {
+        start_sn: ca(start)?,
+        end_sn: ca(end)?,
+        sec_struct,
+        helix_class,
+    })
+}
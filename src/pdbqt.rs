@@ -1,15 +1,7 @@
 //! For reading and writing PDBQT (Autodock) files.
 //! [Unofficial, incomplete spec](https://userguide.mdanalysis.org/2.6.0/formats/reference/pdbqt.html)
 
-use std::{
-    fmt::Display,
-    fs,
-    fs::File,
-    io,
-    io::{ErrorKind, Write},
-    path::Path,
-    str::FromStr,
-};
+use std::{fmt::Display, fs, fs::File, io, io::Write, path::Path, str::FromStr};
 
 use lin_alg::f64::Vec3;
 use na_seq::{AtomTypeInRes, Element};
@@ -17,31 +9,21 @@ use regex::Regex;
 
 use crate::{
     AtomGeneric, BondGeneric, ChainGeneric, ChargeType, MolType, ResidueEnd, ResidueGeneric,
-    ResidueType,
+    ResidueType, el_from_atom_name, element_from_symbol, element_symbol,
+    fixed::{parse_field, parse_field_opt, read_field},
 };
 
-/// Helpers for parsing
-fn parse_usize(s: &str) -> io::Result<usize> {
-    s.parse::<usize>()
-        .map_err(|_| io::Error::new(ErrorKind::InvalidData, "Invalid integer"))
-}
-fn parse_u32(s: &str) -> io::Result<u32> {
-    s.parse::<u32>()
-        .map_err(|_| io::Error::new(ErrorKind::InvalidData, "Invalid integer"))
-}
-fn parse_f64(s: &str) -> io::Result<f64> {
-    s.parse::<f64>()
-        .map_err(|_| io::Error::new(ErrorKind::InvalidData, "Invalid float"))
-}
-
-fn parse_optional_f32(s: &str) -> io::Result<Option<f32>> {
-    if s.is_empty() {
-        Ok(None)
-    } else {
-        let val = s
-            .parse::<f32>()
-            .map_err(|_| io::Error::new(ErrorKind::InvalidData, "Invalid float"))?;
-        Ok(Some(val))
+/// Infer the element from an AutoDock atom type, e.g. "A" (aromatic carbon), "NA" (acceptor
+/// nitrogen), "OA", "HD" (donor hydrogen), "SA". Falls back to the atom name for unknown types.
+fn el_from_ad_type(ad_type: &str, atom_name: &str) -> Element {
+    match ad_type {
+        "A" | "C" => Element::Carbon,
+        "N" | "NA" | "NS" => Element::Nitrogen,
+        "O" | "OA" | "OS" => Element::Oxygen,
+        "S" | "SA" => Element::Sulfur,
+        "H" | "HD" | "HS" => Element::Hydrogen,
+        "" => el_from_atom_name(atom_name),
+        v => element_from_symbol(v).unwrap_or_else(|_| el_from_atom_name(atom_name)),
     }
 }
 
@@ -71,12 +53,15 @@ pub struct Pdbqt {
     pub residues: Vec<ResidueGeneric>,
     pub atoms: Vec<AtomGeneric>,
     pub bonds: Vec<BondGeneric>,
+    /// Torsional degrees of freedom of a ligand, from the `TORSDOF` record.
+    pub torsdof: Option<u32>,
     // pub metadata: HashMap<String, String>,
 }
 
 impl Pdbqt {
-    /// From PQBQT text, e.g. loaded from a file.
-    /// We use the Mol2 structure here, as it's similar enough.
+    /// From PQBQT text, e.g. loaded from a file. Each atom's Gasteiger partial charge is loaded into
+    /// `partial_charge`, and its AutoDock atom type (e.g. "A", "NA", "OA") into `force_field_type`.
+    /// Torsion tree records (`ROOT`, `BRANCH` etc) are skipped, apart from `TORSDOF`.
     pub fn new(text: &str) -> io::Result<Self> {
         let mut atoms = Vec::new();
 
@@ -84,8 +69,11 @@ impl Pdbqt {
 
         let mut chains: Vec<ChainGeneric> = Vec::new();
         let mut residues: Vec<ResidueGeneric> = Vec::new();
+        // (chain id, residue sequence number field) for each residue, for grouping atoms.
+        let mut res_keys: Vec<(String, String)> = Vec::new();
 
         let mut ident = String::new();
+        let mut torsdof = None;
 
         for line in text.lines() {
             if let Some(caps) = re_ident.captures(line) {
@@ -93,125 +81,87 @@ impl Pdbqt {
                 continue;
             }
 
-            if line.len() < 6 {
+            if let Some(v) = line.strip_prefix("TORSDOF") {
+                torsdof = v.split_whitespace().next().and_then(|v| v.parse().ok());
                 continue;
             }
 
-            let record_type = line[0..6].trim();
-
-            // todo: Parse Ligand torsions.
-
-            if record_type == "ATOM" || record_type == "HETATM" {
-                let serial_number = parse_u32(line[6..11].trim())?;
+            let record_type = read_field(line, 0, 6);
 
-                let atom_id = atoms.len(); // index for assigning residues and chains.
-
-                let name = line[12..16].trim();
-
-                let element = Element::from_letter(&name[..1]).unwrap_or(Element::Carbon);
-
-                let res_name = line[17..21].trim();
-                let residue_type = ResidueType::from_str(res_name);
-
-                let type_in_res = AtomTypeInRes::from_str(name)?;
-
-                // let _role = match residue_type {
-                //     ResidueType::AminoAcid(_aa) => Some(AtomRole::from_type_in_res(&type_in_res)),
-                //     ResidueType::Water => Some(AtomRole::Water),
-                //     _ => None,
-                // };
+            if record_type != "ATOM" && record_type != "HETATM" {
+                // E.g. REMARK, ROOT, ENDROOT, BRANCH, ENDBRANCH, CRYST1.
+                continue;
+            }
 
-                let chain_id = line[21..22].trim();
-                let mut chain_found = false;
-                for chain in &mut chains {
-                    if chain.id == *chain_id {
-                        // chain.atom_sns.push(atom_id as u32);
-                        chain.atom_sns.push(serial_number);
-                        chain_found = true;
-                        break;
-                    }
-                }
-                if !chain_found {
-                    chains.push(ChainGeneric {
-                        id: chain_id.to_string(),
-                        residue_sns: Vec::new(), // todo temp
-                        // atom_sns: vec![atom_id as u32],
-                        atom_sns: vec![serial_number],
-                    });
-                }
+            let serial_number = parse_field(line, 6, 11, "serial number")?;
+            let name = read_field(line, 12, 16);
+            let res_name = read_field(line, 17, 21);
+            let chain_id = read_field(line, 21, 22);
+            let res_seq = read_field(line, 22, 26);
+
+            let posit = Vec3::new(
+                parse_field(line, 30, 38, "x")?,
+                parse_field(line, 38, 46, "y")?,
+                parse_field(line, 46, 54, "z")?,
+            );
+
+            let occupancy = parse_field_opt(line, 54, 60, "occupancy")?;
+            let b_factor = parse_field_opt(line, 60, 66, "B-factor")?;
+            // Gasteiger PEOE partial charge q, in columns 71-76.
+            let partial_charge = parse_field_opt(line, 66, 76, "partial charge")?;
+            // Columns 78-79. Some writers start single-letter types in column 77.
+            let ad_type = read_field(line, 76, 79);
+
+            // Ligands often use ATOM records, with names like "C1" that aren't protein atom types.
+            let type_in_res = match AtomTypeInRes::from_str(name) {
+                Ok(v) => Some(v),
+                Err(_) if !name.is_empty() => Some(AtomTypeInRes::Hetero(name.to_owned())),
+                Err(_) => None,
+            };
 
-                let serial_number = parse_usize(line[22..26].trim()).unwrap_or_default() as u32;
-                let mut res_found = false;
-                for res in &mut residues {
-                    if res.serial_number == serial_number {
-                        res.atom_sns.push(serial_number);
-                        res_found = true;
-                        break;
-                    }
-                }
-                if !res_found {
+            let res_key = (chain_id.to_owned(), res_seq.to_owned());
+            match res_keys.iter().position(|k| *k == res_key) {
+                Some(i) => residues[i].atom_sns.push(serial_number),
+                None => {
+                    let res_sn = res_seq.parse::<i32>().unwrap_or_default().max(0) as u32;
                     residues.push(ResidueGeneric {
-                        serial_number: 0, // todo temp
-                        res_type: residue_type.clone(),
-                        atom_sns: vec![atom_id as u32],
+                        serial_number: res_sn,
+                        res_type: ResidueType::from_str(res_name),
+                        atom_sns: vec![serial_number],
                         end: ResidueEnd::Hetero,
-                        // atoms: vec![atom_id],
-                        // dihedral: None,
-                        // end: ResidueEnd::Hetero,
+                        entity_id: None,
+                        auth_serial_number: None,
+                        insertion_code: None,
                     });
+                    res_keys.push(res_key);
+
+                    match chains.iter_mut().find(|c| c.id == chain_id) {
+                        Some(chain) => chain.residue_sns.push(res_sn),
+                        None => chains.push(ChainGeneric {
+                            id: chain_id.to_string(),
+                            residue_sns: vec![res_sn],
+                            atom_sns: Vec::new(),
+                        }),
+                    }
                 }
+            }
 
-                let x = parse_f64(line[30..38].trim())?;
-                let y = parse_f64(line[38..46].trim())?;
-                let z = parse_f64(line[46..54].trim())?;
-
-                let occupancy = parse_optional_f32(line[54..60].trim())?;
-                let _temperature_factor = parse_optional_f32(line[60..66].trim())?;
-                // Gasteiger PEOE partial charge q.
-                let partial_charge = parse_optional_f32(line[66..76].trim())?;
-
-                // todo: May need to take into account lines of len 78 and 79: Is this leaving out single-letter ones?
-                // let dock_type = if line.len() < 80 {
-                //     None
-                // } else {
-                //     // todo
-                //     // Some(DockType::from_str(line[78..80].trim()))
-                //     None
-                // };
-
-                let hetero = record_type == "HETATM";
-
-                atoms.push(AtomGeneric {
-                    serial_number,
-                    posit: Vec3 { x, y, z },
-                    element,
-                    type_in_res: Some(type_in_res),
-                    hetero,
-                    occupancy,
-                    partial_charge,
-                    ..Default::default()
-                });
-
-                // atoms.push(Atom {
-                //     serial_number,
-                //     posit: Vec3 { x, y, z },
-                //     element,
-                //     type_in_res: Some(type_in_res),
-                //     role,
-                //     residue: None,
-                //     chain: None,
-                //     hetero,
-                //     occupancy,
-                //     temperature_factor,
-                //     partial_charge,
-                //     force_field_type: None,
-                //     dock_type,
-                // });
-            } else if record_type == "CRYST1" {
-                // todo: What to do with this?
-            } else {
-                // handle other records if you like, e.g. REMARK, BRANCH, etc.
+            if let Some(chain) = chains.iter_mut().find(|c| c.id == chain_id) {
+                chain.atom_sns.push(serial_number);
             }
+
+            atoms.push(AtomGeneric {
+                serial_number,
+                posit,
+                element: el_from_ad_type(ad_type, name),
+                type_in_res,
+                force_field_type: (!ad_type.is_empty()).then(|| ad_type.to_owned()),
+                hetero: record_type == "HETATM",
+                occupancy,
+                b_factor,
+                partial_charge,
+                ..Default::default()
+            });
         }
 
         // todo: Handle bonds. Are they in the file, or should we infer them?
@@ -226,6 +176,7 @@ impl Pdbqt {
             residues,
             atoms,
             bonds,
+            torsdof,
         })
     }
 
@@ -335,19 +286,22 @@ impl Pdbqt {
 
             let name = match &atom.type_in_res {
                 Some(name) => name.to_string(),
-                None => atom.element.to_letter(),
+                None => element_symbol(atom.element),
             };
 
             let residue_name = "temp";
 
             // todo temp
             let chain_id = "A".to_string();
-            let dock_type = " ".to_string();
-            let temperature_factor = " ".to_string();
+            let dock_type = atom.force_field_type.clone().unwrap_or_default();
+            let temperature_factor = match atom.b_factor {
+                Some(b) => format!("{b:.2}"),
+                None => String::new(),
+            };
 
             writeln!(
                 file,
-                "{:<6}{:>5}  {:<3} {:<3} {:>1}{:>4}    {:>8.3}{:>8.3}{:>8.3}{:>6.2}{:>6.2}    {:>+6.3} {:<2}",
+                "{:<6}{:>5}  {:<3} {:<3} {:>1}{:>4}    {:>8.3}{:>8.3}{:>8.3}{:>6.2}{:>6}    {:>+6.3} {:<2}",
                 record_name,                             // columns 1-6
                 atom.serial_number,                      // columns 7-11
                 name,                                    // columns 13-14 or 13-16
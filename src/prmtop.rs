@@ -1,13 +1,21 @@
+//! Reads and writes Amber prmtop topology files. These are split into `%FLAG` sections, e.g.
+//! `ATOM_NAME`, `CHARGE`, and `BONDS_INC_HYDROGEN`, each with a Fortran `%FORMAT`.
+
 use std::{
     collections::{BTreeMap, HashMap},
+    fs,
     fs::File,
     io::{self, Read, Write},
     path::Path,
+    str::FromStr,
 };
 
+use na_seq::{AtomTypeInRes, Element};
+
 use crate::{
-    AtomGeneric,
+    AtomGeneric, BondGeneric, BondType, ResidueEnd, ResidueGeneric, ResidueType,
     md_params::{ForceFieldParams, LjParams, MassParams},
+    set_residue_ends,
 };
 
 const AMBER_CHARGE_SCALE: f32 = 18.2223; // prmtop stores q * 18.2223 (real q = stored/18.2223)
@@ -66,9 +74,7 @@ fn write_flag<T: Fn(usize) -> String>(
             wline(file, &line)?;
             line.clear();
         }
-        if !line.is_empty() {
-            line.push(' ');
-        }
+        // Fields are fixed-width, per `fmt`, without separators.
         line.push_str(&item(i));
     }
     if !line.is_empty() {
@@ -314,38 +320,233 @@ pub fn save_prmtop(
     Ok(())
 }
 
-pub fn load_prmtop(path: &Path) -> io::Result<(Vec<AtomGeneric>, ForceFieldParams)> {
-    let mut file = File::open(path)?;
-    let mut buf = String::new();
-    file.read_to_string(&mut buf)?;
-
-    #[derive(Default)]
-    struct Block {
-        fmt: String,
-        data: Vec<String>,
-    }
-    let mut blocks: HashMap<String, Block> = HashMap::new();
+/// Split the `%FLAG` sections of a prmtop file into their values, using each section's
+/// `%FORMAT` field width, e.g. 4 for `20a4`, or 8 for `10I8`. Values aren't trimmed, as names may
+/// contain spaces.
+fn parse_sections(text: &str) -> io::Result<HashMap<String, Vec<String>>> {
+    let mut result: HashMap<String, Vec<String>> = HashMap::new();
 
     let mut cur: Option<String> = None;
-    for line in buf.lines() {
+    let mut width = 0;
+    for line in text.lines() {
         let line = line.trim_end();
-        if line.starts_with("%FLAG") {
-            let name = line.split_whitespace().nth(1).unwrap().to_string();
-            blocks.entry(name.clone()).or_default();
+        if let Some(flag) = line.strip_prefix("%FLAG") {
+            let name = flag.trim().to_string();
+            result.entry(name.clone()).or_default();
             cur = Some(name);
-        } else if line.starts_with("%FORMAT") {
-            if let Some(k) = &cur {
-                blocks.get_mut(k).unwrap().fmt = line["%FORMAT(".len()..line.len() - 1].to_string();
+            width = 0;
+        } else if let Some(fmt) = line.strip_prefix("%FORMAT(") {
+            // E.g. "20a4", "10I8", or "5E16.8".
+            let fmt = fmt.trim_end_matches(')');
+            width = fmt
+                .trim_start_matches(|c: char| c.is_ascii_digit())
+                .get(1..)
+                .and_then(|w| w.split('.').next())
+                .and_then(|w| w.parse().ok())
+                .filter(|w| *w > 0)
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Invalid prmtop format: {line}"),
+                    )
+                })?;
+        } else if line.starts_with('%') {
+            // E.g. %VERSION, and %COMMENT
+        } else if let Some(k) = &cur
+            && width > 0
+        {
+            let values = result.get_mut(k).unwrap();
+            let chars: Vec<char> = line.chars().collect();
+            values.extend(chars.chunks(width).map(|c| c.iter().collect::<String>()));
+        }
+    }
+
+    Ok(result)
+}
+
+/// An assembled Amber topology, e.g. as used by an MD engine. This includes atoms, their charges,
+/// and bonds, with atom names, and residues. Coordinates aren't included; they're in a separate file,
+/// e.g. `.inpcrd` or `.rst7`.
+#[derive(Clone, Debug, Default)]
+pub struct Prmtop {
+    pub title: String,
+    /// Partial charges are in elementary charge units; they're converted from Amber's internal
+    /// units, which are scaled by 18.2223. Elements are from the ATOMIC_NUMBER section, if present.
+    pub atoms: Vec<AtomGeneric>,
+    /// Per atom, in Da, in the same order as `atoms`.
+    pub masses: Vec<f32>,
+    /// From BONDS_INC_HYDROGEN and BONDS_WITHOUT_HYDROGEN. Bond orders aren't stored in prmtop
+    /// files, so these are `Unknown`.
+    pub bonds: Vec<BondGeneric>,
+    pub residues: Vec<ResidueGeneric>,
+}
+
+impl Prmtop {
+    pub fn new(text: &str) -> io::Result<Self> {
+        let sections = parse_sections(text)?;
+
+        let err = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+        // Values of a section; there must be at least `len`.
+        let section = |name: &str, len: usize| -> io::Result<&[String]> {
+            let v = sections
+                .get(name)
+                .ok_or_else(|| err(format!("Missing {name}")))?;
+            if v.len() < len {
+                return Err(err(format!("{name} too short")));
             }
-        } else if let Some(k) = &cur {
-            let b = blocks.get_mut(k).unwrap();
-            if !line.is_empty() {
-                // Split into tokens, preserving 4-char fields for 20a4 by whitespace split (OK).
-                b.data
-                    .extend(line.split_whitespace().map(|s| s.to_string()));
+            Ok(&v[..len])
+        };
+        let parse_i = |v: &str| -> io::Result<i32> {
+            v.trim()
+                .parse()
+                .map_err(|_| err(format!("Invalid integer in prmtop: {v}")))
+        };
+        let parse_f = |v: &str| -> io::Result<f32> {
+            v.trim()
+                .parse()
+                .map_err(|_| err(format!("Invalid number in prmtop: {v}")))
+        };
+
+        let natom = parse_i(&section("POINTERS", 12)?[0])? as usize;
+        let nres = parse_i(&section("POINTERS", 12)?[11])? as usize;
+
+        let title = sections
+            .get("TITLE")
+            .or_else(|| sections.get("CTITLE"))
+            .map(|v| v.concat().trim().to_owned())
+            .unwrap_or_default();
+
+        let names = section("ATOM_NAME", natom)?;
+        let charges = section("CHARGE", natom)?;
+        let masses = section("MASS", natom)?
+            .iter()
+            .map(|v| parse_f(v))
+            .collect::<io::Result<Vec<_>>>()?;
+        let types = sections.get("AMBER_ATOM_TYPE");
+        let atomic_numbers = sections.get("ATOMIC_NUMBER");
+
+        // Residue index of each atom, from the 1-based index of each residue's first atom.
+        let res_labels = section("RESIDUE_LABEL", nres)?;
+        let res_ptrs = section("RESIDUE_POINTER", nres)?
+            .iter()
+            .map(|v| Ok((parse_i(v)? as usize).saturating_sub(1)))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let mut residues = Vec::with_capacity(nres);
+        for (i, label) in res_labels.iter().enumerate() {
+            let start = res_ptrs[i];
+            let end = res_ptrs.get(i + 1).copied().unwrap_or(natom);
+            if start > end || end > natom {
+                return Err(err(format!(
+                    "Invalid RESIDUE_POINTER for residue {}",
+                    i + 1
+                )));
+            }
+
+            residues.push(ResidueGeneric {
+                serial_number: i as u32 + 1,
+                res_type: ResidueType::from_str(label.trim()),
+                atom_sns: (start as u32 + 1..end as u32 + 1).collect(),
+                end: ResidueEnd::Internal,
+                entity_id: None,
+                auth_serial_number: None,
+                insertion_code: None,
+            });
+        }
+        set_residue_ends(&mut residues);
+
+        let mut atoms = Vec::with_capacity(natom);
+        for i in 0..natom {
+            let element = match atomic_numbers.and_then(|v| v.get(i)) {
+                Some(n) => Element::from_atomic_number(parse_i(n)?.clamp(0, 255) as u8)
+                    .unwrap_or(Element::Other),
+                None => Element::Other,
+            };
+
+            let name = names[i].trim();
+            atoms.push(AtomGeneric {
+                serial_number: i as u32 + 1,
+                element,
+                type_in_res_general: Some(name.to_owned()),
+                force_field_type: types.and_then(|t| t.get(i)).map(|t| t.trim().to_owned()),
+                partial_charge: Some(parse_f(&charges[i])? / AMBER_CHARGE_SCALE),
+                ..Default::default()
+            });
+        }
+
+        for res in &residues {
+            for sn in &res.atom_sns {
+                let atom = &mut atoms[*sn as usize - 1];
+                let name = atom.type_in_res_general.clone().unwrap_or_default();
+                match res.res_type {
+                    ResidueType::AminoAcid(_) => {
+                        atom.type_in_res = AtomTypeInRes::from_str(&name).ok();
+                    }
+                    _ => {
+                        atom.type_in_res = Some(AtomTypeInRes::Hetero(name));
+                        atom.hetero = true;
+                    }
+                }
             }
         }
+
+        // Triplets of (coordinate index of atom i, of atom j, bond type index); coordinate
+        // indices are 3 × the 0-based atom index.
+        let mut bonds = Vec::new();
+        for name in ["BONDS_INC_HYDROGEN", "BONDS_WITHOUT_HYDROGEN"] {
+            let Some(vals) = sections.get(name) else {
+                continue;
+            };
+            let vals: Vec<&String> = vals.iter().filter(|v| !v.trim().is_empty()).collect();
+
+            for triplet in vals.chunks_exact(3) {
+                let (i, j) = (parse_i(triplet[0])? / 3, parse_i(triplet[1])? / 3);
+                if i < 0 || j < 0 || i as usize >= natom || j as usize >= natom {
+                    return Err(err(format!("Bond atom index out of range in {name}")));
+                }
+                bonds.push(BondGeneric {
+                    bond_type: BondType::Unknown,
+                    atom_0_sn: i as u32 + 1,
+                    atom_1_sn: j as u32 + 1,
+                });
+            }
+        }
+
+        Ok(Self {
+            title,
+            atoms,
+            masses,
+            bonds,
+            residues,
+        })
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let data_str = fs::read_to_string(path)?;
+        Self::new(&data_str)
+    }
+}
+
+pub fn load_prmtop(path: &Path) -> io::Result<(Vec<AtomGeneric>, ForceFieldParams)> {
+    let mut file = File::open(path)?;
+    let mut buf = String::new();
+    file.read_to_string(&mut buf)?;
+
+    struct Block {
+        data: Vec<String>,
     }
+    let blocks: HashMap<String, Block> = parse_sections(&buf)?
+        .into_iter()
+        .map(|(k, v)| {
+            let data = v
+                .iter()
+                .map(|v| v.trim().to_owned())
+                .filter(|v| !v.is_empty())
+                .collect();
+            (k, Block { data })
+        })
+        .collect();
 
     let get_i = |s: &str| -> io::Result<i32> {
         s.parse::<i32>()
@@ -14,19 +14,29 @@ use std::{
 use bio_apis::{drugbank, pdbe, pubchem, pubchem::StructureSearchNamespace};
 use lin_alg::f64::Vec3;
 use na_seq::Element;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 use crate::{
     AtomGeneric, BondGeneric, BondType, ChainGeneric, Mol2, PharmacophoreFeatureGeneric,
-    PharmacophoreTypeGeneric, ResidueEnd, ResidueGeneric, ResidueType,
+    PharmacophoreTypeGeneric, ResidueEnd, ResidueGeneric, ResidueType, element_from_symbol,
+    element_symbol,
 };
 
 /// It's a format used for small organic molecules, and is a common format on online databases
 /// like PubChem and Drugbank. This struct will likely
 /// be used as an intermediate format, and converted to something application-specific.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Sdf {
     pub ident: String,
+    /// Data fields, keyed by tag; if a tag is repeated, this holds its last value. This is what's
+    /// saved: Edit, add, or remove fields here.
     pub metadata: HashMap<String, String>,
+    /// Data fields (`> <TAG>` blocks), as (tag, value), in file order, including repeated tags.
+    /// When saving, this sets the order of fields, and the values of earlier repeats of a tag;
+    /// values from `metadata` take precedence. Tags not in `metadata` aren't written.
+    pub data_fields: Vec<(String, String)>,
     pub atoms: Vec<AtomGeneric>,
     pub bonds: Vec<BondGeneric>,
     pub chains: Vec<ChainGeneric>,
@@ -44,6 +54,10 @@ pub enum SdfFormat {
     V3000,
 }
 
+/// The OpenFF data field for partial charges.
+const PARTIAL_CHARGE_TAG: &str = "atom.dprop.PartialCharge";
+const PHARMACOPHORE_TAG: &str = "PUBCHEM_PHARMACOPHORE_FEATURES";
+
 const PERIODIC_TABLE_SYMBOLS: &[&str] = &[
     "H", "He", "Li", "Be", "B", "C", "N", "O", "F", "Ne", "Na", "Mg", "Al", "Si", "P", "S", "Cl",
     "Ar", "K", "Ca", "Sc", "Ti", "V", "Cr", "Mn", "Fe", "Co", "Ni", "Cu", "Zn", "Ga", "Ge", "As",
@@ -59,7 +73,7 @@ const PERIODIC_TABLE_SYMBOLS: &[&str] = &[
 /// generic atom representation cannot retain the original symbol in that case, so it uses
 /// `Element::Other`; malformed and SDF query-atom symbols still produce an error.
 fn parse_sdf_element(symbol: &str) -> io::Result<Element> {
-    match Element::from_letter(symbol) {
+    match element_from_symbol(symbol) {
         Ok(element) => Ok(element),
         Err(_)
             if PERIODIC_TABLE_SYMBOLS
@@ -111,6 +125,63 @@ fn parse_v2000_counts(line: &str) -> io::Result<(usize, usize)> {
     Ok((n_atoms, n_bonds))
 }
 
+/// Parse the legacy charge field of a V2000 atom line. This is a code, vice the charge itself. 4
+/// is a doublet radical, vice a charge.
+fn parse_v2000_atom_charge(line: &str) -> Option<i8> {
+    let cols: Vec<&str> = line.split_whitespace().collect();
+    let code = if cols.len() >= 6 {
+        cols[5].parse::<u8>().ok()
+    } else {
+        line.get(36..39).and_then(|c| c.trim().parse::<u8>().ok())
+    };
+
+    match code? {
+        1 => Some(3),
+        2 => Some(2),
+        3 => Some(1),
+        5 => Some(-1),
+        6 => Some(-2),
+        7 => Some(-3),
+        _ => None,
+    }
+}
+
+/// Apply `M  CHG` property lines in `lines` to `atoms`. Per the spec, if any are present, they
+/// supersede the atom block's legacy charge field for all atoms.
+fn apply_v2000_charges(lines: &[&str], atoms: &mut [AtomGeneric]) {
+    let mut charges = Vec::new();
+    for line in lines {
+        if line.trim() == "M  END" {
+            break;
+        }
+        let Some(entries) = line.strip_prefix("M  CHG") else {
+            continue;
+        };
+
+        let vals: Vec<i32> = entries
+            .split_whitespace()
+            .filter_map(|v| v.parse().ok())
+            .collect();
+        // The first value is the entry count.
+        for pair in vals.get(1..).unwrap_or_default().chunks_exact(2) {
+            charges.push((pair[0], pair[1]));
+        }
+    }
+
+    if charges.is_empty() {
+        return;
+    }
+
+    for atom in atoms.iter_mut() {
+        atom.formal_charge = None;
+    }
+    for (sn, charge) in charges {
+        if sn >= 1 && sn as usize <= atoms.len() && charge != 0 {
+            atoms[sn as usize - 1].formal_charge = Some(charge as i8);
+        }
+    }
+}
+
 fn parse_v2000_atom_fields(line: &str) -> io::Result<(f64, f64, f64, &str)> {
     let cols: Vec<&str> = line.split_whitespace().collect();
     if cols.len() >= 4
@@ -317,6 +388,7 @@ fn parse_v2000_ctab(
             serial_number: atom_index as u32 + 1,
             posit: Vec3 { x, y, z },
             element: parse_sdf_element(element)?,
+            formal_charge: parse_v2000_atom_charge(line),
             hetero: true,
             ..Default::default()
         });
@@ -336,6 +408,8 @@ fn parse_v2000_ctab(
         });
     }
 
+    apply_v2000_charges(&lines[last_bond_line..], &mut atoms);
+
     Ok((atoms, bonds, last_bond_line))
 }
 /// Parse V3000 atom and bond blocks from the CTAB section.
@@ -415,10 +489,17 @@ fn parse_v3000_ctab(lines: &[&str]) -> io::Result<(Vec<AtomGeneric>, Vec<BondGen
             .parse::<f64>()
             .map_err(|_| io::Error::new(ErrorKind::InvalidData, "V3000: bad Z coordinate"))?;
 
+        let formal_charge = cols[7.min(cols.len())..]
+            .iter()
+            .find_map(|c| c.strip_prefix("CHG="))
+            .and_then(|q| q.parse::<i8>().ok())
+            .filter(|q| *q != 0);
+
         atoms.push(AtomGeneric {
             serial_number,
             posit: Vec3 { x, y, z },
             element: parse_sdf_element(element_str)?,
+            formal_charge,
             hetero: true,
             ..Default::default()
         });
@@ -470,6 +551,13 @@ fn parse_v3000_ctab(lines: &[&str]) -> io::Result<(Vec<AtomGeneric>, Vec<BondGen
     Ok((atoms, bonds))
 }
 
+/// The data fields following the connection table. See [`Sdf`] for the fields.
+struct DataSection {
+    metadata: HashMap<String, String>,
+    data_fields: Vec<(String, String)>,
+    pharmacophore_features: Vec<PharmacophoreFeatureGeneric>,
+}
+
 /// Parse metadata data fields (the `> <KEY>` sections after `M  END`) and apply any
 /// partial-charge fields to `atoms` in place.
 ///
@@ -479,8 +567,9 @@ fn parse_metadata_section(
     lines: &[&str],
     atoms: &mut Vec<AtomGeneric>,
     fallback_start: usize,
-) -> io::Result<(HashMap<String, String>, Vec<PharmacophoreFeatureGeneric>)> {
+) -> io::Result<DataSection> {
     let mut metadata: HashMap<String, String> = HashMap::new();
+    let mut data_fields = Vec::new();
     let mut pharmacophore_features: Vec<PharmacophoreFeatureGeneric> = Vec::new();
 
     // Look for molecule identifiers in the data fields.
@@ -527,7 +616,9 @@ fn parse_metadata_section(
                 rows_pharm.push(v_trim);
                 idx += 1;
             }
-            if key == "PUBCHEM_PHARMACOPHORE_FEATURES" {
+            data_fields.push((key.to_string(), rows_pharm.join("\n")));
+
+            if key == PHARMACOPHORE_TAG {
                 match parse_pharmacophore_features(&rows_pharm) {
                     Ok(v) => pharmacophore_features = v,
                     Err(e) => {
@@ -540,7 +631,7 @@ fn parse_metadata_section(
             }
 
             // OpenFF format.
-            if key == "atom.dprop.PartialCharge" {
+            if key == PARTIAL_CHARGE_TAG {
                 let joined = rows_pharm.join(" ");
                 let charges: Vec<&str> = joined.split_whitespace().collect();
 
@@ -589,7 +680,11 @@ fn parse_metadata_section(
         idx += 1;
     }
 
-    Ok((metadata, pharmacophore_features))
+    Ok(DataSection {
+        metadata,
+        data_fields,
+        pharmacophore_features,
+    })
 }
 
 impl Sdf {
@@ -636,8 +731,11 @@ impl Sdf {
             }
         };
 
-        let (metadata, pharmacophore_features) =
-            parse_metadata_section(&lines, &mut atoms, fallback_start)?;
+        let DataSection {
+            metadata,
+            data_fields,
+            pharmacophore_features,
+        } = parse_metadata_section(&lines, &mut atoms, fallback_start)?;
 
         let atom_sns: Vec<_> = atoms.iter().map(|a| a.serial_number).collect();
 
@@ -649,6 +747,9 @@ impl Sdf {
             res_type: ResidueType::Other("Unknown".to_string()),
             atom_sns: atom_sns.clone(),
             end: ResidueEnd::Hetero,
+            entity_id: None,
+            auth_serial_number: None,
+            insertion_code: None,
         });
 
         chains.push(ChainGeneric {
@@ -660,6 +761,7 @@ impl Sdf {
         Ok(Self {
             ident,
             metadata,
+            data_fields,
             atoms,
             chains,
             residues,
@@ -705,7 +807,7 @@ impl Sdf {
                     let x = atom.posit.x;
                     let y = atom.posit.y;
                     let z = atom.posit.z;
-                    let symbol = atom.element.to_letter();
+                    let symbol = element_symbol(atom.element);
 
                     writeln!(
                         file,
@@ -735,6 +837,26 @@ impl Sdf {
                     )?;
                 }
 
+                // Formal charges supersede the atom block's legacy charge field, which we leave at 0.
+                // The spec allows up to 8 entries per line.
+                let charged: Vec<_> = self
+                    .atoms
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, a)| match a.formal_charge {
+                        Some(q) if q != 0 => Some((i + 1, q)),
+                        _ => None,
+                    })
+                    .collect();
+
+                for chunk in charged.chunks(8) {
+                    write!(file, "M  CHG{:>3}", chunk.len())?;
+                    for (i, q) in chunk {
+                        write!(file, " {i:>3} {q:>3}")?;
+                    }
+                    writeln!(file)?;
+                }
+
                 writeln!(file, "M  END")?;
             }
 
@@ -748,16 +870,20 @@ impl Sdf {
 
                 writeln!(file, "M  V30 BEGIN ATOM")?;
                 for (i, atom) in self.atoms.iter().enumerate() {
-                    // Format: "M  V30 idx elem x y z map_no"
-                    writeln!(
+                    // Format: "M  V30 idx elem x y z map_no [CHG=q]"
+                    write!(
                         file,
                         "M  V30 {} {} {:.6} {:.6} {:.6} 0",
                         i + 1,
-                        atom.element.to_letter(),
+                        element_symbol(atom.element),
                         atom.posit.x,
                         atom.posit.y,
                         atom.posit.z,
                     )?;
+                    match atom.formal_charge {
+                        Some(q) if q != 0 => writeln!(file, " CHG={q}")?,
+                        _ => writeln!(file)?,
+                    }
                 }
                 writeln!(file, "M  V30 END ATOM")?;
 
@@ -780,11 +906,6 @@ impl Sdf {
             }
         }
 
-        // Metadata data fields are format-agnostic — they follow M  END in both V2000 and V3000.
-        for m in &self.metadata {
-            write_metadata(m.0, m.1, file)?;
-        }
-
         // If partial charges are available, write them to metadata. This is an OpenFF convention.
         let mut partial_charges = Vec::new();
         let mut all_partial_charges_present = true;
@@ -798,16 +919,64 @@ impl Sdf {
             }
         }
 
-        if all_partial_charges_present {
+        let mut charge_str = all_partial_charges_present.then(|| {
             let charges_formated: Vec<_> =
                 partial_charges.iter().map(|q| format!("{q:.8}")).collect();
-            let charge_str = charges_formated.join(" ");
-            write_metadata("atom.dprop.PartialCharge", &charge_str, file)?;
+            charges_formated.join(" ")
+        });
+
+        let mut pharmacophore_str = (!self.pharmacophore_features.is_empty())
+            .then(|| format_pharmacophore_features(&self.pharmacophore_features));
+
+        // Metadata data fields are format-agnostic — they follow M  END in both V2000 and V3000.
+        // Fields we generate from atoms and features are written in place of their originals.
+        let last_occurrence: HashMap<&str, usize> = self
+            .data_fields
+            .iter()
+            .enumerate()
+            .map(|(i, (k, _))| (k.as_str(), i))
+            .collect();
+
+        for (i, (key, val)) in self.data_fields.iter().enumerate() {
+            let generated = match key.as_str() {
+                PARTIAL_CHARGE_TAG if all_partial_charges_present => Some(&mut charge_str),
+                PHARMACOPHORE_TAG if !self.pharmacophore_features.is_empty() => {
+                    Some(&mut pharmacophore_str)
+                }
+                _ => None,
+            };
+
+            match generated {
+                // Only write each generated field once, even if the original repeated it.
+                Some(v) => {
+                    if let Some(v) = v.take() {
+                        write_metadata(key, &v, file)?;
+                    }
+                }
+                // `metadata` holds the last value of repeated tags; earlier ones are written as
+                // they were. Tags removed from it aren't written.
+                None => match self.metadata.get(key) {
+                    Some(v) if last_occurrence[key.as_str()] == i => write_metadata(key, v, file)?,
+                    Some(_) => write_metadata(key, val, file)?,
+                    None => (),
+                },
+            }
         }
 
-        if !self.pharmacophore_features.is_empty() {
-            let v = format_pharmacophore_features(&self.pharmacophore_features);
-            write_metadata("PUBCHEM_PHARMACOPHORE_FEATURES", &v, file)?;
+        for (key, val) in &self.metadata {
+            let generated = (key == PARTIAL_CHARGE_TAG && all_partial_charges_present)
+                || (key == PHARMACOPHORE_TAG && !self.pharmacophore_features.is_empty());
+
+            if !generated && !self.data_fields.iter().any(|(k, _)| k == key) {
+                write_metadata(key, val, file)?;
+            }
+        }
+
+        if let Some(v) = charge_str {
+            write_metadata(PARTIAL_CHARGE_TAG, &v, file)?;
+        }
+        if let Some(v) = pharmacophore_str {
+            write_metadata(PHARMACOPHORE_TAG, &v, file)?;
         }
 
         // End of this molecule record in SDF
@@ -898,10 +1067,11 @@ impl From<Mol2> for Sdf {
         Self {
             ident: m.ident.clone(),
             metadata: m.metadata.clone(),
+            data_fields: Vec::new(),
             atoms: m.atoms.clone(),
             bonds: m.bonds.clone(),
-            chains: Vec::new(),
-            residues: Vec::new(),
+            chains: m.chains.clone(),
+            residues: m.residues.clone(),
             pharmacophore_features: Vec::new(),
         }
     }
@@ -0,0 +1,141 @@
+//! Serde adapters for fields with types from `lin_alg` and `na_seq`, which don't implement
+//! `Serialize` and `Deserialize`. Used with `#[serde(with = "...")]`.
+
+use lin_alg::f64::Vec3;
+use na_seq::{AaIdent, AminoAcid, AtomTypeInRes, Element};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+use crate::{element_from_symbol, element_symbol, map::UnitCell};
+
+/// As `[x, y, z]`.
+pub(crate) mod vec3 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(v: &Vec3, s: S) -> Result<S::Ok, S::Error> {
+        [v.x, v.y, v.z].serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec3, D::Error> {
+        let [x, y, z] = <[f64; 3]>::deserialize(d)?;
+        Ok(Vec3::new(x, y, z))
+    }
+}
+
+pub(crate) mod vec3_opt {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(v: &Option<Vec3>, s: S) -> Result<S::Ok, S::Error> {
+        v.map(|v| [v.x, v.y, v.z]).serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Vec3>, D::Error> {
+        let v = Option::<[f64; 3]>::deserialize(d)?;
+        Ok(v.map(|[x, y, z]| Vec3::new(x, y, z)))
+    }
+}
+
+/// E.g. for a set of positions per model.
+pub(crate) mod vec3_nested {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(v: &[Vec<Vec3>], s: S) -> Result<S::Ok, S::Error> {
+        let v: Vec<Vec<[f64; 3]>> = v
+            .iter()
+            .map(|p| p.iter().map(|p| [p.x, p.y, p.z]).collect())
+            .collect();
+        v.serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<Vec<Vec3>>, D::Error> {
+        let v = Vec::<Vec<[f64; 3]>>::deserialize(d)?;
+        Ok(v.into_iter()
+            .map(|p| p.into_iter().map(|[x, y, z]| Vec3::new(x, y, z)).collect())
+            .collect())
+    }
+}
+
+/// As its symbol, e.g. "Fe". `Other` is "X".
+pub(crate) mod element {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(el: &Element, s: S) -> Result<S::Ok, S::Error> {
+        element_symbol(*el).serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Element, D::Error> {
+        let symbol = String::deserialize(d)?;
+        if symbol == "X" {
+            return Ok(Element::Other);
+        }
+        element_from_symbol(&symbol).map_err(Error::custom)
+    }
+}
+
+/// Hetero labels are tagged, as they can't be distinguished from others by the label alone.
+#[derive(Serialize, Deserialize)]
+enum TypeInResRepr {
+    Standard(String),
+    Hetero(String),
+}
+
+pub(crate) mod type_in_res {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(v: &Option<AtomTypeInRes>, s: S) -> Result<S::Ok, S::Error> {
+        let v = v.as_ref().map(|v| match v {
+            AtomTypeInRes::Hetero(name) => TypeInResRepr::Hetero(name.clone()),
+            _ => TypeInResRepr::Standard(v.to_string()),
+        });
+        v.serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<AtomTypeInRes>, D::Error> {
+        Option::<TypeInResRepr>::deserialize(d)?
+            .map(|v| match v {
+                TypeInResRepr::Standard(name) => name.parse().map_err(Error::custom),
+                TypeInResRepr::Hetero(name) => Ok(AtomTypeInRes::Hetero(name)),
+            })
+            .transpose()
+    }
+}
+
+/// As its three-letter code, e.g. "Ala".
+pub(crate) mod amino_acid {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(aa: &AminoAcid, s: S) -> Result<S::Ok, S::Error> {
+        aa.to_str(AaIdent::ThreeLetters).serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<AminoAcid, D::Error> {
+        String::deserialize(d)?.parse().map_err(Error::custom)
+    }
+}
+
+/// As `[a, b, c, α, β, γ]`, with angles in degrees. The orthogonalization matrices are rebuilt on
+/// deserialization.
+pub(crate) mod unit_cell_opt {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(v: &Option<UnitCell>, s: S) -> Result<S::Ok, S::Error> {
+        v.as_ref()
+            .map(|c| {
+                [
+                    c.a,
+                    c.b,
+                    c.c,
+                    c.alpha.to_degrees(),
+                    c.beta.to_degrees(),
+                    c.gamma.to_degrees(),
+                ]
+            })
+            .serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<UnitCell>, D::Error> {
+        let v = Option::<[f64; 6]>::deserialize(d)?;
+        v.map(|[a, b, c, α, β, γ]| UnitCell::try_new(a, b, c, α, β, γ))
+            .transpose()
+            .map_err(D::Error::custom)
+    }
+}
@@ -0,0 +1,184 @@
+//! Crystallographic symmetry operators, e.g. from mmCIF's `_space_group_symop` category, and
+//! generating symmetry mates of a structure from them. Useful for analyzing crystal contacts.
+
+use std::{io, io::ErrorKind};
+
+use lin_alg::f64::Vec3;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{AtomGeneric, UnitCell};
+
+/// A symmetry operator, acting on fractional coordinates: `x' = rot · x + trans`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SymOp {
+    /// Row-major.
+    pub rot: [[f64; 3]; 3],
+    /// Fractional.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_adapters::vec3"))]
+    pub trans: Vec3,
+}
+
+impl SymOp {
+    pub fn identity() -> Self {
+        Self {
+            rot: [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]],
+            trans: Vec3::new_zero(),
+        }
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Self::identity()
+    }
+
+    /// Apply to a point in fractional coordinates.
+    pub fn apply(&self, frac: Vec3) -> Vec3 {
+        let r = &self.rot;
+        Vec3::new(
+            r[0][0] * frac.x + r[0][1] * frac.y + r[0][2] * frac.z + self.trans.x,
+            r[1][0] * frac.x + r[1][1] * frac.y + r[1][2] * frac.z + self.trans.y,
+            r[2][0] * frac.x + r[2][1] * frac.y + r[2][2] * frac.z + self.trans.z,
+        )
+    }
+}
+
+/// Parse a symmetry operator in the "xyz" form used by mmCIF, e.g. "-x,y+1/2,-z", "1/2+X,1/2-Y,Z",
+/// or "x-y,x,z+1/6". Case and whitespace are ignored.
+pub fn parse_symop_xyz(s: &str) -> io::Result<SymOp> {
+    let err = || {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            format!("Invalid symmetry operator: {s}"),
+        )
+    };
+
+    let s: String = s
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '\'' && *c != '"')
+        .collect::<String>()
+        .to_lowercase();
+
+    let components: Vec<_> = s.split(',').collect();
+    if components.len() != 3 {
+        return Err(err());
+    }
+
+    let mut rot = [[0.; 3]; 3];
+    let mut trans = [0.; 3];
+
+    for (i, comp) in components.iter().enumerate() {
+        if comp.is_empty() {
+            return Err(err());
+        }
+
+        // Split into signed terms, e.g. "-x", "+1/2", "+2*y".
+        let mut terms = Vec::new();
+        let mut start = 0;
+        for (j, c) in comp.char_indices() {
+            if (c == '+' || c == '-') && j > start {
+                terms.push(&comp[start..j]);
+                start = j;
+            }
+        }
+        terms.push(&comp[start..]);
+
+        for term in terms {
+            let (sign, body) = match term.as_bytes()[0] {
+                b'-' => (-1., &term[1..]),
+                b'+' => (1., &term[1..]),
+                _ => (1., term),
+            };
+
+            let var = body.chars().last().and_then(|c| match c {
+                'x' => Some(0),
+                'y' => Some(1),
+                'z' => Some(2),
+                _ => None,
+            });
+
+            match var {
+                Some(col) => {
+                    let coef = body[..body.len() - 1].trim_end_matches('*');
+                    let coef = if coef.is_empty() {
+                        1.
+                    } else {
+                        parse_number(coef).ok_or_else(err)?
+                    };
+                    rot[i][col] += sign * coef;
+                }
+                None => trans[i] += sign * parse_number(body).ok_or_else(err)?,
+            }
+        }
+    }
+
+    Ok(SymOp {
+        rot,
+        trans: Vec3::new(trans[0], trans[1], trans[2]),
+    })
+}
+
+/// A decimal, or a fraction like "1/2".
+fn parse_number(s: &str) -> Option<f64> {
+    match s.split_once('/') {
+        Some((num, denom)) => {
+            let denom: f64 = denom.parse().ok()?;
+            if denom == 0. {
+                return None;
+            }
+            Some(num.parse::<f64>().ok()? / denom)
+        }
+        None => s.parse().ok(),
+    }
+}
+
+/// Generate symmetry mates of a set of atoms, e.g. for crystal contact analysis. Each operator is
+/// applied, followed by lattice translations of up to ± `ranges` unit cells along a, b, and c.
+/// Returns one atom list per copy, ordered by operator, then translation. The copy from the
+/// identity operator without translation is the input itself.
+///
+/// Operators act on fractional coordinates; atom positions are converted using `cell`. Other atom
+/// properties, including serial numbers, are unchanged.
+pub fn expand_symmetry(
+    atoms: &[AtomGeneric],
+    cell: &UnitCell,
+    ops: &[SymOp],
+    ranges: (i32, i32, i32),
+) -> Vec<Vec<AtomGeneric>> {
+    let (ra, rb, rc) = (ranges.0.abs(), ranges.1.abs(), ranges.2.abs());
+
+    let frac: Vec<_> = atoms
+        .iter()
+        .map(|a| cell.cartesian_to_fractional(a.posit))
+        .collect();
+
+    let mut result = Vec::new();
+    for op in ops {
+        let frac_op: Vec<_> = frac.iter().map(|f| op.apply(*f)).collect();
+
+        for i in -ra..=ra {
+            for j in -rb..=rb {
+                for k in -rc..=rc {
+                    if op.is_identity() && i == 0 && j == 0 && k == 0 {
+                        result.push(atoms.to_vec());
+                        continue;
+                    }
+
+                    let shift = Vec3::new(i as f64, j as f64, k as f64);
+                    let copy = atoms
+                        .iter()
+                        .zip(&frac_op)
+                        .map(|(atom, f)| AtomGeneric {
+                            posit: cell.fractional_to_cartesian(*f + shift),
+                            ..atom.clone()
+                        })
+                        .collect();
+
+                    result.push(copy);
+                }
+            }
+        }
+    }
+
+    result
+}
@@ -0,0 +1,99 @@
+//! Format-independent trajectory analysis. DCD trajectories and multi-frame XYZ files implement
+//! [`Trajectory`], and share RMSD, average structure, and RMSF computations.
+
+use lin_alg::f64::Vec3;
+
+use crate::{Xyz, dcd::DcdTrajectory};
+
+/// A sequence of frames, each containing positions for the same set of atoms, in the same order.
+///
+/// Frame coordinates are returned as owned `f64` values: DCD stores `f32` coordinates, and XYZ
+/// frames store positions inside their atoms, so neither can lend a slice directly.
+pub trait Trajectory {
+    fn n_frames(&self) -> usize;
+
+    /// Atom positions for frame `i`, in Å. Panics if `i` is out of bounds.
+    fn frame_coords(&self, i: usize) -> Vec<Vec3>;
+
+    /// The root-mean-square deviation of each frame from frame `reference`, in Å. This doesn't
+    /// superimpose frames first; if the system translates or rotates, align the trajectory prior.
+    fn rmsd_series(&self, reference: usize) -> Vec<f64> {
+        let ref_coords = self.frame_coords(reference);
+
+        (0..self.n_frames())
+            .map(|i| {
+                let coords = self.frame_coords(i);
+                if coords.is_empty() {
+                    return 0.;
+                }
+
+                let sum_sq: f64 = coords
+                    .iter()
+                    .zip(&ref_coords)
+                    .map(|(a, b)| (*a - *b).magnitude_squared())
+                    .sum();
+
+                (sum_sq / coords.len() as f64).sqrt()
+            })
+            .collect()
+    }
+
+    /// The mean position of each atom across all frames. Empty if there are no frames.
+    fn average_coords(&self) -> Vec<Vec3> {
+        let n = self.n_frames();
+        if n == 0 {
+            return Vec::new();
+        }
+
+        let mut result = self.frame_coords(0);
+        for i in 1..n {
+            for (sum, p) in result.iter_mut().zip(self.frame_coords(i)) {
+                *sum += p;
+            }
+        }
+
+        for p in &mut result {
+            *p /= n as f64;
+        }
+        result
+    }
+
+    /// The root-mean-square fluctuation of each atom about its average position, in Å.
+    fn rmsf(&self) -> Vec<f64> {
+        let n = self.n_frames();
+        let avg = self.average_coords();
+
+        let mut sum_sq = vec![0.; avg.len()];
+        for i in 0..n {
+            for (s, (p, p_avg)) in sum_sq.iter_mut().zip(self.frame_coords(i).iter().zip(&avg)) {
+                *s += (*p - *p_avg).magnitude_squared();
+            }
+        }
+
+        sum_sq.into_iter().map(|s| (s / n as f64).sqrt()).collect()
+    }
+}
+
+impl Trajectory for DcdTrajectory {
+    fn n_frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    fn frame_coords(&self, i: usize) -> Vec<Vec3> {
+        self.frames[i]
+            .atom_posits
+            .iter()
+            .map(|p| Vec3::new(p.x as f64, p.y as f64, p.z as f64))
+            .collect()
+    }
+}
+
+impl Trajectory for Vec<Xyz> {
+    fn n_frames(&self) -> usize {
+        self.len()
+    }
+
+    fn frame_coords(&self, i: usize) -> Vec<Vec3> {
+        self[i].atoms.iter().map(|a| a.posit).collect()
+    }
+}
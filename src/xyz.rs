@@ -1,21 +1,204 @@
 use std::{
     fs,
     fs::File,
-    io::{self, ErrorKind, Write},
+    io::{self, BufRead, BufReader, ErrorKind, Write},
     path::Path,
+    sync::LazyLock,
 };
 
 use lin_alg::f64::Vec3;
+use regex::Regex;
 
-use crate::AtomGeneric;
+use crate::{AtomGeneric, Element, UnitCell, element_from_symbol, element_symbol};
 
 #[derive(Clone, Debug)]
 pub struct Xyz {
     pub atoms: Vec<AtomGeneric>,
     pub comment: String,
+    /// From the `Lattice` key of an [extended XYZ](https://github.com/libAtoms/extxyz) comment line.
+    pub cell: Option<UnitCell>,
+    /// Net charge, in elementary charge units. Parsed from the comment line if present, as is
+    /// common for XYZ files used as quantum chemistry inputs.
+    pub charge: Option<i32>,
+    /// Spin multiplicity (2S + 1). Parsed from the comment line if present.
+    pub multiplicity: Option<u32>,
+}
+
+static CHARGE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\bcharge\s*[=:]\s*([+-]?\d+)").unwrap());
+static MULT_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\b(?:mult|multiplicity)\s*[=:]\s*(\d+)").unwrap());
+static EXTENDED_KV_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)\b(lattice|properties)\s*=\s*(?:"([^"]*)"|(\S+))"#).unwrap()
+});
+
+/// Parse charge and multiplicity from an XYZ comment line. Supports a bare "charge mult" pair
+/// (e.g. "0 1") at the start of the line, and key-value forms like "charge=0 mult=1".
+fn parse_charge_mult(comment: &str) -> (Option<i32>, Option<u32>) {
+    let charge = CHARGE_RE
+        .captures(comment)
+        .and_then(|c| c[1].parse::<i32>().ok());
+    let multiplicity = MULT_RE
+        .captures(comment)
+        .and_then(|c| c[1].parse::<u32>().ok());
+
+    if charge.is_some() || multiplicity.is_some() {
+        return (charge, multiplicity);
+    }
+
+    let mut tokens = comment.split_whitespace();
+    if let (Some(c), Some(m)) = (tokens.next(), tokens.next())
+        && let (Ok(charge), Ok(mult)) = (c.parse::<i32>(), m.parse::<u32>())
+        && mult >= 1
+    {
+        return (Some(charge), Some(mult));
+    }
+
+    (None, None)
+}
+
+/// One entry of an extended XYZ `Properties` schema, e.g. `pos:R:3`.
+struct XyzProperty {
+    name: String,
+    num_cols: usize,
+}
+
+/// Parse the `Properties` and `Lattice` keys from an extended XYZ comment line. e.g.
+/// `Lattice="5.0 0.0 0.0 0.0 5.0 0.0 0.0 0.0 5.0" Properties=species:S:1:pos:R:3:charge:R:1`.
+/// Without `Properties`, this is the plain XYZ layout: `species:S:1:pos:R:3`.
+fn parse_extended_comment(comment: &str) -> io::Result<(Vec<XyzProperty>, Option<UnitCell>)> {
+    let mut properties = vec![
+        XyzProperty {
+            name: "species".to_owned(),
+            num_cols: 1,
+        },
+        XyzProperty {
+            name: "pos".to_owned(),
+            num_cols: 3,
+        },
+    ];
+    let mut cell = None;
+
+    for caps in EXTENDED_KV_RE.captures_iter(comment) {
+        let val = caps.get(2).or_else(|| caps.get(3)).unwrap().as_str();
+
+        if caps[1].eq_ignore_ascii_case("lattice") {
+            let v: Vec<f64> = val
+                .split_whitespace()
+                .map(|v| v.parse())
+                .collect::<Result<_, _>>()
+                .map_err(|_| io::Error::new(ErrorKind::InvalidData, "Invalid Lattice values"))?;
+
+            if v.len() != 9 {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Lattice must have 9 values; found {}", v.len()),
+                ));
+            }
+
+            cell = Some(UnitCell::from_vectors(
+                Vec3::new(v[0], v[1], v[2]),
+                Vec3::new(v[3], v[4], v[5]),
+                Vec3::new(v[6], v[7], v[8]),
+            ));
+        } else {
+            let fields: Vec<&str> = val.split(':').collect();
+            if !fields.len().is_multiple_of(3) {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Invalid Properties schema: {val}"),
+                ));
+            }
+
+            properties = fields
+                .chunks_exact(3)
+                .map(|f| {
+                    let num_cols = f[2].parse().map_err(|_| {
+                        io::Error::new(
+                            ErrorKind::InvalidData,
+                            format!("Invalid column count in Properties: {}", f[2]),
+                        )
+                    })?;
+
+                    Ok(XyzProperty {
+                        name: f[0].to_lowercase(),
+                        num_cols,
+                    })
+                })
+                .collect::<io::Result<_>>()?;
+        }
+    }
+
+    Ok((properties, cell))
+}
+
+/// Parse an atom line, with columns laid out per `properties`. `line_num` is for error messages.
+fn parse_atom_line(
+    line: &str,
+    line_num: usize,
+    properties: &[XyzProperty],
+) -> io::Result<AtomGeneric> {
+    let cols: Vec<&str> = line.split_whitespace().collect();
+    let mut atom = AtomGeneric::default();
+    let mut col = 0;
+
+    for prop in properties {
+        let vals = cols.get(col..col + prop.num_cols).ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Missing {} on atom line {}", prop.name, line_num),
+            )
+        })?;
+        col += prop.num_cols;
+
+        let parse_f = |v: &str| {
+            v.parse::<f64>().map_err(|_| {
+                io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Invalid {} on atom line {}", prop.name, line_num),
+                )
+            })
+        };
+        let parse_vec = || -> io::Result<Vec3> {
+            if vals.len() != 3 {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{} must have 3 columns", prop.name),
+                ));
+            }
+            Ok(Vec3::new(
+                parse_f(vals[0])?,
+                parse_f(vals[1])?,
+                parse_f(vals[2])?,
+            ))
+        };
+
+        match prop.name.as_str() {
+            "species" | "element" => {
+                atom.element = match vals[0].parse::<u8>() {
+                    Ok(z) => Element::from_atomic_number(z)?,
+                    Err(_) => element_from_symbol(vals[0])?,
+                };
+            }
+            "z" => atom.element = Element::from_atomic_number(parse_f(vals[0])? as u8)?,
+            "pos" => atom.posit = parse_vec()?,
+            "charge" | "charges" | "initial_charges" => {
+                atom.partial_charge = Some(parse_f(vals[0])? as f32);
+            }
+            "forces" | "force" => atom.force = Some(parse_vec()?),
+            "velocities" | "velocity" | "velo" | "vel" => atom.velocity = Some(parse_vec()?),
+            _ => (),
+        }
+    }
+
+    Ok(atom)
 }
 
 impl Xyz {
+    /// Parse plain, or [extended XYZ](https://github.com/libAtoms/extxyz), e.g. as exported by ASE.
+    /// For extended XYZ, columns are read according to the comment line's `Properties`; position,
+    /// species, charge (into `partial_charge`), force, and velocity columns are loaded, and others
+    /// skipped.
     pub fn new(text: &str) -> io::Result<Self> {
         let lines: Vec<&str> = text.lines().collect();
 
@@ -27,6 +210,9 @@ impl Xyz {
         }
 
         let comment = lines[1].to_string();
+        let (charge, multiplicity) = parse_charge_mult(&comment);
+
+        let (properties, cell) = parse_extended_comment(&comment)?;
 
         let mut atoms = Vec::new();
         for (i, line) in lines.iter().enumerate() {
@@ -34,65 +220,16 @@ impl Xyz {
                 continue;
             }
 
-            let mut parts = line.split_whitespace();
-
-            let el_str = parts.next().ok_or_else(|| {
-                io::Error::new(
-                    ErrorKind::InvalidData,
-                    format!("Missing element symbol on atom line {}", i),
-                )
-            })?;
-
-            let x_str = parts.next().ok_or_else(|| {
-                io::Error::new(
-                    ErrorKind::InvalidData,
-                    format!("Missing x coordinate on atom line {}", i),
-                )
-            })?;
-            let y_str = parts.next().ok_or_else(|| {
-                io::Error::new(
-                    ErrorKind::InvalidData,
-                    format!("Missing y coordinate on atom line {}", i),
-                )
-            })?;
-            let z_str = parts.next().ok_or_else(|| {
-                io::Error::new(
-                    ErrorKind::InvalidData,
-                    format!("Missing z coordinate on atom line {}", i),
-                )
-            })?;
-
-            let x: f64 = x_str.parse().map_err(|_| {
-                io::Error::new(
-                    ErrorKind::InvalidData,
-                    format!("Invalid x coordinate on atom line {}", i),
-                )
-            })?;
-            let y: f64 = y_str.parse().map_err(|_| {
-                io::Error::new(
-                    ErrorKind::InvalidData,
-                    format!("Invalid y coordinate on atom line {}", i),
-                )
-            })?;
-            let z: f64 = z_str.parse().map_err(|_| {
-                io::Error::new(
-                    ErrorKind::InvalidData,
-                    format!("Invalid z coordinate on atom line {}", i),
-                )
-            })?;
-
-            // Adjust these two lines to match your actual types if needed:
-            let element = crate::Element::from_letter(el_str)?;
-            let posit = Vec3::new(x, y, z);
-
-            atoms.push(AtomGeneric {
-                element,
-                posit,
-                ..Default::default()
-            });
+            atoms.push(parse_atom_line(line, i, &properties)?);
         }
 
-        Ok(Self { atoms, comment })
+        Ok(Self {
+            atoms,
+            comment,
+            cell,
+            charge,
+            multiplicity,
+        })
     }
 
     pub fn load(path: &Path) -> io::Result<Self> {
@@ -113,7 +250,7 @@ impl Xyz {
             writeln!(
                 file,
                 "{:<2} {:>17.10} {:>17.10} {:>17.10}",
-                atom.element.to_letter(),
+                element_symbol(atom.element),
                 atom.posit.x,
                 atom.posit.y,
                 atom.posit.z
@@ -127,72 +264,131 @@ impl Xyz {
 /// xyz files can contain multiple sets, e.g. in a molecular dynamics
 /// trajectory.
 pub fn new_xyz_trajectory(text: &str) -> io::Result<Vec<Xyz>> {
-    let lines: Vec<&str> = text.lines().collect();
+    XyzTrajectoryReader::new(text.as_bytes()).collect()
+}
 
-    let mut items: Vec<Xyz> = Vec::new();
-    let mut i: usize = 0;
+/// Reads frames from a multi-frame XYZ file one at a time, instead of loading the whole
+/// trajectory into memory. Each call to `next` reads an atom count line, a comment line, and
+/// that many atom lines.
+///
+/// ```ignore
+/// for frame in XyzTrajectoryReader::open(path)? {
+///     let frame = frame?;
+/// }
+/// ```
+pub struct XyzTrajectoryReader<R: BufRead = BufReader<File>> {
+    r: R,
+    /// The number of lines read so far; for error messages.
+    line_num: usize,
+    done: bool,
+}
 
-    while i < lines.len() {
-        while i < lines.len() && lines[i].trim().is_empty() {
-            i += 1;
+impl XyzTrajectoryReader {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Ok(Self::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: BufRead> XyzTrajectoryReader<R> {
+    /// Create a reader from any buffered source, e.g. an in-memory string's bytes.
+    pub fn new(r: R) -> Self {
+        Self {
+            r,
+            line_num: 0,
+            done: false,
         }
-        if i >= lines.len() {
-            break;
+    }
+
+    /// The next line, without its line ending. `None` at EOF.
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        if self.r.read_line(&mut line)? == 0 {
+            return Ok(None);
         }
+        self.line_num += 1;
 
-        let n_atoms: usize = lines[i].trim().parse().map_err(|_| {
+        let len = line.trim_end_matches(['\n', '\r']).len();
+        line.truncate(len);
+        Ok(Some(line))
+    }
+
+    /// Read a frame. `None` if there are only blank lines left.
+    fn read_frame(&mut self) -> io::Result<Option<Xyz>> {
+        let count_line = loop {
+            match self.read_line()? {
+                Some(l) if l.trim().is_empty() => continue,
+                Some(l) => break l,
+                None => return Ok(None),
+            }
+        };
+        let start = self.line_num;
+
+        let n_atoms: usize = count_line.trim().parse().map_err(|_| {
             io::Error::new(
                 ErrorKind::InvalidData,
-                format!("Invalid atom count on line {}", i + 1),
+                format!("Invalid atom count on line {start}"),
             )
         })?;
         if n_atoms == 0 {
             return Err(io::Error::new(
                 ErrorKind::InvalidData,
-                format!("Invalid atom count (0) on line {}", i + 1),
+                format!("Invalid atom count (0) on line {start}"),
             ));
         }
 
-        let comment_line = lines.get(i + 1).ok_or_else(|| {
+        let comment_line = self.read_line()?.ok_or_else(|| {
             io::Error::new(
                 ErrorKind::InvalidData,
-                format!("Missing comment line after atom count on line {}", i + 1),
+                format!("Missing comment line after atom count on line {start}"),
             )
         })?;
 
-        let mut atom_lines: Vec<&str> = Vec::with_capacity(n_atoms);
-        let mut j = i + 2;
-        while atom_lines.len() < n_atoms && j < lines.len() {
-            let l = lines[j];
+        let (charge, multiplicity) = parse_charge_mult(&comment_line);
+        let (properties, cell) = parse_extended_comment(&comment_line)?;
+
+        let mut atoms = Vec::with_capacity(n_atoms);
+        while atoms.len() < n_atoms {
+            let Some(l) = self.read_line()? else {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "Unexpected EOF while reading atoms for frame starting at line {start} (expected {n_atoms}, got {})",
+                        atoms.len()
+                    ),
+                ));
+            };
             if !l.trim().is_empty() {
-                atom_lines.push(l);
+                atoms.push(parse_atom_line(&l, self.line_num, &properties)?);
             }
-            j += 1;
         }
 
-        if atom_lines.len() != n_atoms {
-            return Err(io::Error::new(
-                ErrorKind::InvalidData,
-                format!(
-                    "Unexpected EOF while reading atoms for frame starting at line {} (expected {}, got {})",
-                    i + 1,
-                    n_atoms,
-                    atom_lines.len()
-                ),
-            ));
+        Ok(Some(Xyz {
+            atoms,
+            comment: comment_line,
+            cell,
+            charge,
+            multiplicity,
+        }))
+    }
+}
+
+impl<R: BufRead> Iterator for XyzTrajectoryReader<R> {
+    type Item = io::Result<Xyz>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
         }
 
-        let frame_text = format!(
-            "{n_atoms}\n{comment}\n{atoms}\n",
-            comment = comment_line,
-            atoms = atom_lines.join("\n")
-        );
+        let result = self.read_frame();
 
-        items.push(Xyz::new(&frame_text)?);
-        i = j;
-    }
+        // Stop after an error; the stream position is no longer at a frame boundary.
+        if !matches!(result, Ok(Some(_))) {
+            self.done = true;
+        }
 
-    Ok(items)
+        result.transpose()
+    }
 }
 
 pub fn load_xyz_trajectory(path: &Path) -> io::Result<Vec<Xyz>> {
@@ -211,7 +407,7 @@ pub fn save_xyz_trajectory(items: &[Xyz], path: &Path) -> io::Result<()> {
             writeln!(
                 file,
                 "{:<2} {:>17.10} {:>17.10} {:>17.10}",
-                atom.element.to_letter(),
+                element_symbol(atom.element),
                 atom.posit.x,
                 atom.posit.y,
                 atom.posit.z
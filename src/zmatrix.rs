@@ -0,0 +1,247 @@
+//! For converting between Cartesian coordinates, and internal coordinates in the form of a
+//! [Z-matrix](https://en.wikipedia.org/wiki/Z-matrix_(chemistry)). This is useful for building
+//! quantum chemistry inputs (e.g. ORCA, Gaussian) in internal coordinates, and for setting up
+//! torsion scans.
+//!
+//! Each row defines an atom's position relative to atoms defined in earlier rows: a bond length,
+//! a bond angle, and a dihedral angle. Reference atoms are chosen using the bond graph where
+//! possible, so the internal coordinates correspond to chemically-meaningful bonds, angles, and
+//! torsions.
+
+use std::collections::VecDeque;
+
+use lin_alg::f64::Vec3;
+use na_seq::Element;
+
+use crate::{AtomGeneric, BondGeneric, graph::bond_adjacency};
+
+// Reference atoms that are closer to collinear than this (in degrees) make for an ill-defined dihedral.
+const COLLINEAR_THRESH: f64 = 5.;
+
+/// One atom's internal coordinates. References are indices into [`Zmatrix::rows`], and always
+/// point to earlier rows. The first row has no references, the second only a bond reference, and
+/// the third has no dihedral reference.
+#[derive(Clone, Debug)]
+pub struct ZmatrixRow {
+    /// The serial number of the atom this row was created from.
+    pub serial_number: u32,
+    pub element: Element,
+    pub bond_ref: Option<usize>,
+    /// Å
+    pub bond_len: f64,
+    pub angle_ref: Option<usize>,
+    /// Degrees
+    pub angle: f64,
+    pub dihedral_ref: Option<usize>,
+    /// Degrees, in the range -180 to 180.
+    pub dihedral: f64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Zmatrix {
+    pub rows: Vec<ZmatrixRow>,
+}
+
+fn angle_between(a: Vec3, b: Vec3, c: Vec3) -> f64 {
+    let v0 = (a - b).to_normalized();
+    let v1 = (c - b).to_normalized();
+    v0.dot(v1).clamp(-1., 1.).acos().to_degrees()
+}
+
+/// The signed dihedral angle a-b-c-d, in degrees.
+fn dihedral(a: Vec3, b: Vec3, c: Vec3, d: Vec3) -> f64 {
+    let b0 = a - b;
+    let b1 = (c - b).to_normalized();
+    let b2 = d - c;
+
+    let v = b0 - b1 * b0.dot(b1);
+    let w = b2 - b1 * b2.dot(b1);
+
+    let x = v.dot(w);
+    let y = b1.cross(v).dot(w);
+    y.atan2(x).to_degrees()
+}
+
+/// Place atom `d` from reference atoms `a`, `b`, `c`, using the natural extension reference
+/// frame (NeRF) method. `c` is the bond reference, `b` the angle reference, and `a` the dihedral
+/// reference.
+fn place_atom(a: Vec3, b: Vec3, c: Vec3, bond_len: f64, angle: f64, dihedral: f64) -> Vec3 {
+    let (angle, dihedral) = (angle.to_radians(), dihedral.to_radians());
+
+    let bc = (c - b).to_normalized();
+    let n = (b - a).cross(bc).to_normalized();
+    let m = n.cross(bc);
+
+    let d2 = Vec3::new(
+        -bond_len * angle.cos(),
+        bond_len * angle.sin() * dihedral.cos(),
+        bond_len * angle.sin() * dihedral.sin(),
+    );
+
+    c + bc * d2.x + m * d2.y + n * d2.z
+}
+
+/// Build a Z-matrix from Cartesian coordinates. Atoms are ordered by a breadth-first traversal
+/// of the bond graph, so each atom's bond reference is an atom it's bonded to. Angle and dihedral
+/// references are taken from bonded neighbors when possible, falling back to the nearest
+/// already-defined atoms, e.g. for disconnected fragments.
+pub fn to_zmatrix(atoms: &[AtomGeneric], bonds: &[BondGeneric]) -> Zmatrix {
+    let adj = bond_adjacency(atoms, bonds);
+
+    // Ordering: Breadth-first from each not-yet-visited atom, in input order.
+    let mut order = Vec::with_capacity(atoms.len());
+    let mut visited = vec![false; atoms.len()];
+    for start in 0..atoms.len() {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+
+        let mut queue = VecDeque::from([start]);
+        while let Some(i) = queue.pop_front() {
+            order.push(i);
+            for &(j, _) in &adj[i] {
+                if !visited[j] {
+                    visited[j] = true;
+                    queue.push_back(j);
+                }
+            }
+        }
+    }
+
+    // Maps an atom index to its row in the Z-matrix.
+    let mut row_of: Vec<Option<usize>> = vec![None; atoms.len()];
+    let mut rows = Vec::with_capacity(atoms.len());
+
+    for (row_i, &i) in order.iter().enumerate() {
+        let posit = |j: usize| atoms[j].posit;
+
+        // Defined atoms, nearest first, as a fallback when the bond graph doesn't give a reference.
+        let by_dist = |from: usize, exclude: &[usize]| -> Vec<usize> {
+            let mut v: Vec<usize> = order[..row_i]
+                .iter()
+                .copied()
+                .filter(|j| !exclude.contains(j))
+                .collect();
+            v.sort_by(|&a, &b| {
+                let da = (posit(a) - posit(from)).magnitude_squared();
+                let db = (posit(b) - posit(from)).magnitude_squared();
+                da.total_cmp(&db)
+            });
+            v
+        };
+
+        let defined_neighbors = |j: usize, exclude: &[usize]| -> Vec<usize> {
+            adj[j]
+                .iter()
+                .map(|(k, _)| *k)
+                .filter(|k| row_of[*k].is_some() && !exclude.contains(k))
+                .collect()
+        };
+
+        let bond_ref = if row_i == 0 {
+            None
+        } else {
+            defined_neighbors(i, &[])
+                .first()
+                .copied()
+                .or_else(|| by_dist(i, &[]).first().copied())
+        };
+
+        let angle_ref = bond_ref.and_then(|b| {
+            defined_neighbors(b, &[i])
+                .into_iter()
+                .chain(defined_neighbors(i, &[b]))
+                .chain(by_dist(b, &[b]))
+                .next()
+        });
+
+        let dihedral_ref = match (bond_ref, angle_ref) {
+            (Some(b), Some(a)) => defined_neighbors(a, &[b, i])
+                .into_iter()
+                .chain(defined_neighbors(b, &[a, i]))
+                .chain(by_dist(a, &[a, b]))
+                .find(|&d| {
+                    let ang = angle_between(posit(b), posit(a), posit(d));
+                    ang > COLLINEAR_THRESH && ang < 180. - COLLINEAR_THRESH
+                }),
+            _ => None,
+        };
+
+        let bond_len = bond_ref.map_or(0., |b| (posit(i) - posit(b)).magnitude());
+        let angle = match (bond_ref, angle_ref) {
+            (Some(b), Some(a)) => angle_between(posit(i), posit(b), posit(a)),
+            _ => 0.,
+        };
+        let dihedral_val = match (bond_ref, angle_ref, dihedral_ref) {
+            (Some(b), Some(a), Some(d)) => dihedral(posit(i), posit(b), posit(a), posit(d)),
+            _ => 0.,
+        };
+
+        let to_row = |j: Option<usize>| j.and_then(|j| row_of[j]);
+
+        rows.push(ZmatrixRow {
+            serial_number: atoms[i].serial_number,
+            element: atoms[i].element,
+            bond_ref: to_row(bond_ref),
+            bond_len,
+            angle_ref: to_row(angle_ref),
+            angle,
+            dihedral_ref: to_row(dihedral_ref),
+            dihedral: dihedral_val,
+        });
+
+        row_of[i] = Some(row_i);
+    }
+
+    Zmatrix { rows }
+}
+
+/// Reconstruct Cartesian coordinates from a Z-matrix. The first atom is placed at the origin, the
+/// second along the +X axis, and the third in the XY plane. Atoms are returned in Z-matrix row
+/// order, with serial numbers preserved.
+pub fn from_zmatrix(zmat: &Zmatrix) -> Vec<AtomGeneric> {
+    let mut posits: Vec<Vec3> = Vec::with_capacity(zmat.rows.len());
+
+    for row in &zmat.rows {
+        let posit = match (row.bond_ref, row.angle_ref, row.dihedral_ref) {
+            (None, _, _) => Vec3::new(0., 0., 0.),
+            (Some(b), None, _) => posits[b] + Vec3::new(row.bond_len, 0., 0.),
+            (Some(b), Some(a), d) => {
+                // Without a dihedral reference, use a point off the b-a axis to fix the plane.
+                let d_posit = match d {
+                    Some(d) => posits[d],
+                    None => {
+                        let axis = posits[a] - posits[b];
+                        let off = if axis.to_normalized().y.abs() > 0.9 {
+                            Vec3::new(1., 0., 0.)
+                        } else {
+                            Vec3::new(0., 1., 0.)
+                        };
+                        posits[a] + off
+                    }
+                };
+                place_atom(
+                    d_posit,
+                    posits[a],
+                    posits[b],
+                    row.bond_len,
+                    row.angle,
+                    row.dihedral,
+                )
+            }
+        };
+        posits.push(posit);
+    }
+
+    zmat.rows
+        .iter()
+        .zip(posits)
+        .map(|(row, posit)| AtomGeneric {
+            serial_number: row.serial_number,
+            posit,
+            element: row.element,
+            ..Default::default()
+        })
+        .collect()
+}
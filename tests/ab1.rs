@@ -0,0 +1,226 @@
+use bio_files::{SeqRecordAb1, TRIM_CUTOFF, TRIM_SEGMENT, import_ab1};
+use na_seq::{Nucleotide, seq_from_str};
+
+const SPACING: usize = 12;
+
+/// A clean trace, with a Gaussian peak in the base's channel every `SPACING` points. Channel order
+/// is G, A, T, C.
+fn synthetic_trace(seq: &[Nucleotide]) -> SeqRecordAb1 {
+    let len = (seq.len() + 1) * SPACING;
+    let mut channels = [vec![0; len], vec![0; len], vec![0; len], vec![0; len]];
+
+    for (n, nt) in seq.iter().enumerate() {
+        let ch = match nt {
+            Nucleotide::G => 0,
+            Nucleotide::A => 1,
+            Nucleotide::T => 2,
+            Nucleotide::C => 3,
+        };
+        let center = (n + 1) * SPACING;
+
+        for (i, v) in channels[ch].iter_mut().enumerate() {
+            let d = i as f32 - center as f32;
+            *v += (1_000. * (-d * d / (2. * 2.5 * 2.5)).exp()) as u16;
+        }
+    }
+
+    let [data_ch1, data_ch2, data_ch3, data_ch4] = channels;
+    SeqRecordAb1 {
+        data_ch1,
+        data_ch2,
+        data_ch3,
+        data_ch4,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn calls_bases_from_clean_trace() {
+    let expected = seq_from_str("GATTACACGGT");
+    let mut trace = synthetic_trace(&expected);
+
+    let (seq, quality) = trace.call_bases();
+    assert_eq!(seq, expected);
+    assert_eq!(quality.len(), expected.len());
+    assert!(quality.iter().all(|q| *q == 60));
+
+    // Cross-talk from another channel lowers the quality of that call only.
+    trace.data_ch4[2 * SPACING] = 500;
+    let (seq, quality) = trace.call_bases();
+    assert_eq!(seq, expected);
+    assert_eq!(quality[1], 30);
+    assert_eq!(quality[0], 60);
+
+    // With the instrument's peak locations, the expected spacing comes from them.
+    trace.peak_locations = (1..=expected.len()).map(|i| (i * SPACING) as u16).collect();
+    assert_eq!(trace.call_bases().0, expected);
+}
+
+#[test]
+fn empty_trace_calls_nothing() {
+    let (seq, quality) = SeqRecordAb1::default().call_bases();
+    assert!(seq.is_empty());
+    assert!(quality.is_empty());
+}
+
+#[test]
+fn exports_fasta_and_fastq() {
+    let seq = seq_from_str("ACGTACGTACGTACGTACGTACGTACGTACGTACGT");
+    // Low-quality ends, around a high-quality core.
+    let mut quality = vec![5; 6];
+    quality.extend(vec![40; seq.len() - 12]);
+    quality.extend(vec![5; 6]);
+
+    let record = SeqRecordAb1 {
+        id: "sample".to_owned(),
+        sequence: seq.clone(),
+        quality: Some(quality),
+        ..Default::default()
+    };
+
+    let fasta = record.to_fasta(false);
+    assert_eq!(fasta, format!(">sample\n{}\n", "ACGT".repeat(9)));
+
+    for trimmed in [false, true] {
+        let fastq = record.to_fastq(trimmed);
+        let lines: Vec<_> = fastq.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "@sample");
+        assert_eq!(lines[2], "+");
+        assert_eq!(lines[3].len(), lines[1].len());
+
+        if trimmed {
+            assert!(lines[1].len() < seq.len());
+            assert!(lines[3].chars().all(|c| c == 'I'));
+        } else {
+            assert_eq!(lines[1].len(), seq.len());
+            assert!(lines[3].starts_with("&&&&&&I"));
+        }
+    }
+}
+
+/// A minimal ABIF file: the header, then tag data, then the directory. Each tag is
+/// (name, number, element type, element count, data).
+fn build_ab1(tags: &[(&str, u32, u16, u32, Vec<u8>)]) -> Vec<u8> {
+    const DIR_START: usize = 34;
+    let data_start = DIR_START + 28 * tags.len();
+
+    let mut dir = Vec::new();
+    let mut data = Vec::new();
+    for (name, number, elem_type, count, bytes) in tags {
+        dir.extend_from_slice(name.as_bytes());
+        dir.extend_from_slice(&number.to_be_bytes());
+        dir.extend_from_slice(&elem_type.to_be_bytes());
+        dir.extend_from_slice(&((bytes.len() as u32 / count) as u16).to_be_bytes());
+        dir.extend_from_slice(&count.to_be_bytes());
+        dir.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+        if bytes.len() <= 4 {
+            // Small data is stored in the entry itself.
+            let mut inline = bytes.clone();
+            inline.resize(4, 0);
+            dir.extend_from_slice(&inline);
+        } else {
+            dir.extend_from_slice(&((data_start + data.len()) as u32).to_be_bytes());
+            data.extend_from_slice(bytes);
+        }
+        dir.extend_from_slice(&[0; 4]);
+    }
+
+    let mut result = b"ABIF".to_vec();
+    result.extend_from_slice(&101_u16.to_be_bytes());
+    result.extend_from_slice(b"tdir");
+    result.extend_from_slice(&1_u32.to_be_bytes());
+    result.extend_from_slice(&1023_u16.to_be_bytes());
+    result.extend_from_slice(&28_u16.to_be_bytes());
+    result.extend_from_slice(&(tags.len() as u32).to_be_bytes());
+    result.extend_from_slice(&(dir.len() as u32).to_be_bytes());
+    result.extend_from_slice(&(DIR_START as u32).to_be_bytes());
+    result.extend_from_slice(&[0; 4]);
+    result.extend(dir);
+    result.extend(data);
+    result
+}
+
+fn shorts(v: &[u16]) -> Vec<u8> {
+    v.iter().flat_map(|x| x.to_be_bytes()).collect()
+}
+
+fn pstring(s: &str) -> Vec<u8> {
+    let mut result = vec![s.len() as u8];
+    result.extend_from_slice(s.as_bytes());
+    result
+}
+
+#[test]
+fn load_with_base_order_and_metadata() {
+    let mut run_date = 2024_i16.to_be_bytes().to_vec();
+    run_date.extend_from_slice(&[3, 7]);
+
+    let bytes = build_ab1(&[
+        ("DATA", 9, 4, 3, shorts(&[1, 1, 1])),
+        ("DATA", 10, 4, 3, shorts(&[2, 2, 2])),
+        ("DATA", 11, 4, 3, shorts(&[3, 3, 3])),
+        ("DATA", 12, 4, 3, shorts(&[4, 4, 4])),
+        ("DySN", 1, 18, 1, pstring("Z-BigDyeV3")),
+        ("FWO_", 1, 2, 4, b"ACGT".to_vec()),
+        ("MCHN", 1, 18, 1, pstring("Sequencer-1")),
+        ("MODL", 1, 2, 4, b"3730".to_vec()),
+        ("PBAS", 2, 2, 6, b"GATTAC".to_vec()),
+        ("RUND", 1, 10, 1, run_date),
+        ("RUNT", 1, 11, 1, vec![14, 5, 9, 0]),
+        ("SMPL", 1, 18, 1, pstring("sample-1")),
+    ]);
+
+    let path = std::env::temp_dir().join("bio_files_test_base_order.ab1");
+    std::fs::write(&path, bytes).unwrap();
+    let records = import_ab1(&path).unwrap();
+
+    assert_eq!(records.len(), 1);
+    let record = &records[0];
+    assert_eq!(record.id, "sample-1");
+    assert_eq!(record.sequence, seq_from_str("GATTAC"));
+
+    // Channels are in the file's order, A, C, G, T; fields are G, A, T, C.
+    assert_eq!(record.data_ch1, vec![3; 3]);
+    assert_eq!(record.data_ch2, vec![1; 3]);
+    assert_eq!(record.data_ch3, vec![4; 3]);
+    assert_eq!(record.data_ch4, vec![2; 3]);
+
+    let ann = &record.annotations;
+    assert_eq!(ann["base_order"], "ACGT");
+    assert_eq!(ann["run_start"], "2024-03-07 14:05:09");
+    assert_eq!(ann["machine_model"], "3730");
+    assert_eq!(ann["machine_name"], "Sequencer-1");
+    assert_eq!(ann["dye"], "Z-BigDyeV3");
+    assert!(!ann.contains_key("run_finish"));
+}
+
+#[test]
+fn mott_trim() {
+    let seq = seq_from_str(&"ACGT".repeat(10));
+    // Low-quality ends, around a high-quality core.
+    let mut quality = vec![5; 8];
+    quality.extend(vec![40; seq.len() - 16]);
+    quality.extend(vec![5; 8]);
+
+    let mut record = SeqRecordAb1 {
+        sequence: seq.clone(),
+        quality: Some(quality),
+        ..Default::default()
+    };
+
+    let (start, end) = record.trim(TRIM_CUTOFF, TRIM_SEGMENT);
+    assert!(start > 0 && start <= 8);
+    // As in BioPython, the end is the index of the highest cumulative score: The last good base.
+    assert_eq!(end, seq.len() - 9);
+    assert_eq!(record.trimmed_sequence(), seq[start..end].to_vec());
+
+    // Sequences no longer than the minimum segment are kept whole.
+    assert_eq!(record.trim(TRIM_CUTOFF, seq.len()), (0, seq.len()));
+
+    // A cutoff above every base's error probability keeps everything past the first base.
+    assert_eq!(record.trim(0.5, TRIM_SEGMENT), (1, seq.len() - 1));
+
+    record.quality = None;
+    assert_eq!(record.trimmed_sequence(), seq);
+}
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+use bio_files::{
+    AtomGeneric, BondInferenceParams, BondType, atoms_within_of, create_bonds,
+    create_bonds_with_params, infer_bonds,
+};
+use lin_alg::f64::Vec3;
+use na_seq::Element::{self, Carbon, Hydrogen, Oxygen, Sulfur};
+
+fn atom(sn: u32, element: Element, posit: Vec3) -> AtomGeneric {
+    AtomGeneric {
+        serial_number: sn,
+        posit,
+        element,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn pair_override_suppresses_bond() {
+    // A long C–S bond, and a C–H bond.
+    let atoms = vec![
+        atom(1, Carbon, Vec3::new(0., 0., 0.)),
+        atom(2, Sulfur, Vec3::new(1.83, 0., 0.)),
+        atom(3, Hydrogen, Vec3::new(-0.6, 0.9, 0.)),
+    ];
+    assert!((atoms[2].posit.magnitude() - 1.08).abs() < 0.01);
+
+    assert_eq!(create_bonds(&atoms).len(), 2);
+
+    let params = BondInferenceParams {
+        pair_overrides: HashMap::from([((Sulfur, Carbon), 0.)]),
+        ..Default::default()
+    };
+    let bonds = create_bonds_with_params(&atoms, &params);
+    assert_eq!(bonds.len(), 1);
+    assert!(bonds[0].atom_0_sn == 3 || bonds[0].atom_1_sn == 3);
+}
+
+#[test]
+fn max_bonds_per_atom() {
+    // Two hydrogens within bonding distance of one carbon; only the better match is kept.
+    let atoms = vec![
+        atom(1, Carbon, Vec3::new(0., 0., 0.)),
+        atom(2, Hydrogen, Vec3::new(1.09, 0., 0.)),
+        atom(3, Hydrogen, Vec3::new(-1.11, 0., 0.)),
+    ];
+
+    let params = BondInferenceParams {
+        max_bonds_per_atom: Some(1),
+        ..Default::default()
+    };
+    let bonds = create_bonds_with_params(&atoms, &params);
+    assert_eq!(bonds.len(), 1);
+    assert!(bonds[0].atom_0_sn == 2 || bonds[0].atom_1_sn == 2);
+}
+
+#[test]
+fn contacts_between_groups() {
+    // Two parallel rows of atoms 4.5 Å apart, offset along x, straddling the origin.
+    let mut atoms = Vec::new();
+    for i in 0..10 {
+        atoms.push(atom(i + 1, Carbon, Vec3::new(i as f64 - 5., 0., -1.)));
+    }
+    for i in 0..10 {
+        atoms.push(atom(i + 11, Carbon, Vec3::new(i as f64, 4.5, -1.)));
+    }
+    // Far from everything.
+    atoms.push(atom(21, Carbon, Vec3::new(50., 50., 50.)));
+
+    let group_a: Vec<u32> = (1..=10).collect();
+    let group_b: Vec<u32> = (11..=21).collect();
+
+    // Within 5 Å, |dx| must be at most √(25 - 4.5²) ≈ 2.18.
+    let near = atoms_within_of(&atoms, &group_b, &group_a, 5.);
+    assert_eq!(near, vec![4, 5, 6, 7, 8, 9, 10]);
+
+    let near = atoms_within_of(&atoms, &group_a, &group_b, 5.);
+    assert_eq!(near, vec![11, 12, 13, 14, 15, 16, 17]);
+
+    // Matches a brute-force search.
+    for radius in [1., 4.5, 4.6, 7.3, 12.] {
+        let expected: Vec<u32> = group_a
+            .iter()
+            .copied()
+            .filter(|sn| {
+                let p = atoms[*sn as usize - 1].posit;
+                group_b
+                    .iter()
+                    .any(|q| (atoms[*q as usize - 1].posit - p).magnitude() <= radius)
+            })
+            .collect();
+        assert_eq!(
+            atoms_within_of(&atoms, &group_b, &group_a, radius),
+            expected
+        );
+    }
+}
+
+#[test]
+fn infer_bonds_from_covalent_radii() {
+    let water = vec![
+        atom(1, Oxygen, Vec3::new(0., 0., 0.)),
+        atom(2, Hydrogen, Vec3::new(0.757, 0.586, 0.)),
+        atom(3, Hydrogen, Vec3::new(-0.757, 0.586, 0.)),
+    ];
+
+    let bonds = infer_bonds(&water);
+    assert_eq!(bonds.len(), 2);
+    for (bond, h_sn) in bonds.iter().zip([2, 3]) {
+        assert_eq!((bond.atom_0_sn, bond.atom_1_sn), (1, h_sn));
+        assert_eq!(bond.bond_type, BondType::Single);
+    }
+
+    // A second water, far away, bonds only within itself.
+    let mut atoms = water.clone();
+    for (i, a) in water.iter().enumerate() {
+        let posit = a.posit + Vec3::new(10., 0., 0.);
+        atoms.push(atom(4 + i as u32, a.element, posit));
+    }
+
+    let bonds = infer_bonds(&atoms);
+    assert_eq!(bonds.len(), 4);
+    assert!(bonds.iter().all(|b| (b.atom_0_sn < 4) == (b.atom_1_sn < 4)));
+
+    // Overlapping atoms, e.g. alternate conformations, don't bond.
+    let overlapping = vec![
+        atom(1, Oxygen, Vec3::new(0., 0., 0.)),
+        atom(2, Oxygen, Vec3::new(0.1, 0., 0.)),
+    ];
+    assert!(infer_bonds(&overlapping).is_empty());
+}
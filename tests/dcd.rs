@@ -0,0 +1,381 @@
+use std::{fs, io::Cursor};
+
+use bio_files::{
+    AtomGeneric,
+    dcd::{DcdFrame, DcdMetadata, DcdReader, DcdTrajectory, DcdUnitCell, bounding_cell, write_dcd},
+};
+use lin_alg::f32::Vec3;
+
+fn frames() -> Vec<DcdFrame> {
+    let unit_cell = DcdUnitCell {
+        bounds_low: Vec3::new(0., 0., 0.),
+        bounds_high: Vec3::new(20., 25., 30.),
+        ..Default::default()
+    };
+
+    (0..4)
+        .map(|i| DcdFrame {
+            time: i as f64 * 2.,
+            atom_posits: vec![
+                Vec3::new(i as f32, 0., 1.),
+                Vec3::new(1.5, i as f32 * 0.5, -2.),
+                Vec3::new(-3., 4., i as f32),
+            ],
+            unit_cell: unit_cell.clone(),
+        })
+        .collect()
+}
+
+#[test]
+fn streaming_reader_matches_load() {
+    let path = std::env::temp_dir().join("bio_files_test_stream.dcd");
+    let _ = fs::remove_file(&path);
+    write_dcd(&path, &frames()).unwrap();
+
+    let loaded = DcdTrajectory::load(&path).unwrap();
+    assert_eq!(loaded.frames.len(), 4);
+
+    let reader = DcdReader::open(&path).unwrap();
+    assert_eq!(reader.num_atoms(), 3);
+    assert_eq!(reader.num_frames(), 4);
+
+    let mut n = 0;
+    for (frame, expected) in reader.zip(&loaded.frames) {
+        let frame = frame.unwrap();
+        assert_eq!(frame.time, expected.time);
+        assert_eq!(frame.atom_posits, expected.atom_posits);
+        assert_eq!(frame.unit_cell.bounds_high, expected.unit_cell.bounds_high);
+        n += 1;
+    }
+    assert_eq!(n, 4);
+
+    // Any seekable source works, e.g. an in-memory buffer.
+    let bytes = fs::read(&path).unwrap();
+    let streamed: Vec<DcdFrame> = DcdReader::new(Cursor::new(bytes))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(streamed.len(), 4);
+    assert_eq!(streamed[3].atom_posits, frames()[3].atom_posits);
+}
+
+#[test]
+fn streaming_reader_stops_on_truncation() {
+    let path = std::env::temp_dir().join("bio_files_test_stream_trunc.dcd");
+    let _ = fs::remove_file(&path);
+    write_dcd(&path, &frames()).unwrap();
+
+    let mut bytes = fs::read(&path).unwrap();
+    bytes.truncate(bytes.len() - 10);
+
+    let results: Vec<_> = DcdReader::new(Cursor::new(bytes)).unwrap().collect();
+    assert_eq!(results.len(), 4);
+    assert!(results[..3].iter().all(|r| r.is_ok()));
+    assert!(results[3].is_err());
+}
+
+/// A truncated octahedron, as OpenMM writes it: Angles in degrees, in X-PLOR order.
+fn octahedron_frames() -> Vec<DcdFrame> {
+    let unit_cell = DcdUnitCell {
+        bounds_low: Vec3::new(0., 0., 0.),
+        bounds_high: Vec3::new(50., 50., 50.),
+        alpha: 70.528_78,
+        beta: 109.471_22,
+        gamma: 70.528_78,
+    };
+
+    vec![DcdFrame {
+        time: 0.,
+        atom_posits: vec![Vec3::new(1., 2., 3.)],
+        unit_cell,
+    }]
+}
+
+#[test]
+fn wrap_and_unwrap() {
+    let mut frame = frames().remove(0);
+    let inside = Vec3::new(5., 6., 7.);
+    // Pushed one box length out along x, and two back along z.
+    frame.atom_posits = vec![Vec3::new(25., 6., 7.), Vec3::new(5., 6., -53.), inside];
+
+    let unwrapped = frame.clone();
+    frame.wrap_into_box();
+    for p in &frame.atom_posits {
+        assert!((*p - inside).magnitude() < 1e-4, "{p:?}");
+    }
+
+    // Back to continuous positions, relative to the previous frame.
+    let prev = DcdFrame {
+        atom_posits: vec![
+            Vec3::new(24.5, 6., 7.),
+            Vec3::new(5., 6., -52.),
+            Vec3::new(5., 6., 6.),
+        ],
+        ..frame.clone()
+    };
+    frame.unwrap(&prev).unwrap();
+    for (a, b) in frame.atom_posits.iter().zip(&unwrapped.atom_posits) {
+        assert!((*a - *b).magnitude() < 1e-4, "{a:?}");
+    }
+
+    assert!(frame.unwrap(&frames()[0]).is_ok());
+    let mut short = frame.clone();
+    short.atom_posits.pop();
+    assert!(frame.unwrap(&short).is_err());
+
+    // Triclinic: Wrapping moves by whole box vectors, into the primary cell.
+    let mut frame = octahedron_frames().remove(0);
+    let [a, b, c] = frame.unit_cell.box_vectors();
+    let orig = frame.atom_posits[0];
+    frame.atom_posits[0] = orig + a - b * 2. + c;
+    frame.wrap_into_box();
+    assert!((frame.atom_posits[0] - orig).magnitude() < 1e-3);
+}
+
+#[test]
+fn save_subset() {
+    let traj = DcdTrajectory {
+        frames: (0..3)
+            .map(|i| DcdFrame {
+                time: i as f64,
+                atom_posits: (0..1_000)
+                    .map(|j| Vec3::new(j as f32 * 0.01, i as f32, 0.))
+                    .collect(),
+                unit_cell: frames()[0].unit_cell.clone(),
+            })
+            .collect(),
+    };
+
+    let indices: Vec<usize> = (0..1_000).step_by(20).collect();
+    let path = std::env::temp_dir().join("bio_files_test_subset.dcd");
+    let _ = fs::remove_file(&path);
+    traj.save_subset(&path, &indices).unwrap();
+
+    assert_eq!(DcdMetadata::read(&path).unwrap().num_atoms, 50);
+    let loaded = DcdTrajectory::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.frames.len(), 3);
+    assert!(loaded.frames.iter().all(|f| f.atom_posits.len() == 50));
+    assert_eq!(
+        loaded.frames[2].atom_posits[1],
+        traj.frames[2].atom_posits[20]
+    );
+
+    assert!(traj.save_subset(&path, &[0, 1_000]).is_err());
+    assert!(!path.exists());
+}
+
+#[test]
+fn triclinic_cell_round_trip() {
+    let path = std::env::temp_dir().join("bio_files_test_triclinic.dcd");
+    let _ = fs::remove_file(&path);
+    write_dcd(&path, &octahedron_frames()).unwrap();
+
+    let loaded = DcdTrajectory::load(&path).unwrap();
+    let cell = &loaded.frames[0].unit_cell;
+
+    assert_eq!(cell.bounds_high, Vec3::new(50., 50., 50.));
+    assert!((cell.alpha - 70.528_78).abs() < 1e-4);
+    assert!((cell.beta - 109.471_22).abs() < 1e-4);
+    assert!((cell.gamma - 70.528_78).abs() < 1e-4);
+
+    // Each box vector keeps its length.
+    for v in cell.box_vectors() {
+        assert!((v.magnitude() - 50.).abs() < 1e-3);
+    }
+}
+
+#[test]
+fn charmm_cosine_angles() {
+    let path = std::env::temp_dir().join("bio_files_test_charmm_cell.dcd");
+    let _ = fs::remove_file(&path);
+    write_dcd(&path, &octahedron_frames()).unwrap();
+
+    // Rewrite the angles in the unit cell record as cosines, as CHARMM does.
+    let mut bytes = fs::read(&path).unwrap();
+    let a_bytes = 50_f64.to_le_bytes();
+    let start = bytes.windows(8).position(|w| w == a_bytes).unwrap();
+    for (slot, angle) in [(1, 70.528_78_f32), (3, 109.471_22), (4, 70.528_78)] {
+        let i = start + slot * 8;
+        let cos = (angle as f64).to_radians().cos();
+        bytes[i..i + 8].copy_from_slice(&cos.to_le_bytes());
+    }
+
+    let frame = DcdReader::new(Cursor::new(bytes))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert!((frame.unit_cell.alpha - 70.528_78).abs() < 1e-3);
+    assert!((frame.unit_cell.beta - 109.471_22).abs() < 1e-3);
+}
+
+#[test]
+fn orthorhombic_box_vectors() {
+    let cell = &frames()[0].unit_cell;
+    let [a, b, c] = cell.box_vectors();
+
+    assert_eq!(a, Vec3::new(20., 0., 0.));
+    assert!((b - Vec3::new(0., 25., 0.)).magnitude() < 1e-5);
+    assert!((c - Vec3::new(0., 0., 30.)).magnitude() < 1e-5);
+}
+
+/// Convert a little-endian DCD file, as we write it, to big-endian.
+fn to_big_endian(le: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(le.len());
+    let mut i = 0;
+    let mut record = 0;
+
+    while i < le.len() {
+        let len = u32::from_le_bytes(le[i..i + 4].try_into().unwrap()) as usize;
+        let payload = &le[i + 4..i + 4 + len];
+
+        // Header: "CORD", then 4-byte words. Title: NTITLE, then text. Unit cell: 8-byte words.
+        // NATOM and coordinates: 4-byte words.
+        let (skip, word, words_end) = match record {
+            0 => (4, 4, len),
+            1 => (0, 4, 4),
+            _ if len == 48 => (0, 8, len),
+            _ => (0, 4, len),
+        };
+
+        let mut out = payload.to_vec();
+        let mut j = skip;
+        while j < words_end {
+            out[j..j + word].reverse();
+            j += word;
+        }
+
+        result.extend_from_slice(&(len as u32).to_be_bytes());
+        result.extend_from_slice(&out);
+        result.extend_from_slice(&(len as u32).to_be_bytes());
+
+        i += len + 8;
+        record += 1;
+    }
+
+    result
+}
+
+#[test]
+fn big_endian() {
+    let path = std::env::temp_dir().join("bio_files_test_big_endian.dcd");
+    let _ = fs::remove_file(&path);
+    write_dcd(&path, &frames()).unwrap();
+
+    let le = fs::read(&path).unwrap();
+    let be = to_big_endian(&le);
+    assert_ne!(be, le);
+
+    let reader = DcdReader::new(Cursor::new(be)).unwrap();
+    assert_eq!(reader.num_atoms(), 3);
+    assert_eq!(reader.num_frames(), 4);
+
+    let loaded: Vec<DcdFrame> = reader.collect::<Result<_, _>>().unwrap();
+    let expected = DcdTrajectory::load(&path).unwrap().frames;
+
+    for (frame, exp) in loaded.iter().zip(&expected) {
+        assert_eq!(frame.time, exp.time);
+        assert_eq!(frame.atom_posits, exp.atom_posits);
+        assert_eq!(frame.unit_cell.bounds_high, exp.unit_cell.bounds_high);
+        assert_eq!(frame.unit_cell.gamma, 90.);
+    }
+}
+
+#[test]
+fn vacuum_trajectory_without_unit_cell() {
+    let path = std::env::temp_dir().join("bio_files_test_vacuum.dcd");
+    let path_box = std::env::temp_dir().join("bio_files_test_vacuum_box.dcd");
+    let _ = fs::remove_file(&path);
+    let _ = fs::remove_file(&path_box);
+
+    let mut vacuum = frames();
+    for frame in &mut vacuum {
+        frame.unit_cell = DcdUnitCell::default();
+    }
+    let traj = DcdTrajectory { frames: vacuum };
+    traj.save(&path).unwrap();
+    write_dcd(&path_box, &frames()).unwrap();
+
+    let meta = DcdMetadata::read(&path).unwrap();
+    assert!(!meta.has_unit_cell);
+    assert!(DcdMetadata::read(&path_box).unwrap().has_unit_cell);
+
+    // Each frame saves a 6-value unit cell record: 48 bytes, and two 4-byte length markers.
+    let len = fs::metadata(&path).unwrap().len();
+    let len_box = fs::metadata(&path_box).unwrap().len();
+    assert_eq!(len_box - len, 4 * 56);
+
+    let loaded = DcdTrajectory::load(&path).unwrap();
+    assert_eq!(loaded.frames.len(), 4);
+    for (frame, exp) in loaded.frames.iter().zip(&traj.frames) {
+        assert_eq!(frame.atom_posits, exp.atom_posits);
+        assert!(frame.unit_cell.is_degenerate());
+    }
+
+    // Appending more box-less frames keeps the file position-only.
+    traj.save(&path).unwrap();
+    let loaded = DcdTrajectory::load(&path).unwrap();
+    assert_eq!(loaded.frames.len(), 8);
+    assert_eq!(loaded.frames[7].atom_posits, traj.frames[3].atom_posits);
+}
+
+fn scattered_atoms() -> Vec<AtomGeneric> {
+    [
+        (-3.2, 1., 0.5),
+        (4.1, -2.5, 7.),
+        (0., 0., -6.3),
+        (1.7, 8.8, 2.),
+    ]
+    .into_iter()
+    .enumerate()
+    .map(|(i, (x, y, z))| AtomGeneric {
+        serial_number: i as u32 + 1,
+        posit: lin_alg::f64::Vec3::new(x, y, z),
+        ..Default::default()
+    })
+    .collect()
+}
+
+#[test]
+fn bounding_cell_contains_atoms() {
+    let atoms = scattered_atoms();
+    let cell = bounding_cell(&atoms, 2.);
+
+    for a in &atoms {
+        let p = Vec3::new(a.posit.x as f32, a.posit.y as f32, a.posit.z as f32);
+        assert!(p.x > cell.bounds_low.x && p.x < cell.bounds_high.x);
+        assert!(p.y > cell.bounds_low.y && p.y < cell.bounds_high.y);
+        assert!(p.z > cell.bounds_low.z && p.z < cell.bounds_high.z);
+    }
+
+    assert_eq!(cell.bounds_low, Vec3::new(-5.2, -4.5, -8.3));
+    assert_eq!(cell.bounds_high, Vec3::new(6.1, 10.8, 9.));
+    assert!(!cell.is_degenerate());
+}
+
+#[test]
+fn decimate_keeps_endpoints() {
+    let traj = DcdTrajectory {
+        frames: (0..100)
+            .map(|i| DcdFrame {
+                time: i as f64,
+                atom_posits: vec![Vec3::new(i as f32, 0., 0.)],
+                unit_cell: DcdUnitCell::default(),
+            })
+            .collect(),
+    };
+
+    let times =
+        |t: &DcdTrajectory| -> Vec<usize> { t.frames.iter().map(|f| f.time as usize).collect() };
+
+    let decimated = traj.decimate_to(10);
+    let indices = times(&decimated);
+    assert_eq!(indices, [0, 11, 22, 33, 44, 55, 66, 77, 88, 99]);
+
+    assert_eq!(times(&traj.decimate_to(2)), [0, 99]);
+    assert_eq!(times(&traj.decimate_to(1)), [0]);
+    assert!(traj.decimate_to(0).frames.is_empty());
+    assert_eq!(traj.decimate_to(500).frames.len(), 100);
+}
@@ -0,0 +1,68 @@
+use bio_files::{
+    AtomGeneric, BondGeneric, BondType,
+    fingerprint::{morgan_fingerprint, tanimoto},
+};
+use na_seq::Element::{self, Carbon, Nitrogen, Oxygen};
+
+/// A molecule from heavy-atom elements, and bonds as (index, index, type).
+fn mol(
+    elements: &[Element],
+    bonds: &[(u32, u32, BondType)],
+) -> (Vec<AtomGeneric>, Vec<BondGeneric>) {
+    let atoms = elements
+        .iter()
+        .enumerate()
+        .map(|(i, &element)| AtomGeneric {
+            serial_number: i as u32 + 1,
+            element,
+            ..Default::default()
+        })
+        .collect();
+
+    let bonds = bonds
+        .iter()
+        .map(|&(a, b, bond_type)| BondGeneric {
+            bond_type,
+            atom_0_sn: a + 1,
+            atom_1_sn: b + 1,
+        })
+        .collect();
+
+    (atoms, bonds)
+}
+
+fn fp(elements: &[Element], bonds: &[(u32, u32, BondType)]) -> Vec<u64> {
+    let (atoms, bonds) = mol(elements, bonds);
+    morgan_fingerprint(&atoms, &bonds, 2, 2048)
+}
+
+#[test]
+fn identical_and_distinct() {
+    use BondType::*;
+
+    let ethanol = [(0, 1, Single), (1, 2, Single)];
+    let a = fp(&[Carbon, Carbon, Oxygen], &ethanol);
+    let b = fp(&[Carbon, Carbon, Oxygen], &ethanol);
+    assert_eq!(a.len(), 32);
+    assert_eq!(tanimoto(&a, &b), 1.);
+
+    let ethylamine = fp(&[Carbon, Carbon, Nitrogen], &ethanol);
+    let sim = tanimoto(&a, &ethylamine);
+    assert!(sim < 1.);
+
+    let benzene: Vec<_> = (0..6).map(|i| (i, (i + 1) % 6, Aromatic)).collect();
+    let cyclohexane: Vec<_> = (0..6).map(|i| (i, (i + 1) % 6, Single)).collect();
+    let benzene = fp(&[Carbon; 6], &benzene);
+    let cyclohexane = fp(&[Carbon; 6], &cyclohexane);
+    assert!(tanimoto(&benzene, &cyclohexane) < 1.);
+    assert!(tanimoto(&benzene, &a) < sim);
+}
+
+#[test]
+fn atom_order_independent() {
+    use BondType::*;
+
+    let a = fp(&[Carbon, Carbon, Oxygen], &[(0, 1, Single), (1, 2, Single)]);
+    let b = fp(&[Oxygen, Carbon, Carbon], &[(2, 1, Single), (1, 0, Single)]);
+    assert_eq!(tanimoto(&a, &b), 1.);
+}
@@ -0,0 +1,75 @@
+use bio_files::{
+    fixed::{FixedLine, Justify, parse_field, parse_field_opt, read_field},
+    gromacs::gro::Gro,
+};
+
+#[test]
+fn pdb_atom_layout() {
+    // PDB ATOM record: serial 6..11, name 12..16, resname 17..20, chain 21, resseq 22..26,
+    // x 30..38, y 38..46, z 46..54.
+    let mut line = FixedLine::new();
+    line.str(0, 6, "ATOM", Justify::Left)
+        .int(6, 11, 1234)
+        .unwrap()
+        .str(13, 16, "CA", Justify::Left)
+        .str(17, 20, "ALA", Justify::Left)
+        .str(21, 22, "B", Justify::Left)
+        .int(22, 26, -12)
+        .unwrap()
+        .float(30, 38, -999.999, 3)
+        .unwrap()
+        .float(38, 46, 12.5, 3)
+        .unwrap()
+        .float(46, 54, -0.001, 3)
+        .unwrap();
+
+    let text = line.as_str();
+    assert_eq!(
+        text,
+        "ATOM   1234  CA  ALA B -12    -999.999  12.500  -0.001"
+    );
+
+    assert_eq!(read_field(text, 0, 6), "ATOM");
+    assert_eq!(parse_field::<u32>(text, 6, 11, "serial").unwrap(), 1234);
+    assert_eq!(read_field(text, 12, 16), "CA");
+    assert_eq!(parse_field::<i32>(text, 22, 26, "resseq").unwrap(), -12);
+    // This value fills its field.
+    assert_eq!(parse_field::<f64>(text, 30, 38, "x").unwrap(), -999.999);
+    assert_eq!(parse_field::<f64>(text, 46, 54, "z").unwrap(), -0.001);
+
+    // Past the end of the line.
+    assert_eq!(read_field(text, 76, 78), "");
+    assert_eq!(parse_field_opt::<f32>(text, 60, 66, "b").unwrap(), None);
+    assert!(parse_field::<f32>(text, 60, 66, "b-factor").is_err());
+}
+
+#[test]
+fn overflow_and_truncation() {
+    let mut line = FixedLine::new();
+    assert!(line.float(0, 8, -10000.5, 3).is_err());
+    assert!(line.int(0, 3, 1000).is_err());
+
+    line.str(0, 3, "ABCDE", Justify::Right)
+        .str(5, 9, "X", Justify::Right);
+    assert_eq!(line.as_str(), "ABC     X");
+}
+
+#[test]
+fn gro_negative_fills_field() {
+    let text = "\
+test
+    2
+    1SOL     OW    1-999.999  -1.234-100.500
+    1SOL    HW1    2   1.000   2.000   3.000  0.1000 -0.2000-12.3456
+   3.00000   3.00000   3.00000
+";
+    let gro = Gro::new(text).unwrap();
+
+    assert_eq!(gro.atoms[0].posit.x, -999.999);
+    assert_eq!(gro.atoms[0].posit.z, -100.5);
+    assert_eq!(gro.atoms[1].velocity.unwrap().z, -12.3456);
+
+    let mut out = Vec::new();
+    gro.write_to(&mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), text);
+}
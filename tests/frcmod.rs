@@ -0,0 +1,267 @@
+use bio_files::md_params::{ForceFieldParams, ForceFieldParamsVec};
+
+/// Parameter lines in the layout `parmchk2` writes.
+const FRCMOD: &str = "\
+Remark line goes here
+MASS
+
+BOND
+c -ca  349.70   1.4750   same as c -ca
+ca-f   323.10   1.3590
+
+ANGLE
+ca-c3-c1   64.680     112.590   same as ca-c3-c2
+c -ca-ca   63.720     120.330
+
+DIHE
+c1-c1-c3-ca   1    0.000         0.000           1.000      same as X -c1-c3-X
+X -c -ca-X    4    4.000       180.000           2.000
+c3-c3-n -c    1    1.400         0.000           3.000      same as X -c3-n -X, penalty score= 18.0
+
+IMPROPER
+c2-ca-ca-ca         1.1          180.0         2.0          Using the default value
+ca-ca-ca-ha         1.1          180.0         2.0
+
+NONBON
+  hn          0.6000  0.0157
+  c3          1.9080  0.1094
+";
+
+fn section<'a>(text: &'a str, name: &str) -> Vec<&'a str> {
+    text.lines()
+        .skip_while(|l| *l != name)
+        .skip(1)
+        .take_while(|l| !l.is_empty())
+        .collect()
+}
+
+#[test]
+fn parameter_columns_match_parmchk2() {
+    let params = ForceFieldParamsVec::from_frcmod(FRCMOD).unwrap();
+    assert_eq!(params.bond.len(), 2);
+    assert_eq!(params.dihedral.len(), 3);
+    assert_eq!(params.improper.len(), 2);
+
+    let written = params.to_frcmod();
+
+    for name in ["BOND", "ANGLE", "DIHE", "IMPROPER", "NONBON"] {
+        assert_eq!(section(&written, name), section(FRCMOD, name), "{name}");
+    }
+}
+
+#[test]
+fn round_trip() {
+    let params = ForceFieldParamsVec::from_frcmod(FRCMOD).unwrap();
+    let reloaded = ForceFieldParamsVec::from_frcmod(&params.to_frcmod()).unwrap();
+
+    assert_eq!(
+        reloaded.bond[0].atom_types,
+        ("c".to_owned(), "ca".to_owned())
+    );
+    assert_eq!(reloaded.bond[0].k_b, 349.7);
+    assert!((reloaded.angle[0].theta_0 - params.angle[0].theta_0).abs() < 1e-5);
+    assert_eq!(reloaded.dihedral[1].divider, 4);
+    assert_eq!(reloaded.improper[0].periodicity, 2);
+
+    assert_eq!(reloaded.lennard_jones.len(), 2);
+    for (a, b) in reloaded.lennard_jones.iter().zip(&params.lennard_jones) {
+        assert_eq!(a.atom_type, b.atom_type);
+        assert!((a.sigma - b.sigma).abs() < 1e-4);
+        assert!((a.eps - b.eps).abs() < 1e-6);
+    }
+    // R star 1.908 Å → σ = 2 R* / 2^(1/6)
+    assert!((params.lennard_jones[1].sigma - 3.3997).abs() < 1e-3);
+}
+
+/// A small excerpt in the layout of `gaff2.dat`.
+const DAT: &str = "\
+AMBER General Force Field for organic molecules (Version 2.11, May 2016)
+c  12.01         0.616               Sp2 C carbonyl group
+ca 12.01         0.360               Sp2 C in pure aromatic systems
+f  19.00         0.320               Fluorine
+
+c -ca  345.80   1.4870
+ca-f   359.80   1.3490
+
+c -ca-ca   63.900     120.220
+
+X -c -ca-X    4    4.000       180.000          -2.000
+X -c -ca-X    4    0.300         0.000           1.000
+
+ca-ca-ca-ha         1.1          180.0         2.0
+
+MOD4      RE
+  c           1.9080  0.0860
+  ca          1.9080  0.0860
+
+END
+";
+
+#[test]
+fn merge_frcmod_over_dat() {
+    let mut params = ForceFieldParams::from_dat(DAT).unwrap();
+    let frcmod = ForceFieldParams::from_frcmod(FRCMOD).unwrap();
+    params.merge(&frcmod);
+
+    let ca_c = ("ca".to_owned(), "c".to_owned());
+    let ca_f = ("ca".to_owned(), "f".to_owned());
+
+    // The frcmod's value wins, in either atom order.
+    assert_eq!(params.get_bond(&ca_f, false).unwrap().k_b, 323.1);
+    assert_eq!(params.get_bond(&ca_c, false).unwrap().k_b, 349.7);
+    assert_eq!(params.bond.len(), 2);
+
+    // Terms only in the dat are kept; ones only in the frcmod are added.
+    assert_eq!(params.mass["f"].mass, 19.);
+    assert_eq!(params.lennard_jones["ca"].eps, 0.086);
+    assert_eq!(params.lennard_jones["c3"].eps, 0.1094);
+    assert_eq!(params.angle.len(), 2);
+
+    // `merge_with` has the opposite precedence: The dat's value wins, in either atom order.
+    let dat = ForceFieldParams::from_dat(DAT).unwrap();
+    let merged = dat.merge_with(&frcmod);
+    assert_eq!(merged.get_bond(&ca_f, false).unwrap().k_b, 359.8);
+    assert_eq!(merged.get_bond(&ca_c, false).unwrap().k_b, 345.8);
+    assert_eq!(merged.bond.len(), 2);
+    assert_eq!(merged.lennard_jones["c3"].eps, 0.1094);
+}
+
+#[test]
+fn lj_pair_combining_rules() {
+    let params = ForceFieldParams::from_frcmod(FRCMOD).unwrap();
+
+    let hn = &params.lennard_jones["hn"];
+    let c3 = &params.lennard_jones["c3"];
+
+    let (sigma, eps) = params.lj_pair("hn", "c3").unwrap();
+    assert!((sigma - 0.5 * (hn.sigma + c3.sigma)).abs() < 1e-6);
+    assert!((eps - (0.0157_f32 * 0.1094).sqrt()).abs() < 1e-6);
+    assert_eq!(params.lj_pair("c3", "hn"), Some((sigma, eps)));
+
+    // A type with itself gets its own parameters.
+    assert_eq!(params.lj_pair("c3", "c3"), Some((c3.sigma, c3.eps)));
+
+    assert!(params.lj_pair("hn", "zz").is_none());
+}
+
+#[test]
+fn bond_and_angle_lookup_either_order() {
+    let mut params = ForceFieldParams::from_frcmod(FRCMOD).unwrap();
+    let t = |v: &str| v.to_owned();
+
+    // Stored as `c -ca` and `ca-c3-c1`; queried in reverse.
+    let bond = params.get_bond(&(t("ca"), t("c")), false).unwrap();
+    assert_eq!(bond.atom_types, (t("c"), t("ca")));
+
+    let angle = params
+        .get_valence_angle(&(t("c1"), t("c3"), t("ca")), false)
+        .unwrap();
+    assert_eq!(angle.k, 64.68);
+
+    assert!(params.get_bond(&(t("ca"), t("zz")), false).is_none());
+
+    // Wildcards are only used if allowed, and after exact matches.
+    let mut wild = params.angle[&(t("c"), t("ca"), t("ca"))].clone();
+    wild.atom_types = (t("X"), t("ca"), t("ca"));
+    wild.k = 1.;
+    params.angle.insert(wild.atom_types.clone(), wild);
+
+    let query = (t("ca"), t("ca"), t("zz"));
+    assert!(params.get_valence_angle(&query, false).is_none());
+    assert_eq!(params.get_valence_angle(&query, true).unwrap().k, 1.);
+
+    let exact = (t("ca"), t("ca"), t("c"));
+    assert_eq!(params.get_valence_angle(&exact, true).unwrap().k, 63.72);
+}
+
+#[test]
+fn dihedral_penalty_score() {
+    let params = ForceFieldParamsVec::from_frcmod(FRCMOD).unwrap();
+
+    let d = &params.dihedral[2];
+    assert_eq!(d.penalty_score, Some(18.));
+    assert_eq!(d.comment.as_deref(), Some("same as X -c3-n -X"));
+    assert_eq!(params.dihedral[0].penalty_score, None);
+
+    let reloaded = ForceFieldParamsVec::from_frcmod(&params.to_frcmod()).unwrap();
+    assert_eq!(reloaded.dihedral[2].penalty_score, Some(18.));
+    assert_eq!(reloaded.dihedral[2].comment, d.comment);
+}
+
+#[test]
+fn dat_round_trip() {
+    let mut params = ForceFieldParamsVec::from_dat(DAT).unwrap();
+    assert_eq!(params.dihedral.len(), 2);
+    assert_eq!(params.improper.len(), 1);
+
+    params.angle[0].theta_0 = 118_f32.to_radians();
+    let written = params.to_dat();
+
+    // Multi-term dihedrals are flagged with negative periodicity on all but the last term.
+    assert!(written.contains("-2.000"));
+    assert!(written.starts_with("AMBER General Force Field"));
+    assert!(written.ends_with("END\n"));
+
+    let reloaded = ForceFieldParamsVec::from_dat(&written).unwrap();
+    assert_eq!(reloaded.remarks, params.remarks);
+    assert_eq!(reloaded.mass.len(), 3);
+    assert_eq!(reloaded.mass[0].comment, params.mass[0].comment);
+    assert_eq!(reloaded.bond.len(), 2);
+    assert_eq!(reloaded.bond[1].k_b, 359.8);
+    assert!((reloaded.angle[0].theta_0.to_degrees() - 118.).abs() < 1e-3);
+    assert_eq!(reloaded.dihedral.len(), 2);
+    assert_eq!(reloaded.dihedral[0].periodicity, 2);
+    assert_eq!(reloaded.improper.len(), 1);
+    assert_eq!(reloaded.lennard_jones.len(), 2);
+    assert!((reloaded.lennard_jones[0].sigma - params.lennard_jones[0].sigma).abs() < 1e-4);
+}
+
+/// CMAP blocks in the layout of `frcmod.ff19SB`, with a coarse grid.
+const FRCMOD_CMAP: &str = "\
+ff19SB CMAP excerpt
+CMAP
+%FLAG CMAP_COUNT     1
+%FLAG CMAP_TITLE  ALA
+%FLAG CMAP_RESLIST 2
+ALA NALA
+%FLAG CMAP_RESOLUTION   4
+%FLAG CMAP_PARAMETER
+   0.79271   0.83519   0.66911   0.55280   0.30283  -0.00104  -0.12411  -0.01221
+   0.27341   0.50012   0.44109   0.12042  -0.21210  -0.40045  -0.33327   0.10002
+%FLAG CMAP_COUNT     2
+%FLAG CMAP_TITLE  GLY
+%FLAG CMAP_RESLIST 1
+GLY
+%FLAG CMAP_RESOLUTION   2
+%FLAG CMAP_PARAMETER
+   1.00000   2.00000   3.00000   4.00000
+";
+
+#[test]
+fn cmap() {
+    let params = ForceFieldParamsVec::from_frcmod(FRCMOD_CMAP).unwrap();
+    assert_eq!(params.cmap.len(), 2);
+
+    let ala = &params.cmap[0];
+    assert_eq!(ala.title, "ALA");
+    assert_eq!(ala.residues, vec!["ALA".to_owned(), "NALA".to_owned()]);
+    assert_eq!(ala.resolution, 4);
+    assert_eq!(ala.grid.len(), ala.resolution.pow(2));
+    assert_eq!(ala.grid[5], -0.00104);
+
+    let gly = &params.cmap[1];
+    assert_eq!(gly.grid.len(), gly.resolution.pow(2));
+
+    let reloaded = ForceFieldParamsVec::from_frcmod(&params.to_frcmod()).unwrap();
+    assert_eq!(reloaded.cmap.len(), 2);
+    assert_eq!(reloaded.cmap[0].grid, ala.grid);
+    assert_eq!(reloaded.cmap[0].residues, ala.residues);
+    assert_eq!(reloaded.cmap[1].title, "GLY");
+
+    let keyed = ForceFieldParams::new(&params);
+    assert_eq!(keyed.cmap.len(), 2);
+
+    // The grid must fill the resolution.
+    let truncated = FRCMOD_CMAP.replace("   4.00000", "");
+    assert!(ForceFieldParamsVec::from_frcmod(&truncated).is_err());
+}
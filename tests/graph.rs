@@ -0,0 +1,124 @@
+use bio_files::{
+    AtomGeneric, ResidueEnd, ResidueGeneric, ResidueType, Sdf,
+    graph::{assign_chains, connected_components, largest_component},
+};
+use lin_alg::f64::Vec3;
+use na_seq::{
+    AminoAcid, AtomTypeInRes,
+    Element::{self, Carbon, Nitrogen, Oxygen},
+};
+
+// Sodium acetate: The acetate, and a stray sodium counter-ion listed first.
+const SODIUM_ACETATE: &str = "\
+sodium acetate
+
+
+  5  3  0  0  0  0  0  0  0  0999 V2000
+    4.0000    0.0000    0.0000 Na  0  3  0  0  0  0  0  0  0  0  0  0
+    0.0000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+    1.5000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+    2.1000    1.0500    0.0000 O   0  0  0  0  0  0  0  0  0  0  0  0
+    2.1000   -1.0500    0.0000 O   0  5  0  0  0  0  0  0  0  0  0  0
+  2  3  1  0  0  0  0
+  3  4  2  0  0  0  0
+  3  5  1  0  0  0  0
+M  CHG  2   1   1   5  -1
+M  END
+$$$$
+";
+
+#[test]
+fn strips_counter_ion() {
+    let sdf = Sdf::new(SODIUM_ACETATE).unwrap();
+
+    let components = connected_components(&sdf.atoms, &sdf.bonds);
+    assert_eq!(components, vec![vec![0], vec![1, 2, 3, 4]]);
+
+    let (atoms, bonds) = largest_component(&sdf.atoms, &sdf.bonds);
+    assert_eq!(atoms.len(), 4);
+    assert_eq!(
+        atoms.iter().map(|a| a.element).collect::<Vec<_>>(),
+        vec![Carbon, Carbon, Oxygen, Oxygen]
+    );
+    assert_eq!(
+        atoms.iter().map(|a| a.serial_number).collect::<Vec<_>>(),
+        vec![1, 2, 3, 4]
+    );
+    assert_eq!(atoms[3].formal_charge, Some(-1));
+
+    let pairs: Vec<_> = bonds.iter().map(|b| (b.atom_0_sn, b.atom_1_sn)).collect();
+    assert_eq!(pairs, vec![(1, 2), (2, 3), (2, 4)]);
+}
+
+/// A backbone-only residue starting at x, with N, CA, and C atoms 1.4 Å apart along x.
+fn residue(sn: u32, x: f64, atoms: &mut Vec<AtomGeneric>) -> ResidueGeneric {
+    let first_sn = atoms.len() as u32 + 1;
+    for (i, (el, name)) in [
+        (Nitrogen, AtomTypeInRes::N),
+        (Carbon, AtomTypeInRes::CA),
+        (Carbon, AtomTypeInRes::C),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        atoms.push(AtomGeneric {
+            serial_number: first_sn + i as u32,
+            posit: Vec3::new(x + 1.4 * i as f64, 0., 0.),
+            element: el,
+            type_in_res: Some(name),
+            ..Default::default()
+        });
+    }
+
+    ResidueGeneric {
+        serial_number: sn,
+        res_type: ResidueType::AminoAcid(AminoAcid::Gly),
+        atom_sns: (first_sn..first_sn + 3).collect(),
+        end: ResidueEnd::Internal,
+        entity_id: None,
+        auth_serial_number: None,
+        insertion_code: None,
+    }
+}
+
+#[test]
+fn chains_from_peptide_segments() {
+    let mut atoms = Vec::new();
+    // Each residue spans 2.8 Å; the next starts 1.33 Å after the previous C.
+    let mut residues = vec![
+        residue(1, 0., &mut atoms),
+        residue(2, 4.13, &mut atoms),
+        // A gap: This segment is far from the first, though numbering continues.
+        residue(3, 30., &mut atoms),
+        residue(4, 34.13, &mut atoms),
+    ];
+
+    // A water, after the protein.
+    atoms.push(AtomGeneric {
+        serial_number: 13,
+        posit: Vec3::new(50., 0., 0.),
+        element: Element::Oxygen,
+        hetero: true,
+        ..Default::default()
+    });
+    residues.push(ResidueGeneric {
+        serial_number: 5,
+        res_type: ResidueType::Water,
+        atom_sns: vec![13],
+        end: ResidueEnd::Hetero,
+        entity_id: None,
+        auth_serial_number: None,
+        insertion_code: None,
+    });
+
+    let chains = assign_chains(&atoms, &residues, &[]);
+
+    assert_eq!(chains.len(), 3);
+    assert_eq!(chains[0].id, "A");
+    assert_eq!(chains[0].residue_sns, vec![1, 2]);
+    assert_eq!(chains[0].atom_sns, (1..=6).collect::<Vec<_>>());
+    assert_eq!(chains[1].id, "B");
+    assert_eq!(chains[1].residue_sns, vec![3, 4]);
+    assert_eq!(chains[2].id, "C");
+    assert_eq!(chains[2].residue_sns, vec![5]);
+}
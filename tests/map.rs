@@ -0,0 +1,530 @@
+use std::{
+    fs,
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom, Write},
+};
+
+use bio_files::{
+    AtomGeneric, DensityHeaderInner, DensityMap, MapHeader, MmCif, NormMode, OriginMode, UnitCell,
+    bounding_unit_cell, cif_sf::CifStructureFactors, model_density, read_map_data, read_map_header,
+    read_map_voxels,
+};
+use flate2::{Compression, write::GzEncoder};
+use lin_alg::f64::Vec3;
+use na_seq::Element;
+
+fn test_map() -> DensityMap {
+    let (nx, ny, nz) = (4, 3, 2);
+
+    let inner = DensityHeaderInner {
+        cell: UnitCell::new(8., 6., 4., 90., 90., 90.),
+        mapc: 1,
+        mapr: 2,
+        maps: 3,
+        mx: nx,
+        my: ny,
+        mz: nz,
+        nxstart: 0,
+        nystart: 0,
+        nzstart: 0,
+        ispg: 1,
+        nsymbt: 0,
+        version: 20140,
+        xorigin: None,
+        yorigin: None,
+        zorigin: None,
+    };
+
+    let hdr = MapHeader {
+        inner,
+        nx,
+        ny,
+        nz,
+        mode: 2,
+        dmin: 0.,
+        dmax: 0.,
+        dmean: 0.,
+        nlabl: 0,
+        labels: Vec::new(),
+    };
+
+    let data = (0..nx * ny * nz).map(|v| v as f32).collect();
+    DensityMap::new(hdr, data).unwrap()
+}
+
+#[test]
+fn header_and_data_read_separately() {
+    let path = std::env::temp_dir().join("bio_files_test_header_data.map");
+    let map = test_map();
+    map.save(&path).unwrap();
+
+    let mut r = BufReader::new(File::open(&path).unwrap());
+    let hdr = read_map_header(&mut r).unwrap();
+    assert_eq!((hdr.nx, hdr.ny, hdr.nz), (4, 3, 2));
+    assert_eq!(hdr.mode, 2);
+    assert_eq!(hdr.dmax, 23.);
+
+    let data = read_map_data(&mut r, &hdr).unwrap();
+    assert_eq!(data, map.data);
+
+    let loaded = DensityMap::from_reader(File::open(&path).unwrap()).unwrap();
+    assert_eq!(loaded.data, map.data);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn reads_voxel_subregion() {
+    let path = std::env::temp_dir().join("bio_files_test_voxels.map");
+    test_map().save(&path).unwrap();
+
+    let mut r = File::open(&path).unwrap();
+    let hdr = read_map_header(&mut r).unwrap();
+
+    // Columns 1..3, rows 1..3, section 1: offsets (1 * 3 + j) * 4 + i.
+    let vals = read_map_voxels(&mut r, &hdr, [1, 1, 1], [3, 3, 2]).unwrap();
+    assert_eq!(vals, vec![17., 18., 21., 22.]);
+
+    assert!(read_map_voxels(&mut r, &hdr, [0, 0, 0], [5, 1, 1]).is_err());
+
+    // One read per section, or one for whole sections; this keeps HTTP range requests few.
+    let mut r = CountingReader {
+        inner: File::open(&path).unwrap(),
+        reads: 0,
+    };
+    let hdr = read_map_header(&mut r).unwrap();
+    assert_eq!(r.reads, 1);
+
+    r.reads = 0;
+    let vals = read_map_voxels(&mut r, &hdr, [1, 0, 0], [3, 3, 2]).unwrap();
+    assert_eq!(vals.len(), 2 * 3 * 2);
+    assert_eq!(vals[6..8], [13., 14.]);
+    assert_eq!(r.reads, 2);
+
+    r.reads = 0;
+    let vals = read_map_voxels(&mut r, &hdr, [0, 0, 0], [4, 3, 2]).unwrap();
+    assert_eq!(vals, test_map().data);
+    assert_eq!(r.reads, 1);
+
+    // The header, then the density block.
+    r.reads = 0;
+    let map = DensityMap::from_reader(&mut r).unwrap();
+    assert_eq!(map.data, test_map().data);
+    assert_eq!(r.reads, 2);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+/// Counts calls to `read`, e.g. to check how many range requests a remote read would make.
+struct CountingReader<R> {
+    inner: R,
+    reads: usize,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.reads += 1;
+        self.inner.read(buf)
+    }
+}
+
+impl<R: Seek> Seek for CountingReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+#[test]
+fn min_image_across_faces() {
+    let cell = UnitCell::new(20., 20., 20., 90., 90., 90.);
+    let a = Vec3::new(0.5, 10., 10.);
+    let b = Vec3::new(19.5, 10., 10.);
+    assert!((cell.min_image_distance(a, b) - 1.).abs() < 1e-9);
+
+    // Across a corner.
+    let a = Vec3::new(0.5, 0.5, 10.);
+    let b = Vec3::new(19.5, 19.5, 10.);
+    assert!((cell.min_image_distance(a, b) - 2_f64.sqrt()).abs() < 1e-9);
+
+    // Triclinic: Atoms near opposite a-faces of a skewed cell.
+    let cell = UnitCell::new(20., 20., 20., 90., 90., 60.);
+    let a = cell.fractional_to_cartesian(Vec3::new(0.02, 0.5, 0.5));
+    let b = cell.fractional_to_cartesian(Vec3::new(0.98, 0.5, 0.5));
+    assert!((cell.min_image_distance(a, b) - 0.8).abs() < 1e-9);
+}
+
+#[test]
+fn load_extensions_and_gzip() {
+    let dir = std::env::temp_dir();
+    let path_map = dir.join("bio_files_test_ext.map");
+    let path_mrc = dir.join("bio_files_test_ext.mrc");
+    let path_gz = dir.join("bio_files_test_ext.map.gz");
+
+    let map = test_map();
+    map.save(&path_map).unwrap();
+    fs::copy(&path_map, &path_mrc).unwrap();
+
+    let bytes = fs::read(&path_map).unwrap();
+    let mut enc = GzEncoder::new(File::create(&path_gz).unwrap(), Compression::default());
+    enc.write_all(&bytes).unwrap();
+    enc.finish().unwrap();
+
+    for path in [&path_map, &path_mrc, &path_gz] {
+        let loaded = DensityMap::load(path).unwrap();
+        assert_eq!(loaded.data, map.data);
+        assert_eq!((loaded.hdr.nx, loaded.hdr.ny, loaded.hdr.nz), (4, 3, 2));
+    }
+
+    let from_bytes = DensityMap::from_bytes(&bytes).unwrap();
+    assert_eq!(from_bytes.data, map.data);
+}
+
+#[test]
+fn normalization_modes() {
+    let map = test_map();
+    assert_eq!((map.min(), map.max()), (0., 23.));
+    assert_eq!(map.mean(), 11.5);
+
+    assert_eq!(map.normalize(0., NormMode::MinMax), 0.);
+    assert_eq!(map.normalize(23., NormMode::MinMax), 1.);
+    assert_eq!(map.normalize(11.5, NormMode::MinMax), 0.5);
+
+    assert_eq!(map.normalize(7., NormMode::Raw), 7.);
+    assert_eq!(map.normalize(7., NormMode::Sigma), map.density_to_sig(7.));
+    assert!((map.normalize(11.5 + map.sigma(), NormMode::Sigma) - 1.).abs() < 1e-5);
+}
+
+#[test]
+fn enclosed_volume_decreases_with_level() {
+    let map = test_map();
+
+    // 8 × 6 × 4 Å cell, with 24 voxels of 2 Å³ each.
+    assert!((map.hdr.inner.cell.volume() - 192.).abs() < 1e-9);
+    assert!((map.enclosed_volume(-10.) - 192.).abs() < 1e-9);
+    assert_eq!(map.enclosed_fraction(-10.), 1.);
+    assert_eq!(map.enclosed_volume(10.), 0.);
+
+    let levels = [-1.5, -1., -0.5, 0., 0.5, 1., 1.5];
+    let volumes: Vec<f64> = levels.iter().map(|l| map.enclosed_volume(*l)).collect();
+    assert!(volumes.windows(2).all(|w| w[1] < w[0]));
+
+    // Data are 0..24, evenly spaced, so half the voxels are above the mean.
+    assert_eq!(map.enclosed_fraction(0.), 0.5);
+}
+
+#[test]
+fn save_load_round_trip() {
+    let path_0 = std::env::temp_dir().join("bio_files_test_round_trip_0.map");
+    let path_1 = std::env::temp_dir().join("bio_files_test_round_trip_1.map");
+
+    let mut map = test_map();
+    map.hdr.inner.cell = UnitCell::new(60., 70., 80., 90., 100.5, 90.);
+    map.hdr.inner.mapc = 2;
+    map.hdr.inner.mapr = 1;
+    map.data = (0..24).map(|v| (v as f32 * 0.37).sin()).collect();
+    map.save(&path_0).unwrap();
+
+    let loaded = DensityMap::load(&path_0).unwrap();
+    loaded.save(&path_1).unwrap();
+    let reloaded = DensityMap::load(&path_1).unwrap();
+
+    for m in [&loaded, &reloaded] {
+        assert_eq!((m.hdr.nx, m.hdr.ny, m.hdr.nz), (4, 3, 2));
+        assert_eq!(
+            (m.hdr.inner.mapc, m.hdr.inner.mapr, m.hdr.inner.maps),
+            (2, 1, 3)
+        );
+
+        let cell = &m.hdr.inner.cell;
+        assert_eq!((cell.a, cell.b, cell.c), (60., 70., 80.));
+        assert!((cell.beta.to_degrees() - 100.5).abs() < 1e-4);
+        assert!((cell.alpha.to_degrees() - 90.).abs() < 1e-4);
+
+        let bits: Vec<u32> = m.data.iter().map(|v| v.to_bits()).collect();
+        let bits_expected: Vec<u32> = map.data.iter().map(|v| v.to_bits()).collect();
+        assert_eq!(bits, bits_expected);
+    }
+}
+
+#[test]
+fn reads_int16_mode() {
+    let path_f32 = std::env::temp_dir().join("bio_files_test_mode_2.map");
+    let path_i16 = std::env::temp_dir().join("bio_files_test_mode_1.map");
+
+    let mut map = test_map();
+    let values: Vec<i16> = (0..24).map(|v| (v - 8) * 150).collect();
+    map.data = values.iter().map(|&v| v as f32).collect();
+    map.save(&path_f32).unwrap();
+
+    // Rewrite as mode 1: the header statistics written for the float map remain valid.
+    let bytes = fs::read(&path_f32).unwrap();
+    let mut out = bytes[..1024].to_vec();
+    out[12..16].copy_from_slice(&1_i32.to_le_bytes());
+    for v in &values {
+        out.extend(v.to_le_bytes());
+    }
+    fs::write(&path_i16, out).unwrap();
+
+    let loaded = DensityMap::load(&path_i16).unwrap();
+    assert_eq!(loaded.hdr.mode, 1);
+    assert_eq!(loaded.data, map.data);
+
+    let min = loaded.data.iter().copied().fold(f32::INFINITY, f32::min);
+    let max = loaded
+        .data
+        .iter()
+        .copied()
+        .fold(f32::NEG_INFINITY, f32::max);
+    let mean = loaded.data.iter().sum::<f32>() / loaded.data.len() as f32;
+    assert_eq!(min, loaded.hdr.dmin);
+    assert_eq!(max, loaded.hdr.dmax);
+    assert!((mean - loaded.hdr.dmean).abs() < 1e-3);
+
+    let mut r = BufReader::new(File::open(&path_i16).unwrap());
+    let hdr = read_map_header(&mut r).unwrap();
+    let sub = read_map_voxels(&mut r, &hdr, [1, 1, 1], [3, 3, 2]).unwrap();
+    assert_eq!(
+        sub,
+        vec![map.data[17], map.data[18], map.data[21], map.data[22]]
+    );
+}
+
+#[test]
+fn density_at_point_uses_origin() {
+    // A 1 Å grid covering an 8 Å cube, starting 5 voxels from the cell origin.
+    let mut map = test_map();
+    map.hdr.inner.cell = UnitCell::new(20., 20., 20., 90., 90., 90.);
+    (map.hdr.inner.mx, map.hdr.inner.my, map.hdr.inner.mz) = (20, 20, 20);
+    (
+        map.hdr.inner.nxstart,
+        map.hdr.inner.nystart,
+        map.hdr.inner.nzstart,
+    ) = (5, 5, 5);
+    (map.hdr.nx, map.hdr.ny, map.hdr.nz) = (8, 8, 8);
+
+    // A single peak at file voxel (3, 2, 4), i.e. grid point (8, 7, 9).
+    let mut data = vec![0.; 512];
+    data[(4 * 8 + 2) * 8 + 3] = 10.;
+    let map = DensityMap::new(map.hdr, data).unwrap();
+
+    let atom = Vec3::new(8., 7., 9.);
+    assert_eq!(map.density_at_point(atom), 10.);
+    assert!((map.density_at_point_trilinear(atom) - 10.).abs() < 1e-5);
+
+    // Halfway to the next grid point along x.
+    let between = Vec3::new(8.5, 7., 9.);
+    assert!((map.density_at_point_trilinear(between) - 5.).abs() < 1e-5);
+
+    // Ignoring the origin puts the peak elsewhere.
+    assert_eq!(map.density_at_point_raw(atom), 0.);
+    assert!(map.density_at_point_trilinear_raw(atom).abs() < 1e-5);
+}
+
+#[test]
+fn origin_sources_disagree() {
+    // `n[xyz]start` puts the grid at 5 Å; the MRC-2014 origin words put it at 2 Å.
+    let mut map = test_map();
+    map.hdr.inner.cell = UnitCell::new(20., 20., 20., 90., 90., 90.);
+    (map.hdr.inner.mx, map.hdr.inner.my, map.hdr.inner.mz) = (20, 20, 20);
+    (
+        map.hdr.inner.nxstart,
+        map.hdr.inner.nystart,
+        map.hdr.inner.nzstart,
+    ) = (5, 5, 5);
+    (
+        map.hdr.inner.xorigin,
+        map.hdr.inner.yorigin,
+        map.hdr.inner.zorigin,
+    ) = (Some(2.), Some(2.), Some(2.));
+    (map.hdr.nx, map.hdr.ny, map.hdr.nz) = (8, 8, 8);
+
+    // A single peak at file voxel (3, 2, 4).
+    let mut data = vec![0.; 512];
+    data[(4 * 8 + 2) * 8 + 3] = 10.;
+    let map = DensityMap::new(map.hdr, data).unwrap();
+
+    let at_words = Vec3::new(5., 4., 6.);
+    let at_nstart = Vec3::new(8., 7., 9.);
+
+    // Auto prefers the origin words.
+    assert_eq!(map.origin_mode, OriginMode::Auto);
+    assert_eq!(map.density_at_point(at_words), 10.);
+    assert_eq!(map.density_at_point(at_nstart), 0.);
+
+    let map = map.with_origin_mode(OriginMode::NStart);
+    assert!((map.origin_frac.x - 0.25).abs() < 1e-9);
+    assert_eq!(map.density_at_point(at_nstart), 10.);
+    assert_eq!(map.density_at_point(at_words), 0.);
+
+    let map = map.with_origin_mode(OriginMode::Mrc2014);
+    assert!((map.origin_frac.x - 0.1).abs() < 1e-9);
+    assert_eq!(map.density_at_point(at_words), 10.);
+}
+
+#[test]
+fn bounding_unit_cell_contains_atoms() {
+    let atoms: Vec<AtomGeneric> = [(-3.2, 1., 0.5), (4.1, -2.5, 7.), (0., 0., -6.3)]
+        .into_iter()
+        .map(|(x, y, z)| AtomGeneric {
+            posit: Vec3::new(x, y, z),
+            ..Default::default()
+        })
+        .collect();
+
+    let (cell, origin) = bounding_unit_cell(&atoms, 1.5);
+    assert!((cell.a - 10.3).abs() < 1e-9);
+    assert!((cell.b - 6.5).abs() < 1e-9);
+    assert!((cell.c - 16.3).abs() < 1e-9);
+
+    for a in &atoms {
+        let f = cell.cartesian_to_fractional(a.posit - origin);
+        for v in [f.x, f.y, f.z] {
+            assert!(v > 0. && v < 1.);
+        }
+    }
+
+    // Zero extents, e.g. a planar molecule without padding, are widened instead of panicking.
+    let (cell, origin) = bounding_unit_cell(&atoms[..1], 0.);
+    assert!((cell.a - 1.).abs() < 1e-9);
+    assert!((cell.c - 1.).abs() < 1e-9);
+    assert!((origin.x + 3.7).abs() < 1e-9);
+
+    let (cell, _) = bounding_unit_cell(&[], 0.);
+    assert!((cell.b - 1.).abs() < 1e-9);
+}
+
+#[test]
+fn labels_round_trip() {
+    let path = std::env::temp_dir().join("bio_files_test_labels.map");
+    let mut map = test_map();
+
+    let hdr = &map.hdr;
+    assert_eq!(hdr.nlabl, 0);
+    assert!(hdr.labels.is_empty());
+
+    map.hdr.labels = vec![
+        "::::EMDATABANK.org::::EMD-1234::::".to_owned(),
+        "Created by bio_files   ".to_owned(),
+    ];
+    map.save(&path).unwrap();
+
+    let loaded = DensityMap::load(&path).unwrap();
+    assert_eq!(loaded.hdr.nlabl, 2);
+    assert_eq!(
+        loaded.hdr.labels,
+        vec!["::::EMDATABANK.org::::EMD-1234::::", "Created by bio_files"]
+    );
+    assert_eq!(loaded.data, map.data);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn model_density_scales_with_electrons() {
+    let cell = UnitCell::new(10., 10., 10., 90., 90., 90.);
+    let grid = (50, 50, 50);
+    let voxel_vol = cell.volume() / (50. * 50. * 50.);
+
+    let atom = |element, b_factor| AtomGeneric {
+        posit: Vec3::new(5., 5., 5.),
+        element,
+        b_factor,
+        ..Default::default()
+    };
+    let total = |map: &DensityMap| map.data.iter().map(|v| *v as f64).sum::<f64>() * voxel_vol;
+
+    let carbon = model_density(&[atom(Element::Carbon, None)], &cell, grid, 2.).unwrap();
+    let oxygen = model_density(&[atom(Element::Oxygen, None)], &cell, grid, 2.).unwrap();
+
+    // Integrated density is the electron count.
+    assert!((total(&carbon) - 6.).abs() < 0.05);
+    assert!((total(&oxygen) - 8.).abs() < 0.05);
+
+    // The peak is at the atom.
+    let peak = carbon.density_at_point(Vec3::new(5., 5., 5.));
+    assert!((peak - carbon.max()).abs() < 1e-6);
+    assert!(peak > 0.);
+
+    // A B-factor broadens the peak without changing the electron count.
+    let blurred = model_density(&[atom(Element::Carbon, Some(40.))], &cell, grid, 2.).unwrap();
+    assert!((total(&blurred) - 6.).abs() < 0.05);
+    assert!(blurred.max() < carbon.max());
+
+    // Density wraps across the cell boundary.
+    let mut edge = atom(Element::Carbon, None);
+    edge.posit = Vec3::new(0., 5., 5.);
+    let wrapped = model_density(&[edge], &cell, grid, 2.).unwrap();
+    assert!((total(&wrapped) - 6.).abs() < 0.05);
+
+    // A zero grid dimension is an error, not a panic.
+    assert!(model_density(&[atom(Element::Carbon, None)], &cell, (0, 50, 50), 2.).is_err());
+}
+
+// Cell and a few atoms from 1CRN, and a structure factor excerpt with its cell as deposited there.
+const CRN_MODEL: &str = "data_1CRN
+_entry.id 1CRN
+_cell.length_a    40.960
+_cell.length_b    18.650
+_cell.length_c    22.520
+_cell.angle_alpha 90.00
+_cell.angle_beta  90.77
+_cell.angle_gamma 90.00
+#
+loop_
+_atom_site.group_PDB
+_atom_site.id
+_atom_site.type_symbol
+_atom_site.label_atom_id
+_atom_site.label_alt_id
+_atom_site.label_comp_id
+_atom_site.label_asym_id
+_atom_site.label_seq_id
+_atom_site.Cartn_x
+_atom_site.Cartn_y
+_atom_site.Cartn_z
+_atom_site.occupancy
+_atom_site.B_iso_or_equiv
+ATOM 1 N N . THR A 1 17.047 14.099 3.625 1.00 13.79
+ATOM 2 C CA . THR A 1 16.967 12.784 4.338 1.00 10.80
+#
+";
+
+const CRN_SF: &str = "data_r1crnsf
+_cell.length_a    40.96
+_cell.length_b    18.65
+_cell.length_c    22.52
+_cell.angle_alpha 90.0
+_cell.angle_beta  90.77
+_cell.angle_gamma 90.0
+_symmetry.Int_Tables_number 4
+loop_
+_refln.index_h
+_refln.index_k
+_refln.index_l
+_refln.pdbx_FWT
+_refln.pdbx_PHWT
+1 0 0 120.5 0.0
+0 1 1 85.2 45.0
+2 1 -1 40.1 180.0
+";
+
+#[test]
+fn model_and_map_cells_compatible() {
+    let cif = MmCif::new(CRN_MODEL).unwrap();
+    let cell = cif.unit_cell.unwrap();
+    assert!((cell.beta.to_degrees() - 90.77).abs() < 1e-9);
+
+    let sf = CifStructureFactors::new(CRN_SF).unwrap();
+    assert!(cell.is_compatible(&sf.header.cell));
+    assert!(sf.header.cell.is_compatible(&cell));
+
+    // A different crystal form.
+    let other = CifStructureFactors::new(&CRN_SF.replace("18.65", "24.10")).unwrap();
+    assert!(!cell.is_compatible(&other.header.cell));
+    let other = CifStructureFactors::new(&CRN_SF.replace("90.77", "95.00")).unwrap();
+    assert!(!cell.is_compatible(&other.header.cell));
+
+    // Cryo-EM and NMR entries have no cell.
+    let no_cell = MmCif::new(&CRN_MODEL.replace("_cell.length_c    22.520\n", "")).unwrap();
+    assert!(no_cell.unit_cell.is_none());
+}
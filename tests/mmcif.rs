@@ -0,0 +1,823 @@
+use std::{fs, io::Write};
+
+use bio_files::{
+    AltLocPolicy, BondType, HelixClass, MmCif, SecondaryStructure, SsCode,
+    pdb::{Pdb, write_pdb_atoms},
+};
+use flate2::{Compression, write::GzEncoder};
+
+const CIF: &str = "data_TEST
+_entry.id TEST
+#
+loop_
+_struct_conf.conf_type_id
+_struct_conf.id
+_struct_conf.beg_label_asym_id
+_struct_conf.beg_label_seq_id
+_struct_conf.end_label_asym_id
+_struct_conf.end_label_seq_id
+HELX_P HELX_P1 A 2 A 4
+#
+loop_
+_struct_sheet_range.sheet_id
+_struct_sheet_range.id
+_struct_sheet_range.beg_label_asym_id
+_struct_sheet_range.beg_label_seq_id
+_struct_sheet_range.end_label_asym_id
+_struct_sheet_range.end_label_seq_id
+AA1 1 A 6 A 7
+#
+loop_
+_atom_site.group_PDB
+_atom_site.id
+_atom_site.type_symbol
+_atom_site.label_atom_id
+_atom_site.label_alt_id
+_atom_site.label_comp_id
+_atom_site.label_asym_id
+_atom_site.label_seq_id
+_atom_site.Cartn_x
+_atom_site.Cartn_y
+_atom_site.Cartn_z
+_atom_site.occupancy
+ATOM 1 N N . ALA A 1 3.800 0.500 0.000 1.00
+ATOM 2 C CA . ALA A 1 3.800 1.000 0.000 1.00
+ATOM 3 N N . ALA A 2 7.600 1.500 0.000 1.00
+ATOM 4 C CA . ALA A 2 7.600 2.000 0.000 1.00
+ATOM 5 N N . ALA A 3 11.400 2.500 0.000 1.00
+ATOM 6 C CA . ALA A 3 11.400 3.000 0.000 1.00
+ATOM 7 N N . ALA A 4 15.200 3.500 0.000 1.00
+ATOM 8 C CA . ALA A 4 15.200 4.000 0.000 1.00
+ATOM 9 N N . ALA A 5 19.000 4.500 0.000 1.00
+ATOM 10 C CA . ALA A 5 19.000 5.000 0.000 1.00
+ATOM 11 N N . ALA A 6 22.800 5.500 0.000 1.00
+ATOM 12 C CA . ALA A 6 22.800 6.000 0.000 1.00
+ATOM 13 N N . ALA A 7 26.600 6.500 0.000 1.00
+ATOM 14 C CA . ALA A 7 26.600 7.000 0.000 1.00
+#
+";
+
+#[test]
+fn residue_ss_from_ranges() {
+    let cif = MmCif::new(CIF).unwrap();
+    let ss = cif.residue_ss();
+
+    assert_eq!(ss.len(), 7);
+    assert_eq!(ss[&("A".to_owned(), 1)], SsCode::Coil);
+    for sn in 2..=4 {
+        assert_eq!(ss[&("A".to_owned(), sn)], SsCode::AlphaHelix);
+    }
+    assert_eq!(ss[&("A".to_owned(), 5)], SsCode::Coil);
+    assert_eq!(ss[&("A".to_owned(), 6)], SsCode::Strand);
+    assert_eq!(ss[&("A".to_owned(), 7)], SsCode::Strand);
+
+    assert_eq!(ss_codes(&cif, "A"), "-HHH-EE");
+}
+
+/// Secondary structure codes for residues 1 to 7 of a chain.
+fn ss_codes(cif: &MmCif, chain_id: &str) -> String {
+    let ss = cif.residue_ss();
+    (1..=7)
+        .map(|sn| ss[&(chain_id.to_owned(), sn)].to_char())
+        .collect()
+}
+
+#[test]
+fn residue_ss_two_chains() {
+    // Chain B has the same residue serial numbers as chain A, but no secondary structure.
+    let chain_b: String = CIF
+        .lines()
+        .filter(|l| l.starts_with("ATOM"))
+        .map(|l| {
+            let cols: Vec<&str> = l.split_whitespace().collect();
+            let sn: u32 = cols[1].parse().unwrap();
+            format!(
+                "ATOM {} {} {} . ALA B {} {} {} 5.000 1.00\n",
+                sn + 14,
+                cols[2],
+                cols[3],
+                cols[7],
+                cols[8],
+                cols[9]
+            )
+        })
+        .collect();
+    let text = CIF.replace("1.00\n#\n", &format!("1.00\n{chain_b}#\n"));
+
+    let cif = MmCif::new(&text).unwrap();
+    assert_eq!(cif.chains.len(), 2);
+    assert_eq!(cif.residue_ss().len(), 14);
+
+    assert_eq!(ss_codes(&cif, "A"), "-HHH-EE");
+    assert_eq!(ss_codes(&cif, "B"), "-------");
+}
+
+#[test]
+fn helix_classes() {
+    let text = CIF.replace(
+        "_struct_conf.end_label_seq_id\nHELX_P HELX_P1 A 2 A 4\n",
+        "_struct_conf.end_label_seq_id\n_struct_conf.pdbx_PDB_helix_class\n\
+         HELX_P HELX_P1 A 2 A 4 5\nHELX_RH_PI_P HELX_P2 A 5 A 5 ?\n",
+    );
+    let cif = MmCif::new(&text).unwrap();
+
+    let helices: Vec<_> = cif
+        .secondary_structure
+        .iter()
+        .filter(|ss| ss.sec_struct == SecondaryStructure::Helix)
+        .collect();
+    assert_eq!(helices.len(), 2);
+    assert_eq!(helices[0].helix_class, Some(HelixClass::ThreeTen));
+    assert_eq!(helices[1].helix_class, Some(HelixClass::Pi));
+
+    assert_eq!(ss_codes(&cif, "A"), "-GGGIEE");
+}
+
+const CIF_CONN: &str = "data_CONN
+_entry.id CONN
+#
+loop_
+_struct_conn.id
+_struct_conn.conn_type_id
+_struct_conn.pdbx_leaving_atom_flag
+_struct_conn.ptnr1_label_asym_id
+_struct_conn.ptnr1_label_comp_id
+_struct_conn.ptnr1_label_seq_id
+_struct_conn.ptnr1_label_atom_id
+_struct_conn.ptnr1_auth_seq_id
+_struct_conn.ptnr2_label_asym_id
+_struct_conn.ptnr2_label_comp_id
+_struct_conn.ptnr2_label_seq_id
+_struct_conn.ptnr2_label_atom_id
+_struct_conn.ptnr2_auth_seq_id
+_struct_conn.details
+_struct_conn.pdbx_value_order
+disulf1 disulf ? A CYS 1 SG 1 A CYS 2 SG 2 ? ?
+metalc1 metalc ? A CYS 2 SG 2 B ZN . ZN 101
+'metal coordination' ?
+hydrog1 hydrog ? A CYS 1 N 1 A CYS 2 O 2 ? ?
+#
+loop_
+_chem_comp.id
+_chem_comp.type
+_chem_comp.mon_nstd_flag
+_chem_comp.name
+_chem_comp.pdbx_synonyms
+_chem_comp.formula
+_chem_comp.formula_weight
+CYS 'L-peptide linking' y CYSTEINE ? 'C3 H7 N O2 S' 121.158
+ZN non-polymer . 'ZINC ION' ? 'Zn 2' 65.409
+#
+loop_
+_atom_site.group_PDB
+_atom_site.id
+_atom_site.type_symbol
+_atom_site.label_atom_id
+_atom_site.label_alt_id
+_atom_site.label_comp_id
+_atom_site.label_asym_id
+_atom_site.label_seq_id
+_atom_site.Cartn_x
+_atom_site.Cartn_y
+_atom_site.Cartn_z
+_atom_site.occupancy
+_atom_site.auth_seq_id
+ATOM 1 N N . CYS A 1 0.000 0.000 0.000 1.00 1
+ATOM 2 C CA . CYS A 1 1.458 0.000 0.000 1.00 1
+ATOM 3 S SG . CYS A 1 2.000 1.700 0.000 1.00 1
+ATOM 4 N N . CYS A 2 3.800 0.000 0.000 1.00 2
+ATOM 5 O O . CYS A 2 4.500 1.200 0.000 1.00 2
+ATOM 6 S SG . CYS A 2 3.300 1.900 0.500 1.00 2
+HETATM 7 ZN ZN . ZN B . 3.000 4.200 0.500 1.00 101
+#
+";
+
+#[test]
+fn struct_conn_bonds() {
+    let cif = MmCif::new(CIF_CONN).unwrap();
+
+    let bonds: Vec<_> = cif
+        .bonds
+        .iter()
+        .map(|b| (b.atom_0_sn, b.atom_1_sn, b.bond_type))
+        .collect();
+    assert_eq!(
+        bonds,
+        vec![
+            (3, 6, BondType::Single),
+            (6, 7, BondType::Dummy),
+            (1, 5, BondType::NotConnected),
+        ]
+    );
+
+    // No `_struct_conn` loop.
+    assert!(MmCif::new(CIF).unwrap().bonds.is_empty());
+}
+
+#[test]
+fn chem_comps() {
+    let cif = MmCif::new(CIF_CONN).unwrap();
+    assert_eq!(cif.chem_comps.len(), 2);
+
+    let zn = &cif.chem_comps["ZN"];
+    assert_eq!(zn.name, "ZINC ION");
+    assert_eq!(zn.formula.as_deref(), Some("Zn 2"));
+    assert_eq!(zn.type_, "non-polymer");
+
+    assert_eq!(cif.chem_comps["CYS"].type_, "L-peptide linking");
+    assert!(MmCif::new(CIF).unwrap().chem_comps.is_empty());
+}
+
+#[test]
+fn semicolon_text_fields() {
+    let text = CIF.replacen(
+        "_entry.id TEST\n",
+        "_entry.id TEST
+_struct.title
+;Crystal structure of a test protein,
+_with a line that looks like a tag
+;
+_struct.pdbx_descriptor 'Test protein'
+#
+loop_
+_entity.id
+_entity.type
+_entity.pdbx_description
+1 polymer
+;A long description,
+loop_ spanning lines
+;
+2 water 'water'
+#
+loop_
+_chem_comp.id
+_chem_comp.type
+_chem_comp.name
+ALA 'L-peptide linking'
+;ALANINE
+;
+",
+        1,
+    );
+
+    let cif = MmCif::new(&text).unwrap();
+
+    assert_eq!(
+        cif.metadata["_struct.title"],
+        "Crystal structure of a test protein,\n_with a line that looks like a tag"
+    );
+    assert_eq!(cif.metadata["_struct.pdbx_descriptor"], "Test protein");
+    assert!(!cif.metadata.contains_key("_with"));
+    assert_eq!(cif.chem_comps["ALA"].name, "ALANINE");
+
+    // The rest of the file is still read correctly.
+    assert_eq!(cif.atoms.len(), 14);
+    assert_eq!(cif.residue_ss()[&("A".to_owned(), 3)], SsCode::AlphaHelix);
+
+    // Multi-line values are saved as text fields.
+    let path = std::env::temp_dir().join("bio_files_test_text_fields.cif");
+    cif.save(&path).unwrap();
+    let loaded = MmCif::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        loaded.metadata["_struct.title"],
+        cif.metadata["_struct.title"]
+    );
+    assert_eq!(loaded.metadata["_struct.pdbx_descriptor"], "Test protein");
+    assert_eq!(loaded.atoms.len(), 14);
+}
+
+const CIF_ALT: &str = "data_ALT
+loop_
+_atom_site.group_PDB
+_atom_site.id
+_atom_site.type_symbol
+_atom_site.label_atom_id
+_atom_site.label_alt_id
+_atom_site.label_comp_id
+_atom_site.label_asym_id
+_atom_site.label_seq_id
+_atom_site.Cartn_x
+_atom_site.Cartn_y
+_atom_site.Cartn_z
+_atom_site.occupancy
+_atom_site.B_iso_or_equiv
+ATOM 1 N N . SER A 1 0.000 0.000 0.000 1.00 12.50
+ATOM 2 C CA . SER A 1 1.450 0.000 0.000 1.00 13.10
+ATOM 3 C CB A SER A 1 2.000 1.400 0.000 0.35 20.00
+ATOM 4 C CB B SER A 1 2.000 -1.400 0.000 0.65 18.40
+ATOM 5 O OG A SER A 1 3.400 1.400 0.000 0.35 25.70
+ATOM 6 O OG B SER A 1 3.400 -1.400 0.000 0.65 22.30
+ATOM 7 O O ? SER A 1 2.000 0.000 1.200 1.00 ?
+#
+";
+
+#[test]
+fn alt_conformations() {
+    let mut cif = MmCif::new(CIF_ALT).unwrap();
+
+    assert_eq!(cif.atoms.len(), 7);
+    assert_eq!(cif.atoms[0].alt_conformation_id, None);
+    assert_eq!(cif.atoms[2].alt_conformation_id.as_deref(), Some("A"));
+    assert_eq!(cif.atoms[3].alt_conformation_id.as_deref(), Some("B"));
+    assert_eq!(cif.atoms[6].alt_conformation_id, None);
+
+    cif.collapse_alt_conformations();
+
+    let sns: Vec<u32> = cif.atoms.iter().map(|a| a.serial_number).collect();
+    assert_eq!(sns, vec![1, 2, 4, 6, 7]);
+    assert_eq!(cif.residues[0].atom_sns, vec![1, 2, 4, 6, 7]);
+    assert_eq!(cif.chains[0].atom_sns, vec![1, 2, 4, 6, 7]);
+
+    // Atoms without a name can't be matched to alternates, so they're kept, vice being collapsed
+    // together.
+    let mut cif = MmCif::new(CIF_ALT).unwrap();
+    for atom in &mut cif.atoms {
+        atom.type_in_res = None;
+        atom.type_in_res_general = None;
+    }
+    cif.collapse_alt_conformations();
+    assert_eq!(cif.atoms.len(), 7);
+}
+
+#[test]
+fn altloc_occupancy() {
+    let cif = MmCif::new(CIF_ALT).unwrap();
+    assert!(cif.check_altloc_occupancy().is_empty());
+
+    // A common deposition error: Both conformers at 0.6.
+    let text = CIF_ALT
+        .replace(" 0.35 ", " 0.60 ")
+        .replace(" 0.65 ", " 0.60 ");
+    let mut cif = MmCif::new(&text).unwrap();
+
+    let bad = cif.check_altloc_occupancy();
+    assert_eq!(bad.len(), 1);
+    assert_eq!(bad[0].0, 1);
+    assert!((bad[0].1 - 1.2).abs() < 1e-5);
+
+    cif.normalize_altloc_occupancy();
+    assert!(cif.check_altloc_occupancy().is_empty());
+
+    for i in 2..6 {
+        assert!((cif.atoms[i].occupancy.unwrap() - 0.5).abs() < 1e-5);
+    }
+    assert_eq!(cif.atoms[0].occupancy, Some(1.));
+}
+
+#[test]
+fn altlocs_grouped_by_atom_name() {
+    // Names `AtomTypeInRes` can't parse are still matched to their own alternates only.
+    let text = CIF_ALT
+        .replace("C CB A SER", "C XB1 A SER")
+        .replace("C CB B SER", "C XB1 B SER")
+        .replace("O OG A SER", "O XG1 A SER")
+        .replace("O OG B SER", "O XG1 B SER");
+    let mut cif = MmCif::new(&text).unwrap();
+    assert_eq!(cif.atoms[2].type_in_res_general.as_deref(), Some("XB1"));
+
+    assert!(cif.check_altloc_occupancy().is_empty());
+
+    cif.collapse_alt_conformations();
+    let sns: Vec<u32> = cif.atoms.iter().map(|a| a.serial_number).collect();
+    assert_eq!(sns, vec![1, 2, 4, 6, 7]);
+}
+
+#[test]
+fn flatten_altlocs() {
+    let cif = MmCif::new(CIF_ALT).unwrap();
+    let sns = |policy| -> Vec<u32> {
+        cif.flatten_altlocs(policy)
+            .iter()
+            .map(|a| a.serial_number)
+            .collect()
+    };
+
+    assert_eq!(sns(AltLocPolicy::HighestOccupancy), vec![1, 2, 4, 6, 7]);
+    assert_eq!(sns(AltLocPolicy::First), vec![1, 2, 3, 5, 7]);
+    assert_eq!(sns(AltLocPolicy::SpecificId('A')), vec![1, 2, 3, 5, 7]);
+    assert_eq!(sns(AltLocPolicy::SpecificId('B')), vec![1, 2, 4, 6, 7]);
+    // No conformer 'C'; falls back to the first.
+    assert_eq!(sns(AltLocPolicy::SpecificId('C')), vec![1, 2, 3, 5, 7]);
+
+    // The full altloc data is untouched.
+    assert_eq!(cif.atoms.len(), 7);
+
+    // Occupancy ties go to the first listed.
+    let tied = MmCif::new(&CIF_ALT.replace(" 0.65 ", " 0.35 ")).unwrap();
+    let flat = tied.flatten_altlocs(AltLocPolicy::HighestOccupancy);
+    assert_eq!(flat[2].alt_conformation_id.as_deref(), Some("A"));
+    assert_eq!(flat[3].alt_conformation_id.as_deref(), Some("A"));
+
+    // The conformer is chosen per residue, not per atom: B has the higher mean occupancy.
+    let mixed = CIF_ALT
+        .replace(
+            "C CB A SER A 1 2.000 1.400 0.000 0.35",
+            "C CB A SER A 1 2.000 1.400 0.000 0.60",
+        )
+        .replace(
+            "C CB B SER A 1 2.000 -1.400 0.000 0.65",
+            "C CB B SER A 1 2.000 -1.400 0.000 0.40",
+        )
+        .replace(
+            "O OG A SER A 1 3.400 1.400 0.000 0.35",
+            "O OG A SER A 1 3.400 1.400 0.000 0.20",
+        )
+        .replace(
+            "O OG B SER A 1 3.400 -1.400 0.000 0.65",
+            "O OG B SER A 1 3.400 -1.400 0.000 0.80",
+        );
+    let mut cif = MmCif::new(&mixed).unwrap();
+    let flat = cif.flatten_altlocs(AltLocPolicy::HighestOccupancy);
+    let sns: Vec<u32> = flat.iter().map(|a| a.serial_number).collect();
+    assert_eq!(sns, vec![1, 2, 4, 6, 7]);
+
+    cif.collapse_alt_conformations();
+    let sns: Vec<u32> = cif.atoms.iter().map(|a| a.serial_number).collect();
+    assert_eq!(sns, vec![1, 2, 4, 6, 7]);
+}
+
+#[test]
+fn b_factors() {
+    let cif = MmCif::new(CIF_ALT).unwrap();
+
+    assert_eq!(cif.atoms[0].b_factor, Some(12.5));
+    assert_eq!(cif.atoms[3].b_factor, Some(18.4));
+    assert_eq!(cif.atoms[6].b_factor, None);
+
+    let path = std::env::temp_dir().join("bio_files_test_b_factors.cif");
+    cif.save(&path).unwrap();
+    let loaded = MmCif::load(&path).unwrap();
+
+    let b: Vec<Option<f32>> = loaded.atoms.iter().map(|a| a.b_factor).collect();
+    let b_expected: Vec<Option<f32>> = cif.atoms.iter().map(|a| a.b_factor).collect();
+    assert_eq!(b, b_expected);
+    assert_eq!(loaded.atoms[2].alt_conformation_id.as_deref(), Some("A"));
+}
+
+/// Two ligands in the same chain, neither with a `label_seq_id`. Only the entity separates them.
+const CIF_LIGANDS: &str = "data_LIG
+loop_
+_atom_site.group_PDB
+_atom_site.id
+_atom_site.type_symbol
+_atom_site.label_atom_id
+_atom_site.label_alt_id
+_atom_site.label_comp_id
+_atom_site.label_asym_id
+_atom_site.label_entity_id
+_atom_site.label_seq_id
+_atom_site.Cartn_x
+_atom_site.Cartn_y
+_atom_site.Cartn_z
+_atom_site.occupancy
+HETATM 1 S S . SO4 C 2 . 0.000 0.000 0.000 1.00
+HETATM 2 O O1 . SO4 C 2 . 1.450 0.000 0.000 1.00
+HETATM 3 C C1 . GOL C 3 . 8.000 0.000 0.000 1.00
+HETATM 4 O O1 . GOL C 3 . 9.400 0.000 0.000 1.00
+HETATM 5 C C2 . GOL C 3 . 7.400 1.300 0.000 1.00
+#
+";
+
+#[test]
+fn ligands_separated_by_entity() {
+    let cif = MmCif::new(CIF_LIGANDS).unwrap();
+
+    assert_eq!(cif.residues.len(), 2);
+    assert_eq!(cif.residues[0].entity_id.as_deref(), Some("2"));
+    assert_eq!(cif.residues[0].atom_sns, vec![1, 2]);
+    assert_eq!(cif.residues[1].entity_id.as_deref(), Some("3"));
+    assert_eq!(cif.residues[1].atom_sns, vec![3, 4, 5]);
+
+    let path = std::env::temp_dir().join("bio_files_test_entities.cif");
+    cif.save(&path).unwrap();
+    let loaded = MmCif::load(&path).unwrap();
+
+    assert_eq!(loaded.residues.len(), 2);
+    assert_eq!(loaded.residues[1].entity_id.as_deref(), Some("3"));
+    assert_eq!(loaded.residues[1].atom_sns, vec![3, 4, 5]);
+
+    // Without entity IDs, the two share a key, and are merged.
+    let merged = MmCif::new(
+        &CIF_LIGANDS
+            .replace(" C 2 . ", " C . ")
+            .replace(" C 3 . ", " C . ")
+            .replace("_atom_site.label_entity_id\n", ""),
+    )
+    .unwrap();
+    assert_eq!(merged.residues.len(), 1);
+    assert_eq!(merged.residues[0].entity_id, None);
+}
+
+/// Part of an antibody heavy chain CDR3 loop, with Kabat-style insertions 100A and 100B. As in
+/// wwPDB entries, `label_seq_id` numbers residues sequentially; the insertion codes go with the
+/// author numbering in `auth_seq_id`.
+const CIF_INSERTIONS: &str = "data_FAB
+loop_
+_atom_site.group_PDB
+_atom_site.id
+_atom_site.type_symbol
+_atom_site.label_atom_id
+_atom_site.label_alt_id
+_atom_site.label_comp_id
+_atom_site.label_asym_id
+_atom_site.label_entity_id
+_atom_site.label_seq_id
+_atom_site.pdbx_PDB_ins_code
+_atom_site.Cartn_x
+_atom_site.Cartn_y
+_atom_site.Cartn_z
+_atom_site.occupancy
+_atom_site.auth_seq_id
+ATOM 1 N N . GLY H 1 102 ? 0.000 0.000 0.000 1.00 99
+ATOM 2 C CA . GLY H 1 102 ? 1.450 0.000 0.000 1.00 99
+ATOM 3 N N . TYR H 1 103 ? 3.800 0.000 0.000 1.00 100
+ATOM 4 C CA . TYR H 1 103 ? 5.200 0.000 0.000 1.00 100
+ATOM 5 N N . ASP H 1 104 A 7.600 0.000 0.000 1.00 100
+ATOM 6 C CA . ASP H 1 104 A 9.000 0.000 0.000 1.00 100
+ATOM 7 N N . SER H 1 105 B 11.40 0.000 0.000 1.00 100
+ATOM 8 C CA . SER H 1 105 B 12.80 0.000 0.000 1.00 100
+ATOM 9 N N . TRP H 1 106 ? 15.20 0.000 0.000 1.00 101
+#
+";
+
+#[test]
+fn insertion_codes() {
+    let cif = MmCif::new(CIF_INSERTIONS).unwrap();
+
+    let ids: Vec<_> = cif
+        .residues
+        .iter()
+        .map(|r| (r.serial_number, r.auth_serial_number, r.insertion_code))
+        .collect();
+    assert_eq!(
+        ids,
+        vec![
+            (102, Some(99), None),
+            (103, Some(100), None),
+            (104, Some(100), Some('A')),
+            (105, Some(100), Some('B')),
+            (106, Some(101), None)
+        ]
+    );
+    assert_eq!(cif.residues[2].atom_sns, vec![5, 6]);
+    assert_eq!(cif.chains[0].residue_sns, vec![102, 103, 104, 105, 106]);
+
+    let path = std::env::temp_dir().join("bio_files_test_insertions.cif");
+    cif.save(&path).unwrap();
+    let loaded = MmCif::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.residues.len(), 5);
+    assert_eq!(loaded.residues[3].serial_number, 105);
+    assert_eq!(loaded.residues[3].auth_serial_number, Some(100));
+    assert_eq!(loaded.residues[3].insertion_code, Some('B'));
+
+    // PDB files use author numbering, with insertion codes in column 27.
+    let mut buf = Vec::new();
+    write_pdb_atoms(&mut buf, &cif.atoms, &cif.residues, &cif.chains).unwrap();
+    let text = String::from_utf8(buf).unwrap();
+    assert!(text.contains(" ASP H 100A "));
+
+    let pdb = Pdb::new(&text).unwrap();
+    let ids: Vec<_> = pdb
+        .residues
+        .iter()
+        .map(|r| (r.serial_number, r.insertion_code))
+        .collect();
+    assert_eq!(
+        ids,
+        vec![
+            (99, None),
+            (100, None),
+            (100, Some('A')),
+            (100, Some('B')),
+            (101, None)
+        ]
+    );
+}
+
+/// Large enough to use the parallel atom-loop parser, when the `parallel` feature is enabled.
+#[test]
+fn large_atom_loop() {
+    const NUM_ATOMS: usize = 12_000;
+
+    let mut text = String::from(
+        "data_BIG
+loop_
+_atom_site.group_PDB
+_atom_site.id
+_atom_site.type_symbol
+_atom_site.label_atom_id
+_atom_site.label_alt_id
+_atom_site.label_comp_id
+_atom_site.label_asym_id
+_atom_site.label_seq_id
+_atom_site.Cartn_x
+_atom_site.Cartn_y
+_atom_site.Cartn_z
+_atom_site.occupancy
+",
+    );
+    // 4 atoms per residue, and 1,000 residues per chain. The final chain is A again, so its
+    // residues merge into the first chain's.
+    let chain_ids = ["A", "B", "A"];
+    for i in 0..NUM_ATOMS {
+        let res = i / 4;
+        let chain = chain_ids[res / 1_000];
+        let (el, name) = [("N", "N"), ("C", "CA"), ("C", "C"), ("O", "O")][i % 4];
+        text.push_str(&format!(
+            "ATOM {} {el} {name} . GLY {chain} {} {}.000 0.500 -1.250 1.00\n",
+            i + 1,
+            res + 1,
+            i % 100,
+        ));
+    }
+    text.push_str("#\n");
+
+    let cif = MmCif::new(&text).unwrap();
+
+    assert_eq!(cif.atoms.len(), NUM_ATOMS);
+    assert!(
+        cif.atoms
+            .iter()
+            .enumerate()
+            .all(|(i, a)| a.serial_number == i as u32 + 1)
+    );
+    assert_eq!(cif.atoms[101].posit.x, 1.);
+
+    assert_eq!(cif.residues.len(), 3_000);
+    assert_eq!(
+        cif.residues[2_999].atom_sns,
+        vec![11_997, 11_998, 11_999, 12_000]
+    );
+
+    assert_eq!(cif.chains.len(), 2);
+    assert_eq!(cif.chains[0].id, "A");
+    assert_eq!(cif.chains[0].residue_sns.len(), 2_000);
+    assert_eq!(cif.chains[0].atom_sns.len(), 8_000);
+    assert_eq!(cif.chains[1].residue_sns[0], 1_001);
+}
+
+#[test]
+fn load_gzipped() {
+    let dir = std::env::temp_dir();
+    let path = dir.join("bio_files_test_load.cif");
+    let path_gz = dir.join("bio_files_test_load.cif.gz");
+
+    fs::write(&path, CIF_ALT).unwrap();
+    let mut enc = GzEncoder::new(fs::File::create(&path_gz).unwrap(), Compression::default());
+    enc.write_all(CIF_ALT.as_bytes()).unwrap();
+    enc.finish().unwrap();
+
+    let plain = MmCif::load(&path).unwrap();
+    let gz = MmCif::load(&path_gz).unwrap();
+
+    assert_eq!(gz.atoms.len(), plain.atoms.len());
+    assert_eq!(gz.atoms[3].posit.y, plain.atoms[3].posit.y);
+    assert_eq!(gz.residues.len(), plain.residues.len());
+
+    fs::remove_file(&path).unwrap();
+    fs::remove_file(&path_gz).unwrap();
+}
+
+const CIF_NMR: &str = "data_NMR
+_entry.id NMR
+_exptl.method 'SOLUTION NMR'
+#
+loop_
+_atom_site.group_PDB
+_atom_site.id
+_atom_site.type_symbol
+_atom_site.label_atom_id
+_atom_site.label_alt_id
+_atom_site.label_comp_id
+_atom_site.label_asym_id
+_atom_site.label_seq_id
+_atom_site.Cartn_x
+_atom_site.Cartn_y
+_atom_site.Cartn_z
+_atom_site.occupancy
+_atom_site.pdbx_PDB_model_num
+ATOM 1 N N . GLY A 1 0.000 0.000 0.000 1.00 1
+ATOM 2 C CA . GLY A 1 1.450 0.000 0.000 1.00 1
+ATOM 3 N N . GLY A 1 0.100 0.200 -0.100 1.00 2
+ATOM 4 C CA . GLY A 1 1.520 0.150 0.080 1.00 2
+#
+";
+
+#[test]
+fn multi_model_round_trip() {
+    let cif = MmCif::new(CIF_NMR).unwrap();
+
+    // The first model provides the atoms; the rest share its topology.
+    assert_eq!(cif.atoms.len(), 2);
+    assert_eq!(cif.residues[0].atom_sns, vec![1, 2]);
+    assert_eq!(cif.models.len(), 2);
+    assert_eq!(cif.models[0][1], cif.atoms[1].posit);
+    assert!((cif.models[1][1].x - 1.52).abs() < 1e-9);
+
+    let path = std::env::temp_dir().join("bio_files_test_nmr.cif");
+    cif.save(&path).unwrap();
+    let loaded = MmCif::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.atoms.len(), 2);
+    assert_eq!(loaded.models.len(), 2);
+    for (model, model_loaded) in cif.models.iter().zip(&loaded.models) {
+        for (p, p_loaded) in model.iter().zip(model_loaded) {
+            assert!((*p - *p_loaded).magnitude() < 1e-9);
+        }
+    }
+
+    // Edits to atom positions are saved, as the first model.
+    let mut edited = cif.clone();
+    edited.atoms[1].posit.x = 2.;
+    edited.save(&path).unwrap();
+    let loaded = MmCif::load(&path).unwrap();
+    assert!((loaded.atoms[1].posit.x - 2.).abs() < 1e-9);
+    assert!((loaded.models[1][1].x - 1.52).abs() < 1e-9);
+
+    // Models that don't match the atoms.
+    edited.atoms.push(edited.atoms[1].clone());
+    assert!(edited.save(&path).is_err());
+    fs::remove_file(&path).unwrap();
+
+    // Removing alternate conformers keeps models in sync.
+    let text = CIF_NMR
+        .replace(
+            "ATOM 2 C CA . GLY A 1 1.450 0.000 0.000 1.00 1\n",
+            "ATOM 2 C CA A GLY A 1 1.450 0.000 0.000 0.60 1\n\
+             ATOM 5 C CA B GLY A 1 1.400 0.300 0.000 0.40 1\n",
+        )
+        .replace(
+            "ATOM 4 C CA . GLY A 1 1.520 0.150 0.080 1.00 2\n",
+            "ATOM 4 C CA A GLY A 1 1.520 0.150 0.080 0.60 2\n\
+             ATOM 6 C CA B GLY A 1 1.300 0.300 0.080 0.40 2\n",
+        );
+    let mut alt = MmCif::new(&text).unwrap();
+    assert_eq!(alt.models[1].len(), 3);
+    alt.collapse_alt_conformations();
+    assert_eq!(alt.atoms.len(), 2);
+    assert_eq!(alt.models[1].len(), 2);
+    assert!((alt.models[1][1].x - 1.52).abs() < 1e-9);
+    alt.save(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    // Single-model files have no separate models.
+    assert!(MmCif::new(CIF_ALT).unwrap().models.is_empty());
+
+    // Models that don't have the same atoms as the first are skipped.
+    let skipped =
+        MmCif::new(&CIF_NMR.replace("ATOM 4 C CA . GLY A 1 1.520 0.150 0.080 1.00 2\n", ""))
+            .unwrap();
+    assert_eq!(skipped.atoms.len(), 2);
+    assert!(skipped.models.is_empty());
+}
+
+#[test]
+fn unit_cell_and_space_group() {
+    let header = "data_XTAL
+_entry.id XTAL
+#
+_cell.entry_id           XTAL
+_cell.length_a           40.960
+_cell.length_b           18.650
+_cell.length_c           22.520
+_cell.angle_alpha        90.00
+_cell.angle_beta         90.77
+_cell.angle_gamma        90.00
+#
+_symmetry.entry_id                 XTAL
+_symmetry.space_group_name_H-M     'P 1 21 1'
+_symmetry.Int_Tables_number        4
+#
+";
+    let cif = MmCif::new(&CIF.replace("data_TEST\n_entry.id TEST\n#\n", header)).unwrap();
+
+    let cell = cif.unit_cell.clone().unwrap();
+    assert!((cell.a - 40.96).abs() < 1e-9);
+    assert!((cell.c - 22.52).abs() < 1e-9);
+    assert!((cell.beta.to_degrees() - 90.77).abs() < 1e-9);
+    assert_eq!(cif.space_group.as_deref(), Some("P 1 21 1"));
+    assert!((cif.cell().unwrap().b - 18.65).abs() < 1e-9);
+
+    // A cell without volume is skipped, instead of panicking.
+    let flat = header.replace("40.960", "0");
+    let cif = MmCif::new(&CIF.replace("data_TEST\n_entry.id TEST\n#\n", &flat)).unwrap();
+    assert!(cif.unit_cell.is_none());
+
+    // Only the International Tables number.
+    let cif = MmCif::new(&CIF.replace(
+        "data_TEST\n_entry.id TEST\n#\n",
+        "data_XTAL\n_space_group.IT_number 19\n#\n",
+    ))
+    .unwrap();
+    assert_eq!(cif.space_group.as_deref(), Some("19"));
+
+    // Entries without `_cell` or symmetry categories, e.g. NMR ones.
+    let cif = MmCif::new(CIF).unwrap();
+    assert!(cif.unit_cell.is_none());
+    assert!(cif.space_group.is_none());
+}
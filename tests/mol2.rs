@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+
+use bio_files::{
+    AtomGeneric, BondGeneric, BondType, ChargeType, MmCif, Mol2, MolType, ResidueEnd, ResidueType,
+};
+use lin_alg::f64::Vec3;
+use na_seq::{
+    AminoAcid, AtomTypeInRes,
+    Element::{self, Carbon, Hydrogen, Oxygen},
+};
+
+fn atom(sn: u32, element: Element, name: &str, ff: &str, q: f32, x: f64) -> AtomGeneric {
+    AtomGeneric {
+        serial_number: sn,
+        posit: Vec3::new(x, 0.5 * x, 0.),
+        element,
+        type_in_res: Some(AtomTypeInRes::Hetero(name.to_owned())),
+        force_field_type: Some(ff.to_owned()),
+        partial_charge: Some(q),
+        hetero: true,
+        ..Default::default()
+    }
+}
+
+/// Methanol, with GAFF2 types and charges.
+fn methanol() -> Mol2 {
+    let atoms = vec![
+        atom(1, Carbon, "C1", "c3", 0.1166, 0.),
+        atom(2, Oxygen, "O1", "oh", -0.5988, 1.43),
+        atom(3, Hydrogen, "H1", "ho", 0.3966, 1.8),
+        atom(4, Hydrogen, "H2", "h1", 0.0285, -0.5),
+    ];
+    let bonds = [(1, 2), (2, 3), (1, 4)]
+        .iter()
+        .map(|&(a, b)| BondGeneric {
+            bond_type: BondType::Single,
+            atom_0_sn: a,
+            atom_1_sn: b,
+        })
+        .collect();
+
+    Mol2 {
+        ident: "MOH".to_owned(),
+        metadata: HashMap::new(),
+        atoms,
+        bonds,
+        mol_type: MolType::Small,
+        charge_type: ChargeType::None,
+        pharmacophore_features: Vec::new(),
+        comment: None,
+        residues: Vec::new(),
+        chains: Vec::new(),
+    }
+}
+
+#[test]
+fn charges_and_types_round_trip() {
+    let mol = methanol();
+
+    let mut buf = Vec::new();
+    mol.write_to(&mut buf).unwrap();
+    let text = String::from_utf8(buf).unwrap();
+    assert!(text.contains("USER_CHARGES"));
+
+    let loaded = Mol2::new(&text).unwrap();
+    assert_eq!(loaded.charge_type, ChargeType::User);
+    assert_eq!(loaded.atoms.len(), mol.atoms.len());
+
+    for (orig, new) in mol.atoms.iter().zip(&loaded.atoms) {
+        assert_eq!(orig.force_field_type, new.force_field_type);
+        assert!((orig.partial_charge.unwrap() - new.partial_charge.unwrap()).abs() < 1e-5);
+        assert_eq!(orig.element, new.element);
+    }
+}
+
+#[test]
+fn explicit_charge_type_is_kept() {
+    let mut mol = methanol();
+    mol.charge_type = ChargeType::Amber;
+
+    let mut buf = Vec::new();
+    mol.write_to(&mut buf).unwrap();
+    let loaded = Mol2::new(&String::from_utf8(buf).unwrap()).unwrap();
+    assert_eq!(loaded.charge_type, ChargeType::Amber);
+}
+
+#[test]
+fn text_round_trip() {
+    let mut mol = methanol();
+    mol.comment = Some("GAFF2 types, Gasteiger charges".to_owned());
+    mol.charge_type = ChargeType::Gasteiger;
+
+    // Non-sequential serial numbers are renumbered from 1, and bonds follow.
+    for (atom, sn) in mol.atoms.iter_mut().zip([10, 20, 30, 40]) {
+        atom.serial_number = sn;
+    }
+    for bond in &mut mol.bonds {
+        bond.atom_0_sn *= 10;
+        bond.atom_1_sn *= 10;
+    }
+
+    let mut buf = Vec::new();
+    mol.write_to(&mut buf).unwrap();
+    let text = String::from_utf8(buf).unwrap();
+
+    let header: Vec<&str> = text.lines().take(7).collect();
+    assert_eq!(header[0], "@<TRIPOS>MOLECULE");
+    assert_eq!(header[2].split_whitespace().collect::<Vec<_>>(), ["4", "3"]);
+    assert_eq!(header[5], "****");
+
+    let loaded = Mol2::new(&text).unwrap();
+    assert_eq!(loaded.ident, "MOH");
+    assert_eq!(loaded.charge_type, ChargeType::Gasteiger);
+    assert_eq!(loaded.comment, mol.comment);
+
+    let sns: Vec<u32> = loaded.atoms.iter().map(|a| a.serial_number).collect();
+    assert_eq!(sns, [1, 2, 3, 4]);
+    for (orig, new) in mol.atoms.iter().zip(&loaded.atoms) {
+        assert_eq!(orig.type_in_res, new.type_in_res);
+        assert_eq!(orig.force_field_type, new.force_field_type);
+        assert_eq!(orig.posit, new.posit);
+    }
+
+    let bonds: Vec<(u32, u32)> = loaded
+        .bonds
+        .iter()
+        .map(|b| (b.atom_0_sn, b.atom_1_sn))
+        .collect();
+    assert_eq!(bonds, [(1, 2), (2, 3), (1, 4)]);
+
+    // Writing the loaded molecule reproduces the file.
+    let mut buf = Vec::new();
+    loaded.write_to(&mut buf).unwrap();
+    assert_eq!(String::from_utf8(buf).unwrap(), text);
+}
+
+/// A Ser-Val fragment in chain A, and a water in chain B. Heavy atoms only.
+const PEPTIDE: &str = "\
+@<TRIPOS>MOLECULE
+fragment
+    9     7     3     0     0
+PROTEIN
+NO_CHARGES
+****
+Ser-Val, with a water
+
+@<TRIPOS>ATOM
+      1 N          0.0000     0.0000     0.0000 N.am      1 SER1       0.0000
+      2 CA         1.4600     0.0000     0.0000 C.3       1 SER1       0.0000
+      3 C          2.0100     1.4200     0.0000 C.2       1 SER1       0.0000
+      4 O          1.2800     2.4100     0.0000 O.2       1 SER1       0.0000
+      5 N          3.3300     1.5400     0.0000 N.am      2 VAL2       0.0000
+      6 CA         3.9600     2.8500     0.0000 C.3       2 VAL2       0.0000
+      7 C          5.4800     2.7200     0.0000 C.2       2 VAL2       0.0000
+      8 O          6.0900     1.6500     0.0000 O.co2     2 VAL2       0.0000
+      9 O         12.0000     0.0000     0.0000 O.3       3 HOH3       0.0000
+@<TRIPOS>BOND
+     1     1     2 1
+     2     2     3 1
+     3     3     4 2
+     4     3     5 am
+     5     5     6 1
+     6     6     7 1
+     7     7     8 ar
+@<TRIPOS>SUBSTRUCTURE
+     1 SER1        1 RESIDUE    1 A     SER      1 ROOT
+     2 VAL2        5 RESIDUE    1 A     VAL      1
+     3 HOH3        9 RESIDUE    1 B     HOH      0
+";
+
+#[test]
+fn substructures() {
+    let mol = Mol2::new(PEPTIDE).unwrap();
+    assert_eq!(mol.comment.as_deref(), Some("Ser-Val, with a water"));
+
+    let check = |mol: &Mol2| {
+        assert_eq!(mol.residues.len(), 3);
+        assert_eq!(
+            mol.residues[0].res_type,
+            ResidueType::AminoAcid(AminoAcid::Ser)
+        );
+        assert_eq!(mol.residues[0].serial_number, 1);
+        assert_eq!(mol.residues[0].atom_sns, [1, 2, 3, 4]);
+        assert_eq!(
+            mol.residues[1].res_type,
+            ResidueType::AminoAcid(AminoAcid::Val)
+        );
+        assert_eq!(mol.residues[1].atom_sns, [5, 6, 7, 8]);
+        assert_eq!(mol.residues[2].res_type, ResidueType::Water);
+
+        assert_eq!(mol.chains.len(), 2);
+        assert_eq!(mol.chains[0].id, "A");
+        assert_eq!(mol.chains[0].residue_sns, [1, 2]);
+        assert_eq!(mol.chains[1].id, "B");
+        assert_eq!(mol.chains[1].atom_sns, [9]);
+    };
+    check(&mol);
+
+    let mut buf = Vec::new();
+    mol.write_to(&mut buf).unwrap();
+    check(&Mol2::new(&String::from_utf8(buf).unwrap()).unwrap());
+}
+
+#[test]
+fn small_molecule_is_one_residue() {
+    let mut buf = Vec::new();
+    methanol().write_to(&mut buf).unwrap();
+    let mol = Mol2::new(&String::from_utf8(buf).unwrap()).unwrap();
+
+    assert_eq!(mol.residues.len(), 1);
+    assert_eq!(
+        mol.residues[0].res_type,
+        ResidueType::Other("MOH".to_owned())
+    );
+    assert_eq!(mol.residues[0].atom_sns, [1, 2, 3, 4]);
+    assert_eq!(mol.chains.len(), 1);
+}
+
+#[test]
+fn bond_types() {
+    let mol = Mol2::new(PEPTIDE).unwrap();
+    let types: Vec<BondType> = mol.bonds.iter().map(|b| b.bond_type).collect();
+    assert_eq!(
+        types,
+        [
+            BondType::Single,
+            BondType::Single,
+            BondType::Double,
+            BondType::Amide,
+            BondType::Single,
+            BondType::Single,
+            BondType::Aromatic,
+        ]
+    );
+
+    // A non-standard token doesn't fail the file.
+    let text = PEPTIDE.replace("8 ar\n", "8 xx\n");
+    let mol = Mol2::new(&text).unwrap();
+    assert_eq!(mol.bonds[6].bond_type, BondType::Unknown);
+}
+
+/// The same atoms as `PEPTIDE`.
+const PEPTIDE_CIF: &str = "data_PEP
+loop_
+_atom_site.group_PDB
+_atom_site.id
+_atom_site.type_symbol
+_atom_site.label_atom_id
+_atom_site.label_alt_id
+_atom_site.label_comp_id
+_atom_site.label_asym_id
+_atom_site.label_seq_id
+_atom_site.Cartn_x
+_atom_site.Cartn_y
+_atom_site.Cartn_z
+_atom_site.occupancy
+ATOM 1 N N . SER A 1 0.000 0.000 0.000 1.00
+ATOM 2 C CA . SER A 1 1.460 0.000 0.000 1.00
+ATOM 3 C C . SER A 1 2.010 1.420 0.000 1.00
+ATOM 4 O O . SER A 1 1.280 2.410 0.000 1.00
+ATOM 5 N N . VAL A 2 3.330 1.540 0.000 1.00
+ATOM 6 C CA . VAL A 2 3.960 2.850 0.000 1.00
+ATOM 7 C C . VAL A 2 5.480 2.720 0.000 1.00
+ATOM 8 O O . VAL A 2 6.090 1.650 0.000 1.00
+HETATM 9 O O . HOH B 3 12.000 0.000 0.000 1.00
+#
+";
+
+#[test]
+fn matches_mmcif_structure() {
+    let from_mol2: MmCif = Mol2::new(PEPTIDE).unwrap().into();
+    let cif = MmCif::new(PEPTIDE_CIF).unwrap();
+
+    assert_eq!(from_mol2.atoms.len(), cif.atoms.len());
+    assert_eq!(from_mol2.bonds.len(), 7);
+
+    assert_eq!(from_mol2.residues.len(), cif.residues.len());
+    for (a, b) in from_mol2.residues.iter().zip(&cif.residues) {
+        assert_eq!(a.serial_number, b.serial_number);
+        assert_eq!(a.res_type, b.res_type);
+        assert_eq!(a.end, b.end);
+        assert_eq!(a.atom_sns, b.atom_sns);
+    }
+    assert_eq!(from_mol2.residues[0].end, ResidueEnd::NTerminus);
+    assert_eq!(from_mol2.residues[1].end, ResidueEnd::CTerminus);
+    assert_eq!(from_mol2.residues[2].end, ResidueEnd::Hetero);
+
+    assert_eq!(from_mol2.chains.len(), cif.chains.len());
+    for (a, b) in from_mol2.chains.iter().zip(&cif.chains) {
+        assert_eq!(a.id, b.id);
+        assert_eq!(a.residue_sns, b.residue_sns);
+        assert_eq!(a.atom_sns, b.atom_sns);
+    }
+}
@@ -0,0 +1,225 @@
+use std::fs;
+
+use bio_files::{
+    Molecule, Sdf, center_of_mass, geometric_center, molecular_formula, molecular_mass, translate,
+};
+use lin_alg::f64::Vec3;
+
+/// β-D-glucose, with hydrogens. Coordinates are arbitrary.
+const GLUCOSE: &str = "\
+glucose
+
+
+ 24 24  0  0  0  0  0  0  0  0999 V2000
+    1.5000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+    1.1973    0.9663    0.1000 C   0  0  0  0  0  0  0  0  0  0  0  0
+    0.3550    1.4782    0.2000 C   0  0  0  0  0  0  0  0  0  0  0  0
+   -0.6073    1.2948    0.3000 C   0  0  0  0  0  0  0  0  0  0  0  0
+   -1.2133    0.5025    0.4000 C   0  0  0  0  0  0  0  0  0  0  0  0
+   -1.1547   -0.5262    0.5000 C   0  0  0  0  0  0  0  0  0  0  0  0
+   -0.4354   -1.3074    0.6000 O   0  0  0  0  0  0  0  0  0  0  0  0
+    0.6298   -1.4737    0.7000 O   0  0  0  0  0  0  0  0  0  0  0  0
+    1.5633   -0.9469    0.8000 O   0  0  0  0  0  0  0  0  0  0  0  0
+    1.9498    0.0252    0.9000 O   0  0  0  0  0  0  0  0  0  0  0  0
+    1.6309    0.9855    1.0000 O   0  0  0  0  0  0  0  0  0  0  0  0
+    0.7801    1.4823    1.1000 O   0  0  0  0  0  0  0  0  0  0  0  0
+   -0.1789    1.2819    1.2000 H   0  0  0  0  0  0  0  0  0  0  0  0
+   -0.7716    0.4786    1.3000 H   0  0  0  0  0  0  0  0  0  0  0  0
+   -0.6956   -0.5497    1.4000 H   0  0  0  0  0  0  0  0  0  0  0  0
+    0.0367   -1.3195    1.5000 H   0  0  0  0  0  0  0  0  0  0  0  0
+    1.1045   -1.4688    1.6000 H   0  0  0  0  0  0  0  0  0  0  0  0
+    2.0291   -0.9272    1.7000 H   0  0  0  0  0  0  0  0  0  0  0  0
+    2.3992    0.0504    1.8000 H   0  0  0  0  0  0  0  0  0  0  0  0
+    2.0641    1.0044    1.9000 H   0  0  0  0  0  0  0  0  0  0  0  0
+    1.2051    1.4859    2.0000 H   0  0  0  0  0  0  0  0  0  0  0  0
+    0.2496    1.2686    2.1000 H   0  0  0  0  0  0  0  0  0  0  0  0
+   -0.3294    0.4547    2.2000 H   0  0  0  0  0  0  0  0  0  0  0  0
+   -0.2362   -0.5731    2.3000 H   0  0  0  0  0  0  0  0  0  0  0  0
+  1  2  1  0  0  0  0
+  2  3  1  0  0  0  0
+  3  4  1  0  0  0  0
+  4  5  1  0  0  0  0
+  5 11  1  0  0  0  0
+ 11  1  1  0  0  0  0
+  5  6  1  0  0  0  0
+  1  7  1  0  0  0  0
+  2  8  1  0  0  0  0
+  3  9  1  0  0  0  0
+  4 10  1  0  0  0  0
+  6 12  1  0  0  0  0
+  1 13  1  0  0  0  0
+  2 14  1  0  0  0  0
+  3 15  1  0  0  0  0
+  4 16  1  0  0  0  0
+  5 17  1  0  0  0  0
+  6 18  1  0  0  0  0
+  6 19  1  0  0  0  0
+  7 20  1  0  0  0  0
+  8 21  1  0  0  0  0
+  9 22  1  0  0  0  0
+ 10 23  1  0  0  0  0
+ 12 24  1  0  0  0  0
+M  END
+$$$$
+";
+
+#[test]
+fn glucose() {
+    let sdf = Sdf::new(GLUCOSE).unwrap();
+
+    assert_eq!(molecular_formula(&sdf.atoms), "C6H12O6");
+    assert!((molecular_mass(&sdf.atoms) - 180.16).abs() < 0.01);
+}
+
+#[test]
+fn hill_order() {
+    let sdf = Sdf::new(GLUCOSE).unwrap();
+
+    // Without carbon, all elements are alphabetical, including hydrogen.
+    let no_carbon: Vec<_> = sdf
+        .atoms
+        .iter()
+        .filter(|a| a.element.to_letter() != "C")
+        .cloned()
+        .collect();
+    assert_eq!(molecular_formula(&no_carbon), "H12O6");
+
+    let water: Vec<_> = sdf.atoms[6..7]
+        .iter()
+        .chain(&sdf.atoms[18..20])
+        .cloned()
+        .collect();
+    assert_eq!(molecular_formula(&water), "H2O");
+
+    assert_eq!(molecular_formula(&[]), "");
+    assert_eq!(molecular_mass(&[]), 0.);
+}
+
+#[test]
+fn recenter() {
+    let mut atoms = Sdf::new(GLUCOSE).unwrap().atoms;
+
+    let com = center_of_mass(&atoms);
+    let center = geometric_center(&atoms);
+    // Hydrogens are light, so these differ.
+    assert!((com - center).magnitude() > 0.01);
+
+    translate(&mut atoms, -com);
+    assert!(center_of_mass(&atoms).magnitude() < 1e-9);
+    assert!((geometric_center(&atoms) - (center - com)).magnitude() < 1e-9);
+
+    let offset = Vec3::new(1., -2., 3.);
+    translate(&mut atoms[..1], offset);
+    assert!((center_of_mass(&atoms) - offset * (12.011 / 180.156)).magnitude() < 1e-3);
+
+    assert_eq!(center_of_mass(&[]), Vec3::new_zero());
+}
+
+/// Water, in each format `Molecule` loads.
+const WATER_MOL2: &str = "\
+@<TRIPOS>MOLECULE
+HOH
+    3     2     1     0     0
+SMALL
+NO_CHARGES
+
+@<TRIPOS>ATOM
+      1 O          0.0000     0.0000     0.1173 O.3       1 HOH1       0.0000
+      2 H1         0.0000     0.7572    -0.4692 H         1 HOH1       0.0000
+      3 H2         0.0000    -0.7572    -0.4692 H         1 HOH1       0.0000
+@<TRIPOS>BOND
+     1     1     2 1
+     2     1     3 1
+@<TRIPOS>SUBSTRUCTURE
+     1 HOH1        1 RESIDUE    1 A     HOH      0
+";
+
+const WATER_SDF: &str = "\
+HOH
+
+
+  3  2  0  0  0  0  0  0  0  0999 V2000
+    0.0000    0.0000    0.1173 O   0  0  0  0  0  0  0  0  0  0  0  0
+    0.0000    0.7572   -0.4692 H   0  0  0  0  0  0  0  0  0  0  0  0
+    0.0000   -0.7572   -0.4692 H   0  0  0  0  0  0  0  0  0  0  0  0
+  1  2  1  0
+  1  3  1  0
+M  END
+$$$$
+";
+
+const WATER_PDB: &str = "\
+HETATM    1  O   HOH A   1       0.000   0.000   0.117  1.00  0.00           O
+HETATM    2  H1  HOH A   1       0.000   0.757  -0.469  1.00  0.00           H
+HETATM    3  H2  HOH A   1       0.000  -0.757  -0.469  1.00  0.00           H
+END
+";
+
+const WATER_CIF: &str = "data_HOH
+#
+loop_
+_atom_site.group_PDB
+_atom_site.id
+_atom_site.type_symbol
+_atom_site.label_atom_id
+_atom_site.label_alt_id
+_atom_site.label_comp_id
+_atom_site.label_asym_id
+_atom_site.label_seq_id
+_atom_site.Cartn_x
+_atom_site.Cartn_y
+_atom_site.Cartn_z
+_atom_site.occupancy
+_atom_site.B_iso_or_equiv
+HETATM 1 O O . HOH A . 0.000 0.000 0.117 1.00 0.00
+HETATM 2 H H1 . HOH A . 0.000 0.757 -0.469 1.00 0.00
+HETATM 3 H H2 . HOH A . 0.000 -0.757 -0.469 1.00 0.00
+#
+";
+
+const WATER_XYZ: &str = "3
+water
+O 0.0000 0.0000 0.1173
+H 0.0000 0.7572 -0.4692
+H 0.0000 -0.7572 -0.4692
+";
+
+#[test]
+fn load_any_format() {
+    let dir = std::env::temp_dir().join("bio_files_test_molecule");
+    fs::create_dir_all(&dir).unwrap();
+
+    let files = [
+        ("water.mol2", WATER_MOL2, 2, 1),
+        ("water.sdf", WATER_SDF, 2, 1),
+        ("water.PDB", WATER_PDB, 0, 1),
+        ("water.cif", WATER_CIF, 0, 1),
+        ("water.xyz", WATER_XYZ, 0, 0),
+    ];
+
+    for (name, text, bond_count, res_count) in files {
+        let path = dir.join(name);
+        fs::write(&path, text).unwrap();
+        let mol = Molecule::load(&path).unwrap();
+
+        assert_eq!(mol.atoms().len(), 3, "{name}");
+        assert_eq!(mol.bonds().len(), bond_count, "{name}");
+        assert_eq!(mol.residues().len(), res_count, "{name}");
+        assert_eq!(molecular_formula(mol.atoms()), "H2O", "{name}");
+    }
+
+    assert!(matches!(
+        Molecule::load(&dir.join("water.mol2")).unwrap(),
+        Molecule::Mol2(_)
+    ));
+    assert!(matches!(
+        Molecule::load(&dir.join("water.cif")).unwrap(),
+        Molecule::MmCif(_)
+    ));
+
+    let path = dir.join("water.txt");
+    fs::write(&path, WATER_XYZ).unwrap();
+    assert!(Molecule::load(&path).is_err());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
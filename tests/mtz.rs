@@ -0,0 +1,180 @@
+use std::fs;
+
+use bio_files::mtz::Mtz;
+
+/// Build an MTZ file, with little-endian numbers, from header records after the column count,
+/// column descriptions as (label, type), and reflection rows.
+fn build_mtz(headers: &[&str], columns: &[(&str, char)], rows: &[Vec<f32>]) -> Vec<u8> {
+    let data: Vec<u8> = rows
+        .iter()
+        .flatten()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+
+    let mut result = Vec::new();
+    result.extend_from_slice(b"MTZ ");
+    // Header position, in 1-based words.
+    result.extend_from_slice(&(((80 + data.len()) / 4 + 1) as i32).to_le_bytes());
+    result.extend_from_slice(&[0x44, 0x41, 0, 0]);
+    result.resize(80, 0);
+    result.extend(data);
+
+    let mut records = vec![
+        "VERS MTZ:V1.1".to_owned(),
+        "TITLE test data".to_owned(),
+        format!("NCOL {:>8} {:>12} {:>8}", columns.len(), rows.len(), 0),
+    ];
+    records.extend(headers.iter().map(|h| h.to_string()));
+    for (i, (label, type_)) in columns.iter().enumerate() {
+        let vals: Vec<f32> = rows
+            .iter()
+            .map(|r| r[i])
+            .filter(|v| v.is_finite())
+            .collect();
+        let min = vals.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = vals.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        records.push(format!(
+            "COLUMN {label:<30} {type_} {min:>17.4} {max:>17.4} {:>4}",
+            1
+        ));
+    }
+    records.push("END".to_owned());
+    records.push("MTZENDOFHEADERS".to_owned());
+
+    for record in records {
+        result.extend_from_slice(format!("{record:<80}").as_bytes());
+    }
+    result
+}
+
+const COLUMNS: [(&str, char); 6] = [
+    ("H", 'H'),
+    ("K", 'H'),
+    ("L", 'H'),
+    ("FP", 'F'),
+    ("FWT", 'F'),
+    ("PHWT", 'P'),
+];
+
+#[test]
+fn header_and_columns() {
+    let rows = vec![
+        vec![1., 0., 0., 120.5, 110., 0.],
+        vec![0., 1., 1., f32::NAN, 80., 45.],
+        vec![2., 1., -1., 40.1, 35., 180.],
+    ];
+    let buf = build_mtz(
+        &[
+            "CELL    40.9600   18.6500   22.5200   90.0000   90.7700   90.0000",
+            "SORT    1   2   3   0   0",
+            "SYMINF   2  2 P     4                 'P 1 21 1' PG2",
+            "SYMM X,  Y,  Z",
+            "SYMM -X,  1/2+Y,  -Z",
+            "RESO 0.00160000 0.25000000",
+            "VALM NAN",
+        ],
+        &COLUMNS,
+        &rows,
+    );
+
+    let path = std::env::temp_dir().join("bio_files_test.mtz");
+    fs::write(&path, &buf).unwrap();
+    let mtz = Mtz::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(mtz.title, "test data");
+    assert!((mtz.cell.a - 40.96).abs() < 1e-9);
+    assert!((mtz.cell.beta.to_degrees() - 90.77).abs() < 1e-9);
+    assert_eq!(mtz.space_group, "P 1 21 1");
+    assert_eq!(mtz.space_group_number, 4);
+    assert_eq!(mtz.symmetry_ops.len(), 2);
+    assert!(mtz.symmetry_ops[0].is_identity());
+
+    let (low, high) = mtz.resolution.unwrap();
+    assert!((low - 25.).abs() < 1e-3);
+    assert!((high - 2.).abs() < 1e-3);
+
+    assert_eq!(mtz.columns.len(), 6);
+    assert_eq!(mtz.columns[5].label, "PHWT");
+    assert_eq!(mtz.columns[5].type_, 'P');
+    assert_eq!(mtz.num_reflections(), 3);
+    assert_eq!(mtz.column("FWT").unwrap(), vec![110., 80., 35.]);
+    assert!(mtz.column("FP").unwrap()[1].is_nan());
+    assert!(mtz.column("SIGFP").is_none());
+
+    // A numeric missing-value marker.
+    let rows = vec![vec![1., 0., 0., -999., 110., 0.]];
+    let buf = build_mtz(&["CELL 10 10 10 90 90 90", "VALM -999"], &COLUMNS, &rows);
+    assert!(Mtz::new(&buf).unwrap().column("FP").unwrap()[0].is_nan());
+
+    // No cell.
+    assert!(Mtz::new(&build_mtz(&[], &COLUMNS, &rows)).is_err());
+    assert!(Mtz::new(b"not an MTZ file").is_err());
+}
+
+#[test]
+fn density_map_p1() {
+    // A single reflection, with F(100) = V / 2, gives ρ(x) = cos(2πx).
+    let rows = vec![vec![1., 0., 0., 0., 500., 0.]];
+    let buf = build_mtz(&["CELL 10 10 10 90 90 90"], &COLUMNS, &rows);
+    let map = Mtz::new(&buf).unwrap().to_density_map().unwrap();
+
+    assert_eq!((map.hdr.nx, map.hdr.ny, map.hdr.nz), (4, 1, 1));
+    let expected = [1., 0., -1., 0.];
+    for (v, e) in map.data.iter().zip(expected) {
+        assert!((v - e).abs() < 1e-5, "{:?}", map.data);
+    }
+
+    // Without map coefficients.
+    let mtz = Mtz::new(&build_mtz(
+        &["CELL 10 10 10 90 90 90"],
+        &COLUMNS[..4],
+        &[vec![1., 0., 0., 10.]],
+    ))
+    .unwrap();
+    assert!(mtz.to_density_map().is_err());
+}
+
+#[test]
+fn density_map_symmetry() {
+    // h0l reflections are centric in P2₁, so their phases are 0 or 180°.
+    let rows = vec![
+        vec![1., 0., 1., 0., 100., 180.],
+        vec![1., 1., 0., 0., 50., 60.],
+        vec![2., 1., 1., 0., 30., 200.],
+    ];
+    let buf = build_mtz(
+        &[
+            "CELL 8 8 8 90 90 90",
+            "SYMINF   2  2 P     4                 'P 1 21 1' PG2",
+            "SYMM X,  Y,  Z",
+            "SYMM -X,  1/2+Y,  -Z",
+        ],
+        &COLUMNS,
+        &rows,
+    );
+    let map = Mtz::new(&buf).unwrap().to_density_map().unwrap();
+
+    let (nx, ny, nz) = (
+        map.hdr.nx as usize,
+        map.hdr.ny as usize,
+        map.hdr.nz as usize,
+    );
+    assert_eq!((nx, ny, nz), (8, 4, 4));
+    assert_eq!(map.hdr.inner.ispg, 4);
+
+    // The 2₁ screw axis: ρ(x, y, z) = ρ(-x, y + 1/2, -z)
+    let at = |i: usize, j: usize, k: usize| map.data[(k * ny + j) * nx + i];
+    for k in 0..nz {
+        for j in 0..ny {
+            for i in 0..nx {
+                let mate = at((nx - i) % nx, (j + ny / 2) % ny, (nz - k) % nz);
+                assert!((at(i, j, k) - mate).abs() < 1e-5);
+            }
+        }
+    }
+
+    // Density is real, and there's no F000 term.
+    assert!(map.data.iter().any(|v| v.abs() > 0.1));
+    assert!(map.data.iter().sum::<f32>().abs() < 1e-3);
+}
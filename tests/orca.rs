@@ -0,0 +1,1114 @@
+use bio_files::{
+    Xyz,
+    orca::{
+        GeomOptThresh, GeometryOutput, Keyword, OUTPUT_BASE, OrcaInput, OrcaOutput, OrcaRunError,
+        Task, TerminationStatus,
+        basis_sets::BasisSet,
+        charges::{
+            ChelpgCfg, ChelpgChargesOutput, PopulationMethod, SinglePointOutput, parse_nbo_charges,
+            parse_population, parse_scf_history,
+        },
+        conformers::ConformerSearchOutput,
+        final_job_output, find_sidecar,
+        geom::{Convergence, Geom, InternalCoord, Scan},
+        hess::{FrequenciesOutput, Hessian},
+        method::Method,
+    },
+};
+use na_seq::Element;
+
+const SCF_FAILURE: &str = "\
+--------------
+SCF ITERATIONS
+--------------
+ITER       Energy         Delta-E        Max-DP      RMS-DP      [F,P]     Damp
+  124   -230.1234567890  -1.2e-04  3.1e-03  6.2e-04  1.1e-02  0.700
+
+               *****************************************************
+               *                      ERROR                        *
+               *        SCF NOT CONVERGED AFTER 125 CYCLES         *
+               *****************************************************
+
+ORCA finished by error termination in SCF
+Calling Command: mpirun -np 4  /opt/orca/orca_scf_mpi input.gbw b input
+[file orca_tools/qcmsg.cpp, line 465]:
+  .... aborting the run
+";
+
+const GEOM_FAILURE: &str = "\
+                    *******************************************************
+                    *                 GEOMETRY OPTIMIZATION CYCLE 100         *
+                    *******************************************************
+
+The optimization did not converge but reached the maximum number of optimization cycles.
+Please check your results very carefully.
+
+                             ****ORCA TERMINATED NORMALLY****
+";
+
+const INPUT_FAILURE: &str = "\
+INPUT ERROR: UNRECOGNIZED OR DUPLICATED KEYWORD(S) IN SIMPLE INPUT LINE
+
+  FOOBASIS
+
+[file orca_main/maininp1.cpp, line 1234]:
+  .... aborting the run
+";
+
+#[test]
+fn classifies_scf_failure() {
+    assert_eq!(
+        TerminationStatus::from_output(SCF_FAILURE),
+        TerminationStatus::ScfNotConverged
+    );
+}
+
+#[test]
+fn scf_warning_with_normal_termination() {
+    let text =
+        format!("{SCF_FAILURE}\n                             ****ORCA TERMINATED NORMALLY****\n");
+    assert_eq!(
+        TerminationStatus::from_output(&text),
+        TerminationStatus::Normal
+    );
+    assert!(TerminationStatus::has_scf_warning(&text));
+    assert!(!TerminationStatus::has_scf_warning(INPUT_FAILURE));
+}
+
+#[test]
+fn geom_failure_is_not_normal() {
+    // ORCA reports normal termination here, but the geometry isn't usable.
+    assert_eq!(
+        TerminationStatus::from_output(GEOM_FAILURE),
+        TerminationStatus::GeomOptNotConverged
+    );
+
+    let text = GEOM_FAILURE.replace("did not converge but reached", "converged before");
+    assert_eq!(
+        TerminationStatus::from_output(&text),
+        TerminationStatus::Normal
+    );
+}
+
+#[test]
+fn run_error_keeps_output() {
+    let err = std::io::Error::other(OrcaRunError {
+        status: TerminationStatus::from_output(SCF_FAILURE),
+        text: SCF_FAILURE.to_owned(),
+    });
+    let run_err = err
+        .get_ref()
+        .unwrap()
+        .downcast_ref::<OrcaRunError>()
+        .unwrap();
+
+    assert_eq!(run_err.status, TerminationStatus::ScfNotConverged);
+    assert_eq!(run_err.text, SCF_FAILURE);
+    assert_eq!(err.to_string(), "ORCA SCF did not converge");
+}
+
+#[test]
+fn classifies_generic_abort() {
+    let status = TerminationStatus::from_output(INPUT_FAILURE);
+    assert_eq!(
+        status,
+        TerminationStatus::Aborted(
+            "INPUT ERROR: UNRECOGNIZED OR DUPLICATED KEYWORD(S) IN SIMPLE INPUT LINE".to_owned()
+        )
+    );
+
+    assert_eq!(
+        TerminationStatus::from_output("Starting ORCA..."),
+        TerminationStatus::Incomplete
+    );
+}
+
+#[test]
+fn xyz_charge_mult_to_orca_input() {
+    let text = "3\n-1 2\nO 0.0 0.0 0.0\nH 0.96 0.0 0.0\nH -0.24 0.93 0.0\n";
+    let xyz = Xyz::new(text).unwrap();
+    assert_eq!((xyz.charge, xyz.multiplicity), (Some(-1), Some(2)));
+
+    let inp = OrcaInput::from_xyz(&xyz, Method::default(), BasisSet::default());
+    assert!(inp.make_inp().contains("* xyz -1 2\n"));
+
+    let text = text.replace("-1 2", "water charge=1 mult=2 from optimization");
+    let xyz = Xyz::new(&text).unwrap();
+    assert_eq!((xyz.charge, xyz.multiplicity), (Some(1), Some(2)));
+
+    let xyz = Xyz::new(&text.replace("charge=1 mult=2", "step 12")).unwrap();
+    assert_eq!((xyz.charge, xyz.multiplicity), (None, None));
+    let inp = OrcaInput::from_xyz(&xyz, Method::default(), BasisSet::default());
+    assert!(inp.make_inp().contains("* xyz 0 1\n"));
+}
+
+#[test]
+fn counterpoise_ghost_atoms() {
+    // A water dimer, with the second monomer ghosted.
+    let text = "6\n\n\
+O  0.0 0.0 0.0\nH 0.96 0.0 0.0\nH -0.24 0.93 0.0\n\
+O  2.9 0.0 0.0\nH 3.2 0.9 0.0\nH 3.2 -0.9 0.0\n";
+    let xyz = Xyz::new(text).unwrap();
+
+    let mut inp = OrcaInput::from_xyz(&xyz, Method::default(), BasisSet::default());
+    inp.ghost_atoms = vec![3, 4, 5];
+
+    let expected = "\
+* xyz 0 1
+O       0.00000      0.00000      0.00000
+H       0.96000      0.00000      0.00000
+H      -0.24000      0.93000      0.00000
+O:      2.90000      0.00000      0.00000
+H:      3.20000      0.90000      0.00000
+H:      3.20000     -0.90000      0.00000
+*";
+    assert!(inp.make_inp().ends_with(expected));
+
+    // Both ghost forms are read back from the echoed input.
+    let echo = ECHOED_INPUT
+        .replace("|  9> H  ", "|  9> H: ")
+        .replace("| 10> H  ", "| 10> H : ");
+    let reparsed = OrcaInput::from_output(&echo).unwrap();
+    assert_eq!(reparsed.ghost_atoms, vec![1, 2]);
+    assert_eq!(reparsed.atoms.len(), 3);
+    assert!((reparsed.atoms[2].posit.y + 0.7572).abs() < 1e-9);
+}
+
+#[test]
+fn geom_constraints_and_scan() {
+    let xyz =
+        Xyz::new("4\n\nC 0.0 0.0 0.0\nC 1.5 0.0 0.0\nH -0.5 0.9 0.0\nH 2.0 0.9 0.3\n").unwrap();
+
+    let geom = Geom {
+        max_iter: 100,
+        convergence: Convergence::Normal,
+        in_hess: None,
+        print_internal_hess: false,
+        constraints: vec![InternalCoord::Bond(0, 1), InternalCoord::Angle(2, 0, 1)],
+        scans: vec![Scan {
+            coord: InternalCoord::Dihedral(2, 0, 1, 3),
+            start: 0.,
+            end: 180.,
+            steps: 19,
+        }],
+    };
+
+    let mut inp = OrcaInput::from_xyz(&xyz, Method::default(), BasisSet::default());
+    inp.task = Task::GeometryOptimization((GeomOptThresh::Opt, Some(geom)));
+    inp.keywords = vec![Keyword::Freq];
+
+    let text = inp.make_inp();
+    // Keywords stay on the first line, ahead of the block.
+    assert!(text.lines().next().unwrap().ends_with(" Opt FREQ"));
+    assert!(text.contains(
+        "\
+%geom
+    Convergence normal
+    Constraints
+        { B 0 1 C }
+        { A 2 0 1 C }
+    end
+    Scan
+        D 2 0 1 3 = 0, 180, 19
+    end
+end
+"
+    ));
+}
+
+#[test]
+fn multi_job_input() {
+    let xyz = Xyz::new("3\n0 1\nO 0.0 0.0 0.0\nH 0.96 0.0 0.0\nH -0.24 0.93 0.0\n").unwrap();
+
+    let mut opt = OrcaInput::from_xyz(&xyz, Method::default(), BasisSet::default());
+    opt.task = Task::GeometryOptimization((GeomOptThresh::Opt, None));
+
+    let mut freq = OrcaInput::from_xyz(&xyz, Method::default(), BasisSet::default());
+    freq.keywords = vec![Keyword::Freq];
+
+    let text = OrcaInput::make_multi_inp(&[opt.clone(), freq]);
+    assert_eq!(text.matches("$new_job").count(), 1);
+    assert!(text.starts_with(&opt.make_inp()));
+
+    let (_, second) = text.split_once("\n\n$new_job\n").unwrap();
+    assert!(second.lines().next().unwrap().ends_with(" FREQ"));
+    assert!(second.ends_with("\n\n* xyzfile 0 1 orca_job.xyz\n"));
+    // The geometry is only written for the first job.
+    assert_eq!(text.matches("O       0.00000").count(), 1);
+
+    let out = concat!(
+        "FINAL SINGLE POINT ENERGY       -76.3\n",
+        "                         $$$$$$$$$$$$$$$$  JOB NUMBER  2 $$$$$$$$$$$$$$\n",
+        "FINAL SINGLE POINT ENERGY       -76.4\n",
+    );
+    assert!(final_job_output(out).starts_with("                         $$$$"));
+    assert!(final_job_output(out).ends_with("-76.4\n"));
+    assert_eq!(final_job_output(SINGLE_POINT), SINGLE_POINT);
+}
+
+#[test]
+fn orca_input_elements() {
+    let text = "3\n\nZn 0.0 0.0 0.0\nCl 2.2 0.0 0.0\nCl -2.2 0.0 0.0\n";
+    let xyz = Xyz::new(text).unwrap();
+    let mut inp = OrcaInput::from_xyz(&xyz, Method::default(), BasisSet::default());
+
+    let expected = "\
+* xyz 0 1
+Zn      0.00000      0.00000      0.00000
+Cl      2.20000      0.00000      0.00000
+Cl     -2.20000      0.00000      0.00000
+*";
+    assert!(inp.make_inp().ends_with(expected));
+
+    // An element that couldn't be parsed.
+    inp.atoms[1].element = Element::Other;
+    let path = std::env::temp_dir().join("bio_files_test_unknown_el.inp");
+    let err = inp.save(&path).unwrap_err();
+    assert!(err.to_string().contains("index 1"));
+    assert!(!path.exists());
+}
+
+#[test]
+fn finds_sidecar_files_by_base() {
+    let inp = OrcaInput::new(Method::default(), BasisSet::default(), &[]);
+    assert!(
+        inp.make_inp()
+            .contains(&format!("%base \"{OUTPUT_BASE}\"\n"))
+    );
+
+    let dir = std::env::temp_dir().join("bio_files_test_orca_sidecar");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join(format!("{OUTPUT_BASE}.hess")), HESS).unwrap();
+
+    let path = find_sidecar(&dir, "hess").unwrap();
+    assert_eq!(Hessian::load(&path).unwrap().frequencies.len(), 9);
+
+    let err = find_sidecar(&dir, "property.txt").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
+
+#[test]
+fn charge_and_multiplicity() {
+    let mut o2 = OrcaInput::new(Method::default(), BasisSet::default(), &[]);
+    assert!(o2.make_inp().contains("* xyz 0 1\n"));
+
+    // Triplet oxygen.
+    o2.multiplicity = Some(3);
+    assert!(o2.make_inp().contains("* xyz 0 3\n"));
+
+    o2.charge = Some(-1);
+    o2.multiplicity = Some(2);
+    assert!(o2.make_inp().contains("* xyz -1 2\n"));
+
+    o2.multiplicity = Some(0);
+    let path = std::env::temp_dir().join("bio_files_test_orca_mult.inp");
+    let err = o2.save(&path).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+// Abbreviated from a GOAT `.finalensemble.xyz` on water dimer. Not in energy order.
+const ENSEMBLE: &str = "\
+6
+-152.68001234
+O   -1.551007   -0.114520    0.000000
+H   -1.934259    0.762503    0.000000
+H   -0.599677    0.040712    0.000000
+O    1.350625    0.111469    0.000000
+H    1.680398   -0.373741   -0.758561
+H    1.680398   -0.373741    0.758561
+6
+-152.68251987
+O   -1.521007   -0.104520    0.010000
+H   -1.904259    0.772503    0.000000
+H   -0.569677    0.050712    0.000000
+O    1.320625    0.101469    0.000000
+H    1.650398   -0.383741   -0.758561
+H    1.650398   -0.383741    0.758561
+6
+-152.67911111
+O   -1.571007   -0.124520    0.000000
+H   -1.954259    0.752503    0.000000
+H   -0.619677    0.030712    0.000000
+O    1.370625    0.121469    0.000000
+H    1.700398   -0.363741   -0.758561
+H    1.700398   -0.363741    0.758561
+";
+
+#[test]
+fn parses_goat_ensemble() {
+    let out = ConformerSearchOutput::new(ENSEMBLE, String::new()).unwrap();
+
+    assert_eq!(out.conformers.len(), 3);
+    let energies: Vec<f64> = out.conformers.iter().map(|c| c.1).collect();
+    assert_eq!(energies, vec![-152.68251987, -152.68001234, -152.67911111]);
+
+    let (lowest, _) = &out.conformers[0];
+    assert_eq!(lowest.atoms.len(), 6);
+    assert!((lowest.atoms[0].posit.x - -1.521007).abs() < 1e-9);
+}
+
+// A `.hess` file in ORCA's format for water, with placeholder Hessian and mode values.
+const HESS: &str = "\
+$orca_hessian_file
+
+$act_atom
+  0
+
+$act_coord
+  0
+
+$act_energy
+        0.000000
+
+$hessian
+9
+                          0                  1                  2                  3                  4
+      0      5.0000000000E-01   1.0000000000E-02   2.0000000000E-02   3.0000000000E-02   4.0000000000E-02
+      1      1.0000000000E-02   5.0000000000E-01   3.0000000000E-02   4.0000000000E-02   5.0000000000E-02
+      2      2.0000000000E-02   3.0000000000E-02   5.0000000000E-01   5.0000000000E-02   6.0000000000E-02
+      3      3.0000000000E-02   4.0000000000E-02   5.0000000000E-02   5.0000000000E-01   7.0000000000E-02
+      4      4.0000000000E-02   5.0000000000E-02   6.0000000000E-02   7.0000000000E-02   5.0000000000E-01
+      5      5.0000000000E-02   6.0000000000E-02   7.0000000000E-02   8.0000000000E-02   9.0000000000E-02
+      6      6.0000000000E-02   7.0000000000E-02   8.0000000000E-02   9.0000000000E-02   1.0000000000E-01
+      7      7.0000000000E-02   8.0000000000E-02   9.0000000000E-02   1.0000000000E-01   1.1000000000E-01
+      8      8.0000000000E-02   9.0000000000E-02   1.0000000000E-01   1.1000000000E-01   1.2000000000E-01
+                          5                  6                  7                  8
+      0      5.0000000000E-02   6.0000000000E-02   7.0000000000E-02   8.0000000000E-02
+      1      6.0000000000E-02   7.0000000000E-02   8.0000000000E-02   9.0000000000E-02
+      2      7.0000000000E-02   8.0000000000E-02   9.0000000000E-02   1.0000000000E-01
+      3      8.0000000000E-02   9.0000000000E-02   1.0000000000E-01   1.1000000000E-01
+      4      9.0000000000E-02   1.0000000000E-01   1.1000000000E-01   1.2000000000E-01
+      5      5.0000000000E-01   1.1000000000E-01   1.2000000000E-01   1.3000000000E-01
+      6      1.1000000000E-01   5.0000000000E-01   1.3000000000E-01   1.4000000000E-01
+      7      1.2000000000E-01   1.3000000000E-01   5.0000000000E-01   1.5000000000E-01
+      8      1.3000000000E-01   1.4000000000E-01   1.5000000000E-01   5.0000000000E-01
+
+$vibrational_frequencies
+9
+    0          0.000000
+    1          0.000000
+    2          0.000000
+    3          0.000000
+    4          0.000000
+    5          0.000000
+    6       1595.120000
+    7       3657.050000
+    8       3756.000000
+
+$normal_modes
+9 9
+                          0                  1                  2                  3                  4
+      0      5.0000000000E-01   0.0000000000E+00   0.0000000000E+00   0.0000000000E+00   0.0000000000E+00
+      1      1.0000000000E-03   5.0100000000E-01   1.0000000000E-03   1.0000000000E-03   1.0000000000E-03
+      2      2.0000000000E-03   2.0000000000E-03   5.0200000000E-01   2.0000000000E-03   2.0000000000E-03
+      3      3.0000000000E-03   3.0000000000E-03   3.0000000000E-03   5.0300000000E-01   3.0000000000E-03
+      4      4.0000000000E-03   4.0000000000E-03   4.0000000000E-03   4.0000000000E-03   5.0400000000E-01
+      5      5.0000000000E-03   5.0000000000E-03   5.0000000000E-03   5.0000000000E-03   5.0000000000E-03
+      6      6.0000000000E-03   6.0000000000E-03   6.0000000000E-03   6.0000000000E-03   6.0000000000E-03
+      7      7.0000000000E-03   7.0000000000E-03   7.0000000000E-03   7.0000000000E-03   7.0000000000E-03
+      8      8.0000000000E-03   8.0000000000E-03   8.0000000000E-03   8.0000000000E-03   8.0000000000E-03
+                          5                  6                  7                  8
+      0      0.0000000000E+00   0.0000000000E+00   0.0000000000E+00   0.0000000000E+00
+      1      1.0000000000E-03   1.0000000000E-03   1.0000000000E-03   1.0000000000E-03
+      2      2.0000000000E-03   2.0000000000E-03   2.0000000000E-03   2.0000000000E-03
+      3      3.0000000000E-03   3.0000000000E-03   3.0000000000E-03   3.0000000000E-03
+      4      4.0000000000E-03   4.0000000000E-03   4.0000000000E-03   4.0000000000E-03
+      5      5.0500000000E-01   5.0000000000E-03   5.0000000000E-03   5.0000000000E-03
+      6      6.0000000000E-03   5.0600000000E-01   6.0000000000E-03   6.0000000000E-03
+      7      7.0000000000E-03   7.0000000000E-03   5.0700000000E-01   7.0000000000E-03
+      8      8.0000000000E-03   8.0000000000E-03   8.0000000000E-03   5.0800000000E-01
+
+#
+# The atoms: label  mass x y z (in bohrs)
+#
+$atoms
+3
+ O     15.99900      0.000000    0.000000    0.123000
+ H      1.00800      0.000000    1.430000   -0.980000
+ H      1.00800      0.000000   -1.430000   -0.980000
+
+#
+# The IR spectrum
+#  wavenumber[cm-1]  eps  Int  TX  TY  TZ
+#
+$ir_spectrum
+9
+      0.00       0.00000000       0.00000000       0.100000     0.000000     0.000000
+      0.00       0.00000000       0.00000000       0.100000     0.000000     0.000000
+      0.00       0.00000000       0.00000000       0.100000     0.000000     0.000000
+      0.00       0.00000000       0.00000000       0.100000     0.000000     0.000000
+      0.00       0.00000000       0.00000000       0.100000     0.000000     0.000000
+      0.00       0.00000000       0.00000000       0.100000     0.000000     0.000000
+   1595.12      29.16000000      72.90000000       0.100000     0.000000     0.000000
+   3657.05       1.64800000       4.12000000       0.100000     0.000000     0.000000
+   3756.00      16.20000000      40.50000000       0.100000     0.000000     0.000000
+
+$end
+";
+
+#[test]
+fn parses_hess_file() {
+    let hess = Hessian::new(HESS).unwrap();
+
+    assert_eq!(hess.matrix.len(), 9);
+    assert!(hess.matrix.iter().all(|r| r.len() == 9));
+    assert!((hess.matrix[0][0] - 0.5).abs() < 1e-12);
+    assert!((hess.matrix[2][7] - 0.09).abs() < 1e-12);
+    assert!((hess.matrix[8][6] - 0.14).abs() < 1e-12);
+
+    assert_eq!(hess.frequencies.len(), 9);
+    assert!((hess.frequencies[6] - 1595.12).abs() < 1e-9);
+
+    assert_eq!(hess.modes.len(), 9);
+    assert!((hess.modes[7][7] - 0.507).abs() < 1e-12);
+    assert!((hess.modes[7][3] - 0.003).abs() < 1e-12);
+
+    assert_eq!(hess.ir_intensities.len(), 9);
+    assert!((hess.ir_intensities[6] - 72.9).abs() < 1e-9);
+}
+
+/// Excerpt from an open-shell (methyl radical) single point, with NBO enabled.
+const OPEN_SHELL: &str = "\
+            ********************************
+            * MULLIKEN POPULATION ANALYSIS *
+            ********************************
+
+--------------------------------------------
+MULLIKEN ATOMIC CHARGES AND SPIN DENSITIES
+--------------------------------------------
+   0 C :   -0.412345    1.152301
+   1 H :    0.137448   -0.050767
+   2 H :    0.137449   -0.050767
+   3 H :    0.137448   -0.050767
+Sum of atomic charges         :   -0.0000000
+Sum of atomic spin densities  :    1.0000000
+
+            *******************************
+            * LOEWDIN POPULATION ANALYSIS *
+            *******************************
+
+-------------------------------------------
+LOEWDIN ATOMIC CHARGES AND SPIN DENSITIES
+-------------------------------------------
+   0 C :   -0.290010    1.101112
+   1 H :    0.096670   -0.033704
+   2 H :    0.096670   -0.033704
+   3 H :    0.096670   -0.033704
+
+ Summary of Natural Population Analysis:
+
+                                     Natural Population
+             Natural    ---------------------------------------------
+  Atom No    Charge        Core      Valence    Rydberg      Total
+ --------------------------------------------------------------------
+    C  1   -0.59262      1.99932     4.58478    0.00852     6.59262
+    H  2    0.19754      0.00000     0.80095    0.00151     0.80246
+    H  3    0.19754      0.00000     0.80095    0.00151     0.80246
+    H  4    0.19754      0.00000     0.80095    0.00151     0.80246
+ ====================================================================
+ * Total *  0.00000      1.99932     6.98763    0.01305     9.00000
+";
+
+#[test]
+fn parses_open_shell_populations() {
+    let mulliken = parse_population(OPEN_SHELL, PopulationMethod::Mulliken).unwrap();
+    assert_eq!(mulliken.len(), 4);
+    assert_eq!(mulliken[0].charge, -0.412345);
+    assert_eq!(mulliken[0].spin_density, Some(1.152301));
+    assert_eq!(mulliken[3].spin_density, Some(-0.050767));
+
+    let spin_total: f64 = mulliken.iter().filter_map(|p| p.spin_density).sum();
+    assert!((spin_total - 1.).abs() < 1e-5);
+
+    let loewdin = parse_population(OPEN_SHELL, PopulationMethod::Loewdin).unwrap();
+    assert_eq!(loewdin.len(), 4);
+    assert_eq!(loewdin[1].charge, 0.09667);
+    assert_eq!(loewdin[1].spin_density, Some(-0.033704));
+
+    let nbo = parse_nbo_charges(OPEN_SHELL).unwrap();
+    assert_eq!(nbo, vec![-0.59262, 0.19754, 0.19754, 0.19754]);
+}
+
+#[test]
+fn closed_shell_population_has_no_spin() {
+    let text = "\
+-----------------------
+MULLIKEN ATOMIC CHARGES
+-----------------------
+   0 O :   -0.651234
+   1 H :    0.325617
+   2 H :    0.325617
+Sum of atomic charges:    0.0000000
+";
+    let mulliken = parse_population(text, PopulationMethod::Mulliken).unwrap();
+    assert_eq!(mulliken.len(), 3);
+    assert_eq!(mulliken[0].spin_density, None);
+
+    assert!(parse_population(text, PopulationMethod::Loewdin).is_err());
+    assert!(parse_nbo_charges(text).is_err());
+}
+
+/// Excerpt from a closed-shell ammonium (NH4+) single point `.out` file.
+const SINGLE_POINT: &str = "\
+----------------------------------------------------------------------------
+                                                       ORCA SCF
+----------------------------------------------------------------------------
+
+ Total Charge           Charge          ....    1
+ Multiplicity           Mult            ....    1
+ Number of Electrons    NEL             ....   10
+
+               *****************************************************
+               *                     SUCCESS                       *
+               *           SCF CONVERGED AFTER  10 CYCLES          *
+               *****************************************************
+
+----------------
+ORBITAL ENERGIES
+----------------
+
+  NO   OCC          E(Eh)            E(eV) 
+   0   2.0000     -15.911234123      -432.9669 
+   1   2.0000      -1.498801456       -40.7843 
+   2   2.0000      -0.937364012       -25.5070 
+   3   2.0000      -0.937363387       -25.5070 
+   4   2.0000      -0.937362718       -25.5069 
+   5   0.0000      -0.135467301        -3.6862 
+   6   0.0000      -0.053212044        -1.4480 
+
+            ***********************************************************
+            *        MULLIKEN POPULATION ANALYSIS                    *
+            ***********************************************************
+
+-----------------------
+MULLIKEN ATOMIC CHARGES
+-----------------------
+   0 N :   -0.781204
+   1 H :    0.445301
+   2 H :    0.445301
+   3 H :    0.445301
+   4 H :    0.445301
+Sum of atomic charges:    1.0000000
+
+-----------------------------------------
+MULLIKEN REDUCED ORBITAL CHARGES
+-----------------------------------------
+  0 N s       :     3.654377  s :     3.654377
+
+            *******************************
+            * LOEWDIN POPULATION ANALYSIS *
+            *******************************
+
+----------------------
+LOEWDIN ATOMIC CHARGES
+----------------------
+   0 N :   -0.350088
+   1 H :    0.337522
+   2 H :    0.337522
+   3 H :    0.337522
+   4 H :    0.337522
+
+-------------------------   --------------------
+FINAL SINGLE POINT ENERGY       -56.865412873421
+-------------------------   --------------------
+
+                             ****ORCA TERMINATED NORMALLY****
+";
+
+#[test]
+fn single_point_charges() {
+    assert_eq!(
+        TerminationStatus::from_output(SINGLE_POINT),
+        TerminationStatus::Normal
+    );
+
+    let out = SinglePointOutput::new(SINGLE_POINT.to_owned()).unwrap();
+    assert!((out.energy_hartree.unwrap() - -56.865412873421).abs() < 1e-8);
+
+    assert_eq!(out.orbital_energies.len(), 7);
+    assert_eq!(out.homo_index, Some(4));
+    assert!((out.orbital_energies[0] - -15.911234123).abs() < 1e-8);
+    let gap = out.homo_lumo_gap().unwrap();
+    assert!((gap - (-0.135467301 - -0.937362718)).abs() < 1e-8);
+
+    assert_eq!(out.mulliken.len(), 5);
+    assert_eq!(out.loewdin.len(), 5);
+    assert_eq!(out.mulliken[0], -0.781204);
+    assert_eq!(out.loewdin[4], 0.337522);
+
+    // Both sum to the molecule's total charge.
+    assert!((out.mulliken.iter().sum::<f64>() - 1.).abs() < 1e-5);
+    assert!((out.loewdin.iter().sum::<f64>() - 1.).abs() < 1e-5);
+}
+
+#[test]
+fn single_point_without_populations() {
+    let i = SINGLE_POINT
+        .find("-----------------------\nMULLIKEN")
+        .unwrap();
+    let j = SINGLE_POINT
+        .find("-------------------------   ---")
+        .unwrap();
+    let text = format!("{}{}", &SINGLE_POINT[..i], &SINGLE_POINT[j..]);
+
+    let out = SinglePointOutput::new(text).unwrap();
+    assert!(out.mulliken.is_empty());
+    assert!(out.loewdin.is_empty());
+    assert_eq!(out.homo_index, Some(4));
+
+    // Without the energy or orbital energies.
+    let out = SinglePointOutput::new(GEOM_FAILURE.to_owned()).unwrap();
+    assert!(out.energy_hartree.is_none());
+    assert!(out.orbital_energies.is_empty());
+    assert!(out.homo_index.is_none());
+    assert!(out.homo_lumo_gap().is_none());
+}
+
+/// Excerpt from a water frequency calculation's output, matching `HESS`.
+const FREQ_OUT: &str = "\
+-----------------------
+VIBRATIONAL FREQUENCIES
+-----------------------
+
+Scaling factor for frequencies =  1.000000000  (already applied!)
+
+     0:       0.00 cm**-1
+     1:       0.00 cm**-1
+     2:       0.00 cm**-1
+     3:       0.00 cm**-1
+     4:       0.00 cm**-1
+     5:       0.00 cm**-1
+     6:    1595.12 cm**-1
+     7:    3654.33 cm**-1
+     8:    3756.11 cm**-1
+
+
+------------
+NORMAL MODES
+------------
+
+These modes are the Cartesian displacements weighted by the diagonal matrix
+
+-----------
+IR SPECTRUM
+-----------
+
+ Mode   freq       eps      Int      T**2         TX        TY        TZ
+       cm**-1   L/(mol*cm) km/mol    a.u.
+----------------------------------------------------------------------------
+  6:   1595.12   0.014140   71.46  0.002766  ( 0.000000  0.052594  0.000000)
+  7:   3654.33   0.000712    3.60  0.000061  (-0.000000 -0.007798  0.000000)
+  8:   3756.11   0.010512   53.12  0.000873  (-0.000000  0.000000  0.029550)
+
+* The epsilon (eps) is given for a Dirac delta lineshape.
+";
+
+#[test]
+fn parses_frequencies_from_output() {
+    let out = FrequenciesOutput::new(FREQ_OUT.to_owned()).unwrap();
+    assert!(out.hessian.is_none());
+
+    // 3N - 6, for water's 3 atoms.
+    assert_eq!(out.frequencies_cm1.len(), 3 * 3 - 6);
+    assert_eq!(out.frequencies_cm1, vec![1595.12, 3654.33, 3756.11]);
+    assert_eq!(out.ir_intensities, vec![71.46, 3.60, 53.12]);
+
+    // An imaginary mode, e.g. at a transition state, stays negative.
+    let text = FREQ_OUT.replace(
+        "     6:    1595.12 cm**-1",
+        "     6:    -412.77 cm**-1 ***imaginary mode***",
+    );
+    let out = FrequenciesOutput::new(text).unwrap();
+    assert_eq!(out.frequencies_cm1[0], -412.77);
+    assert_eq!(out.frequencies_cm1.len(), 3);
+}
+
+#[test]
+fn frequencies_with_any_task() {
+    let dir = std::env::temp_dir().join("bio_files_test_orca_freq");
+    std::fs::create_dir_all(&dir).unwrap();
+    let _ = std::fs::remove_file(dir.join(format!("{OUTPUT_BASE}.hess")));
+
+    // A single point with `Freq`, without a `.hess` file: Parsed from the output text.
+    let mut inp = OrcaInput::new(Method::default(), BasisSet::default(), &[]);
+    inp.keywords.push(Keyword::Freq);
+    let OrcaOutput::Frequencies(out) = inp.parse_output(&dir, FREQ_OUT.to_owned()).unwrap() else {
+        panic!("Expected frequencies");
+    };
+    assert_eq!(out.frequencies_cm1, vec![1595.12, 3654.33, 3756.11]);
+    assert!(out.hessian.is_none());
+
+    // Opt + Freq keeps both the geometry, and the frequencies; the `.hess` file is loaded if present.
+    std::fs::write(dir.join(format!("{OUTPUT_BASE}.hess")), HESS).unwrap();
+    inp.task = Task::GeometryOptimization((GeomOptThresh::Opt, None));
+    let text = format!("{GEOM_OPT}{FREQ_OUT}");
+    let OrcaOutput::Geometry(out) = inp.parse_output(&dir, text).unwrap() else {
+        panic!("Expected a geometry");
+    };
+    assert!(!out.posits.is_empty());
+    let freqs = out.frequencies.unwrap();
+    assert_eq!(freqs.ir_intensities, vec![71.46, 3.60, 53.12]);
+    assert_eq!(freqs.hessian.unwrap().frequencies.len(), 9);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+const ECHOED_INPUT: &str = "\
+                                 *****************
+                                 * O   R   C   A *
+                                 *****************
+
+================================================================================
+                                       INPUT FILE
+================================================================================
+NAME = /tmp/orca_temp/step.inp
+|  1> !B3LYP def2-SVP TightOpt FREQ D4
+|  2> %base \"orca_job\"
+|  3> %scf
+|  4>     MaxIter 200
+|  5> end
+|  6> 
+|  7> * xyz -1 2
+|  8> O       0.00000      0.00000      0.11730
+|  9> H       0.00000      0.75720     -0.46920
+| 10> H       0.00000     -0.75720     -0.46920
+| 11> *
+| 12> 
+| 13>                          ****END OF INPUT****
+================================================================================
+";
+
+#[test]
+fn input_from_output() {
+    let inp = OrcaInput::from_output(ECHOED_INPUT).unwrap();
+
+    assert_eq!(inp.method, Method::B3LYP);
+    assert_eq!(inp.basis_set, BasisSet::Def2Svp);
+    assert!(matches!(
+        inp.task,
+        Task::GeometryOptimization((GeomOptThresh::Tight, None))
+    ));
+    assert_eq!(inp.keywords, vec![Keyword::Freq, Keyword::D4Dispersion]);
+    assert_eq!(inp.charge, Some(-1));
+    assert_eq!(inp.multiplicity, Some(2));
+    assert_eq!(inp.atoms.len(), 3);
+    assert_eq!(inp.atoms[1].serial_number, 2);
+    assert!((inp.atoms[2].posit.y + 0.7572).abs() < 1e-9);
+
+    // The `%scf` block isn't reconstructed; everything else re-serializes unchanged.
+    let expected = "\
+!B3LYP def2-SVP TightOpt FREQ D4
+%base \"orca_job\"
+
+* xyz -1 2
+O       0.00000      0.00000      0.11730
+H       0.00000      0.75720     -0.46920
+H       0.00000     -0.75720     -0.46920
+*";
+    assert_eq!(inp.make_inp(), expected);
+
+    let reparsed = OrcaInput::from_output(&ECHOED_INPUT.replace(
+        "|  1> !B3LYP def2-SVP TightOpt FREQ D4",
+        "|  1> !b3lyp DEF2-SVP tightopt freq d4 Gcp(dft/svp)",
+    ))
+    .unwrap();
+    assert_eq!(reparsed.method, Method::B3LYP);
+    assert_eq!(reparsed.basis_set, BasisSet::Def2Svp);
+    assert_eq!(reparsed.keywords.len(), 3);
+
+    // Keywords we don't model are kept as-is.
+    let raw = OrcaInput::from_output(
+        &ECHOED_INPUT.replace("FREQ D4", "FREQ D3BJ TightSCF RIJCOSX def2/J"),
+    )
+    .unwrap();
+    assert_eq!(raw.keywords, vec![Keyword::Freq]);
+    assert_eq!(raw.raw_keywords, ["D3BJ", "TightSCF", "RIJCOSX", "def2/J"]);
+    assert!(
+        raw.make_inp()
+            .starts_with("!B3LYP def2-SVP TightOpt FREQ D3BJ TightSCF RIJCOSX def2/J\n")
+    );
+
+    assert!(OrcaInput::from_output("no echo here").is_err());
+}
+
+/// A water optimization, abridged.
+const GEOM_OPT: &str = "\
+                         *****************************
+                         *     GEOMETRY OPTIMIZATION CYCLE   1            *
+                         *****************************
+---------------------------------
+CARTESIAN COORDINATES (ANGSTROEM)
+---------------------------------
+  O        0.000000       0.000000       0.126000
+  H        0.000000       0.820000      -0.460000
+  H        0.000000      -0.820000      -0.460000
+
+----------------------------
+CARTESIAN COORDINATES (A.U.)
+----------------------------
+
+-------------------------   --------------------
+FINAL SINGLE POINT ENERGY       -76.321845120334
+-------------------------   --------------------
+
+                         *****************************
+                         *     GEOMETRY OPTIMIZATION CYCLE   2            *
+                         *****************************
+---------------------------------
+CARTESIAN COORDINATES (ANGSTROEM)
+---------------------------------
+  O        0.000000       0.000000       0.119000
+  H        0.000000       0.780000      -0.470000
+  H        0.000000      -0.780000      -0.470000
+
+----------------------------
+CARTESIAN COORDINATES (A.U.)
+----------------------------
+
+-------------------------   --------------------
+FINAL SINGLE POINT ENERGY       -76.325912004517
+-------------------------   --------------------
+
+                         *****************************
+                         *     GEOMETRY OPTIMIZATION CYCLE   3            *
+                         *****************************
+---------------------------------
+CARTESIAN COORDINATES (ANGSTROEM)
+---------------------------------
+  O        0.000000       0.000000       0.117000
+  H        0.000000       0.757200      -0.469200
+  H        0.000000      -0.757200      -0.469200
+
+----------------------------
+CARTESIAN COORDINATES (A.U.)
+----------------------------
+
+-------------------------   --------------------
+FINAL SINGLE POINT ENERGY       -76.326101377642
+-------------------------   --------------------
+
+                    ***********************HURRAY********************
+                    ***        THE OPTIMIZATION HAS CONVERGED     ***
+                    *************************************************
+
+                  *******************************************************
+                  *** FINAL ENERGY EVALUATION AT THE STATIONARY POINT ***
+                  *******************************************************
+
+---------------------------------
+CARTESIAN COORDINATES (ANGSTROEM)
+---------------------------------
+  O        0.000000       0.000000       0.117000
+  H        0.000000       0.757200      -0.469200
+  H        0.000000      -0.757200      -0.469200
+
+-------------------------   --------------------
+FINAL SINGLE POINT ENERGY       -76.326101377650
+-------------------------   --------------------
+
+                             ****ORCA TERMINATED NORMALLY****
+";
+
+#[test]
+fn geometry_optimization_trajectory() {
+    let out = GeometryOutput::new(GEOM_OPT.to_owned()).unwrap();
+
+    // Three cycles, and the final evaluation.
+    assert_eq!(out.energies.len(), 4);
+    assert_eq!(out.trajectory.len(), 4);
+    assert!((out.energies[0] + 76.321845120334).abs() < 1e-12);
+    for pair in out.energies.windows(2) {
+        assert!(pair[1] <= pair[0] + 1e-9);
+    }
+
+    assert_eq!(out.trajectory[0].len(), 3);
+    assert!((out.trajectory[0][1].y - 0.82).abs() < 1e-9);
+    assert_eq!(out.posits, *out.trajectory.last().unwrap());
+    assert!((out.posits[1].y - 0.7572).abs() < 1e-9);
+
+    // Back onto the input atoms, e.g. to save.
+    let xyz = Xyz::new("3\n\nO 0.0 0.0 0.1\nH 0.0 0.8 -0.5\nH 0.0 -0.8 -0.5\n").unwrap();
+    let atoms = out.apply_to(&xyz.atoms).unwrap();
+    assert_eq!(atoms.len(), 3);
+    assert_eq!(atoms[1].element, xyz.atoms[1].element);
+    assert_eq!(atoms[1].serial_number, xyz.atoms[1].serial_number);
+    assert_eq!(atoms[1].posit, out.posits[1]);
+    assert!(out.apply_to(&xyz.atoms[..2]).is_err());
+
+    // Not converged.
+    let unconverged = GEOM_OPT.replace("FINAL ENERGY EVALUATION AT THE STATIONARY POINT", "");
+    assert!(GeometryOutput::new(unconverged).is_err());
+}
+
+/// A water single point, abridged.
+const WATER_SP: &str = "\
+----------------
+ORBITAL ENERGIES
+----------------
+
+  NO   OCC          E(Eh)            E(eV) 
+   0   2.0000     -20.550919      -559.2200 
+   1   2.0000      -1.335730       -36.3471 
+   2   2.0000      -0.697803       -18.9882 
+   3   2.0000      -0.566362       -15.4115 
+   4   2.0000      -0.492917       -13.4130 
+   5   0.0000       0.185096         5.0367 
+
+-------------------------   --------------------
+FINAL SINGLE POINT ENERGY       -76.326101377650
+-------------------------   --------------------
+
+-------------
+DIPOLE MOMENT
+-------------
+                                X             Y             Z
+Electronic contribution:      0.000000     -0.000000     -0.117540
+Nuclear contribution   :      0.000000      0.000000      0.861610
+                        -----------------------------------------
+Total Dipole Moment    :      0.000000     -0.000000      0.744070
+                        -----------------------------------------
+Magnitude (a.u.)       :      0.744070
+Magnitude (Debye)      :      1.891277
+
+--------------------
+Rotational spectrum 
+--------------------
+ 
+Rotational constants in cm-1:    27.345667    14.516421     9.482561 
+Rotational constants in MHz : 819794.122993 435188.957262 284278.693547 
+
+                             ****ORCA TERMINATED NORMALLY****
+";
+
+#[test]
+fn dipole_and_rotational_constants() {
+    let out = SinglePointOutput::new(WATER_SP.to_owned()).unwrap();
+
+    let total = out.dipole_total.unwrap();
+    assert!((1.8..2.0).contains(&total));
+
+    // Converted from atomic units.
+    let dipole = out.dipole_debye.unwrap();
+    assert!(dipole.x.abs() < 1e-9);
+    assert!((dipole.magnitude() - total).abs() < 1e-4);
+
+    let [a, b, c] = out.rotational_constants.unwrap();
+    assert!((a - 27.345667).abs() < 1e-9);
+    assert!(b > c);
+
+    // Without these blocks.
+    let out = SinglePointOutput::new(SINGLE_POINT.to_owned()).unwrap();
+    assert!(out.dipole_debye.is_none());
+    assert!(out.dipole_total.is_none());
+    assert!(out.rotational_constants.is_none());
+}
+
+const WATER_CHELPG: &str = "\
+----------------
+TOTAL SCF ENERGY
+----------------
+
+Total Energy       :          -76.32271836 Eh           -2076.84906 eV
+
+------------------------------------------------------------------------------
+                              ORCA CHELPG CHARGES GENERATION
+------------------------------------------------------------------------------
+
+GBWName                      ... water.gbw
+Electron density             ... water.scfp
+Charge                       ... 0
+Grid spacing                 ...     0.300000
+Point Cut-Off                ...     2.800000
+Van-der-Waals Radii          ...        COSMO
+Total charge                 ...            0
+
+Number of points X direction ...           24
+Number of points Y direction ...           27
+Number of points Z direction ...           21
+Maximum number of possible points ...    13608
+Final number of points            ...     3190
+
+CHELP-G charges calculation...   done
+
+--------------------------------
+CHELPG Charges            
+--------------------------------
+  0   O   :      -0.774912
+  1   H   :       0.387456
+  2   H   :       0.387456
+--------------------------------
+Total charge:    -0.000000
+--------------------------------
+
+CHELPG charges calculated...
+
+                             ****ORCA TERMINATED NORMALLY****
+";
+
+#[test]
+fn chelpg_charges() {
+    let out = ChelpgChargesOutput::new(WATER_CHELPG.to_owned()).unwrap();
+
+    assert_eq!(out.charges.len(), 3);
+    assert!((out.charges[0] + 0.774912).abs() < 1e-9);
+    assert!((out.charges[1] - 0.387456).abs() < 1e-9);
+    assert!(out.charges.iter().sum::<f64>().abs() < 1e-5);
+
+    assert!(ChelpgChargesOutput::new(SINGLE_POINT.to_owned()).is_err());
+
+    // Input generation, with the grid block.
+    let inp = OrcaInput::from_output(&ECHOED_INPUT.replace("TightOpt", "CHELPG")).unwrap();
+    assert!(matches!(&inp.task, Task::ChelpgCharges(cfg) if *cfg == ChelpgCfg::default()));
+
+    let text = inp.make_inp();
+    assert!(text.starts_with("!B3LYP def2-SVP CHELPG FREQ D4\n"));
+    assert!(text.contains("%chelpg\n    GRID 0.3\n    RMAX 2.8\n    DIPOLE FALSE\nend"));
+}
+
+const SCF_ITERATIONS: &str = "\
+--------------
+SCF ITERATIONS
+--------------
+ITER       Energy         Delta-E        Max-DP      RMS-DP      [F,P]     Damp
+  0    -76.2829417440   0.000000000000 0.03398720  0.00192618  0.0862598 0.7000
+                               ***Turning on DIIS***
+  1    -76.2925621735  -0.009620429483 0.02816435  0.00160253  0.0558101 0.7000
+  2    -76.3222034711  -0.029641297651 0.00867539  0.00048011  0.0130247 0.0000
+                      *** Initiating the SOSCF procedure ***
+                           *** Shutting down DIIS ***
+                      *** Re-Reading the Fockian *** 
+                      *** Removing any level shift *** 
+ITER      Energy       Delta-E        Grad      Rot      Max-DP    RMS-DP
+  3    -76.32270581  -0.0004923389  0.001041  0.001994  0.000326  0.000027
+  4    -76.32271836  -0.0000125499  0.000118  0.000233  0.000038  0.000003
+
+               *****************************************************
+               *                     SUCCESS                       *
+               *           SCF CONVERGED AFTER   5 CYCLES          *
+               *****************************************************
+
+";
+
+#[test]
+fn scf_convergence_history() {
+    let out = SinglePointOutput::new(format!("{SCF_ITERATIONS}{WATER_SP}")).unwrap();
+    assert!(out.scf_converged);
+    assert_eq!(out.scf_energies.len(), 5);
+    assert!((out.scf_energies[0] + 76.282941744).abs() < 1e-9);
+    assert!((out.scf_energies[4] + 76.32271836).abs() < 1e-9);
+
+    // Without an iteration table.
+    let out = SinglePointOutput::new(WATER_SP.to_owned()).unwrap();
+    assert!(out.scf_energies.is_empty());
+    assert!(!out.scf_converged);
+
+    // Truncated, e.g. a run that was killed.
+    let truncated = &SCF_ITERATIONS[..SCF_ITERATIONS.find("  3 ").unwrap()];
+    let (energies, converged) = parse_scf_history(truncated);
+    assert_eq!(energies.len(), 3);
+    assert!(!converged);
+
+    let (energies, converged) = parse_scf_history(SCF_FAILURE);
+    assert_eq!(energies, vec![-230.123456789]);
+    assert!(!converged);
+}
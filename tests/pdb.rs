@@ -0,0 +1,262 @@
+use std::{fs, io::Write};
+
+use bio_files::{
+    HelixClass, MmCif, ResidueEnd, ResidueType, SecondaryStructure,
+    pdb::{Pdb, PdbHeader, write_pdb_atoms},
+};
+use flate2::{Compression, write::GzEncoder};
+use lin_alg::f64::Vec3;
+use na_seq::{AminoAcid, AtomTypeInRes, Element};
+
+const HEADER: &str = "\
+HEADER    HYDROLASE/HYDROLASE INHIBITOR           16-JUL-99   1C8K              
+TITLE     CRYSTAL STRUCTURE OF A PROTEASE IN COMPLEX WITH AN                    
+TITLE    2 INHIBITOR                                                            
+REMARK   2                                                                      
+REMARK   2 RESOLUTION.    2.50 ANGSTROMS.                                       
+REMARK 350 BIOMOLECULE: 1                                                       
+REMARK 350 AUTHOR DETERMINED BIOLOGICAL UNIT: DIMERIC                           
+REMARK 350 APPLY THE FOLLOWING TO CHAINS: A, B                                  
+REMARK 350                    AND CHAINS: C                                     
+REMARK 350   BIOMT1   1  1.000000  0.000000  0.000000        0.00000            
+REMARK 350   BIOMT2   1  0.000000  1.000000  0.000000        0.00000            
+REMARK 350   BIOMT3   1  0.000000  0.000000  1.000000        0.00000            
+REMARK 350   BIOMT1   2 -1.000000  0.000000  0.000000       50.00000            
+REMARK 350   BIOMT2   2  0.000000 -1.000000  0.000000        0.00000            
+REMARK 350   BIOMT3   2  0.000000  0.000000  1.000000        0.00000            
+ATOM      1  N   ALA A   1      11.104   6.134  -6.504  1.00  0.00           N  
+END
+";
+
+#[test]
+fn parses_header_metadata() {
+    let hdr = PdbHeader::new(HEADER).unwrap();
+
+    assert_eq!(hdr.ident, "1C8K");
+    assert_eq!(hdr.classification, "HYDROLASE/HYDROLASE INHIBITOR");
+    assert_eq!(hdr.deposition_date.as_deref(), Some("16-JUL-99"));
+    assert_eq!(
+        hdr.title.as_deref(),
+        Some("CRYSTAL STRUCTURE OF A PROTEASE IN COMPLEX WITH AN INHIBITOR")
+    );
+    assert_eq!(hdr.resolution, Some(2.5));
+
+    assert_eq!(hdr.assemblies.len(), 1);
+    let assembly = &hdr.assemblies[0];
+    assert_eq!(assembly.chains, vec!["A", "B", "C"]);
+    assert_eq!(assembly.transforms.len(), 2);
+
+    let p = assembly.transforms[1].apply(Vec3::new(1., 2., 3.));
+    assert!((p - Vec3::new(49., -2., 3.)).magnitude() < 1e-9);
+}
+
+#[test]
+fn resolution_not_applicable() {
+    let text = "\
+HEADER    STRUCTURAL PROTEIN                      01-JAN-00   1ABC              
+REMARK   2 RESOLUTION. NOT APPLICABLE.                                          
+";
+    let hdr = PdbHeader::new(text).unwrap();
+    assert_eq!(hdr.ident, "1ABC");
+    assert_eq!(hdr.resolution, None);
+}
+
+const COORDS: &str = "\
+HEADER    PEPTIDE                                 01-JAN-00   1XYZ              
+HELIX    1   1 ALA A    1  GLY A    3  1                                   3    
+SHEET    1   A 2 SER B  10  SER B  11  0                                        
+ATOM      1  N   ALA A   1       0.000   0.000   0.000  1.00 10.00           N  
+ATOM      2  CA  ALA A   1       1.458   0.000   0.000  1.00 11.00           C  
+ATOM      3  C   ALA A   1       2.009   1.420   0.000  1.00 12.00           C  
+ATOM      4  N   LYS A   2       3.300   1.500   0.000  1.00 10.00           N  
+ATOM      5  CA  LYS A   2       3.900   2.800   0.000  1.00 10.00           C  
+ATOM      6  CB ALYS A   2       4.500   3.600   1.200  0.60 20.00           C  
+ATOM      7  CB BLYS A   2       4.500   3.600  -1.200  0.40 21.00           C  
+ATOM      8  N   GLY A   3       5.400   2.700   0.000  1.00 10.00           N  
+ATOM      9  CA  GLY A   3    -999.999-999.999   0.000  1.00 10.00           C  
+TER      10      GLY A   3
+ATOM     11  N   SER B  10      10.000   0.000   0.000  1.00 10.00           N  
+ATOM     12  CA  SER B  10      11.400   0.000   0.000  1.00 10.00           C  
+ATOM     13  N   SER B  11      12.000   1.000   0.000  1.00 10.00           N  
+ATOM     14  CA  SER B  11      13.400   1.000   0.000  1.00 10.00           C  
+HETATM   15  ZN   ZN B 101      20.000  20.000  20.000  1.00 30.00          ZN2+
+HETATM   16  O   HOH B 201      25.000  20.000  20.000  1.00 30.00           O  
+ENDMDL
+ATOM      1  N   ALA A   1       9.000   9.000   9.000  1.00 10.00           N  
+END
+";
+
+#[test]
+fn parses_coordinates() {
+    let pdb = Pdb::new(COORDS).unwrap();
+    assert_eq!(pdb.header.ident, "1XYZ");
+
+    // Only the first model.
+    assert_eq!(pdb.atoms.len(), 15);
+
+    let a = &pdb.atoms[5];
+    assert_eq!(a.serial_number, 6);
+    assert_eq!(a.alt_conformation_id.as_deref(), Some("A"));
+    assert_eq!(a.occupancy, Some(0.6));
+    assert_eq!(a.b_factor, Some(20.));
+    assert_eq!(a.type_in_res, Some(AtomTypeInRes::CB));
+    assert!(!a.hetero);
+
+    // Fields that fill their columns.
+    assert_eq!(pdb.atoms[8].posit, Vec3::new(-999.999, -999.999, 0.));
+
+    let zn = &pdb.atoms[13];
+    assert!(zn.hetero);
+    assert_eq!(zn.element, Element::Zinc);
+    assert_eq!(zn.formal_charge, Some(2));
+
+    assert_eq!(pdb.chains.len(), 2);
+    assert_eq!(pdb.chains[0].id, "A");
+    assert_eq!(pdb.chains[0].residue_sns, [1, 2, 3]);
+    assert_eq!(pdb.chains[1].residue_sns, [10, 11, 101, 201]);
+    assert_eq!(pdb.chains[1].atom_sns, [11, 12, 13, 14, 15, 16]);
+
+    assert_eq!(pdb.residues.len(), 7);
+    assert_eq!(
+        pdb.residues[1].res_type,
+        ResidueType::AminoAcid(AminoAcid::Lys)
+    );
+    assert_eq!(pdb.residues[1].atom_sns, [4, 5, 6, 7]);
+    assert_eq!(pdb.residues[0].end, ResidueEnd::NTerminus);
+    assert_eq!(pdb.residues[6].res_type, ResidueType::Water);
+    assert_eq!(pdb.residues[6].end, ResidueEnd::Hetero);
+
+    assert_eq!(pdb.secondary_structure.len(), 2);
+    let helix = &pdb.secondary_structure[0];
+    assert_eq!((helix.start_sn, helix.end_sn), (2, 9));
+    assert_eq!(helix.sec_struct, SecondaryStructure::Helix);
+    assert_eq!(helix.helix_class, Some(HelixClass::Alpha));
+    let strand = &pdb.secondary_structure[1];
+    assert_eq!((strand.start_sn, strand.end_sn), (12, 14));
+    assert_eq!(strand.sec_struct, SecondaryStructure::Sheet);
+}
+
+/// Chain A of `COORDS`, as mmCIF.
+const COORDS_CIF: &str = "data_1XYZ
+loop_
+_atom_site.group_PDB
+_atom_site.id
+_atom_site.type_symbol
+_atom_site.label_atom_id
+_atom_site.label_alt_id
+_atom_site.label_comp_id
+_atom_site.label_asym_id
+_atom_site.label_seq_id
+_atom_site.Cartn_x
+_atom_site.Cartn_y
+_atom_site.Cartn_z
+_atom_site.occupancy
+ATOM 1 N N . ALA A 1 0.000 0.000 0.000 1.00
+ATOM 2 C CA . ALA A 1 1.458 0.000 0.000 1.00
+ATOM 3 C C . ALA A 1 2.009 1.420 0.000 1.00
+ATOM 4 N N . LYS A 2 3.300 1.500 0.000 1.00
+ATOM 5 C CA . LYS A 2 3.900 2.800 0.000 1.00
+ATOM 6 C CB A LYS A 2 4.500 3.600 1.200 0.60
+ATOM 7 C CB B LYS A 2 4.500 3.600 -1.200 0.40
+ATOM 8 N N . GLY A 3 5.400 2.700 0.000 1.00
+ATOM 9 C CA . GLY A 3 -999.999 -999.999 0.000 1.00
+#
+";
+
+#[test]
+fn matches_mmcif_model() {
+    let pdb = Pdb::new(COORDS).unwrap();
+    let cif = MmCif::new(COORDS_CIF).unwrap();
+
+    for (a, b) in cif.atoms.iter().zip(&pdb.atoms) {
+        assert_eq!(a.serial_number, b.serial_number);
+        assert_eq!(a.posit, b.posit);
+        assert_eq!(a.element, b.element);
+        assert_eq!(a.type_in_res, b.type_in_res);
+        assert_eq!(a.alt_conformation_id, b.alt_conformation_id);
+        assert_eq!(a.occupancy, b.occupancy);
+    }
+
+    assert_eq!(cif.residues.len(), 3);
+    for (a, b) in cif.residues.iter().zip(&pdb.residues) {
+        assert_eq!(a.serial_number, b.serial_number);
+        assert_eq!(a.res_type, b.res_type);
+        assert_eq!(a.atom_sns, b.atom_sns);
+    }
+    assert_eq!(cif.chains[0].atom_sns, pdb.chains[0].atom_sns);
+}
+
+#[test]
+fn write_round_trip() {
+    let pdb = Pdb::new(COORDS).unwrap();
+
+    let mut buf = Vec::new();
+    pdb.write_to(&mut buf).unwrap();
+    let text = String::from_utf8(buf).unwrap();
+
+    // Standard columns, e.g. for atom names and elements, match the original.
+    for line in COORDS.lines().filter(|l| l.starts_with("ATOM")).take(9) {
+        assert!(text.lines().any(|l| l == line), "{line}");
+    }
+    // Two-letter elements start in the first column of the atom name field.
+    assert!(text.contains("HETATM   15 ZN    ZN B 101"));
+    assert!(text.ends_with("END\n"));
+
+    let loaded = Pdb::new(&text).unwrap();
+    assert_eq!(loaded.header.ident, "1XYZ");
+    assert_eq!(loaded.atoms.len(), pdb.atoms.len());
+    for (a, b) in loaded.atoms.iter().zip(&pdb.atoms) {
+        assert_eq!(a.serial_number, b.serial_number);
+        assert!((a.posit - b.posit).magnitude() < 1e-3);
+        assert_eq!(a.element, b.element);
+        assert_eq!(a.type_in_res, b.type_in_res);
+        assert_eq!(a.alt_conformation_id, b.alt_conformation_id);
+        assert_eq!(a.formal_charge, b.formal_charge);
+        assert_eq!(a.hetero, b.hetero);
+    }
+    assert_eq!(loaded.chains[1].residue_sns, pdb.chains[1].residue_sns);
+}
+
+#[test]
+fn write_filtered_mmcif_atoms() {
+    let cif = MmCif::new(COORDS_CIF).unwrap();
+    let atoms: Vec<_> = cif
+        .atoms
+        .iter()
+        .filter(|a| a.alt_conformation_id.as_deref() != Some("B"))
+        .cloned()
+        .collect();
+
+    let mut buf = Vec::new();
+    write_pdb_atoms(&mut buf, &atoms, &cif.residues, &cif.chains).unwrap();
+    let pdb = Pdb::new(&String::from_utf8(buf).unwrap()).unwrap();
+
+    assert_eq!(pdb.atoms.len(), 8);
+    for (a, b) in pdb.atoms.iter().zip(&atoms) {
+        assert!((a.posit - b.posit).magnitude() < 1e-3);
+        assert_eq!(a.type_in_res, b.type_in_res);
+    }
+    assert_eq!(pdb.residues.len(), 3);
+    assert_eq!(
+        pdb.residues[2].res_type,
+        ResidueType::AminoAcid(AminoAcid::Gly)
+    );
+    assert_eq!(pdb.chains[0].id, "A");
+}
+
+#[test]
+fn load_gzipped() {
+    let path = std::env::temp_dir().join("bio_files_test_load.pdb.gz");
+
+    let mut enc = GzEncoder::new(fs::File::create(&path).unwrap(), Compression::default());
+    enc.write_all(COORDS.as_bytes()).unwrap();
+    enc.finish().unwrap();
+
+    let loaded = Pdb::load(&path).unwrap();
+    let expected = Pdb::new(COORDS).unwrap();
+
+    assert_eq!(loaded.atoms.len(), expected.atoms.len());
+    assert_eq!(loaded.header.ident, expected.header.ident);
+
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,79 @@
+use bio_files::Pdbqt;
+use na_seq::Element;
+
+const LIGAND: &str = "\
+REMARK  Name = ethanol
+REMARK  2 active torsions:
+REMARK  status: ('A' for Active; 'I' for Inactive)
+REMARK    1  A    between atoms: C1_1  and  C2_2
+REMARK    2  A    between atoms: C2_2  and  O1_3
+ROOT
+ATOM      1  C1  UNL     1      -0.748   0.015   0.024  0.00  0.00    +0.034 C 
+ENDROOT
+BRANCH   1   2
+ATOM      2  C2  UNL     1       0.558  -0.748  -0.015  0.00  0.00    +0.199 C 
+BRANCH   2   3
+ATOM      3  O1  UNL     1       1.658   0.135   0.042  0.00  0.00    -0.398 OA
+ATOM      4  H1  UNL     1       2.479  -0.388   0.019  0.00  0.00    +0.209 HD
+ENDBRANCH   2   3
+ENDBRANCH   1   2
+HETATM    5  C3  UNL     2      -1.212   1.027  -1.012  1.00 12.50    +0.011 A
+TORSDOF 2
+";
+
+#[test]
+fn parses_charges_and_ad_types() {
+    let mol = Pdbqt::new(LIGAND).unwrap();
+
+    assert_eq!(mol.ident, "ethanol");
+    assert_eq!(mol.torsdof, Some(2));
+    assert_eq!(mol.atoms.len(), 5);
+
+    let charges: Vec<f32> = mol
+        .atoms
+        .iter()
+        .map(|a| a.partial_charge.unwrap())
+        .collect();
+    assert_eq!(charges, [0.034, 0.199, -0.398, 0.209, 0.011]);
+
+    let types: Vec<&str> = mol
+        .atoms
+        .iter()
+        .map(|a| a.force_field_type.as_deref().unwrap())
+        .collect();
+    assert_eq!(types, ["C", "C", "OA", "HD", "A"]);
+
+    let elements: Vec<Element> = mol.atoms.iter().map(|a| a.element).collect();
+    assert_eq!(
+        elements,
+        [
+            Element::Carbon,
+            Element::Carbon,
+            Element::Oxygen,
+            Element::Hydrogen,
+            Element::Carbon
+        ]
+    );
+
+    let last = &mol.atoms[4];
+    assert!(last.hetero);
+    assert_eq!(last.b_factor, Some(12.5));
+    assert!((last.posit.z + 1.012).abs() < 1e-9);
+
+    assert_eq!(mol.residues.len(), 2);
+    assert_eq!(mol.residues[0].atom_sns, [1, 2, 3, 4]);
+    assert_eq!(mol.residues[1].serial_number, 2);
+    assert_eq!(mol.chains.len(), 1);
+    assert_eq!(mol.chains[0].residue_sns, [1, 2]);
+}
+
+#[test]
+fn short_lines() {
+    // No charge or type columns, e.g. from a plain PDB ATOM record.
+    let mol = Pdbqt::new("ATOM      1  N   GLY A   1      11.104   6.134  -6.504").unwrap();
+
+    assert_eq!(mol.atoms[0].element, Element::Nitrogen);
+    assert_eq!(mol.atoms[0].partial_charge, None);
+    assert_eq!(mol.atoms[0].force_field_type, None);
+    assert_eq!(mol.torsdof, None);
+}
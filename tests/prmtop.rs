@@ -0,0 +1,140 @@
+use std::fs;
+
+use bio_files::{
+    BondType, ResidueType,
+    prmtop::{Prmtop, load_prmtop, save_prmtop},
+};
+use na_seq::Element;
+
+/// A TIP3P water, and a sodium ion.
+const PRMTOP: &str = "\
+%VERSION  VERSION_STAMP = V0001.000  DATE = 01/01/24  12:00:00
+%FLAG TITLE
+%FORMAT(20a4)
+water and sodium
+%FLAG POINTERS
+%FORMAT(10I8)
+       4       3       3       0       0       0       0       0       0       0
+       9       2       0       0       0       0       0       0       0       0
+       0       0       0       0       0       0       0       0       0       0
+       0
+%FLAG ATOM_NAME
+%FORMAT(20a4)
+O   H1  H2  Na+
+%FLAG CHARGE
+%FORMAT(5E16.8)
+ -1.51973982E+01  7.59869910E+00  7.59869910E+00  1.82223000E+01
+%FLAG ATOMIC_NUMBER
+%FORMAT(10I8)
+       8       1       1      11
+%FLAG MASS
+%FORMAT(5E16.8)
+  1.60000000E+01  1.00800000E+00  1.00800000E+00  2.29900000E+01
+%FLAG ATOM_TYPE_INDEX
+%FORMAT(10I8)
+       1       2       2       3
+%FLAG NONBONDED_PARM_INDEX
+%FORMAT(10I8)
+       1       2       4       2       3       5       4       5       6
+%FLAG RESIDUE_LABEL
+%FORMAT(20a4)
+WAT Na+
+%FLAG RESIDUE_POINTER
+%FORMAT(10I8)
+       1       4
+%FLAG BONDS_INC_HYDROGEN
+%FORMAT(10I8)
+       0       3       1       0       6       1       3       6       2
+%FLAG BONDS_WITHOUT_HYDROGEN
+%FORMAT(10I8)
+
+%FLAG AMBER_ATOM_TYPE
+%FORMAT(20a4)
+OW  HW  HW  Na+
+%FLAG LENNARD_JONES_ACOEF
+%FORMAT(5E16.8)
+  5.82000000E+05  0.00000000E+00  0.00000000E+00  0.00000000E+00  0.00000000E+00
+  1.00000000E+03
+%FLAG LENNARD_JONES_BCOEF
+%FORMAT(5E16.8)
+  5.95000000E+02  0.00000000E+00  0.00000000E+00  0.00000000E+00  0.00000000E+00
+  1.00000000E+01
+";
+
+#[test]
+fn atoms_bonds_and_charges() {
+    let top = Prmtop::new(PRMTOP).unwrap();
+
+    assert_eq!(top.title, "water and sodium");
+    assert_eq!(top.atoms.len(), 4);
+    assert_eq!(top.masses, vec![16.0, 1.008, 1.008, 22.99]);
+
+    let names: Vec<_> = top
+        .atoms
+        .iter()
+        .map(|a| a.type_in_res_general.as_deref().unwrap())
+        .collect();
+    assert_eq!(names, ["O", "H1", "H2", "Na+"]);
+    assert_eq!(top.atoms[0].element, Element::Oxygen);
+    assert_eq!(top.atoms[3].element, Element::Sodium);
+    assert_eq!(top.atoms[1].force_field_type.as_deref(), Some("HW"));
+
+    // Converted from Amber's units.
+    assert!((top.atoms[0].partial_charge.unwrap() + 0.834).abs() < 1e-5);
+    let net: f32 = top.atoms.iter().map(|a| a.partial_charge.unwrap()).sum();
+    assert!((net - 1.).abs() < 1e-5);
+
+    // From coordinate indices, i.e. 3 × atom index.
+    let bonds: Vec<_> = top
+        .bonds
+        .iter()
+        .map(|b| (b.atom_0_sn, b.atom_1_sn))
+        .collect();
+    assert_eq!(bonds, [(1, 2), (1, 3), (2, 3)]);
+    assert!(top.bonds.iter().all(|b| b.bond_type == BondType::Unknown));
+
+    assert_eq!(top.residues.len(), 2);
+    assert_eq!(
+        top.residues[0].res_type,
+        ResidueType::Other("WAT".to_owned())
+    );
+    assert_eq!(top.residues[0].atom_sns, [1, 2, 3]);
+    assert_eq!(top.residues[1].atom_sns, [4]);
+
+    assert!(Prmtop::new(&PRMTOP.replace("%FLAG CHARGE", "%FLAG CHARGES")).is_err());
+}
+
+#[test]
+fn load_with_params() {
+    let path = std::env::temp_dir().join("bio_files_test.prmtop");
+    fs::write(&path, PRMTOP).unwrap();
+    let top = Prmtop::load(&path).unwrap();
+    let (atoms, params) = load_prmtop(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(top.atoms.len(), atoms.len());
+    assert_eq!(atoms[3].force_field_type.as_deref(), Some("Na+"));
+    assert!((atoms[1].partial_charge.unwrap() - 0.417).abs() < 1e-5);
+    assert!((params.mass["OW"].mass - 16.).abs() < 1e-5);
+}
+
+#[test]
+fn save_load_round_trip() {
+    let path = std::env::temp_dir().join("bio_files_test_round_trip.prmtop");
+    fs::write(&path, PRMTOP).unwrap();
+    let (atoms, params) = load_prmtop(&path).unwrap();
+
+    save_prmtop(&atoms, &params, &path).unwrap();
+    let top = Prmtop::load(&path).unwrap();
+    let (atoms_2, params_2) = load_prmtop(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(top.atoms.len(), atoms.len());
+    assert_eq!(atoms_2.len(), atoms.len());
+    for (a, b) in atoms.iter().zip(&atoms_2) {
+        assert_eq!(a.force_field_type, b.force_field_type);
+        assert!((a.partial_charge.unwrap() - b.partial_charge.unwrap()).abs() < 1e-5);
+    }
+    assert!((top.masses[1] - 1.008).abs() < 1e-5);
+    assert!((params_2.mass["HW"].mass - params.mass["HW"].mass).abs() < 1e-5);
+}
@@ -0,0 +1,108 @@
+use bio_files::{Sdf, SdfFormat, validate_formal_charges};
+
+/// Glycine zwitterion. The legacy charge column marks N as +1, and O2 as -1.
+fn glycine(m_chg: &str) -> String {
+    format!(
+        "\
+glycine
+
+
+  5  4  0  0  0  0  0  0  0  0999 V2000
+    1.2000    0.0000    0.0000 N   0  3  0  0  0  0  0  0  0  0  0  0
+    0.0000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+   -0.7500    1.2990    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+   -0.1500    2.3382    0.0000 O   0  0  0  0  0  0  0  0  0  0  0  0
+   -2.0000    1.2990    0.0000 O   0  5  0  0  0  0  0  0  0  0  0  0
+  1  2  1  0  0  0  0
+  2  3  1  0  0  0  0
+  3  4  2  0  0  0  0
+  3  5  1  0  0  0  0
+{m_chg}M  END
+> <atom.dprop.PartialCharge>
+0.45 0.10 0.55 -0.50 -0.60
+
+$$$$
+"
+    )
+}
+
+#[test]
+fn legacy_charges() {
+    let sdf = Sdf::new(&glycine("")).unwrap();
+
+    assert_eq!(sdf.atoms[0].formal_charge, Some(1));
+    assert_eq!(sdf.atoms[4].formal_charge, Some(-1));
+    assert_eq!(sdf.atoms[1].formal_charge, None);
+    assert_eq!(validate_formal_charges(&sdf.atoms), Ok(0));
+}
+
+#[test]
+fn legacy_charges_disagree_with_partial_charges() {
+    // The legacy column omits the carboxylate oxygen, so the formal charges no longer sum to the
+    // net charge of the partial charges.
+    let text = glycine("").replace(
+        "-2.0000    1.2990    0.0000 O   0  5",
+        "-2.0000    1.2990    0.0000 O   0  0",
+    );
+    let sdf = Sdf::new(&text).unwrap();
+
+    assert_eq!(sdf.atoms[4].formal_charge, None);
+
+    let err = validate_formal_charges(&sdf.atoms).unwrap_err();
+    assert!(err.contains("sum to 1"));
+}
+
+#[test]
+fn m_chg_disagrees_with_legacy() {
+    // The M  CHG block omits the carboxylate oxygen. It supersedes the legacy column, so the
+    // formal charges no longer sum to the net charge.
+    let sdf = Sdf::new(&glycine("M  CHG  1   1   1\n")).unwrap();
+
+    assert_eq!(sdf.atoms[0].formal_charge, Some(1));
+    assert_eq!(sdf.atoms[4].formal_charge, None);
+
+    let err = validate_formal_charges(&sdf.atoms).unwrap_err();
+    assert!(err.contains("sum to 1"));
+}
+
+#[test]
+fn m_chg_consistent() {
+    let sdf = Sdf::new(&glycine("M  CHG  2   1   1   5  -1\n")).unwrap();
+    assert_eq!(validate_formal_charges(&sdf.atoms), Ok(0));
+}
+
+#[test]
+fn ammonium_round_trip() {
+    // Methylammonium, without hydrogens: The nitrogen carries +1.
+    let text = "\
+methylammonium
+
+
+  2  1  0  0  0  0  0  0  0  0999 V2000
+    0.0000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+    1.4700    0.0000    0.0000 N   0  0  0  0  0  0  0  0  0  0  0  0
+  1  2  1  0  0  0  0
+M  CHG  1   2   1
+M  END
+$$$$
+";
+    let sdf = Sdf::new(text).unwrap();
+    assert_eq!(sdf.atoms[1].formal_charge, Some(1));
+
+    for (format, name) in [
+        (SdfFormat::V2000, "bio_files_test_ammonium_v2000.sdf"),
+        (SdfFormat::V3000, "bio_files_test_ammonium_v3000.sdf"),
+    ] {
+        let path = std::env::temp_dir().join(name);
+        sdf.save(&path, format).unwrap();
+
+        let saved = std::fs::read_to_string(&path).unwrap();
+        if let SdfFormat::V2000 = format {
+            assert!(saved.contains("M  CHG  1   2   1\n"));
+        }
+
+        let loaded = Sdf::load(&path).unwrap();
+        assert_eq!(loaded.atoms[0].formal_charge, None);
+        assert_eq!(loaded.atoms[1].formal_charge, Some(1));
+    }
+}
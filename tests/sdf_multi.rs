@@ -145,3 +145,90 @@ fn parses_touching_fixed_width_bond_indices() {
     assert_eq!(mol.bonds[0].atom_0_sn, 96);
     assert_eq!(mol.bonds[0].atom_1_sn, 114);
 }
+
+#[test]
+fn each_record_keeps_its_own_metadata() {
+    let mut text = String::new();
+    for (name, cid) in [("methane", 297), ("ammonia", 222), ("neon", 23935)] {
+        write!(
+            text,
+            "{name}\n\n\n  1  0  0  0  0  0            999 V2000\n    \
+             0.0000    0.0000    0.0000 C   0  0\nM  END\n\
+             > <PUBCHEM_COMPOUND_CID>\n{cid}\n\n$$$$\n"
+        )
+        .unwrap();
+    }
+
+    let path = std::env::temp_dir().join("bio_files_sdf_multi_cids.sdf");
+    std::fs::write(&path, &text).unwrap();
+    let mols = Sdf::load_multi(&path).unwrap();
+
+    assert_eq!(mols.len(), 3);
+    let idents: Vec<_> = mols.iter().map(|m| m.ident.as_str()).collect();
+    assert_eq!(idents, ["methane", "ammonia", "neon"]);
+
+    let cids: Vec<_> = mols
+        .iter()
+        .map(|m| m.metadata["PUBCHEM_COMPOUND_CID"].as_str())
+        .collect();
+    assert_eq!(cids, ["297", "222", "23935"]);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn data_fields_keep_order_and_duplicates() {
+    let text = TWO_MOLS.split("$$$$").next().unwrap().replace(
+        "> <NAME>\nWater\n",
+        "> <ZETA>\n3\n\n> <NAME>\nWater\n\n> <SYNONYM>\nOxidane\n\n> <SYNONYM>\nDihydrogen monoxide\n",
+    );
+    let mol = Sdf::new(&text).unwrap();
+
+    let expected = [
+        ("ZETA", "3"),
+        ("NAME", "Water"),
+        ("SYNONYM", "Oxidane"),
+        ("SYNONYM", "Dihydrogen monoxide"),
+    ];
+    let fields: Vec<(&str, &str)> = mol
+        .data_fields
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+    assert_eq!(fields, expected);
+    assert_eq!(mol.metadata["SYNONYM"], "Dihydrogen monoxide");
+
+    let path = std::env::temp_dir().join("bio_files_sdf_data_fields.sdf");
+    mol.save(&path, Default::default()).unwrap();
+    let loaded = Sdf::load(&path).unwrap();
+    assert_eq!(loaded.data_fields, mol.data_fields);
+
+    // Edits to `metadata` are saved in place; removed tags aren't written.
+    let mut edited = mol.clone();
+    edited
+        .metadata
+        .insert("NAME".to_owned(), "Heavy water".to_owned());
+    edited
+        .metadata
+        .insert("SYNONYM".to_owned(), "Deuterium oxide".to_owned());
+    edited.metadata.remove("ZETA");
+    edited.save(&path, Default::default()).unwrap();
+    let loaded = Sdf::load(&path).unwrap();
+
+    let fields: Vec<(&str, &str)> = loaded
+        .data_fields
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+    assert_eq!(
+        fields,
+        [
+            ("NAME", "Heavy water"),
+            ("SYNONYM", "Oxidane"),
+            ("SYNONYM", "Deuterium oxide"),
+        ]
+    );
+    assert_eq!(loaded.metadata["NAME"], "Heavy water");
+
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,65 @@
+use std::fmt::Write as _;
+
+use bio_files::{BondType, Sdf, SdfFormat};
+use na_seq::Element;
+
+/// A V3000 connection table for a zigzag carbon chain, too large for V2000's 3-digit counts.
+fn chain_v3000(n: usize) -> String {
+    let mut text =
+        String::from("polyethylene\n  bio_files\n\n  0  0  0     0  0              0 V3000\n");
+    text.push_str("M  V30 BEGIN CTAB\n");
+    writeln!(text, "M  V30 COUNTS {n} {} 0 0 0", n - 1).unwrap();
+
+    text.push_str("M  V30 BEGIN ATOM\n");
+    for i in 0..n {
+        let y = if i % 2 == 0 { 0. } else { 0.89 };
+        writeln!(
+            text,
+            "M  V30 {} C {:.4} {y:.4} 0.0000 0",
+            i + 1,
+            i as f64 * 1.26
+        )
+        .unwrap();
+    }
+    text.push_str("M  V30 END ATOM\nM  V30 BEGIN BOND\n");
+    for i in 1..n {
+        writeln!(text, "M  V30 {i} 1 {i} {}", i + 1).unwrap();
+    }
+    text.push_str("M  V30 END BOND\nM  V30 END CTAB\nM  END\n$$$$\n");
+
+    text
+}
+
+#[test]
+fn large_v3000() {
+    let sdf = Sdf::new(&chain_v3000(1_200)).unwrap();
+
+    assert_eq!(sdf.ident, "polyethylene");
+    assert_eq!(sdf.atoms.len(), 1_200);
+    assert_eq!(sdf.bonds.len(), 1_199);
+    assert!(sdf.atoms.iter().all(|a| a.element == Element::Carbon));
+
+    let last = &sdf.atoms[1_199];
+    assert_eq!(last.serial_number, 1_200);
+    assert!((last.posit.x - 1_199. * 1.26).abs() < 1e-6);
+    assert!((last.posit.y - 0.89).abs() < 1e-6);
+
+    let bond = &sdf.bonds[1_198];
+    assert_eq!((bond.atom_0_sn, bond.atom_1_sn), (1_199, 1_200));
+    assert_eq!(bond.bond_type, BondType::Single);
+}
+
+#[test]
+fn v3000_round_trip() {
+    let sdf = Sdf::new(&chain_v3000(1_200)).unwrap();
+
+    let path = std::env::temp_dir().join("bio_files_test_v3000.sdf");
+    sdf.save(&path, SdfFormat::V3000).unwrap();
+    let loaded = Sdf::load(&path).unwrap();
+
+    assert_eq!(loaded.atoms.len(), 1_200);
+    assert_eq!(loaded.bonds.len(), 1_199);
+    assert_eq!(loaded.atoms[600].posit, sdf.atoms[600].posit);
+
+    let _ = std::fs::remove_file(&path);
+}
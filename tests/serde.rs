@@ -0,0 +1,85 @@
+#![cfg(feature = "serde")]
+
+use bio_files::{BondType, MmCif, ResidueType, Sdf};
+use na_seq::{AminoAcid, AtomTypeInRes, Element};
+
+/// Methanol, with a residue for the round trip.
+const METHANOL: &str = "\
+methanol
+  test
+
+  6  5  0  0  0  0  0  0  0  0999 V2000
+   -0.3700    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+    1.0500    0.0000    0.0000 O   0  0  0  0  0  0  0  0  0  0  0  0
+   -0.7300    1.0300    0.0000 H   0  0  0  0  0  0  0  0  0  0  0  0
+   -0.7300   -0.5100    0.8900 H   0  0  0  0  0  0  0  0  0  0  0  0
+   -0.7300   -0.5100   -0.8900 H   0  0  0  0  0  0  0  0  0  0  0  0
+    1.3700   -0.9100    0.0000 H   0  0  0  0  0  0  0  0  0  0  0  0
+  1  2  1  0  0  0  0
+  1  3  1  0  0  0  0
+  1  4  1  0  0  0  0
+  1  5  1  0  0  0  0
+  2  6  1  0  0  0  0
+M  END
+> <PUBCHEM_COMPOUND_CID>
+887
+
+$$$$
+";
+
+#[test]
+fn sdf_json_round_trip() {
+    let mut sdf = Sdf::new(METHANOL).unwrap();
+    sdf.atoms[0].type_in_res = Some(AtomTypeInRes::Hetero("C1".to_owned()));
+    sdf.atoms[1].element = Element::Zinc;
+    sdf.atoms[2].type_in_res = Some(AtomTypeInRes::CA);
+    sdf.bonds[0].bond_type = BondType::Double;
+    sdf.residues[0].res_type = ResidueType::AminoAcid(AminoAcid::Ala);
+
+    let json = serde_json::to_string(&sdf).unwrap();
+    let loaded: Sdf = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(loaded.ident, sdf.ident);
+    assert_eq!(loaded.metadata, sdf.metadata);
+    assert_eq!(loaded.atoms.len(), 6);
+    for (a, b) in loaded.atoms.iter().zip(&sdf.atoms) {
+        assert_eq!(a.serial_number, b.serial_number);
+        assert_eq!(a.posit, b.posit);
+        assert_eq!(a.element, b.element);
+        assert_eq!(a.type_in_res, b.type_in_res);
+    }
+
+    assert_eq!(loaded.bonds.len(), 5);
+    assert_eq!(loaded.bonds[0].bond_type, BondType::Double);
+    assert_eq!(loaded.bonds[4].atom_1_sn, 6);
+
+    assert_eq!(loaded.residues.len(), sdf.residues.len());
+    for (a, b) in loaded.residues.iter().zip(&sdf.residues) {
+        assert_eq!(a.res_type, b.res_type);
+        assert_eq!(a.atom_sns, b.atom_sns);
+    }
+}
+
+#[test]
+fn invalid_unit_cell_is_an_error() {
+    let cif = MmCif::new(
+        "data_XTAL
+_cell.length_a 40.96
+_cell.length_b 18.65
+_cell.length_c 22.52
+_cell.angle_alpha 90
+_cell.angle_beta 90.77
+_cell.angle_gamma 90
+#
+",
+    )
+    .unwrap();
+
+    let mut json: serde_json::Value = serde_json::to_value(&cif).unwrap();
+    let loaded: MmCif = serde_json::from_value(json.clone()).unwrap();
+    assert!((loaded.unit_cell.unwrap().a - 40.96).abs() < 1e-9);
+
+    // A singular cell is rejected, instead of panicking.
+    json["unit_cell"][0] = 0.into();
+    assert!(serde_json::from_value::<MmCif>(json).is_err());
+}
@@ -0,0 +1,134 @@
+use bio_files::{AtomGeneric, MmCif, SymOp, UnitCell, expand_symmetry, parse_symop_xyz};
+use lin_alg::f64::Vec3;
+use na_seq::Element::{Carbon, Oxygen};
+
+fn atoms() -> Vec<AtomGeneric> {
+    vec![
+        AtomGeneric {
+            serial_number: 1,
+            posit: Vec3::new(1.0, 2.0, 3.0),
+            element: Carbon,
+            ..Default::default()
+        },
+        AtomGeneric {
+            serial_number: 2,
+            posit: Vec3::new(2.2, 2.5, 3.1),
+            element: Oxygen,
+            ..Default::default()
+        },
+    ]
+}
+
+#[test]
+fn parse_symops() {
+    assert!(parse_symop_xyz("x,y,z").unwrap().is_identity());
+    assert!(parse_symop_xyz("X, Y, Z").unwrap().is_identity());
+
+    let op = parse_symop_xyz("-x,y+1/2,-z").unwrap();
+    assert_eq!(op.rot, [[-1., 0., 0.], [0., 1., 0.], [0., 0., -1.]]);
+    assert_eq!(op.trans, Vec3::new(0., 0.5, 0.));
+
+    // Translation first, and a hexagonal operator.
+    let op = parse_symop_xyz("1/2+X,x-y,z+1/6").unwrap();
+    assert_eq!(op.rot, [[1., 0., 0.], [1., -1., 0.], [0., 0., 1.]]);
+    assert!((op.trans.x - 0.5).abs() < 1e-12);
+    assert!((op.trans.z - 1. / 6.).abs() < 1e-12);
+
+    assert!(parse_symop_xyz("x,y").is_err());
+    assert!(parse_symop_xyz("x,y,w").is_err());
+    assert!(parse_symop_xyz("x,y+,z").is_err());
+}
+
+#[test]
+fn p1_identity_is_input() {
+    let cell = UnitCell::new(20., 30., 40., 90., 90., 90.);
+    let atoms = atoms();
+
+    let copies = expand_symmetry(&atoms, &cell, &[SymOp::identity()], (0, 0, 0));
+    assert_eq!(copies.len(), 1);
+    for (a, b) in copies[0].iter().zip(&atoms) {
+        assert_eq!(a.posit, b.posit);
+        assert_eq!(a.serial_number, b.serial_number);
+    }
+}
+
+#[test]
+fn p21_with_translations() {
+    let cell = UnitCell::new(20., 30., 40., 90., 95., 90.);
+    let atoms = atoms();
+    let ops = [
+        parse_symop_xyz("x,y,z").unwrap(),
+        parse_symop_xyz("-x,y+1/2,-z").unwrap(),
+    ];
+
+    let copies = expand_symmetry(&atoms, &cell, &ops, (1, 0, 0));
+    assert_eq!(copies.len(), 6);
+
+    // Identity, translated by -a.
+    let a_vec = cell.fractional_to_cartesian(Vec3::new(1., 0., 0.));
+    assert!((copies[0][0].posit - (atoms[0].posit - a_vec)).magnitude() < 1e-9);
+    // Identity, untranslated.
+    assert_eq!(copies[1][0].posit, atoms[0].posit);
+
+    // The screw axis, untranslated.
+    let f = cell.cartesian_to_fractional(atoms[1].posit);
+    let expected = cell.fractional_to_cartesian(Vec3::new(-f.x, f.y + 0.5, -f.z));
+    assert!((copies[4][1].posit - expected).magnitude() < 1e-9);
+    assert_eq!(copies[4][1].element, Oxygen);
+
+    // Copies keep the interatomic distances.
+    let d = (atoms[0].posit - atoms[1].posit).magnitude();
+    for copy in &copies {
+        assert!(((copy[0].posit - copy[1].posit).magnitude() - d).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn symmetry_ops_from_mmcif() {
+    let cif = MmCif::new(
+        "data_XTAL
+_cell.length_a 40.960
+_cell.length_b 18.650
+_cell.length_c 22.520
+_cell.angle_alpha 90.00
+_cell.angle_beta 90.77
+_cell.angle_gamma 90.00
+#
+loop_
+_space_group_symop.id
+_space_group_symop.operation_xyz
+1 x,y,z
+2 '-x,y+1/2,-z'
+#
+loop_
+_atom_site.group_PDB
+_atom_site.id
+_atom_site.type_symbol
+_atom_site.label_atom_id
+_atom_site.label_alt_id
+_atom_site.label_comp_id
+_atom_site.label_asym_id
+_atom_site.label_seq_id
+_atom_site.Cartn_x
+_atom_site.Cartn_y
+_atom_site.Cartn_z
+_atom_site.occupancy
+_atom_site.B_iso_or_equiv
+ATOM 1 C CA . GLY A 1 1.000 2.000 3.000 1.00 10.00
+#
+",
+    )
+    .unwrap();
+
+    assert_eq!(cif.symmetry_ops.len(), 2);
+    assert!(cif.symmetry_ops[0].is_identity());
+    assert_eq!(cif.symmetry_ops[1], parse_symop_xyz("-x,y+1/2,-z").unwrap());
+
+    let copies = expand_symmetry(
+        &cif.atoms,
+        cif.unit_cell.as_ref().unwrap(),
+        &cif.symmetry_ops,
+        (0, 0, 0),
+    );
+    assert_eq!(copies.len(), 2);
+}
@@ -0,0 +1,94 @@
+use bio_files::{
+    AtomGeneric, Trajectory, Xyz,
+    dcd::{DcdFrame, DcdTrajectory, DcdUnitCell},
+};
+use lin_alg::{f32::Vec3 as Vec3F32, f64::Vec3};
+use na_seq::Element;
+
+/// Three frames of a two-atom system. The first atom is fixed, and the second moves along x.
+fn positions() -> Vec<Vec<Vec3>> {
+    vec![
+        vec![Vec3::new(0., 0., 0.), Vec3::new(1., 0., 0.)],
+        vec![Vec3::new(0., 0., 0.), Vec3::new(3., 0., 0.)],
+        vec![Vec3::new(0., 0., 0.), Vec3::new(5., 0., 0.)],
+    ]
+}
+
+fn dcd_trajectory() -> DcdTrajectory {
+    let unit_cell = DcdUnitCell {
+        bounds_low: Vec3F32::new(0., 0., 0.),
+        bounds_high: Vec3F32::new(10., 10., 10.),
+        ..Default::default()
+    };
+
+    let frames = positions()
+        .into_iter()
+        .enumerate()
+        .map(|(i, frame)| DcdFrame {
+            time: i as f64,
+            atom_posits: frame
+                .iter()
+                .map(|p| Vec3F32::new(p.x as f32, p.y as f32, p.z as f32))
+                .collect(),
+            unit_cell: unit_cell.clone(),
+        })
+        .collect();
+
+    DcdTrajectory { frames }
+}
+
+fn xyz_trajectory() -> Vec<Xyz> {
+    positions()
+        .into_iter()
+        .map(|frame| Xyz {
+            atoms: frame
+                .into_iter()
+                .enumerate()
+                .map(|(i, posit)| AtomGeneric {
+                    serial_number: i as u32 + 1,
+                    posit,
+                    element: Element::Carbon,
+                    ..Default::default()
+                })
+                .collect(),
+            comment: String::new(),
+            cell: None,
+            charge: None,
+            multiplicity: None,
+        })
+        .collect()
+}
+
+#[test]
+fn rmsd_series_matches_across_formats() {
+    let dcd = dcd_trajectory();
+    let xyz = xyz_trajectory();
+
+    assert_eq!(dcd.n_frames(), 3);
+    assert_eq!(xyz.n_frames(), 3);
+
+    // Only one of two atoms moves, so RMSD = displacement / sqrt(2).
+    let expected = [0., 2. / 2_f64.sqrt(), 4. / 2_f64.sqrt()];
+
+    for series in [dcd.rmsd_series(0), xyz.rmsd_series(0)] {
+        assert_eq!(series.len(), 3);
+        for (v, e) in series.iter().zip(expected) {
+            assert!((v - e).abs() < 1e-6);
+        }
+    }
+}
+
+#[test]
+fn average_and_rmsf() {
+    let xyz = xyz_trajectory();
+
+    let avg = xyz.average_coords();
+    assert!((avg[1] - Vec3::new(3., 0., 0.)).magnitude() < 1e-12);
+
+    let rmsf = xyz.rmsf();
+    assert_eq!(rmsf[0], 0.);
+    assert!((rmsf[1] - (8_f64 / 3.).sqrt()).abs() < 1e-12);
+
+    let rmsf_dcd = dcd_trajectory().rmsf();
+    assert!((rmsf_dcd[1] - rmsf[1]).abs() < 1e-6);
+}
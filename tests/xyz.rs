@@ -0,0 +1,194 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use bio_files::{AtomGeneric, Xyz, XyzTrajectoryReader, new_xyz_trajectory};
+use lin_alg::f64::Vec3;
+use na_seq::Element;
+
+const EXTENDED: &str = r#"3
+Lattice="10.0 0.0 0.0 0.0 12.0 0.0 0.0 0.0 8.0" Properties=species:S:1:pos:R:3:initial_charges:R:1:forces:R:3:tags:I:1 energy=-14.2 pbc="T T T"
+O        5.00000000       6.00000000       4.11730000      -0.83400000       0.01000000       0.00000000      -0.21000000        0
+H        5.00000000       6.75720000       3.53080000       0.41700000       0.00000000       0.12000000       0.10500000        0
+H        5.00000000       5.24280000       3.53080000       0.41700000       0.00000000      -0.12000000       0.10500000        0
+"#;
+
+const PLAIN: &str = "3
+Water, charge=0 mult=1
+O 0.000 0.000 0.117
+H 0.000 0.757 -0.469
+H 0.000 -0.757 -0.469
+";
+
+#[test]
+fn extended_xyz() {
+    let xyz = Xyz::new(EXTENDED).unwrap();
+
+    assert_eq!(xyz.atoms.len(), 3);
+    assert_eq!(xyz.atoms[0].element, Element::Oxygen);
+    assert!((xyz.atoms[1].posit.y - 6.7572).abs() < 1e-9);
+
+    let charges: Vec<f32> = xyz
+        .atoms
+        .iter()
+        .map(|a| a.partial_charge.unwrap())
+        .collect();
+    assert_eq!(charges, [-0.834, 0.417, 0.417]);
+
+    let forces: Vec<Vec3> = xyz.atoms.iter().map(|a| a.force.unwrap()).collect();
+    assert!((forces[0].z + 0.21).abs() < 1e-9);
+    assert!((forces[2].y + 0.12).abs() < 1e-9);
+    assert!(xyz.atoms[0].velocity.is_none());
+
+    let cell = xyz.cell.unwrap();
+    assert!((cell.a - 10.).abs() < 1e-9);
+    assert!((cell.b - 12.).abs() < 1e-9);
+    assert!((cell.c - 8.).abs() < 1e-9);
+    assert!((cell.gamma.to_degrees() - 90.).abs() < 1e-9);
+
+    // Properties in a different order, with atomic numbers instead of symbols.
+    let reordered = "1\nProperties=Z:I:1:charge:R:1:pos:R:3\n8 -0.5 1.0 2.0 3.0\n";
+    let xyz = Xyz::new(reordered).unwrap();
+    assert_eq!(xyz.atoms[0].element, Element::Oxygen);
+    assert_eq!(xyz.atoms[0].partial_charge, Some(-0.5));
+    assert!((xyz.atoms[0].posit.z - 3.).abs() < 1e-9);
+
+    let with_velocities =
+        "1\nProperties=species:S:1:pos:R:3:velocities:R:3\nC 0.0 0.0 0.0 0.01 -0.02 0.03\n";
+    let xyz = Xyz::new(with_velocities).unwrap();
+    let vel = xyz.atoms[0].velocity.unwrap();
+    assert!((vel.y + 0.02).abs() < 1e-9);
+    assert!(xyz.atoms[0].force.is_none());
+
+    // Missing columns.
+    assert!(Xyz::new("1\nProperties=species:S:1:pos:R:3:charge:R:1\nO 0.0 0.0 0.0\n").is_err());
+}
+
+#[test]
+fn plain_xyz() {
+    let xyz = Xyz::new(PLAIN).unwrap();
+
+    assert_eq!(xyz.atoms.len(), 3);
+    assert_eq!(xyz.atoms[2].element, Element::Hydrogen);
+    assert!((xyz.atoms[2].posit.y + 0.757).abs() < 1e-9);
+    assert_eq!(xyz.atoms[0].partial_charge, None);
+    assert!(xyz.cell.is_none());
+    assert!(
+        xyz.atoms
+            .iter()
+            .all(|a| a.force.is_none() && a.velocity.is_none())
+    );
+    assert_eq!(xyz.charge, Some(0));
+    assert_eq!(xyz.multiplicity, Some(1));
+}
+
+#[test]
+fn writes_element_symbols() {
+    let mut xyz = Xyz::new(PLAIN).unwrap();
+    xyz.atoms[0].element = Element::Zinc;
+
+    let path = std::env::temp_dir().join("bio_files_test_symbols.xyz");
+    xyz.save(&path).unwrap();
+
+    let text = std::fs::read_to_string(&path).unwrap();
+    assert!(text.lines().nth(2).unwrap().starts_with("Zn "));
+    assert_eq!(Xyz::load(&path).unwrap().atoms[0].element, Element::Zinc);
+
+    // Every element is written with its symbol, and read back. `Other` is written as "X", which
+    // doesn't identify an element, so it's excluded.
+    use Element::*;
+    let elements = [
+        (Hydrogen, "H"),
+        (Carbon, "C"),
+        (Oxygen, "O"),
+        (Nitrogen, "N"),
+        (Fluorine, "F"),
+        (Sulfur, "S"),
+        (Phosphorus, "P"),
+        (Iron, "Fe"),
+        (Copper, "Cu"),
+        (Calcium, "Ca"),
+        (Potassium, "K"),
+        (Aluminum, "Al"),
+        (Lead, "Pb"),
+        (Gold, "Au"),
+        (Silver, "Ag"),
+        (Mercury, "Hg"),
+        (Tin, "Sn"),
+        (Zinc, "Zn"),
+        (Magnesium, "Mg"),
+        (Manganese, "Mn"),
+        (Iodine, "I"),
+        (Chlorine, "Cl"),
+        (Tungsten, "W"),
+        (Tellurium, "Te"),
+        (Selenium, "Se"),
+        (Bromine, "Br"),
+        (Rubidium, "Rb"),
+        (Silicon, "Si"),
+        (Sodium, "Na"),
+        (Indium, "In"),
+        (Titanium, "Ti"),
+        (Barium, "Ba"),
+        (Molybdenum, "Mo"),
+        (Boron, "B"),
+    ];
+    xyz.atoms = elements
+        .iter()
+        .map(|(element, _)| AtomGeneric {
+            element: *element,
+            ..Default::default()
+        })
+        .collect();
+    xyz.save(&path).unwrap();
+
+    let text = std::fs::read_to_string(&path).unwrap();
+    for (line, (_, symbol)) in text.lines().skip(2).zip(&elements) {
+        assert_eq!(line.split_whitespace().next(), Some(*symbol));
+    }
+    let loaded = Xyz::load(&path).unwrap();
+    for (atom, (element, _)) in loaded.atoms.iter().zip(&elements) {
+        assert_eq!(atom.element, *element);
+    }
+    assert_eq!(loaded.atoms.len(), elements.len());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn stream_trajectory() {
+    const NUM_FRAMES: usize = 10_000;
+
+    let path = std::env::temp_dir().join("bio_files_test_stream.xyz");
+    {
+        let mut f = BufWriter::new(File::create(&path).unwrap());
+        for i in 0..NUM_FRAMES {
+            writeln!(f, "2\nenergy={:.1}", i as f64 * 0.5).unwrap();
+            writeln!(f, "O 0.0 0.0 {i}.0\nH 0.0 0.9 {i}.0").unwrap();
+        }
+    }
+
+    let mut num_frames = 0;
+    let mut energy = 0.;
+    for frame in XyzTrajectoryReader::open(&path).unwrap() {
+        let frame = frame.unwrap();
+        assert_eq!(frame.atoms.len(), 2);
+        assert_eq!(frame.atoms[0].posit.z, num_frames as f64);
+
+        let e = frame.comment.strip_prefix("energy=").unwrap();
+        energy += e.parse::<f64>().unwrap();
+        num_frames += 1;
+    }
+
+    assert_eq!(num_frames, NUM_FRAMES);
+    assert_eq!(energy, 0.5 * (NUM_FRAMES * (NUM_FRAMES - 1) / 2) as f64);
+
+    // An incomplete last frame is an error, after which the reader stops.
+    let text = "1\nfirst\nC 0 0 0\n\n2\nsecond\nC 0 0 0\n";
+    let frames: Vec<_> = XyzTrajectoryReader::new(text.as_bytes()).collect();
+    assert_eq!(frames.len(), 2);
+    assert!(frames[0].is_ok());
+    assert!(frames[1].is_err());
+    assert!(new_xyz_trajectory(text).is_err());
+}
@@ -0,0 +1,107 @@
+use bio_files::{
+    AtomGeneric, BondGeneric, BondType,
+    zmatrix::{from_zmatrix, to_zmatrix},
+};
+use lin_alg::f64::Vec3;
+use na_seq::Element::{self, Carbon, Hydrogen, Oxygen};
+
+fn atom(sn: u32, element: Element, x: f64, y: f64, z: f64) -> AtomGeneric {
+    AtomGeneric {
+        serial_number: sn,
+        posit: Vec3::new(x, y, z),
+        element,
+        ..Default::default()
+    }
+}
+
+fn bond(a: u32, b: u32) -> BondGeneric {
+    BondGeneric {
+        bond_type: BondType::Single,
+        atom_0_sn: a,
+        atom_1_sn: b,
+    }
+}
+
+/// Ethanol, with hydrogens.
+fn ethanol() -> (Vec<AtomGeneric>, Vec<BondGeneric>) {
+    let atoms = vec![
+        atom(1, Carbon, -0.0426, -0.4173, 0.0),
+        atom(2, Carbon, 1.4719, -0.2869, 0.0),
+        atom(3, Oxygen, 1.8841, 1.0751, 0.0),
+        atom(4, Hydrogen, -0.3733, -1.4578, 0.0),
+        atom(5, Hydrogen, -0.4611, 0.0611, 0.8898),
+        atom(6, Hydrogen, -0.4611, 0.0611, -0.8898),
+        atom(7, Hydrogen, 1.8834, -0.7799, 0.8843),
+        atom(8, Hydrogen, 1.8834, -0.7799, -0.8843),
+        atom(9, Hydrogen, 2.8514, 1.1144, 0.0),
+    ];
+    let bonds = vec![
+        bond(1, 2),
+        bond(2, 3),
+        bond(1, 4),
+        bond(1, 5),
+        bond(1, 6),
+        bond(2, 7),
+        bond(2, 8),
+        bond(3, 9),
+    ];
+    (atoms, bonds)
+}
+
+#[test]
+fn references_use_bond_graph() {
+    let (atoms, bonds) = ethanol();
+    let zmat = to_zmatrix(&atoms, &bonds);
+    assert_eq!(zmat.rows.len(), atoms.len());
+
+    for (i, row) in zmat.rows.iter().enumerate() {
+        for r in [row.bond_ref, row.angle_ref, row.dihedral_ref]
+            .into_iter()
+            .flatten()
+        {
+            assert!(r < i);
+        }
+
+        if let Some(b) = row.bond_ref {
+            let (sn0, sn1) = (row.serial_number, zmat.rows[b].serial_number);
+            assert!(
+                bonds
+                    .iter()
+                    .any(|bd| (bd.atom_0_sn == sn0 && bd.atom_1_sn == sn1)
+                        || (bd.atom_0_sn == sn1 && bd.atom_1_sn == sn0))
+            );
+        }
+    }
+
+    // C-O bond length.
+    let o = zmat.rows.iter().find(|r| r.serial_number == 3).unwrap();
+    assert!((o.bond_len - 1.423).abs() < 1e-3);
+}
+
+#[test]
+fn round_trip_preserves_geometry() {
+    let (atoms, bonds) = ethanol();
+    let rebuilt = from_zmatrix(&to_zmatrix(&atoms, &bonds));
+    assert_eq!(rebuilt.len(), atoms.len());
+
+    // Compare all pairwise distances, as the result is only defined up to a rigid transform.
+    let find = |sn: u32| {
+        rebuilt
+            .iter()
+            .find(|a| a.serial_number == sn)
+            .unwrap()
+            .posit
+    };
+    for a0 in &atoms {
+        for a1 in &atoms {
+            let orig = (a0.posit - a1.posit).magnitude();
+            let new = (find(a0.serial_number) - find(a1.serial_number)).magnitude();
+            assert!(
+                (orig - new).abs() < 1e-6,
+                "{} - {}: {orig} vs {new}",
+                a0.serial_number,
+                a1.serial_number
+            );
+        }
+    }
+}